@@ -0,0 +1,161 @@
+use crate::util::rational::Rational;
+
+use super::{
+    factor, monomial::Monomial, polynomial::Polynomial, rational_function::RationalFunction,
+    univariate::UnivariatePolynomial, InterpreterError,
+};
+
+/// One term `numerator / denominator^power` of a partial fraction decomposition,
+/// where the denominator is an irreducible primitive factor
+#[derive(Debug)]
+pub struct PartialFraction {
+    pub numerator: Polynomial,
+    pub denominator: Polynomial,
+    pub power: usize,
+}
+
+/// A rational function split into a polynomial part plus proper fractions
+#[derive(Debug)]
+pub struct PartialFractions {
+    pub polynomial: Polynomial,
+    pub fractions: Vec<PartialFraction>,
+}
+
+/// Decompose a rational function of one variable into partial fractions
+///
+/// The denominator is factored over the integers, and for each factor `p^e` the
+/// matching numerator is found from the extended Euclidean algorithm and then
+/// expanded in powers of `p`, giving terms `a_1/p + a_2/p^2 + ... + a_e/p^e`
+/// with `deg a_i < deg p`.
+pub fn apart(
+    value: &RationalFunction,
+    variable: &str,
+) -> Result<PartialFractions, InterpreterError> {
+    let (numerator, numerator_shift) = to_univariate(&value.numerator, variable)?;
+    let (denominator, denominator_shift) = to_univariate(&value.denominator, variable)?;
+
+    // clear negative powers written directly in the numerator or denominator
+    let shift = numerator_shift - denominator_shift;
+    let (numerator, denominator) = if shift >= 0 {
+        (
+            numerator.mul(&UnivariatePolynomial::monomial(
+                Rational::one(),
+                shift as usize,
+            )),
+            denominator,
+        )
+    } else {
+        (
+            numerator,
+            denominator.mul(&UnivariatePolynomial::monomial(
+                Rational::one(),
+                (-shift) as usize,
+            )),
+        )
+    };
+
+    let (polynomial, remainder) = numerator.div_rem(&denominator);
+    let mut decomposition = PartialFractions {
+        polynomial: from_univariate(&polynomial, variable),
+        fractions: Vec::new(),
+    };
+    if remainder.is_zero() {
+        return Ok(decomposition);
+    }
+
+    let factorization = factor::factor(&from_univariate(&denominator, variable))?;
+    let mut pieces: Vec<(Polynomial, usize)> = factorization
+        .monomial
+        .iter()
+        .map(|(name, power)| (Polynomial::variable(name, 1.0), *power as usize))
+        .collect();
+    pieces.extend(factorization.factors);
+
+    let remainder = remainder.scale(&factorization.coefficient.recip());
+    let univariate_pieces = pieces
+        .iter()
+        .map(|(p, power)| {
+            let (polynomial, shift) = to_univariate(p, variable)?;
+            let polynomial = polynomial.mul(&UnivariatePolynomial::monomial(
+                Rational::one(),
+                shift as usize,
+            ));
+            Ok((polynomial, *power))
+        })
+        .collect::<Result<Vec<_>, InterpreterError>>()?;
+
+    for (i, ((factor, power), (p, _))) in univariate_pieces.iter().zip(pieces.iter()).enumerate() {
+        let full = factor.pow(*power);
+        let cofactor = univariate_pieces
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(
+                UnivariatePolynomial::constant(Rational::one()),
+                |acc, (_, (q, e))| acc.mul(&q.pow(*e)),
+            );
+
+        // remainder / (cofactor * full) contributes a / full with a = remainder * cofactor^-1 mod full
+        let (_, inverse, _) = cofactor.extended_gcd(&full);
+        let (_, mut a) = remainder.mul(&inverse).div_rem(&full);
+
+        let mut terms = Vec::new();
+        for j in 0..*power {
+            let (quotient, digit) = a.div_rem(factor);
+            if !digit.is_zero() {
+                terms.push(PartialFraction {
+                    numerator: from_univariate(&digit, variable),
+                    denominator: p.clone(),
+                    power: power - j,
+                });
+            }
+            a = quotient;
+        }
+        decomposition.fractions.extend(terms.into_iter().rev());
+    }
+
+    Ok(decomposition)
+}
+
+/// Convert a polynomial in one variable to dense form, returning it with the
+/// lowest power of the variable divided out
+pub(crate) fn to_univariate(
+    expression: &Polynomial,
+    variable: &str,
+) -> Result<(UnivariatePolynomial, i64), InterpreterError> {
+    let mut terms = Vec::new();
+    for (monomial, coefficient) in expression.terms().iter() {
+        let power = match monomial.powers().as_slice() {
+            [] => 0,
+            [(name, exponent)] if name == variable && exponent.0.fract() == 0.0 => {
+                exponent.0 as i64
+            }
+            _ => {
+                return Err(InterpreterError::unsupported(format!(
+                    "apart only supports rational functions of {} with integer powers",
+                    variable
+                )))
+            }
+        };
+        terms.push((power, coefficient.clone()));
+    }
+
+    let lowest = terms.iter().map(|(p, _)| *p).min().unwrap_or(0);
+    let highest = terms.iter().map(|(p, _)| *p).max().unwrap_or(0);
+    let mut coefficients = vec![Rational::zero(); (highest - lowest) as usize + 1];
+    for (power, coefficient) in terms {
+        coefficients[(power - lowest) as usize] = coefficient;
+    }
+    Ok((UnivariatePolynomial::new(coefficients), lowest))
+}
+
+pub(crate) fn from_univariate(polynomial: &UnivariatePolynomial, variable: &str) -> Polynomial {
+    let mut expression = Polynomial::new();
+    for (power, coefficient) in polynomial.coefficients().iter().enumerate() {
+        expression.add_term(
+            Monomial::variable(variable, power as f64),
+            coefficient.clone(),
+        );
+    }
+    expression
+}
@@ -0,0 +1,349 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use num_integer::Integer;
+
+use crate::util::{
+    complex::Complex,
+    rational::{Rational, ROUNDING},
+};
+
+use super::{log, polynomial::Polynomial, symbol::Symbol, trig, InterpreterError};
+
+/// A property a variable can be assumed to have, e.g. `assume x > 0` or `assume n integer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Property {
+    Positive,
+    Negative,
+    Nonzero,
+    Integer,
+    Real,
+}
+
+impl Property {
+    pub fn from_name(name: &str) -> Option<Property> {
+        match name {
+            "positive" => Some(Property::Positive),
+            "negative" => Some(Property::Negative),
+            "nonzero" => Some(Property::Nonzero),
+            "integer" => Some(Property::Integer),
+            "real" => Some(Property::Real),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Property {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Property::Positive => write!(f, "> 0"),
+            Property::Negative => write!(f, "< 0"),
+            Property::Nonzero => write!(f, "nonzero"),
+            Property::Integer => write!(f, "integer"),
+            Property::Real => write!(f, "real"),
+        }
+    }
+}
+
+/// The answer to a question about a variable: known to hold, known to fail, or unknown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Truth {
+    True,
+    False,
+    Unknown,
+}
+
+impl Truth {
+    pub fn is_true(self) -> bool {
+        self == Truth::True
+    }
+
+    pub fn is_false(self) -> bool {
+        self == Truth::False
+    }
+}
+
+/// What is assumed about each variable in a session
+///
+/// Facts are stored per variable name and what follows from them is worked out when
+/// asked, so a positive variable is also known to be nonzero and real. Constants such as
+/// e, pi and square roots of positive numbers are known to be positive without assuming it.
+#[derive(Debug, Clone, Default)]
+pub struct Assumptions {
+    facts: HashMap<String, HashSet<Property>>,
+}
+
+impl Assumptions {
+    pub fn new() -> Self {
+        Assumptions::default()
+    }
+
+    /// Assume a property of a variable, failing if it contradicts what is already assumed
+    pub fn assume(&mut self, name: &str, property: Property) -> Result<(), InterpreterError> {
+        let contradiction = match property {
+            Property::Positive => self.ask(name, Property::Negative).is_true(),
+            Property::Negative => self.ask(name, Property::Positive).is_true(),
+            _ => self.ask(name, property).is_false(),
+        };
+        if contradiction {
+            return Err(InterpreterError::unsupported(format!(
+                "Assuming {} {} contradicts an earlier assumption",
+                name, property
+            )));
+        }
+        self.facts
+            .entry(name.to_string())
+            .or_default()
+            .insert(property);
+        Ok(())
+    }
+
+    /// Forget everything assumed about a variable
+    pub fn forget(&mut self, name: &str) {
+        self.facts.remove(name);
+    }
+
+    /// Whether a variable has a property
+    pub fn ask(&self, name: &str, property: Property) -> Truth {
+        if let Some(known) = constant(name, property) {
+            return known;
+        }
+        let Some(facts) = self.facts.get(name) else {
+            return Truth::Unknown;
+        };
+        let has = |p: Property| facts.contains(&p);
+        let holds = match property {
+            Property::Positive if has(Property::Negative) => return Truth::False,
+            Property::Negative if has(Property::Positive) => return Truth::False,
+            Property::Nonzero => {
+                has(Property::Nonzero) || has(Property::Positive) || has(Property::Negative)
+            }
+            Property::Real => {
+                has(Property::Real)
+                    || has(Property::Integer)
+                    || has(Property::Positive)
+                    || has(Property::Negative)
+            }
+            _ => has(property),
+        };
+        match holds {
+            true => Truth::True,
+            false => Truth::Unknown,
+        }
+    }
+
+    /// Whether a polynomial is positive for every value the assumptions allow
+    ///
+    /// Numbers are compared with zero. Otherwise the sign of each term is worked out from its
+    /// coefficient and the signs of its factors, an even power of a nonzero real being
+    /// positive, and the polynomial is positive when every term is, and known not to be
+    /// when every term is negative.
+    pub fn is_positive(&self, polynomial: &Polynomial) -> Truth {
+        if let Some(c) = polynomial.as_constant() {
+            return match c > 0 {
+                true => Truth::True,
+                false => Truth::False,
+            };
+        }
+        let factor_sign = |name: &str, exponent: f64| {
+            let even = exponent.rem_euclid(2.0) == 0.0;
+            let nonzero_real = self.ask(name, Property::Real).is_true()
+                && self.ask(name, Property::Nonzero).is_true();
+            if self.ask(name, Property::Positive).is_true() || (even && nonzero_real) {
+                Some(1.0)
+            } else if exponent.fract() == 0.0 && self.ask(name, Property::Negative).is_true() {
+                Some(if even { 1.0 } else { -1.0 })
+            } else {
+                None
+            }
+        };
+        let signs: Option<Vec<f64>> = polynomial
+            .terms()
+            .iter()
+            .map(|(monomial, coefficient)| {
+                monomial
+                    .symbols()
+                    .try_fold(coefficient.to_f64().signum(), |sign, symbol| {
+                        Some(sign * factor_sign(&symbol.name(), monomial.exponent(symbol))?)
+                    })
+            })
+            .collect();
+        match signs {
+            Some(signs) if signs.iter().all(|s| *s > 0.0) => Truth::True,
+            Some(signs) if signs.iter().all(|s| *s < 0.0) => Truth::False,
+            _ => Truth::Unknown,
+        }
+    }
+
+    /// Whether a polynomial is at least zero for every value the assumptions allow: when it
+    /// is positive, or a single positive term of even powers of real variables, such as x^2y^4
+    pub fn is_nonnegative(&self, polynomial: &Polynomial) -> Truth {
+        if self.is_positive(polynomial).is_true() {
+            return Truth::True;
+        }
+        let even_powers = match polynomial.terms().iter().collect::<Vec<_>>().as_slice() {
+            [(monomial, coefficient)] => {
+                !coefficient.is_negative()
+                    && monomial.symbols().all(|symbol| {
+                        monomial.exponent(symbol).rem_euclid(2.0) == 0.0
+                            && self.ask(&symbol.name(), Property::Real).is_true()
+                    })
+            }
+            _ => false,
+        };
+        match even_powers {
+            true => Truth::True,
+            false => Truth::Unknown,
+        }
+    }
+
+    /// Whether a polynomial is real for every value the assumptions allow
+    ///
+    /// It is when each of its factors is a real variable to a power with an odd
+    /// denominator, taking odd roots to be real as for numbers, so (-8)^(1/3) is -2, or a
+    /// positive one to any power. Otherwise it is not known, as the imaginary parts may cancel.
+    pub fn is_real(&self, polynomial: &Polynomial) -> Truth {
+        let real = polynomial.terms().keys().all(|monomial| {
+            monomial.symbols().all(|symbol| {
+                let name = symbol.name();
+                let odd_root = Rational::from_f64_within(monomial.exponent(symbol), ROUNDING)
+                    .is_some_and(|e| e.denominator().is_odd());
+                self.ask(&name, Property::Positive).is_true()
+                    || (odd_root && self.ask(&name, Property::Real).is_true())
+            })
+        });
+        match real {
+            true => Truth::True,
+            false => Truth::Unknown,
+        }
+    }
+
+    /// Whether a value for a variable is consistent with what is assumed about it
+    pub fn admits(&self, name: &str, value: Rational) -> bool {
+        let violates =
+            |property: Property, holds: bool| self.ask(name, property).is_true() && !holds;
+        !(violates(Property::Positive, value > Rational::zero())
+            || violates(Property::Negative, value < Rational::zero())
+            || violates(Property::Nonzero, !value.is_zero())
+            || violates(Property::Integer, value.is_integer()))
+    }
+
+    /// Whether an irrational or complex value for a variable, approximately `value`, is
+    /// consistent with what is assumed about it
+    pub fn admits_approximately(&self, name: &str, value: Complex) -> bool {
+        let violates =
+            |property: Property, holds: bool| self.ask(name, property).is_true() && !holds;
+        let real = value.is_real();
+        !(violates(Property::Positive, real && value.re > 0.0)
+            || violates(Property::Negative, real && value.re < 0.0)
+            || violates(Property::Integer, false)
+            || violates(Property::Real, real))
+    }
+}
+
+/// What is known about e, pi, radicals, the imaginary unit and powers of positive numbers
+///
+/// Names are only looked up, so asking about a variable never adds it to the symbol table.
+fn constant(name: &str, property: Property) -> Option<Truth> {
+    let symbol = Symbol::lookup(name);
+    let positive = name == log::E
+        || name == trig::PI
+        || symbol.is_some_and(|symbol| {
+            symbol.as_radical().is_some()
+                || symbol
+                    .as_power()
+                    .is_some_and(|power| log::is_positive_base(&power.base))
+        });
+    if positive {
+        return Some(match property {
+            Property::Negative | Property::Integer => Truth::False,
+            _ => Truth::True,
+        });
+    }
+    if symbol.is_some_and(|symbol| symbol.is_imaginary()) {
+        return Some(match property {
+            Property::Nonzero => Truth::True,
+            _ => Truth::False,
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::symbol::SymbolTable;
+
+    #[test]
+    fn test_ask() {
+        let mut assumptions = Assumptions::new();
+        assumptions.assume("x", Property::Positive).unwrap();
+        assumptions.assume("n", Property::Integer).unwrap();
+
+        assert_eq!(assumptions.ask("x", Property::Positive), Truth::True);
+        assert_eq!(assumptions.ask("x", Property::Negative), Truth::False);
+        assert_eq!(assumptions.ask("x", Property::Nonzero), Truth::True);
+        assert_eq!(assumptions.ask("n", Property::Real), Truth::True);
+        assert_eq!(assumptions.ask("n", Property::Positive), Truth::Unknown);
+        assert_eq!(assumptions.ask("y", Property::Real), Truth::Unknown);
+        assert_eq!(assumptions.ask("pi", Property::Positive), Truth::True);
+        assert!(assumptions.assume("x", Property::Negative).is_err());
+
+        assumptions.forget("x");
+        assert_eq!(assumptions.ask("x", Property::Positive), Truth::Unknown);
+    }
+
+    #[test]
+    fn test_ask_is_read_only() {
+        let mut table = SymbolTable::new();
+        let assumptions = Assumptions::new();
+        table.enter(|| {
+            assert_eq!(assumptions.ask("z", Property::Real), Truth::Unknown);
+            assert_eq!(assumptions.ask("pi", Property::Positive), Truth::True);
+        });
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_is_positive() {
+        let mut assumptions = Assumptions::new();
+        assumptions.assume("x", Property::Positive).unwrap();
+        let x = Polynomial::variable("x", 1.0);
+        let y = Polynomial::variable("y", 2.0);
+
+        assert_eq!(
+            assumptions.is_positive(&(&x + &Polynomial::constant(1))),
+            Truth::True
+        );
+        assert_eq!(assumptions.is_positive(&x.scale(-2)), Truth::False);
+        assert_eq!(assumptions.is_positive(&(&x + &y)), Truth::Unknown);
+        assert!(assumptions.admits("x", Rational::from_integer(2)));
+        assert!(!assumptions.admits("x", Rational::from_integer(-2)));
+    }
+
+    #[test]
+    fn test_is_real() {
+        let mut assumptions = Assumptions::new();
+        assumptions.assume("x", Property::Real).unwrap();
+        let x = Polynomial::variable("x", 1.0);
+
+        assert_eq!(
+            assumptions.is_real(&(&x.pow(2) + &Polynomial::variable("pi", 1.0))),
+            Truth::True
+        );
+        assert_eq!(
+            assumptions.is_real(&Polynomial::variable("x", 0.5)),
+            Truth::Unknown
+        );
+        assert_eq!(
+            assumptions.is_real(&Polynomial::variable("x", 2.0 / 3.0)),
+            Truth::True
+        );
+        assert_eq!(
+            assumptions.is_real(&Polynomial::variable("y", 1.0)),
+            Truth::Unknown
+        );
+    }
+}
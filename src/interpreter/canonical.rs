@@ -0,0 +1,325 @@
+use crate::util::rational::{Rational, ROUNDING};
+
+use super::{
+    assumptions::Property,
+    monomial::Monomial,
+    monomial_order::MonomialOrder,
+    polynomial::Polynomial,
+    rational_function::RationalFunction,
+    render::{Factors, Notation},
+};
+
+// Canonical output is plain text that reads back through the lexer and parser as the
+// same value. Every product is written with '*' and every exponent other than a
+// non-negative integer is parenthesised, so neither the multiplication mode nor a
+// sign after '^' changes how it is read.
+
+/// Plain text that reads back in as the same value, e.g. `3*x^(-2) - 1`
+pub struct Canonical;
+
+impl Notation for Canonical {
+    type Piece = String;
+
+    fn write(&self, piece: String) -> String {
+        piece
+    }
+
+    fn read(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// A variable name, as it was read in
+    fn name(&self, name: &str) -> String {
+        name.to_string()
+    }
+
+    /// A number, as the simplest fraction within rounding of it, e.g. `14/3`
+    fn number(&self, n: f64) -> String {
+        match Rational::from_f64_within(n, ROUNDING) {
+            Some(r) => self.rational(&r),
+            None => binary(n),
+        }
+    }
+
+    /// A rational, e.g. `3` or `-1/2`
+    fn rational(&self, r: &Rational) -> String {
+        r.to_string()
+    }
+
+    /// A single term, coefficient then the factors of the monomial
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, order: MonomialOrder) -> String {
+        self.times(coefficient, self.factors(monomial, order))
+    }
+
+    /// Terms joined into a sum, with a negative term subtracted
+    fn sum(&self, terms: Vec<String>) -> String {
+        let mut output = String::new();
+        for (i, term) in terms.iter().enumerate() {
+            match (i, term.strip_prefix('-')) {
+                (0, _) => output.push_str(term),
+                (_, Some(negated)) => output.push_str(&format!(" - {}", negated)),
+                (_, None) => output.push_str(&format!(" + {}", term)),
+            }
+        }
+        match output.is_empty() {
+            true => "0".to_string(),
+            false => output,
+        }
+    }
+
+    fn power(&self, base: String, exponent: f64) -> String {
+        power(&base, exponent)
+    }
+
+    fn parenthesize(&self, expression: String) -> String {
+        parenthesize(&expression)
+    }
+
+    /// Factors multiplied by an exact coefficient, 1 and -1 written as just the sign
+    fn times(&self, coefficient: &Rational, factors: Vec<String>) -> String {
+        if factors.is_empty() || coefficient.is_zero() {
+            return self.rational(coefficient);
+        }
+        let product = factors.join("*");
+        if coefficient.is_one() {
+            product
+        } else if *coefficient == -1 {
+            format!("-{}", product)
+        } else if coefficient.is_integer() {
+            format!("{}*{}", coefficient, product)
+        } else {
+            // a fraction multiplies the factors as a whole, not just the denominator
+            let sign = if coefficient.is_negative() { "-" } else { "" };
+            format!("{}({})*{}", sign, coefficient.abs(), product)
+        }
+    }
+
+    fn negate(&self, piece: String) -> String {
+        format!("-{}", parenthesize(&piece))
+    }
+
+    fn over(&self, numerator: String, denominator: String) -> String {
+        quotient(&numerator, &denominator)
+    }
+
+    /// An equation `lhs = rhs`
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        format!("{} = {}", lhs, rhs)
+    }
+
+    /// Equations listed as the system they solve, e.g. `{x = 2, y = 1}`
+    ///
+    /// Free unknowns are written as nothing, since solving the assignments leaves them
+    /// free again. The branches of a solution set are already systems, so a single one is
+    /// written as it is and several as a system of them, `{{x = -1}, {x = 1}}`, which is
+    /// read back as the solutions of any one.
+    fn list(&self, elements: Vec<String>) -> String {
+        let elements: Vec<String> = elements.into_iter().filter(|e| !e.is_empty()).collect();
+        match elements.as_slice() {
+            [branch] if branch.starts_with('{') => branch.clone(),
+            _ => format!("{{{}}}", elements.join(", ")),
+        }
+    }
+
+    /// Expressions listed in braces, leaving out free unknowns
+    fn set(&self, elements: Vec<String>) -> String {
+        let elements: Vec<String> = elements.into_iter().filter(|e| !e.is_empty()).collect();
+        format!("{{{}}}", elements.join(", "))
+    }
+
+    fn text(&self, words: &str) -> String {
+        words.to_string()
+    }
+
+    /// A system with no solution, `{0 = 1}`
+    fn no_solution(&self) -> String {
+        "{0 = 1}".to_string()
+    }
+
+    /// A system every value solves, `{0 = 0}`
+    fn always_true(&self) -> String {
+        "{0 = 0}".to_string()
+    }
+
+    /// A basis as the call that computes it, e.g. `groebner({x - y = 0, y^2 - 1 = 0})`,
+    /// which gives the same reduced basis back
+    fn basis(&self, elements: Vec<String>, order: MonomialOrder) -> String {
+        let equations: Vec<String> = elements
+            .iter()
+            .map(|element| self.equation(element, "0"))
+            .collect();
+        match order {
+            MonomialOrder::Lex => format!("groebner({})", self.set(equations)),
+            _ => format!("groebner({}, {})", self.set(equations), order.name()),
+        }
+    }
+
+    /// An unknown left free in a solution, written as nothing
+    fn free(&self, _variable: &str) -> String {
+        String::new()
+    }
+
+    /// An assumption as the command that makes it, e.g. `assume x, y > 0`
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        format!("assume {} {}", names.join(", "), property)
+    }
+
+    /// Every factor of a factorisation in parentheses, e.g. `2*x*(x + 1)^2`
+    fn multiple(&self, factor: String, multiplicity: usize, _alone: bool) -> String {
+        power(&parenthesize(&factor), multiplicity as f64)
+    }
+
+    /// A rational function as a quotient, e.g. `(x + 1)/(2*x)`, or a polynomial when the
+    /// denominator is a number, so a value is written the same however it was reached
+    fn fraction(&self, value: &RationalFunction, order: MonomialOrder) -> String {
+        if let Some(denominator) = value.denominator.as_constant() {
+            return self.polynomial(&value.numerator.scale(denominator.recip()), order);
+        }
+        quotient(
+            &self.polynomial(&value.numerator, order),
+            &self.polynomial(&value.denominator, order),
+        )
+    }
+}
+
+impl Factors for Canonical {
+    fn root(&self, radicand: String, index: u32) -> String {
+        match index {
+            2 => format!("sqrt({})", radicand),
+            q => format!("root({}, {})", radicand, q),
+        }
+    }
+
+    fn function(&self, name: &str, argument: String, exponent: f64) -> String {
+        power(&format!("{}({})", name, argument), exponent)
+    }
+
+    /// A base raised to a symbolic exponent, e.g. `2^(x)`, `e^(x)` or `(x + 1)^(n)`
+    ///
+    /// A lone name stays bare, since `(e)^(x)` would be read as a group to a power rather
+    /// than as exp(x).
+    fn raise(&self, base: &Polynomial, written: String, exponent: String) -> String {
+        let bare = match base.as_constant() {
+            Some(c) => !c.is_negative() && c.is_integer(),
+            None => is_name(&written),
+        };
+        let written = match bare {
+            true => written,
+            false => parenthesize(&written),
+        };
+        format!("{}^{}", written, parenthesize(&exponent))
+    }
+}
+
+/// A base to a numeric power, bare for a non-negative integer and parenthesised
+/// otherwise, e.g. `x^2`, `x^(-2)` or `x^(1/2)`
+///
+/// The exponent is written exactly, since a power read back to within rounding is a
+/// different monomial.
+fn power(base: &str, exponent: f64) -> String {
+    match exponent {
+        1.0 => base.to_string(),
+        e if e >= 0.0 && e.fract() == 0.0 => format!("{}^{}", base, e),
+        e => match Rational::from_f64_within(e, 0.0) {
+            Some(r) => format!("{}^({})", base, r),
+            None => format!("{}^({})", base, binary(e)),
+        },
+    }
+}
+
+/// A numerator over a denominator, each parenthesised unless it is a lone integer or name
+fn quotient(numerator: &str, denominator: &str) -> String {
+    let operand = |side: &str| match is_name(side.strip_prefix('-').unwrap_or(side)) {
+        true => side.to_string(),
+        false => parenthesize(side),
+    };
+    format!("{}/{}", operand(numerator), operand(denominator))
+}
+
+/// A float as the odd whole number times a power of two it holds exactly, e.g.
+/// `3*2^(-60)` or `2^128`
+fn binary(n: f64) -> String {
+    if !n.is_finite() {
+        return n.to_string();
+    }
+    let (mut whole, mut exponent) = (n, 0);
+    while whole.fract() != 0.0 {
+        whole *= 2.0;
+        exponent -= 1;
+    }
+    while whole != 0.0 && (whole / 2.0).fract() == 0.0 {
+        whole /= 2.0;
+        exponent += 1;
+    }
+    let two = power("2", exponent as f64);
+    match (exponent, whole) {
+        (0, _) => format!("{:.0}", whole),
+        (_, 1.0) => two,
+        (_, -1.0) => format!("-{}", two),
+        _ => format!("{:.0}*{}", whole, two),
+    }
+}
+
+/// Whether the text is a single integer or name, which reads the same anywhere
+fn is_name(written: &str) -> bool {
+    written.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parenthesize(expression: &str) -> String {
+    format!("({})", expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polynomial() {
+        let x = Polynomial::variable("x", 1.0);
+        let p = &(&x.pow(2).scale(2) - &x.scale(3)) - &Polynomial::constant(1);
+        assert_eq!(
+            Canonical.polynomial(&p, MonomialOrder::Lex),
+            "2*x^2 - 3*x - 1"
+        );
+        assert_eq!(
+            Canonical.polynomial(&Polynomial::variable("x", 25.0 / 3.0), MonomialOrder::Lex),
+            "x^(4691249611844267*2^(-49))"
+        );
+        assert_eq!(
+            Canonical.polynomial(
+                &Polynomial::variable("x", -2.0).scale(3),
+                MonomialOrder::Lex
+            ),
+            "3*x^(-2)"
+        );
+        assert_eq!(
+            Canonical.polynomial(
+                &Polynomial::variable("x", 0.5).scale(Rational::new(-1, 2)),
+                MonomialOrder::Lex
+            ),
+            "-(1/2)*x^(1/2)"
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(Canonical.number(0.75), "3/4");
+        assert_eq!(Canonical.number(0.1), "1/10");
+        assert_eq!(Canonical.number(3.0 + 5.0 / 3.0), "14/3");
+        assert_eq!(
+            Canonical.number(1.0 / 3.0 + 1e-14),
+            "6004799503160841*2^(-54)"
+        );
+        assert_eq!(Canonical.number(2f64.powi(128)), "2^128");
+        assert_eq!(Canonical.number(-3.0 * 2f64.powi(60)), "-3*2^60");
+        assert_eq!(Canonical.number(2f64.powi(-128)), "2^(-128)");
+        assert_eq!(Canonical.number(-3.0 * 2f64.powi(-130)), "-3*2^(-130)");
+    }
+
+    #[test]
+    fn test_quotient() {
+        assert_eq!(quotient("x + 1", "2*x"), "(x + 1)/(2*x)");
+        assert_eq!(quotient("-1", "x"), "-1/x");
+        assert_eq!(quotient("-x - 1", "2"), "(-x - 1)/2");
+    }
+}
@@ -0,0 +1,327 @@
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property,
+    log,
+    mathml::{escape, math},
+    monomial::Monomial,
+    monomial_order::MonomialOrder,
+    parser::ASTNode,
+    polynomial::Polynomial,
+    render::{self, Factors, Notation},
+    symbol::Symbol,
+    trig,
+};
+
+/// Functions with a content MathML element of their own, by the name they are written with
+const ELEMENTS: [(&str, &str); 15] = [
+    ("sin", "sin"),
+    ("cos", "cos"),
+    ("tan", "tan"),
+    ("csc", "csc"),
+    ("sec", "sec"),
+    ("cot", "cot"),
+    ("asin", "arcsin"),
+    ("acos", "arccos"),
+    ("atan", "arctan"),
+    ("ln", "ln"),
+    ("log", "log"),
+    ("exp", "exp"),
+    ("abs", "abs"),
+    ("conj", "conjugate"),
+    ("arg", "arg"),
+];
+
+/// Content MathML, the operations a result is made of, for interchange
+pub struct ContentMathML;
+
+impl Notation for ContentMathML {
+    type Piece = String;
+
+    fn write(&self, piece: String) -> String {
+        piece
+    }
+
+    fn read(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// A variable, with pi, e and the imaginary unit as the constants they stand for
+    fn name(&self, name: &str) -> String {
+        if name == trig::PI {
+            "<pi/>".to_string()
+        } else if name == log::E {
+            "<exponentiale/>".to_string()
+        } else if Symbol::imaginary_unit().is_some_and(|unit| unit == name) {
+            "<imaginaryi/>".to_string()
+        } else {
+            format!("<ci>{}</ci>", escape(name))
+        }
+    }
+
+    /// A number, as a rational when it has a small denominator
+    fn number(&self, n: f64) -> String {
+        match Rational::from_f64(n) {
+            Some(r) if render::is_small(&r) => self.rational(&r),
+            _ => format!("<cn type=\"real\">{}</cn>", n),
+        }
+    }
+
+    /// A rational, e.g. `<cn type="integer">3</cn>` or `<cn type="rational">1<sep/>2</cn>`
+    fn rational(&self, r: &Rational) -> String {
+        if r.is_integer() {
+            format!("<cn type=\"integer\">{}</cn>", r.numerator())
+        } else {
+            format!(
+                "<cn type=\"rational\">{}<sep/>{}</cn>",
+                r.numerator(),
+                r.denominator()
+            )
+        }
+    }
+
+    /// A single term as a product, with the coefficient 1 left out and -1 as a negation
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, order: MonomialOrder) -> String {
+        let factors = self.factors(monomial, order);
+        match coefficient {
+            c if factors.is_empty() => self.rational(c),
+            c if c.is_one() => times(factors),
+            c if *c == -1 => apply("<minus/>", &[times(factors)]),
+            c => times([vec![self.rational(c)], factors].concat()),
+        }
+    }
+
+    /// Terms joined into a sum
+    fn sum(&self, terms: Vec<String>) -> String {
+        match terms.len() {
+            0 => self.rational(&Rational::zero()),
+            1 => terms.into_iter().next().unwrap(),
+            _ => apply("<plus/>", &terms),
+        }
+    }
+
+    /// A numeric power of a base, left off when it is 1
+    fn power(&self, base: String, exponent: f64) -> String {
+        match exponent {
+            1.0 => base,
+            e => apply("<power/>", &[base, self.number(e)]),
+        }
+    }
+
+    /// A base as it is, as an operation's arguments need no grouping
+    fn parenthesize(&self, piece: String) -> String {
+        piece
+    }
+
+    /// A product of powers, with the coefficient 1 left out
+    fn times(&self, coefficient: &Rational, factors: Vec<String>) -> String {
+        match coefficient.is_one() && !factors.is_empty() {
+            true => times(factors),
+            false => times([vec![self.rational(coefficient)], factors].concat()),
+        }
+    }
+
+    fn negate(&self, piece: String) -> String {
+        apply("<minus/>", &[piece])
+    }
+
+    fn over(&self, numerator: String, denominator: String) -> String {
+        apply("<divide/>", &[numerator, denominator])
+    }
+
+    /// An equation `lhs = rhs`
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        apply("<eq/>", &[lhs.to_string(), rhs.to_string()])
+    }
+
+    /// Several results, such as the assignments of a solution
+    fn list(&self, elements: Vec<String>) -> String {
+        format!("<list>{}</list>", elements.concat())
+    }
+
+    /// Expressions listed as a set
+    fn set(&self, elements: Vec<String>) -> String {
+        format!("<set>{}</set>", elements.concat())
+    }
+
+    /// A phrase standing in for a result, No solution as the empty set and Always true as true
+    fn text(&self, words: &str) -> String {
+        match words {
+            "No solution" => "<emptyset/>".to_string(),
+            "Always true" => "<true/>".to_string(),
+            words => format!("<csymbol>{}</csymbol>", escape(words)),
+        }
+    }
+
+    /// An unknown left free in a solution
+    fn free(&self, variable: &str) -> String {
+        apply("<csymbol>free</csymbol>", &[self.name(variable)])
+    }
+
+    /// An assumption as a relation for each variable, all of them holding together
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        let zero = || self.rational(&Rational::zero());
+        let relations: Vec<String> = names
+            .iter()
+            .map(|n| {
+                let (relation, set) = match property {
+                    Property::Positive => ("<gt/>", zero()),
+                    Property::Negative => ("<lt/>", zero()),
+                    Property::Nonzero => ("<neq/>", zero()),
+                    Property::Integer => ("<in/>", "<integers/>".to_string()),
+                    Property::Real => ("<in/>", "<reals/>".to_string()),
+                };
+                apply(relation, &[self.name(n), set])
+            })
+            .collect();
+        match relations.len() {
+            1 => relations.into_iter().next().unwrap(),
+            _ => apply("<and/>", &relations),
+        }
+    }
+
+    fn finish(&self, output: String) -> String {
+        match output.is_empty() {
+            true => output,
+            false => math(&output),
+        }
+    }
+}
+
+impl Factors for ContentMathML {
+    const ROOTS: bool = true;
+
+    /// The root of a number, the square root when the index is 2
+    fn root(&self, radicand: String, index: u32) -> String {
+        match index {
+            2 => apply("<root/>", &[radicand]),
+            q => apply(
+                "<root/>",
+                &[
+                    format!(
+                        "<degree>{}</degree>",
+                        self.rational(&Rational::from(q as i128))
+                    ),
+                    radicand,
+                ],
+            ),
+        }
+    }
+
+    fn function(&self, name: &str, argument: String, exponent: f64) -> String {
+        self.power(applied(name, &[argument]), exponent)
+    }
+
+    fn raise(&self, _: &Polynomial, written: String, exponent: String) -> String {
+        apply("<power/>", &[written, exponent])
+    }
+}
+
+/// An expression tree as the operations it applies
+pub fn ast(node: &ASTNode) -> String {
+    match node {
+        ASTNode::Number(n) => ContentMathML.number(*n),
+        ASTNode::Variable(variable, exponent) => match exponent.as_ref() {
+            ASTNode::Number(e) if *e == 1.0 => ContentMathML.name(variable),
+            exponent => apply("<power/>", &[ContentMathML.name(variable), ast(exponent)]),
+        },
+        ASTNode::Operation(operator, lhs, rhs) => {
+            let operator = match operator.as_str() {
+                "/" => "<divide/>",
+                "*" => "<times/>",
+                _ => "<power/>",
+            };
+            apply(operator, &[ast(lhs), ast(rhs)])
+        }
+        ASTNode::Function(function, arguments) => call(function, arguments),
+        ASTNode::Equation(lhs, rhs) => ContentMathML.equation(&ast(lhs), &ast(rhs)),
+        ASTNode::System(equations) => {
+            apply("<and/>", &equations.iter().map(ast).collect::<Vec<_>>())
+        }
+        ASTNode::Expression(terms) => ContentMathML.sum(terms.iter().map(ast).collect()),
+        ASTNode::Term(coefficient, factors) => {
+            let factors: Vec<String> = factors.iter().map(ast).collect();
+            match coefficient.as_ref() {
+                ASTNode::Number(c) if *c == 1.0 && !factors.is_empty() => times(factors),
+                ASTNode::Number(c) if *c == -1.0 && !factors.is_empty() => {
+                    apply("<minus/>", &[times(factors)])
+                }
+                coefficient => times([vec![ast(coefficient)], factors].concat()),
+            }
+        }
+        ASTNode::Assumption(names, property) => ContentMathML.assumption(names, *property),
+    }
+}
+
+/// A function call from an expression tree
+fn call(function: &str, arguments: &[ASTNode]) -> String {
+    let arguments: Vec<String> = arguments.iter().map(ast).collect();
+    match (function, arguments.as_slice()) {
+        ("sqrt", [argument]) => apply("<root/>", std::slice::from_ref(argument)),
+        ("root", [argument, index]) => apply(
+            "<root/>",
+            &[format!("<degree>{}</degree>", index), argument.clone()],
+        ),
+        ("pow", [base, exponent]) => apply("<power/>", &[base.clone(), exponent.clone()]),
+        ("log", [argument, base]) => apply(
+            "<log/>",
+            &[format!("<logbase>{}</logbase>", base), argument.clone()],
+        ),
+        ("log10", [argument]) => apply(
+            "<log/>",
+            &[
+                format!(
+                    "<logbase>{}</logbase>",
+                    ContentMathML.rational(&Rational::from(10))
+                ),
+                argument.clone(),
+            ],
+        ),
+        (function, arguments) => applied(function, arguments),
+    }
+}
+
+/// A function applied to its arguments, as its own element where content MathML has one
+fn applied(function: &str, arguments: &[String]) -> String {
+    match ELEMENTS.iter().find(|(name, _)| *name == function) {
+        Some((_, element)) => apply(&format!("<{}/>", element), arguments),
+        None => apply(&format!("<ci>{}</ci>", escape(function)), arguments),
+    }
+}
+
+/// Factors multiplied together, a single factor standing alone
+fn times(factors: Vec<String>) -> String {
+    match factors.len() {
+        1 => factors.into_iter().next().unwrap(),
+        _ => apply("<times/>", &factors),
+    }
+}
+
+fn apply(operator: &str, arguments: &[String]) -> String {
+    format!("<apply>{}{}</apply>", operator, arguments.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polynomial() {
+        let x = Polynomial::variable("x", 1.0);
+        let p = &x.pow(2).scale(3) - &Polynomial::constant(Rational::new(1, 2));
+        assert_eq!(
+            ContentMathML.polynomial(&p, MonomialOrder::Lex),
+            "<apply><plus/><apply><times/><cn type=\"integer\">3</cn>\
+             <apply><power/><ci>x</ci><cn type=\"integer\">2</cn></apply></apply>\
+             <cn type=\"rational\">-1<sep/>2</cn></apply>"
+        );
+        assert_eq!(
+            ContentMathML.polynomial(&x.scale(-1), MonomialOrder::Lex),
+            "<apply><minus/><ci>x</ci></apply>"
+        );
+        assert_eq!(
+            ContentMathML.polynomial(&Polynomial::variable("pi", 1.0), MonomialOrder::Lex),
+            "<pi/>"
+        );
+    }
+}
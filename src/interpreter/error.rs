@@ -0,0 +1,269 @@
+use serde::Serialize;
+
+/// Where in the input something is, as character offsets with the end excluded
+///
+/// Written to JSON as `[start, end]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(into = "(usize, usize)")]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// The empty span just past the last character, where a missing token would be
+    pub fn end_of(len: usize) -> Span {
+        Span::new(len, len)
+    }
+}
+
+impl From<Span> for (usize, usize) {
+    fn from(span: Span) -> (usize, usize) {
+        (span.start, span.end)
+    }
+}
+
+/// Why an input could not be read or evaluated
+///
+/// Each kind of error has a stable code, given by [`InterpreterError::code`], and the
+/// span of the input at fault where there is one. Errors raised while evaluating span the
+/// whole input, since the expression tree does not keep where each part was written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpreterError {
+    /// a character the lexer does not read, such as `$` or `;`
+    UnexpectedCharacter {
+        character: char,
+        span: Option<Span>,
+    },
+    /// a LaTeX command the LaTeX lexer does not read, such as `\infty`
+    UnknownCommand {
+        command: String,
+        span: Option<Span>,
+    },
+    /// a token where the parser expected something else
+    UnexpectedToken {
+        message: String,
+        span: Option<Span>,
+    },
+    /// a parenthesis or brace left open, or one closed that was never opened
+    UnbalancedParenthesis {
+        message: String,
+        span: Option<Span>,
+    },
+    /// an operator without the operand it applies to, such as `x/` or `x^`
+    MissingOperand {
+        message: String,
+        span: Option<Span>,
+    },
+    DivisionByZero {
+        span: Option<Span>,
+    },
+    /// a function outside where it is defined, such as ln(0) or acos(2)
+    DomainError {
+        message: String,
+        span: Option<Span>,
+    },
+    /// a number or computation too large to carry out exactly
+    Overflow {
+        message: String,
+        span: Option<Span>,
+    },
+    /// evaluation took longer than the interpreter's time limit
+    Timeout {
+        span: Option<Span>,
+    },
+    /// a well-formed input the interpreter cannot evaluate, such as an unknown function
+    /// or an equation that is not linear
+    Unsupported {
+        message: String,
+        span: Option<Span>,
+    },
+}
+
+impl InterpreterError {
+    pub(crate) fn unexpected_token(message: impl Into<String>) -> InterpreterError {
+        InterpreterError::UnexpectedToken {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn unbalanced(message: impl Into<String>) -> InterpreterError {
+        InterpreterError::UnbalancedParenthesis {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn missing_operand(message: impl Into<String>) -> InterpreterError {
+        InterpreterError::MissingOperand {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn division_by_zero() -> InterpreterError {
+        InterpreterError::DivisionByZero { span: None }
+    }
+
+    pub(crate) fn domain(message: impl Into<String>) -> InterpreterError {
+        InterpreterError::DomainError {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn overflow(message: impl Into<String>) -> InterpreterError {
+        InterpreterError::Overflow {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub(crate) fn unsupported(message: impl Into<String>) -> InterpreterError {
+        InterpreterError::Unsupported {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// The same error at the given place in the input
+    pub fn at(mut self, place: Option<Span>) -> InterpreterError {
+        match &mut self {
+            InterpreterError::UnexpectedCharacter { span, .. }
+            | InterpreterError::UnknownCommand { span, .. }
+            | InterpreterError::UnexpectedToken { span, .. }
+            | InterpreterError::UnbalancedParenthesis { span, .. }
+            | InterpreterError::MissingOperand { span, .. }
+            | InterpreterError::DivisionByZero { span }
+            | InterpreterError::DomainError { span, .. }
+            | InterpreterError::Overflow { span, .. }
+            | InterpreterError::Timeout { span }
+            | InterpreterError::Unsupported { span, .. } => *span = place,
+        }
+        self
+    }
+
+    /// The same error, placed at `place` when it has no place of its own yet
+    pub fn within(self, place: Option<Span>) -> InterpreterError {
+        match self.span() {
+            Some(_) => self,
+            None => self.at(place),
+        }
+    }
+
+    /// The stable code of the kind of error, e.g. `E202` for an unbalanced parenthesis
+    ///
+    /// The hundreds give the family: 1 lexing, 2 parsing, 3 mathematics, 4 limits and
+    /// 5 anything else. Codes are never reused for another kind.
+    pub fn code(&self) -> &'static str {
+        match self {
+            InterpreterError::UnexpectedCharacter { .. } => "E101",
+            InterpreterError::UnknownCommand { .. } => "E102",
+            InterpreterError::UnexpectedToken { .. } => "E201",
+            InterpreterError::UnbalancedParenthesis { .. } => "E202",
+            InterpreterError::MissingOperand { .. } => "E203",
+            InterpreterError::DivisionByZero { .. } => "E301",
+            InterpreterError::DomainError { .. } => "E302",
+            InterpreterError::Overflow { .. } => "E401",
+            InterpreterError::Timeout { .. } => "E402",
+            InterpreterError::Unsupported { .. } => "E501",
+        }
+    }
+
+    /// The kind of error in words, e.g. `unbalanced_parenthesis`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InterpreterError::UnexpectedCharacter { .. } => "unexpected_character",
+            InterpreterError::UnknownCommand { .. } => "unknown_command",
+            InterpreterError::UnexpectedToken { .. } => "unexpected_token",
+            InterpreterError::UnbalancedParenthesis { .. } => "unbalanced_parenthesis",
+            InterpreterError::MissingOperand { .. } => "missing_operand",
+            InterpreterError::DivisionByZero { .. } => "division_by_zero",
+            InterpreterError::DomainError { .. } => "domain_error",
+            InterpreterError::Overflow { .. } => "overflow",
+            InterpreterError::Timeout { .. } => "timeout",
+            InterpreterError::Unsupported { .. } => "unsupported",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            InterpreterError::UnexpectedCharacter { character, .. } => {
+                format!("Unexpected character '{}'", character)
+            }
+            InterpreterError::UnknownCommand { command, .. } => {
+                format!("Unknown LaTeX command \\{}", command)
+            }
+            InterpreterError::DivisionByZero { .. } => "Division by zero".to_string(),
+            InterpreterError::Timeout { .. } => "Evaluation took too long".to_string(),
+            InterpreterError::UnexpectedToken { message, .. }
+            | InterpreterError::UnbalancedParenthesis { message, .. }
+            | InterpreterError::MissingOperand { message, .. }
+            | InterpreterError::DomainError { message, .. }
+            | InterpreterError::Overflow { message, .. }
+            | InterpreterError::Unsupported { message, .. } => message.clone(),
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            InterpreterError::UnexpectedCharacter { span, .. }
+            | InterpreterError::UnknownCommand { span, .. }
+            | InterpreterError::UnexpectedToken { span, .. }
+            | InterpreterError::UnbalancedParenthesis { span, .. }
+            | InterpreterError::MissingOperand { span, .. }
+            | InterpreterError::DivisionByZero { span }
+            | InterpreterError::DomainError { span, .. }
+            | InterpreterError::Overflow { span, .. }
+            | InterpreterError::Timeout { span }
+            | InterpreterError::Unsupported { span, .. } => *span,
+        }
+    }
+}
+
+/// Written to JSON as `{"code", "kind", "message", "span"}`
+impl Serialize for InterpreterError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut error = serializer.serialize_struct("InterpreterError", 4)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.message())?;
+        error.serialize_field("span", &self.span())?;
+        error.end()
+    }
+}
+
+impl std::fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InterpreterError: {}", self.message())
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let error = InterpreterError::unbalanced("Expected ')' to close the group")
+            .at(Some(Span::end_of(2)));
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            "{\"code\":\"E202\",\"kind\":\"unbalanced_parenthesis\",\
+             \"message\":\"Expected ')' to close the group\",\"span\":[2,2]}"
+        );
+        assert_eq!(
+            serde_json::to_string(&InterpreterError::division_by_zero()).unwrap(),
+            "{\"code\":\"E301\",\"kind\":\"division_by_zero\",\
+             \"message\":\"Division by zero\",\"span\":null}"
+        );
+    }
+}
@@ -0,0 +1,1230 @@
+use std::collections::{BTreeMap, HashSet};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::util::rational::Rational;
+
+use super::{
+    linear_system, monomial::Monomial, polynomial::Polynomial, univariate::UnivariatePolynomial,
+    InterpreterError,
+};
+
+/// Primes tried for the modular factorisation step
+const PRIMES: [i128; 24] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+/// Number of suitable primes to try before settling on the one giving the fewest factors
+const PRIME_CANDIDATES: usize = 3;
+
+/// Largest modulus for the modular greatest common divisor, small enough that products
+/// of residues fit an i128
+const GCD_MODULUS: i128 = 1 << 31;
+
+/// Sparse multivariate integer polynomial, keyed by exponent vectors over a fixed
+/// variable list. The BTreeMap ordering makes the last entry the lex-leading term.
+type IntegerPolynomial = BTreeMap<Vec<u32>, BigInt>;
+
+/// A polynomial written as a product of irreducible factors over the integers
+#[derive(Debug, Clone)]
+pub struct Factorization {
+    /// rational content, carrying the overall sign
+    pub coefficient: Rational,
+    /// variables pulled out as a monomial, with their (possibly negative) powers
+    pub monomial: Vec<(String, i64)>,
+    /// irreducible primitive factors with their multiplicities
+    pub factors: Vec<(Polynomial, usize)>,
+}
+
+/// Factor a polynomial into irreducible factors over the integers
+///
+/// The rational content and any monomial factor are pulled out first, then the
+/// primitive part is split with a square-free decomposition followed by
+/// Berlekamp–Zassenhaus for univariate polynomials, or Kronecker substitution
+/// for multivariate ones.
+pub fn factor(expression: &Polynomial) -> Result<Factorization, InterpreterError> {
+    split(expression, factor_primitive)
+}
+
+/// Factor a polynomial only by the given irreducible factors, each divided out as often
+/// as it goes, with the rest of the primitive part left whole as one more factor
+///
+/// Cheaper than [`factor`] when only the factors shared with another polynomial matter.
+pub fn factor_by(
+    expression: &Polynomial,
+    factors: &[(Polynomial, usize)],
+) -> Result<Factorization, InterpreterError> {
+    let mut factorization = split(expression, |primitive, _| {
+        Ok(match primitive.keys().any(|e| e.iter().any(|d| *d > 0)) {
+            true => vec![(primitive.clone(), 1)],
+            false => Vec::new(),
+        })
+    })?;
+    let Some((mut rest, _)) = factorization.factors.pop() else {
+        return Ok(factorization);
+    };
+    for (f, _) in factors {
+        let mut multiplicity = 0;
+        while let Some(quotient) = divide(&rest, f) {
+            rest = quotient;
+            multiplicity += 1;
+        }
+        if multiplicity > 0 {
+            factorization.factors.push((f.clone(), multiplicity));
+        }
+    }
+    match rest.as_constant() {
+        Some(c) => factorization.coefficient = &factorization.coefficient * &c,
+        None => factorization.factors.push((rest, 1)),
+    }
+    Ok(factorization)
+}
+
+/// Pull out the rational content and monomial factor, then split the primitive part
+/// with `factor_primitive`
+fn split(
+    expression: &Polynomial,
+    factor_primitive: impl Fn(
+        &IntegerPolynomial,
+        usize,
+    ) -> Result<Vec<(IntegerPolynomial, usize)>, InterpreterError>,
+) -> Result<Factorization, InterpreterError> {
+    // constants such as sqrt(2) and pi go last, so the lex-leading term that fixes the
+    // sign of each factor is led by a true variable
+    let mut variables: Vec<String> = expression.variables().into_iter().collect();
+    variables.sort_by_key(|name| linear_system::is_constant(name));
+
+    let mut terms: BTreeMap<Vec<i64>, Rational> = BTreeMap::new();
+    for (monomial, coefficient) in expression.terms().iter() {
+        let mut exponents = vec![0i64; variables.len()];
+        for (name, exponent) in monomial.powers() {
+            if exponent.0.fract() != 0.0 || !exponent.0.is_finite() {
+                return Err(InterpreterError::unsupported(format!(
+                    "Cannot factor non-integer exponent {}^{}",
+                    name, exponent
+                )));
+            }
+            let index = variables
+                .iter()
+                .position(|v| *v == name)
+                .expect("variables are collected from the polynomial's own monomials");
+            exponents[index] = exponent.0 as i64;
+        }
+        *terms.entry(exponents).or_default() += coefficient;
+    }
+    terms.retain(|_, c| !c.is_zero());
+
+    if terms.is_empty() {
+        return Ok(Factorization {
+            coefficient: Rational::zero(),
+            monomial: Vec::new(),
+            factors: Vec::new(),
+        });
+    }
+
+    // monomial content, which may carry negative powers
+    let lowest: Vec<i64> = (0..variables.len())
+        .map(|i| terms.keys().map(|e| e[i]).min().unwrap())
+        .collect();
+    let mut monomial: Vec<(String, i64)> = variables
+        .iter()
+        .zip(lowest.iter())
+        .filter(|(_, e)| **e != 0)
+        .map(|(name, e)| (name.clone(), *e))
+        .collect();
+    monomial.sort();
+
+    // rational content, with the sign chosen so the lex-leading coefficient is positive
+    let denominator = terms
+        .values()
+        .fold(BigInt::one(), |acc, c| acc.lcm(c.denominator()));
+    let integer = |c: &Rational| c.numerator() * (&denominator / c.denominator());
+    let mut divisor = terms
+        .values()
+        .fold(BigInt::zero(), |acc, c| acc.gcd(&integer(c)));
+    if terms.values().last().unwrap().is_negative() {
+        divisor = -divisor;
+    }
+
+    let primitive: IntegerPolynomial = terms
+        .iter()
+        .map(|(exponents, c)| {
+            (
+                exponents
+                    .iter()
+                    .zip(lowest.iter())
+                    .map(|(e, low)| (e - low) as u32)
+                    .collect(),
+                integer(c) / &divisor,
+            )
+        })
+        .collect();
+    let coefficient = Rational::from_parts(divisor, denominator).unwrap_or_default();
+
+    let mut factors = factor_primitive(&primitive, variables.len())?;
+    factors.sort_by(|(a, _), (b, _)| {
+        total_degree(a)
+            .cmp(&total_degree(b))
+            .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    });
+
+    Ok(Factorization {
+        coefficient,
+        monomial,
+        factors: factors
+            .iter()
+            .map(|(f, multiplicity)| (to_parsed_expression(f, &variables), *multiplicity))
+            .collect(),
+    })
+}
+
+fn total_degree(polynomial: &IntegerPolynomial) -> u32 {
+    polynomial
+        .keys()
+        .map(|e| e.iter().sum::<u32>())
+        .max()
+        .unwrap_or(0)
+}
+
+fn to_parsed_expression(polynomial: &IntegerPolynomial, variables: &[String]) -> Polynomial {
+    let mut expression = Polynomial::new();
+    for (exponents, c) in polynomial.iter() {
+        let monomial = Monomial::new(
+            variables
+                .iter()
+                .zip(exponents.iter())
+                .map(|(name, e)| (name, *e as f64)),
+        );
+        expression.add_term(monomial, Rational::from(c.clone()));
+    }
+    expression
+}
+
+/// Factor a primitive integer polynomial with no monomial content
+fn factor_primitive(
+    polynomial: &IntegerPolynomial,
+    variable_count: usize,
+) -> Result<Vec<(IntegerPolynomial, usize)>, InterpreterError> {
+    let present: Vec<usize> = (0..variable_count)
+        .filter(|&i| polynomial.keys().any(|e| e[i] > 0))
+        .collect();
+
+    let factors = match present.len() {
+        0 => Vec::new(),
+        1 => {
+            let variable = present[0];
+            let degree = polynomial.keys().map(|e| e[variable]).max().unwrap() as usize;
+            let mut dense = vec![BigInt::zero(); degree + 1];
+            for (exponents, c) in polynomial.iter() {
+                dense[exponents[variable] as usize] = c.clone();
+            }
+            factor_univariate(&dense)?
+                .into_iter()
+                .map(|(f, multiplicity)| {
+                    let mut sparse = IntegerPolynomial::new();
+                    for (degree, c) in f.into_iter().enumerate() {
+                        if !c.is_zero() {
+                            let mut exponents = vec![0; variable_count];
+                            exponents[variable] = degree as u32;
+                            sparse.insert(exponents, c);
+                        }
+                    }
+                    (sparse, multiplicity)
+                })
+                .collect()
+        }
+        _ => factor_kronecker(polynomial, variable_count)?,
+    };
+
+    // merge repeated factors into multiplicities
+    let mut merged: Vec<(IntegerPolynomial, usize)> = Vec::new();
+    for (f, multiplicity) in factors {
+        match merged.iter_mut().find(|(g, _)| *g == f) {
+            Some((_, m)) => *m += multiplicity,
+            None => merged.push((f, multiplicity)),
+        }
+    }
+    Ok(merged)
+}
+
+/// Multivariate factorisation by Kronecker substitution
+///
+/// Maps `x_i -> t^(d^i)` with `d` above every partial degree, factors the univariate
+/// image, and trial-divides by the preimages of products of its factors.
+fn factor_kronecker(
+    polynomial: &IntegerPolynomial,
+    variable_count: usize,
+) -> Result<Vec<(IntegerPolynomial, usize)>, InterpreterError> {
+    // variables absent from this polynomial take no place, since each one would
+    // multiply the image degree by the base for nothing
+    let present: Vec<usize> = (0..variable_count)
+        .filter(|&i| polynomial.keys().any(|e| e[i] > 0))
+        .collect();
+    let base = present
+        .iter()
+        .map(|&i| polynomial.keys().map(|e| e[i]).max().unwrap())
+        .max()
+        .unwrap() as u64
+        + 1;
+    let too_large = || InterpreterError::overflow("Polynomial too large to factor".to_string());
+
+    let substitute = |exponents: &Vec<u32>| -> Option<usize> {
+        let mut degree = 0u64;
+        let mut place = 1u64;
+        for &i in present.iter() {
+            degree = degree.checked_add((exponents[i] as u64).checked_mul(place)?)?;
+            place = place.checked_mul(base)?;
+        }
+        usize::try_from(degree).ok().filter(|d| *d < 4096)
+    };
+    let invert = |dense: &[BigInt]| -> IntegerPolynomial {
+        let mut sparse = IntegerPolynomial::new();
+        for (degree, c) in dense.iter().enumerate() {
+            if c.is_zero() {
+                continue;
+            }
+            let mut exponents = vec![0; variable_count];
+            let mut rest = degree as u64;
+            for &i in present.iter() {
+                exponents[i] = (rest % base) as u32;
+                rest /= base;
+            }
+            sparse.insert(exponents, c.clone());
+        }
+        sparse
+    };
+
+    let mut image = Vec::new();
+    for (exponents, c) in polynomial.iter() {
+        let degree = substitute(exponents).ok_or_else(too_large)?;
+        if image.len() <= degree {
+            image.resize(degree + 1, BigInt::zero());
+        }
+        image[degree] = c.clone();
+    }
+    if image.last().unwrap().is_negative() {
+        image = integer_subtract(&[], &image);
+    }
+
+    // powers of t are kept apart rather than as pieces of their own, since every
+    // candidate takes some number of them and trying each subset of copies of t
+    // grows exponentially, e.g. a x^2 - b y has t^19 (t^11 - 1) as its image
+    let mut shift = 0;
+    let mut pieces: Vec<Vec<BigInt>> = Vec::new();
+    for (f, multiplicity) in factor_univariate(&image)? {
+        if f == [BigInt::zero(), BigInt::one()] {
+            shift = multiplicity;
+            continue;
+        }
+        for _ in 0..multiplicity {
+            pieces.push(f.clone());
+        }
+    }
+
+    let mut remaining = polynomial.clone();
+    let mut factors = Vec::new();
+    let mut size = 1;
+    while 2 * size <= pieces.len() {
+        let mut found = None;
+        // repeated pieces give the same product from many subsets, tried only once
+        let mut tried = HashSet::new();
+        'search: for subset in Combinations::new(pieces.len(), size) {
+            let product = subset.iter().fold(vec![BigInt::one()], |acc, &i| {
+                integer_multiply(&acc, &pieces[i])
+            });
+            if !tried.insert(product.clone()) {
+                continue;
+            }
+            for power in 0..=shift {
+                let shifted: Vec<BigInt> = std::iter::repeat_n(BigInt::zero(), power)
+                    .chain(product.iter().cloned())
+                    .collect();
+                let candidate = normalize_sign(invert(&shifted));
+                if let Some(quotient) = divide_multivariate(&remaining, &candidate) {
+                    found = Some((subset, power, candidate, quotient));
+                    break 'search;
+                }
+            }
+        }
+        match found {
+            Some((subset, power, candidate, quotient)) => {
+                factors.push((candidate, 1));
+                remaining = quotient;
+                shift -= power;
+                for i in subset.iter().rev() {
+                    pieces.remove(*i);
+                }
+            }
+            None => size += 1,
+        }
+    }
+    if remaining.keys().any(|e| e.iter().any(|d| *d > 0)) {
+        factors.push((normalize_sign(remaining), 1));
+    }
+    Ok(factors)
+}
+
+fn normalize_sign(mut polynomial: IntegerPolynomial) -> IntegerPolynomial {
+    if polynomial.values().last().is_some_and(|c| c.is_negative()) {
+        polynomial.values_mut().for_each(|c| *c = -&*c);
+    }
+    polynomial
+}
+
+/// Exact division of polynomials with integer coefficients and powers, if the divisor
+/// divides evenly
+fn divide(dividend: &Polynomial, divisor: &Polynomial) -> Option<Polynomial> {
+    let variables: Vec<String> = dividend
+        .variables()
+        .union(&divisor.variables())
+        .cloned()
+        .collect();
+    let quotient = divide_multivariate(
+        &integer_polynomial(dividend, &variables)?,
+        &integer_polynomial(divisor, &variables)?,
+    )?;
+    Some(to_parsed_expression(&quotient, &variables))
+}
+
+/// A polynomial over the given variables, if its coefficients are integers and its powers
+/// whole and non-negative
+fn integer_polynomial(polynomial: &Polynomial, variables: &[String]) -> Option<IntegerPolynomial> {
+    polynomial
+        .terms()
+        .iter()
+        .map(|(monomial, c)| {
+            let mut exponents = vec![0; variables.len()];
+            for (name, e) in monomial.powers() {
+                if e.0 < 0.0 || e.0.fract() != 0.0 {
+                    return None;
+                }
+                exponents[variables.binary_search(&name).ok()?] = e.0 as u32;
+            }
+            c.is_integer().then(|| (exponents, c.numerator().clone()))
+        })
+        .collect()
+}
+
+/// Exact division of multivariate integer polynomials, if the divisor divides evenly
+fn divide_multivariate(
+    dividend: &IntegerPolynomial,
+    divisor: &IntegerPolynomial,
+) -> Option<IntegerPolynomial> {
+    let (lead_exponents, lead) = divisor.iter().next_back()?;
+    let mut remainder = dividend.clone();
+    let mut quotient = IntegerPolynomial::new();
+
+    while let Some((exponents, c)) = remainder
+        .iter()
+        .next_back()
+        .map(|(e, c)| (e.clone(), c.clone()))
+    {
+        if !c.is_multiple_of(lead) {
+            return None;
+        }
+        let shift: Vec<u32> = exponents
+            .iter()
+            .zip(lead_exponents.iter())
+            .map(|(e, l)| e.checked_sub(*l))
+            .collect::<Option<_>>()?;
+        let q = c / lead;
+        for (exponents, d) in divisor.iter() {
+            let key: Vec<u32> = exponents
+                .iter()
+                .zip(shift.iter())
+                .map(|(a, b)| a + b)
+                .collect();
+            let entry = remainder.entry(key.clone()).or_default();
+            *entry -= &q * d;
+            if entry.is_zero() {
+                remainder.remove(&key);
+            }
+        }
+        quotient.insert(shift, q);
+    }
+    Some(quotient)
+}
+
+/// Factor a univariate integer polynomial (lowest degree first) that is primitive
+/// with a positive leading coefficient, returning primitive irreducible factors
+fn factor_univariate(polynomial: &[BigInt]) -> Result<Vec<(Vec<BigInt>, usize)>, InterpreterError> {
+    let mut result = Vec::new();
+
+    // pull out powers of x, which the square-free step would otherwise handle slowly
+    let shift = polynomial.iter().take_while(|c| c.is_zero()).count();
+    if shift > 0 {
+        result.push((vec![BigInt::zero(), BigInt::one()], shift));
+    }
+    let polynomial = &polynomial[shift..];
+    if polynomial.len() <= 1 {
+        return Ok(result);
+    }
+
+    for (square_free, multiplicity) in square_free_decomposition(polynomial)? {
+        for f in zassenhaus(&square_free)? {
+            result.push((f, multiplicity));
+        }
+    }
+    Ok(result)
+}
+
+/// Yun's square-free decomposition, returning primitive integer parts with multiplicities
+fn square_free_decomposition(
+    polynomial: &[BigInt],
+) -> Result<Vec<(Vec<BigInt>, usize)>, InterpreterError> {
+    let f = UnivariatePolynomial::from_integers(polynomial);
+    let derivative = f.derivative();
+    let c = f.gcd(&derivative)?;
+    let (mut w, _) = f.div_rem(&c);
+    let (y, _) = derivative.div_rem(&c);
+    let mut z = y.sub(&w.derivative());
+
+    let mut result = Vec::new();
+    let mut multiplicity = 1;
+    while w.degree() > 0 {
+        let g = w.gcd(&z)?;
+        (w, _) = w.div_rem(&g);
+        let (y, _) = z.div_rem(&g);
+        z = y.sub(&w.derivative());
+        if g.degree() > 0 {
+            result.push((g.primitive_part().1, multiplicity));
+        }
+        multiplicity += 1;
+    }
+    Ok(result)
+}
+
+/// Greatest common divisor of two primitive integer polynomials (lowest degree first),
+/// primitive with a positive leading coefficient
+///
+/// The gcd is found modulo primes just below 2^31 and the images are combined by the
+/// Chinese remainder theorem until the result divides both inputs, so no coefficient
+/// grows past the gcd's own. A gcd of 1 modulo one prime that divides neither leading
+/// coefficient proves the inputs coprime, which ends most calls after a single image.
+pub(crate) fn integer_gcd(f: &[BigInt], g: &[BigInt]) -> Result<Vec<BigInt>, InterpreterError> {
+    let (f, g) = (trim(f.to_vec()), trim(g.to_vec()));
+    let (Some(lead_f), Some(lead_g)) = (f.last(), g.last()) else {
+        return Ok(primitive(if f.is_empty() { &g } else { &f }));
+    };
+    // the gcd's leading coefficient divides this, so scaling every image by it fixes
+    // the leading coefficient the images are combined with
+    let lead = lead_f.gcd(lead_g);
+    let bound = 2 * norm(&f).min(norm(&g)) * &lead;
+
+    let mut image: Vec<BigInt> = Vec::new();
+    let mut modulus = BigInt::one();
+    let mut degree = usize::MAX;
+    for p in primes_below(GCD_MODULUS) {
+        if residue(lead_f, p) == 0 || residue(lead_g, p) == 0 {
+            continue;
+        }
+        let h = gcd_mod(&residues(&f, p), &residues(&g, p), p);
+        if h.len() == 1 {
+            return Ok(vec![BigInt::one()]);
+        }
+        let h = reduce(&scale(&h, residue(&lead, p)), p);
+        let previous = image.clone();
+        match (h.len() - 1).cmp(&degree) {
+            // a prime with a larger image divides a resultant, so it is skipped
+            std::cmp::Ordering::Greater => continue,
+            // every earlier prime was such a prime
+            std::cmp::Ordering::Less => {
+                degree = h.len() - 1;
+                image = integers(&h);
+                modulus = BigInt::from(p);
+            }
+            std::cmp::Ordering::Equal => {
+                image = chinese_remainder(&image, &modulus, &h, p);
+                modulus *= p;
+            }
+        }
+        if image == previous || modulus > bound {
+            let candidate = primitive(&symmetric(&image, &modulus));
+            if divide_exact(&f, &candidate).is_some() && divide_exact(&g, &candidate).is_some() {
+                return Ok(candidate);
+            }
+        }
+    }
+    Err(InterpreterError::overflow(
+        "Polynomial too large for a gcd".to_string(),
+    ))
+}
+
+/// The polynomial congruent to `a` mod `m` and to `b` mod `p`, reduced mod `m*p`
+fn chinese_remainder(a: &[BigInt], m: &BigInt, b: &[i128], p: i128) -> Vec<BigInt> {
+    let inverse = inverse_mod(residue(m, p), p);
+    let product = m * p;
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            let x = a.get(i).cloned().unwrap_or_default();
+            let y = *b.get(i).unwrap_or(&0);
+            let t = ((y - residue(&x, p)).rem_euclid(p) * inverse).rem_euclid(p);
+            (x + m * t).mod_floor(&product)
+        })
+        .collect()
+}
+
+/// Primes below `limit`, largest first
+fn primes_below(limit: i128) -> impl Iterator<Item = i128> {
+    (2..limit)
+        .rev()
+        .filter(|n| (2..).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+}
+
+/// Mignotte's bound 2^n·|f| on the coefficients of any factor of `f` of degree n,
+/// with the norm rounded up to an integer
+fn norm(f: &[BigInt]) -> BigInt {
+    let squares: BigInt = f.iter().map(|c| c * c).sum();
+    (squares.sqrt() + 1) << (f.len().saturating_sub(1))
+}
+
+/// Berlekamp–Zassenhaus factorisation of a square-free primitive polynomial
+///
+/// The modular factors are Hensel lifted to a power of the prime past the bound on any
+/// factor's coefficients, however many digits that takes.
+fn zassenhaus(f: &[BigInt]) -> Result<Vec<Vec<BigInt>>, InterpreterError> {
+    let degree = f.len() - 1;
+    if degree <= 1 {
+        return Ok(vec![f.to_vec()]);
+    }
+    let lead = &f[degree];
+    let bound = 2 * norm(f) * lead.abs();
+
+    // pick the prime giving the fewest modular factors among the first few suitable ones
+    let mut best: Option<(i128, Vec<Vec<i128>>)> = None;
+    let mut tried = 0;
+    for &p in PRIMES.iter() {
+        if residue(lead, p) == 0 {
+            continue;
+        }
+        let reduced = residues(f, p);
+        if gcd_mod(&reduced, &derivative_mod(&reduced, p), p).len() > 1 {
+            continue;
+        }
+        let factors = berlekamp(&make_monic(&reduced, p), p);
+        if best.as_ref().is_none_or(|(_, b)| factors.len() < b.len()) {
+            best = Some((p, factors));
+        }
+        tried += 1;
+        if tried == PRIME_CANDIDATES {
+            break;
+        }
+    }
+    let (p, modular_factors) = best.ok_or_else(|| {
+        InterpreterError::unsupported("No suitable prime found for factorisation".to_string())
+    })?;
+    if modular_factors.len() == 1 {
+        return Ok(vec![f.to_vec()]);
+    }
+
+    // lift far enough to recover any factor's coefficients
+    let mut modulus = BigInt::from(p);
+    let mut exponent = 1;
+    while modulus <= bound {
+        modulus *= p;
+        exponent += 1;
+    }
+
+    let lifted = hensel_lift(f, &modular_factors, p, exponent);
+    Ok(recombine(f, lifted, &modulus))
+}
+
+/// Try products of lifted modular factors as true factors, smallest subsets first
+fn recombine(f: &[BigInt], mut lifted: Vec<Vec<BigInt>>, modulus: &BigInt) -> Vec<Vec<BigInt>> {
+    let mut remaining = f.to_vec();
+    let mut factors = Vec::new();
+    let mut size = 1;
+
+    while 2 * size <= lifted.len() {
+        let mut found = None;
+        for subset in Combinations::new(lifted.len(), size) {
+            let lead = remaining.last().unwrap().clone();
+            let product = subset.iter().fold(vec![lead], |acc, &i| {
+                integer_multiply_mod(&acc, &lifted[i], modulus)
+            });
+            let candidate = primitive(&symmetric(&product, modulus));
+            if let Some(quotient) = divide_exact(&remaining, &candidate) {
+                found = Some((subset, candidate, quotient));
+                break;
+            }
+        }
+        match found {
+            Some((subset, candidate, quotient)) => {
+                factors.push(candidate);
+                remaining = quotient;
+                for i in subset.iter().rev() {
+                    lifted.remove(*i);
+                }
+            }
+            None => size += 1,
+        }
+    }
+    factors.push(primitive(&remaining));
+    factors
+}
+
+/// Lift a factorisation `f = lc * g_1 * ... * g_r (mod p)` with monic `g_i` to modulus `p^k`
+fn hensel_lift(f: &[BigInt], factors: &[Vec<i128>], p: i128, k: u32) -> Vec<Vec<BigInt>> {
+    let modulus = BigInt::from(p).pow(k);
+    let mut current = integer_reduce(f, &modulus);
+    let mut lifted = Vec::new();
+
+    for i in 0..factors.len() - 1 {
+        let lead = residue(current.last().unwrap(), p);
+        let rest = factors[i + 1..]
+            .iter()
+            .fold(vec![lead], |acc, g| multiply_mod(&acc, g, p));
+        let (g, h) = hensel_step(&current, &factors[i], &rest, p, &modulus);
+        lifted.push(g);
+        current = h;
+    }
+
+    let inverse = current
+        .last()
+        .unwrap()
+        .extended_gcd(&modulus)
+        .x
+        .mod_floor(&modulus);
+    lifted.push(integer_reduce(&integer_scale(&current, &inverse), &modulus));
+    lifted
+}
+
+/// Lift `f = g * h (mod p)` with monic `g` to `f = g' * h' (mod modulus)`, for a power of p
+///
+/// Each step squares the modulus, lifting the Bézout coefficients `s*g + t*h = 1` along
+/// with the factors, so a modulus of k digits takes log k steps rather than k.
+fn hensel_step(
+    f: &[BigInt],
+    g: &[i128],
+    h: &[i128],
+    p: i128,
+    modulus: &BigInt,
+) -> (Vec<BigInt>, Vec<BigInt>) {
+    let (_, s, t) = extended_gcd_mod(g, h, p);
+    let (mut g, mut h, mut s, mut t) = (integers(g), integers(h), integers(&s), integers(&t));
+    let mut current = BigInt::from(p);
+
+    while current < *modulus {
+        let next = (&current * &current).min(modulus.clone());
+        let reduce = |a: &[BigInt]| integer_reduce(a, &next);
+        let error = reduce(&integer_subtract(f, &integer_multiply(&g, &h)));
+
+        let (quotient, remainder) = integer_div_rem_mod(&integer_multiply(&t, &error), &g, &next);
+        h = reduce(&integer_add(
+            &h,
+            &integer_add(
+                &integer_multiply(&s, &error),
+                &integer_multiply(&quotient, &h),
+            ),
+        ));
+        g = reduce(&integer_add(&g, &remainder));
+
+        let one = vec![BigInt::one()];
+        let excess = reduce(&integer_subtract(
+            &integer_add(&integer_multiply(&t, &h), &integer_multiply(&s, &g)),
+            &one,
+        ));
+        let (quotient, remainder) = integer_div_rem_mod(&integer_multiply(&t, &excess), &g, &next);
+        t = reduce(&integer_subtract(&t, &remainder));
+        s = reduce(&integer_subtract(
+            &s,
+            &integer_add(
+                &integer_multiply(&s, &excess),
+                &integer_multiply(&quotient, &h),
+            ),
+        ));
+        current = next;
+    }
+    (g, h)
+}
+
+/// Berlekamp's algorithm: split a monic square-free polynomial into irreducibles mod p
+fn berlekamp(f: &[i128], p: i128) -> Vec<Vec<i128>> {
+    let n = f.len() - 1;
+
+    // rows of Q are x^(i*p) mod f
+    let x_p = pow_mod(&[0, 1], p as u64, f, p);
+    let mut rows = vec![vec![1]];
+    for i in 1..n {
+        rows.push(div_rem_mod(&multiply_mod(&rows[i - 1], &x_p, p), f, p).1);
+    }
+
+    // null space of (Q - I)^T gives the Berlekamp subalgebra
+    let mut matrix = vec![vec![0; n]; n];
+    for (i, row) in rows.iter().enumerate() {
+        for (j, c) in row.iter().enumerate() {
+            matrix[j][i] = *c;
+        }
+        matrix[i][i] -= 1;
+    }
+    let basis = null_space_mod(matrix, p);
+    let count = basis.len();
+
+    let mut factors = vec![f.to_vec()];
+    for v in basis.iter() {
+        if factors.len() == count {
+            break;
+        }
+        if v.len() <= 1 {
+            continue;
+        }
+        let mut split = Vec::new();
+        for u in factors {
+            if u.len() <= 2 {
+                split.push(u);
+                continue;
+            }
+            for s in 0..p {
+                let mut shifted = v.clone();
+                shifted[0] = (shifted[0] - s).rem_euclid(p);
+                let g = gcd_mod(&u, &trim(shifted), p);
+                if g.len() > 1 {
+                    split.push(g);
+                }
+            }
+        }
+        factors = split;
+    }
+    factors
+}
+
+fn null_space_mod(mut matrix: Vec<Vec<i128>>, p: i128) -> Vec<Vec<i128>> {
+    let n = matrix.len();
+    let mut pivots = Vec::new();
+    let mut row = 0;
+
+    for column in 0..n {
+        let Some(pivot) = (row..n).find(|&r| matrix[r][column].rem_euclid(p) != 0) else {
+            continue;
+        };
+        matrix.swap(row, pivot);
+        let inverse = inverse_mod(matrix[row][column], p);
+        for entry in matrix[row].iter_mut() {
+            *entry = (*entry * inverse).rem_euclid(p);
+        }
+        let pivot_row = matrix[row].clone();
+        for (r, current) in matrix.iter_mut().enumerate() {
+            let factor = current[column];
+            if r != row && factor != 0 {
+                for (entry, pivot_entry) in current.iter_mut().zip(pivot_row.iter()) {
+                    *entry = (*entry - factor * pivot_entry).rem_euclid(p);
+                }
+            }
+        }
+        pivots.push(column);
+        row += 1;
+    }
+
+    (0..n)
+        .filter(|c| !pivots.contains(c))
+        .map(|free| {
+            let mut vector = vec![0; n];
+            vector[free] = 1;
+            for (r, pivot) in pivots.iter().enumerate() {
+                vector[*pivot] = (-matrix[r][free]).rem_euclid(p);
+            }
+            trim(vector)
+        })
+        .collect()
+}
+
+/// Iterator over the `k`-element subsets of `0..n` in lexicographic order
+struct Combinations {
+    indices: Vec<usize>,
+    n: usize,
+    done: bool,
+}
+
+impl Combinations {
+    fn new(n: usize, k: usize) -> Self {
+        Combinations {
+            indices: (0..k).collect(),
+            n,
+            done: k > n,
+        }
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.done {
+            return None;
+        }
+        let current = self.indices.clone();
+        let k = self.indices.len();
+        match (0..k).rev().find(|&i| self.indices[i] < self.n - k + i) {
+            Some(i) => {
+                self.indices[i] += 1;
+                for j in i + 1..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        Some(current)
+    }
+}
+
+// Dense integer polynomial helpers, lowest degree first
+
+fn trim<T: Zero>(mut polynomial: Vec<T>) -> Vec<T> {
+    while polynomial.last().is_some_and(|c| c.is_zero()) {
+        polynomial.pop();
+    }
+    polynomial
+}
+
+/// An integer mod a small prime
+fn residue(c: &BigInt, p: i128) -> i128 {
+    c.mod_floor(&BigInt::from(p)).to_i128().unwrap_or_default()
+}
+
+/// An integer polynomial mod a small prime
+fn residues(polynomial: &[BigInt], p: i128) -> Vec<i128> {
+    trim(polynomial.iter().map(|c| residue(c, p)).collect())
+}
+
+fn integers(polynomial: &[i128]) -> Vec<BigInt> {
+    polynomial.iter().map(|c| BigInt::from(*c)).collect()
+}
+
+fn integer_reduce(polynomial: &[BigInt], modulus: &BigInt) -> Vec<BigInt> {
+    trim(polynomial.iter().map(|c| c.mod_floor(modulus)).collect())
+}
+
+/// Map residues into the symmetric range `(-m/2, m/2]`
+fn symmetric(polynomial: &[BigInt], modulus: &BigInt) -> Vec<BigInt> {
+    polynomial
+        .iter()
+        .map(|c| match c * 2 > *modulus {
+            true => c - modulus,
+            false => c.clone(),
+        })
+        .collect()
+}
+
+fn primitive(polynomial: &[BigInt]) -> Vec<BigInt> {
+    let polynomial = trim(polynomial.to_vec());
+    let mut divisor = polynomial.iter().fold(BigInt::zero(), |acc, c| acc.gcd(c));
+    if polynomial.last().is_some_and(|c| c.is_negative()) {
+        divisor = -divisor;
+    }
+    polynomial.iter().map(|c| c / &divisor).collect()
+}
+
+fn integer_add(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let len = a.len().max(b.len());
+    trim(
+        (0..len)
+            .map(|i| a.get(i).cloned().unwrap_or_default() + b.get(i).cloned().unwrap_or_default())
+            .collect(),
+    )
+}
+
+fn integer_subtract(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    integer_add(a, &integer_scale(b, &BigInt::from(-1)))
+}
+
+fn integer_scale(a: &[BigInt], c: &BigInt) -> Vec<BigInt> {
+    trim(a.iter().map(|x| x * c).collect())
+}
+
+fn integer_multiply(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    trim(product)
+}
+
+/// Division by a monic polynomial mod a power of a prime
+fn integer_div_rem_mod(
+    dividend: &[BigInt],
+    divisor: &[BigInt],
+    modulus: &BigInt,
+) -> (Vec<BigInt>, Vec<BigInt>) {
+    let mut remainder = integer_reduce(dividend, modulus);
+    if remainder.len() < divisor.len() {
+        return (Vec::new(), remainder);
+    }
+    let mut quotient = vec![BigInt::zero(); remainder.len() - divisor.len() + 1];
+    for i in (0..quotient.len()).rev() {
+        let q = remainder[i + divisor.len() - 1].mod_floor(modulus);
+        for (j, d) in divisor.iter().enumerate() {
+            remainder[i + j] = (&remainder[i + j] - &q * d).mod_floor(modulus);
+        }
+        quotient[i] = q;
+    }
+    (trim(quotient), integer_reduce(&remainder, modulus))
+}
+
+/// Product mod a power of a prime
+fn integer_multiply_mod(a: &[BigInt], b: &[BigInt], modulus: &BigInt) -> Vec<BigInt> {
+    integer_reduce(&integer_multiply(a, b), modulus)
+}
+
+/// Exact division over the integers, if `divisor` divides `dividend`
+fn divide_exact(dividend: &[BigInt], divisor: &[BigInt]) -> Option<Vec<BigInt>> {
+    if divisor.len() > dividend.len() {
+        return None;
+    }
+    // cheap rejection: the constant terms must divide too
+    if !divisor[0].is_zero() && !dividend[0].is_multiple_of(&divisor[0]) {
+        return None;
+    }
+    let lead = divisor.last()?;
+    let mut remainder = dividend.to_vec();
+    let mut quotient = vec![BigInt::zero(); dividend.len() - divisor.len() + 1];
+
+    for i in (0..quotient.len()).rev() {
+        let top = &remainder[i + divisor.len() - 1];
+        if !top.is_multiple_of(lead) {
+            return None;
+        }
+        let q = top / lead;
+        for (j, d) in divisor.iter().enumerate() {
+            remainder[i + j] -= &q * d;
+        }
+        quotient[i] = q;
+    }
+    if remainder.iter().all(|c| c.is_zero()) {
+        Some(trim(quotient))
+    } else {
+        None
+    }
+}
+
+// Polynomial arithmetic over the integers mod p, lowest degree first
+
+fn reduce(polynomial: &[i128], modulus: i128) -> Vec<i128> {
+    trim(polynomial.iter().map(|c| c.rem_euclid(modulus)).collect())
+}
+
+fn add(a: &[i128], b: &[i128]) -> Vec<i128> {
+    let len = a.len().max(b.len());
+    trim(
+        (0..len)
+            .map(|i| a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0))
+            .collect(),
+    )
+}
+
+fn subtract(a: &[i128], b: &[i128]) -> Vec<i128> {
+    add(a, &scale(b, -1))
+}
+
+fn scale(a: &[i128], c: i128) -> Vec<i128> {
+    trim(a.iter().map(|x| x * c).collect())
+}
+
+/// Product mod `modulus`, reduced after every step so that nothing overflows for any
+/// modulus below 2^62
+fn multiply_mod(a: &[i128], b: &[i128], modulus: i128) -> Vec<i128> {
+    let (a, b) = (reduce(a, modulus), reduce(b, modulus));
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] = (product[i + j] + x * y).rem_euclid(modulus);
+        }
+    }
+    trim(product)
+}
+
+fn inverse_mod(a: i128, modulus: i128) -> i128 {
+    let (mut r0, mut r1) = (a.rem_euclid(modulus), modulus);
+    let (mut s0, mut s1) = (1i128, 0i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+    s0.rem_euclid(modulus)
+}
+
+fn make_monic(polynomial: &[i128], p: i128) -> Vec<i128> {
+    match polynomial.last() {
+        Some(lead) => {
+            let inverse = inverse_mod(*lead, p);
+            reduce(&scale(polynomial, inverse), p)
+        }
+        None => Vec::new(),
+    }
+}
+
+fn derivative_mod(polynomial: &[i128], p: i128) -> Vec<i128> {
+    reduce(
+        &polynomial
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| c * i as i128)
+            .collect::<Vec<_>>(),
+        p,
+    )
+}
+
+fn div_rem_mod(dividend: &[i128], divisor: &[i128], p: i128) -> (Vec<i128>, Vec<i128>) {
+    let mut remainder = reduce(dividend, p);
+    if remainder.len() < divisor.len() {
+        return (Vec::new(), remainder);
+    }
+    let inverse = inverse_mod(*divisor.last().unwrap(), p);
+    let mut quotient = vec![0; remainder.len() - divisor.len() + 1];
+
+    for i in (0..quotient.len()).rev() {
+        let q = (remainder[i + divisor.len() - 1] * inverse).rem_euclid(p);
+        quotient[i] = q;
+        for (j, d) in divisor.iter().enumerate() {
+            remainder[i + j] = (remainder[i + j] - q * d).rem_euclid(p);
+        }
+    }
+    (trim(quotient), trim(remainder))
+}
+
+/// Monic greatest common divisor mod p
+fn gcd_mod(a: &[i128], b: &[i128], p: i128) -> Vec<i128> {
+    let (mut a, mut b) = (reduce(a, p), reduce(b, p));
+    while !b.is_empty() {
+        let (_, r) = div_rem_mod(&a, &b, p);
+        (a, b) = (b, r);
+    }
+    make_monic(&a, p)
+}
+
+/// Extended Euclid mod p: returns `(g, s, t)` with `s*a + t*b = g` and `g` monic
+fn extended_gcd_mod(a: &[i128], b: &[i128], p: i128) -> (Vec<i128>, Vec<i128>, Vec<i128>) {
+    let (mut r0, mut r1) = (reduce(a, p), reduce(b, p));
+    let (mut s0, mut s1) = (vec![1], Vec::new());
+    let (mut t0, mut t1) = (Vec::new(), vec![1]);
+
+    while !r1.is_empty() {
+        let (q, r) = div_rem_mod(&r0, &r1, p);
+        (r0, r1) = (r1, r);
+        let s = reduce(&subtract(&s0, &multiply_mod(&q, &s1, p)), p);
+        (s0, s1) = (s1, s);
+        let t = reduce(&subtract(&t0, &multiply_mod(&q, &t1, p)), p);
+        (t0, t1) = (t1, t);
+    }
+
+    let inverse = inverse_mod(*r0.last().unwrap_or(&1), p);
+    (
+        reduce(&scale(&r0, inverse), p),
+        reduce(&scale(&s0, inverse), p),
+        reduce(&scale(&t0, inverse), p),
+    )
+}
+
+/// `base^exponent mod (modulus, p)` by repeated squaring
+fn pow_mod(base: &[i128], mut exponent: u64, modulus: &[i128], p: i128) -> Vec<i128> {
+    let mut result = vec![1];
+    let mut base = div_rem_mod(base, modulus, p).1;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = div_rem_mod(&multiply_mod(&result, &base, p), modulus, p).1;
+        }
+        base = div_rem_mod(&multiply_mod(&base, &base, p), modulus, p).1;
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zassenhaus() {
+        // x^4 - 1 = (x - 1)(x + 1)(x^2 + 1)
+        let mut factors = zassenhaus(&integers(&[-1, 0, 0, 0, 1])).unwrap();
+        factors.sort();
+        assert_eq!(
+            factors,
+            vec![integers(&[-1, 1]), integers(&[1, 0, 1]), integers(&[1, 1])]
+        );
+
+        // x^4 + 1 is irreducible over the integers but splits mod every prime
+        assert_eq!(
+            zassenhaus(&integers(&[1, 0, 0, 0, 1])).unwrap(),
+            vec![integers(&[1, 0, 0, 0, 1])]
+        );
+    }
+
+    #[test]
+    fn test_square_free_decomposition() {
+        // (x + 1)^2 (x - 2)
+        let decomposition = square_free_decomposition(&integers(&[-2, -3, 0, 1])).unwrap();
+        assert_eq!(
+            decomposition,
+            vec![(integers(&[-2, 1]), 1), (integers(&[1, 1]), 2)]
+        );
+    }
+
+    #[test]
+    fn test_integer_gcd() {
+        // (x^5 + 3x + 7)(x^5 - 2x + 11) and its derivative are coprime
+        let f = integer_multiply(
+            &integers(&[7, 3, 0, 0, 0, 1]),
+            &integers(&[11, -2, 0, 0, 0, 1]),
+        );
+        let derivative: Vec<BigInt> = f.iter().enumerate().skip(1).map(|(i, c)| c * i).collect();
+        assert_eq!(integer_gcd(&f, &derivative).unwrap(), integers(&[1]));
+
+        // (2x + 3)(x - 1) and (2x + 3)(x + 5)
+        let g = integer_gcd(&integers(&[-3, 1, 2]), &integers(&[15, 13, 2])).unwrap();
+        assert_eq!(g, integers(&[3, 2]));
+    }
+
+    #[test]
+    fn test_factor_kronecker() {
+        // (x - z)^3 over [x, y, z], with y absent taking no place in the substitution
+        let cube: IntegerPolynomial = [
+            (vec![3, 0, 0], 1),
+            (vec![2, 0, 1], -3),
+            (vec![1, 0, 2], 3),
+            (vec![0, 0, 3], -1),
+        ]
+        .into_iter()
+        .map(|(exponents, c)| (exponents, BigInt::from(c)))
+        .collect();
+        let difference: IntegerPolynomial = [(vec![1, 0, 0], 1), (vec![0, 0, 1], -1)]
+            .into_iter()
+            .map(|(exponents, c)| (exponents, BigInt::from(c)))
+            .collect();
+        assert_eq!(factor_primitive(&cube, 3).unwrap(), vec![(difference, 3)]);
+    }
+
+    #[test]
+    fn test_factor_by() {
+        // 2(x - 1)^2(x + y + 1) by x - 1 and x + 2, the rest left whole
+        let x = Polynomial::variable("x", 1.0);
+        let y = Polynomial::variable("y", 1.0);
+        let one = Polynomial::constant(1);
+        let (below, other) = (&x - &one, &x + &Polynomial::constant(2));
+        let rest = &(&x + &y) + &one;
+        let expression = (&below.pow(2) * &rest).scale(2);
+        let factorization = factor_by(&expression, &[(below.clone(), 1), (other, 1)]).unwrap();
+        assert_eq!(factorization.coefficient, Rational::from(2));
+        assert_eq!(factorization.factors, vec![(below, 2), (rest, 1)]);
+    }
+
+    #[test]
+    fn test_berlekamp() {
+        // x^2 + 1 = (x + 2)(x + 3) mod 5
+        let mut factors = berlekamp(&[1, 0, 1], 5);
+        factors.sort();
+        assert_eq!(factors, vec![vec![2, 1], vec![3, 1]]);
+    }
+}
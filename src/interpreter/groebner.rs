@@ -0,0 +1,1030 @@
+use std::{cmp::Ordering, collections::BTreeSet};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::One;
+
+use crate::util::rational::Rational;
+
+use super::{
+    apart, assumptions::Assumptions, factor, linear_system, monomial::Monomial,
+    monomial_order::MonomialOrder, polynomial::Polynomial, radical,
+    rational_function::RationalFunction, symbol::Symbol, univariate::UnivariatePolynomial,
+    InterpreterError,
+};
+
+/// Largest basis Buchberger's algorithm is allowed to build before giving up
+const MAX_BASIS: usize = 256;
+
+/// Largest value a relation among constants such as sqrt(2) and pi may have and still hold
+const ROUNDING: f64 = 1e-9;
+
+/// Sparse polynomial with exact coefficients over a fixed variable list,
+/// with terms kept in decreasing monomial order so the first is the leading term
+#[derive(Debug, Clone, PartialEq)]
+struct SparsePolynomial {
+    terms: Vec<(Vec<u32>, Rational)>,
+}
+
+impl SparsePolynomial {
+    fn from_terms(mut terms: Vec<(Vec<u32>, Rational)>, order: MonomialOrder) -> Self {
+        terms.sort_by(|(a, _), (b, _)| order.compare(b, a));
+        let mut merged: Vec<(Vec<u32>, Rational)> = Vec::with_capacity(terms.len());
+        for (exponents, coefficient) in terms {
+            match merged.last_mut() {
+                Some((last, c)) if *last == exponents => *c += &coefficient,
+                _ => merged.push((exponents, coefficient)),
+            }
+        }
+        merged.retain(|(_, c)| !c.is_zero());
+        SparsePolynomial { terms: merged }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn is_constant(&self) -> bool {
+        matches!(self.terms.as_slice(), [(e, _)] if e.iter().all(|&p| p == 0))
+    }
+
+    fn leading_monomial(&self) -> &[u32] {
+        &self.terms[0].0
+    }
+
+    fn leading_coefficient(&self) -> &Rational {
+        &self.terms[0].1
+    }
+
+    fn monic(&self) -> Self {
+        let inverse = self.leading_coefficient().recip();
+        SparsePolynomial {
+            terms: self
+                .terms
+                .iter()
+                .map(|(e, c)| (e.clone(), c * &inverse))
+                .collect(),
+        }
+    }
+
+    /// self - coefficient * x^shift * other, merging the two sorted term lists
+    fn sub_multiple(
+        &self,
+        coefficient: &Rational,
+        shift: &[u32],
+        other: &SparsePolynomial,
+        order: MonomialOrder,
+    ) -> SparsePolynomial {
+        let mut terms = Vec::with_capacity(self.terms.len() + other.terms.len());
+        let mut left = self.terms.iter().peekable();
+        let mut right = other
+            .terms
+            .iter()
+            .map(|(e, c)| {
+                let exponents: Vec<u32> = e.iter().zip(shift).map(|(a, b)| a + b).collect();
+                (exponents, -(coefficient * c))
+            })
+            .peekable();
+
+        loop {
+            let ordering = match (left.peek(), right.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (Some((a, _)), Some((b, _))) => order.compare(a, b),
+            };
+            match ordering {
+                Ordering::Greater => terms.push(left.next().unwrap().clone()),
+                Ordering::Less => terms.push(right.next().unwrap()),
+                Ordering::Equal => {
+                    let (exponents, a) = left.next().unwrap();
+                    let (_, b) = right.next().unwrap();
+                    let sum = a + b;
+                    if !sum.is_zero() {
+                        terms.push((exponents.clone(), sum));
+                    }
+                }
+            }
+        }
+        SparsePolynomial { terms }
+    }
+
+    /// Remainder of full division by the basis: no term is divisible by any leading monomial
+    fn reduce(&self, basis: &[SparsePolynomial], order: MonomialOrder) -> SparsePolynomial {
+        self.divide(basis, order).1
+    }
+
+    /// Multivariate division, returning a quotient for each divisor and the remainder
+    ///
+    /// The leading term is cancelled by the first divisor whose leading monomial divides it,
+    /// or otherwise moved to the remainder.
+    fn divide(
+        &self,
+        divisors: &[SparsePolynomial],
+        order: MonomialOrder,
+    ) -> (Vec<SparsePolynomial>, SparsePolynomial) {
+        let mut p = self.clone();
+        let mut quotients = vec![SparsePolynomial { terms: Vec::new() }; divisors.len()];
+        let mut remainder = Vec::new();
+        while !p.is_zero() {
+            let lead = p.leading_monomial().to_vec();
+            match divisors
+                .iter()
+                .position(|g| divides(g.leading_monomial(), &lead))
+            {
+                Some(i) => {
+                    let g = &divisors[i];
+                    let shift: Vec<u32> = lead
+                        .iter()
+                        .zip(g.leading_monomial())
+                        .map(|(a, b)| a - b)
+                        .collect();
+                    let coefficient = p.leading_coefficient() / g.leading_coefficient();
+                    p = p.sub_multiple(&coefficient, &shift, g, order);
+                    // leading monomials strictly decrease, so each quotient stays sorted
+                    quotients[i].terms.push((shift, coefficient));
+                }
+                None => remainder.push(p.terms.remove(0)),
+            }
+        }
+        (quotients, SparsePolynomial { terms: remainder })
+    }
+
+    /// Scale by the common denominator of the coefficients, leaving integers
+    fn clear_denominators(&self) -> SparsePolynomial {
+        let denominator = Rational::from(
+            self.terms
+                .iter()
+                .fold(BigInt::one(), |acc, (_, c)| acc.lcm(c.denominator())),
+        );
+        SparsePolynomial {
+            terms: self
+                .terms
+                .iter()
+                .map(|(e, c)| (e.clone(), c * &denominator))
+                .collect(),
+        }
+    }
+
+    /// Substitute values for some of the variables, leaving their exponents at zero
+    fn substitute(&self, values: &[Option<Rational>], order: MonomialOrder) -> SparsePolynomial {
+        let terms = self
+            .terms
+            .iter()
+            .map(|(exponents, coefficient)| {
+                let mut coefficient = coefficient.clone();
+                let mut exponents = exponents.clone();
+                for (e, value) in exponents.iter_mut().zip(values) {
+                    if let Some(value) = value {
+                        for _ in 0..*e {
+                            coefficient *= value;
+                        }
+                        *e = 0;
+                    }
+                }
+                (exponents, coefficient)
+            })
+            .collect();
+        SparsePolynomial::from_terms(terms, order)
+    }
+
+    /// Index of the first variable the polynomial depends on
+    fn main_variable(&self) -> Option<usize> {
+        self.terms
+            .iter()
+            .filter_map(|(e, _)| e.iter().position(|&p| p > 0))
+            .min()
+    }
+}
+
+fn divides(a: &[u32], b: &[u32]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x <= y)
+}
+
+fn monomial_lcm(a: &[u32], b: &[u32]) -> Vec<u32> {
+    a.iter().zip(b).map(|(x, y)| *x.max(y)).collect()
+}
+
+/// S-polynomial, cancelling the leading terms of two monic polynomials
+fn s_polynomial(
+    f: &SparsePolynomial,
+    g: &SparsePolynomial,
+    order: MonomialOrder,
+) -> SparsePolynomial {
+    let lcm = monomial_lcm(f.leading_monomial(), g.leading_monomial());
+    let shift = |p: &SparsePolynomial| -> Vec<u32> {
+        lcm.iter()
+            .zip(p.leading_monomial())
+            .map(|(a, b)| a - b)
+            .collect()
+    };
+    SparsePolynomial::from_terms(Vec::new(), order)
+        .sub_multiple(&-Rational::one(), &shift(f), f, order)
+        .sub_multiple(&Rational::one(), &shift(g), g, order)
+}
+
+/// Reduced Gröbner basis of the ideal generated by some polynomials, by Buchberger's algorithm
+///
+/// Pairs whose leading monomials are coprime are skipped (Buchberger's first criterion).
+/// The result is monic, interreduced and sorted by decreasing leading monomial.
+fn buchberger(
+    generators: Vec<SparsePolynomial>,
+    order: MonomialOrder,
+) -> Result<Vec<SparsePolynomial>, InterpreterError> {
+    let mut basis: Vec<SparsePolynomial> = generators
+        .into_iter()
+        .filter(|p| !p.is_zero())
+        .map(|p| p.monic())
+        .collect();
+    let mut pairs: Vec<(usize, usize)> = (0..basis.len())
+        .flat_map(|j| (0..j).map(move |i| (i, j)))
+        .collect();
+
+    while let Some((i, j)) = pairs.pop() {
+        if basis.iter().any(|g| g.is_constant()) {
+            break;
+        }
+        let (f, g) = (&basis[i], &basis[j]);
+        if f.leading_monomial()
+            .iter()
+            .zip(g.leading_monomial())
+            .all(|(a, b)| *a == 0 || *b == 0)
+        {
+            continue;
+        }
+        let remainder = s_polynomial(f, g, order).reduce(&basis, order);
+        if !remainder.is_zero() {
+            if basis.len() >= MAX_BASIS {
+                return Err(InterpreterError::overflow(
+                    "System too large to compute a Gröbner basis".to_string(),
+                ));
+            }
+            basis.push(remainder.monic());
+            let last = basis.len() - 1;
+            pairs.extend((0..last).map(|k| (k, last)));
+        }
+    }
+
+    if let Some(unit) = basis.iter().find(|g| g.is_constant()) {
+        return Ok(vec![unit.monic()]);
+    }
+
+    // drop elements whose leading monomial is divisible by another's, then interreduce
+    let mut minimal: Vec<SparsePolynomial> = Vec::new();
+    for (i, g) in basis.iter().enumerate() {
+        let redundant = basis.iter().enumerate().any(|(j, h)| {
+            j != i
+                && divides(h.leading_monomial(), g.leading_monomial())
+                && (h.leading_monomial() != g.leading_monomial() || j < i)
+        });
+        if !redundant {
+            minimal.push(g.clone());
+        }
+    }
+    let mut reduced: Vec<SparsePolynomial> = (0..minimal.len())
+        .map(|i| {
+            let others: Vec<SparsePolynomial> = minimal
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, g)| g.clone())
+                .collect();
+            let lead = SparsePolynomial {
+                terms: vec![minimal[i].terms[0].clone()],
+            };
+            let tail = SparsePolynomial {
+                terms: minimal[i].terms[1..].to_vec(),
+            };
+            SparsePolynomial {
+                terms: lead
+                    .terms
+                    .into_iter()
+                    .chain(tail.reduce(&others, order).terms)
+                    .collect(),
+            }
+        })
+        .collect();
+    reduced.sort_by(|f, g| order.compare(g.leading_monomial(), f.leading_monomial()));
+    Ok(reduced)
+}
+
+/// The power of a radical or the imaginary unit at `index` that makes it a number, as
+/// sqrt(2)^2 - 2 or i^2 + 1
+fn definition(variables: &[String], index: usize) -> Option<SparsePolynomial> {
+    let symbol = Symbol::lookup(&variables[index])?;
+    let (power, value) = match symbol.as_radical() {
+        Some((radicand, root)) => (root, Rational::from(radicand)),
+        None if symbol.is_imaginary() => (2, Rational::from(-1)),
+        None => return None,
+    };
+    let mut exponents = vec![0; variables.len()];
+    exponents[index] = power;
+    Some(SparsePolynomial {
+        terms: vec![
+            (exponents, Rational::one()),
+            (vec![0; variables.len()], -value),
+        ],
+    })
+}
+
+/// Sorted list of every variable appearing in the polynomials
+fn collect_variables(polynomials: &[Polynomial]) -> Vec<String> {
+    polynomials
+        .iter()
+        .flat_map(|p| p.variables())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+fn to_polynomial(
+    expression: &Polynomial,
+    variables: &[String],
+    order: MonomialOrder,
+) -> Result<SparsePolynomial, InterpreterError> {
+    let mut terms = Vec::new();
+    for (monomial, coefficient) in expression.terms().iter() {
+        let mut exponents = vec![0u32; variables.len()];
+        for (name, exponent) in monomial.powers() {
+            if exponent.0 < 0.0 || exponent.0.fract() != 0.0 {
+                return Err(InterpreterError::unsupported(format!(
+                    "Gröbner bases need non-negative integer powers, found {}^{}",
+                    name, exponent
+                )));
+            }
+            let index = variables.iter().position(|v| *v == name).unwrap();
+            exponents[index] = exponent.0 as u32;
+        }
+        terms.push((exponents, coefficient.clone()));
+    }
+    Ok(SparsePolynomial::from_terms(terms, order))
+}
+
+fn from_polynomial(polynomial: &SparsePolynomial, variables: &[String]) -> Polynomial {
+    let mut expression = Polynomial::new();
+    for (exponents, coefficient) in polynomial.terms.iter() {
+        let monomial = Monomial::new(
+            variables
+                .iter()
+                .zip(exponents)
+                .map(|(name, &e)| (name, e as f64)),
+        );
+        expression.add_term(monomial, coefficient.clone());
+    }
+    expression
+}
+
+/// Reduced Gröbner basis of the ideal generated by some polynomials
+///
+/// Elements are returned with integer coefficients, in decreasing order of their
+/// leading monomials under the given order.
+pub fn groebner_basis(
+    polynomials: &[Polynomial],
+    order: MonomialOrder,
+) -> Result<Vec<Polynomial>, InterpreterError> {
+    check_well_ordering(order)?;
+    let variables = collect_variables(polynomials);
+    let generators = polynomials
+        .iter()
+        .map(|p| to_polynomial(p, &variables, order))
+        .collect::<Result<Vec<_>, InterpreterError>>()?;
+    Ok(buchberger(generators, order)?
+        .iter()
+        .map(|g| from_polynomial(&g.clear_denominators(), &variables))
+        .collect())
+}
+
+/// Divide a polynomial by a list of divisors, returning the quotients and the remainder
+///
+/// The result depends on the order of the divisors and the monomial order, unless the
+/// divisors form a Gröbner basis, in which case the remainder is the normal form.
+pub fn divide(
+    dividend: &Polynomial,
+    divisors: &[Polynomial],
+    order: MonomialOrder,
+) -> Result<(Vec<Polynomial>, Polynomial), InterpreterError> {
+    check_well_ordering(order)?;
+    if divisors.iter().any(|d| d.is_zero()) {
+        return Err(InterpreterError::division_by_zero());
+    }
+    let mut all = vec![dividend.clone()];
+    all.extend(divisors.iter().cloned());
+    let variables = collect_variables(&all);
+    let divisors = divisors
+        .iter()
+        .map(|d| to_polynomial(d, &variables, order))
+        .collect::<Result<Vec<_>, InterpreterError>>()?;
+    let (quotients, remainder) =
+        to_polynomial(dividend, &variables, order)?.divide(&divisors, order);
+    Ok((
+        quotients
+            .iter()
+            .map(|q| from_polynomial(q, &variables))
+            .collect(),
+        from_polynomial(&remainder, &variables),
+    ))
+}
+
+fn check_well_ordering(order: MonomialOrder) -> Result<(), InterpreterError> {
+    if order.is_well_ordering() {
+        Ok(())
+    } else {
+        Err(InterpreterError::unsupported(
+            "Division needs a well-ordering such as lex, grlex or grevlex".to_string(),
+        ))
+    }
+}
+
+/// One branch of the solution set of a polynomial system
+///
+/// Unknowns with a rational value are assigned directly, and those whose value is a root
+/// of a quadratic are given exactly in radicals, e.g. x = 1 + sqrt(2) or x = i. An unknown
+/// that nothing constrains is free, one linear in the free ones is given in terms of them,
+/// e.g. x = -y, and the equations left in the remaining unknowns are kept as a triangular
+/// list of polynomials equal to zero.
+#[derive(Debug)]
+pub struct PolynomialSolution {
+    pub assignments: Vec<(String, Rational)>,
+    pub roots: Vec<(String, Polynomial)>,
+    pub remaining: Vec<Polynomial>,
+}
+
+/// Solve a system of polynomial equations
+///
+/// Each equation is given as `lhs - rhs`, to be solved for zero. A lex Gröbner basis
+/// triangularises the system, so the last unknown can be solved from a univariate
+/// polynomial, and each root back-substituted into the earlier unknowns. Linear factors
+/// give rational roots, and quadratic factors and quadratics in x^2 roots in radicals,
+/// complex ones only when the imaginary unit is on; other factors are unsupported.
+/// Constants such as sqrt(2) and pi are never unknowns: they come last in the order, so
+/// the basis ends in the relations among them, which must hold numerically. An unknown
+/// depending on one is solved the same way when it can be, as x = root(2, 4) from
+/// x^2 - sqrt(2), and left unsolved otherwise.
+/// An empty result means the system has no solution.
+pub fn solve_polynomial_system(
+    equations: &[RationalFunction],
+) -> Result<Vec<PolynomialSolution>, InterpreterError> {
+    if equations
+        .iter()
+        .any(|equation| equation.denominator.as_constant().is_none())
+    {
+        return Err(InterpreterError::unsupported(
+            "Equation is not polynomial: unknowns appear in a denominator".to_string(),
+        ));
+    }
+    let numerators: Vec<Polynomial> = equations.iter().map(|e| e.numerator.clone()).collect();
+    let (mut variables, constants): (Vec<String>, Vec<String>) = collect_variables(&numerators)
+        .into_iter()
+        .partition(|name| !linear_system::is_constant(name));
+    let unknowns = variables.len();
+    variables.extend(constants);
+    let order = MonomialOrder::Lex;
+    let mut generators = numerators
+        .iter()
+        .map(|p| to_polynomial(p, &variables, order))
+        .collect::<Result<Vec<_>, InterpreterError>>()?;
+    // sqrt(2)^2 = 2 and i^2 = -1 hold too, so the basis is the same however the system
+    // writes its constants
+    generators.extend((unknowns..variables.len()).filter_map(|k| definition(&variables, k)));
+    let basis = buchberger(generators, order)?;
+    if basis.iter().any(|g| g.is_constant()) {
+        return Ok(Vec::new());
+    }
+    // x^2 = sqrt(2) and x = 1 leave sqrt(2) - 1, which does not hold
+    let (relations, basis): (Vec<SparsePolynomial>, Vec<SparsePolynomial>) = basis
+        .into_iter()
+        .partition(|g| g.main_variable().is_some_and(|m| m >= unknowns));
+    if relations.iter().any(|g| {
+        radical::approximate(&from_polynomial(g, &variables))
+            .is_some_and(|value| value.modulus() > ROUNDING)
+    }) {
+        return Ok(Vec::new());
+    }
+    let has_constants = |p: &SparsePolynomial| {
+        p.terms
+            .iter()
+            .any(|(e, _)| e[unknowns..].iter().any(|&p| p > 0))
+    };
+
+    let mut solutions = Vec::new();
+    let mut branches: Vec<Vec<Option<Rational>>> = vec![vec![None; variables.len()]];
+    for k in (0..unknowns).rev() {
+        let level: Vec<&SparsePolynomial> = basis
+            .iter()
+            .filter(|g| g.main_variable() == Some(k))
+            .collect();
+
+        let mut next = Vec::new();
+        for values in branches {
+            let substituted: Vec<SparsePolynomial> = level
+                .iter()
+                .map(|g| g.substitute(&values, order))
+                .filter(|p| !p.is_zero())
+                .collect();
+            if !substituted.is_empty() && substituted.iter().any(has_constants) {
+                // an unknown with constants in its equations, as in sqrt(2)y = sqrt(2) - 2,
+                // has values in the constants, which the earlier unknowns are then solved from
+                let written: Vec<Polynomial> = substituted
+                    .iter()
+                    .map(|p| from_polynomial(p, &variables))
+                    .collect();
+                if let Ok(Some(roots)) = level_roots(&written, &variables[k]) {
+                    let found = roots
+                        .into_iter()
+                        .map(|root| back_substitute(&basis, &values, k, root, &variables, order))
+                        .collect::<Result<Vec<_>, InterpreterError>>();
+                    if let Ok(found) = found {
+                        solutions.extend(found.into_iter().flatten());
+                        continue;
+                    }
+                }
+            }
+            if substituted.is_empty() || substituted.iter().any(has_constants) {
+                // nothing constrains this unknown, so it is free, or it depends on a constant
+                // and has no value that can be given, leaving the later basis elements unsolved
+                solutions.push(unsolved(&basis, &values, k, &variables, order)?);
+                continue;
+            }
+            let mut polynomials = substituted.iter().map(|p| to_univariate(p, k));
+            let first = polynomials.next().unwrap();
+            let univariate = polynomials.try_fold(first, |a, b| a.gcd(&b))?;
+
+            let expression = apart::from_univariate(&univariate, &variables[k]);
+            let factorization = factor::factor(&expression)?;
+            let mut roots = Vec::new();
+            if !factorization.monomial.is_empty() {
+                roots.push(Rational::zero());
+            }
+            for (f, _) in factorization.factors.iter() {
+                let (p, _) = apart::to_univariate(f, &variables[k])?;
+                if p.degree() == 1 {
+                    roots.push(-p.coefficient(0) / p.coefficient(1));
+                    continue;
+                }
+                let coefficients: Vec<Polynomial> = (0..=p.degree())
+                    .map(|i| Polynomial::constant(p.coefficient(i)))
+                    .collect();
+                let Some(irrational) = radical_roots(&coefficients)? else {
+                    return Err(unsolvable(f));
+                };
+                for root in irrational {
+                    solutions.extend(back_substitute(
+                        &basis, &values, k, root, &variables, order,
+                    )?);
+                }
+            }
+            roots.sort();
+            for root in roots {
+                let mut values = values.clone();
+                values[k] = Some(root);
+                next.push(values);
+            }
+        }
+        branches = next;
+    }
+
+    // fully solved branches first, in increasing order of their values
+    branches.sort();
+    let mut complete: Vec<PolynomialSolution> = branches
+        .iter()
+        .map(|values| PolynomialSolution {
+            assignments: assignments(values, &variables),
+            roots: Vec::new(),
+            remaining: Vec::new(),
+        })
+        .collect();
+    complete.extend(solutions);
+    Ok(complete)
+}
+
+/// The error for a polynomial whose roots cannot be written in radicals here
+fn unsolvable(p: &Polynomial) -> InterpreterError {
+    InterpreterError::unsupported(format!(
+        "Only linear and quadratic factors and quadratics in a square can be solved, \
+         found {} = 0",
+        p
+    ))
+}
+
+/// The roots of a polynomial given by its coefficients from the constant term up, which
+/// are numbers written with constants such as sqrt(2)
+///
+/// Linear and quadratic polynomials are solved, and quadratics in x^2 such as
+/// y^4 - 4y^2 + 1 through the square roots of the roots of the quadratic in y^2, so
+/// y = ±sqrt(2 ± sqrt(3)), denested where that can be done. None for any other degree.
+fn radical_roots(coefficients: &[Polynomial]) -> Result<Option<Vec<Polynomial>>, InterpreterError> {
+    match coefficients {
+        [c, b] => Ok(quotient(c.scale(-1), b.clone()).map(|root| vec![root])),
+        [c, b, a] => quadratic_roots(a, b, c),
+        [c, zero, b, also_zero, a] if zero.is_zero() && also_zero.is_zero() => {
+            let Some(squares) = quadratic_roots(a, b, c)? else {
+                return Ok(None);
+            };
+            let mut roots = Vec::new();
+            for square in squares {
+                match square_roots(&square)? {
+                    Some(found) => roots.extend(found),
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some(roots))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Both roots of ax^2 + bx + c by the quadratic formula, minus the square root first,
+/// none when they are complex and the imaginary unit is off, or None when a root does
+/// not divide out to a polynomial
+fn quadratic_roots(
+    a: &Polynomial,
+    b: &Polynomial,
+    c: &Polynomial,
+) -> Result<Option<Vec<Polynomial>>, InterpreterError> {
+    // a positive leading coefficient keeps the minus root first
+    let sign = match a.as_constant().is_some_and(|a| a.is_negative()) {
+        true => -1,
+        false => 1,
+    };
+    let (a, b, c) = (a.scale(sign), b.scale(sign), c.scale(sign));
+    let discriminant = &(&b * &b) - &(&a * &c).scale(4);
+    let Some(square_roots) = square_roots(&discriminant)? else {
+        return Ok(None);
+    };
+    Ok(square_roots
+        .into_iter()
+        .map(|root| quotient(&root - &b, a.scale(2)))
+        .collect())
+}
+
+/// Both square roots of a number written with constants, the negative one first, none
+/// when it is negative and the imaginary unit is off, or None when the root cannot be
+/// written as a polynomial in radicals and the other constants
+fn square_roots(value: &Polynomial) -> Result<Option<Vec<Polynomial>>, InterpreterError> {
+    let half = Rational::new(1, 2);
+    let root = match value.as_constant() {
+        Some(c) => match radical::rational_power(c, half) {
+            Ok(root) => root,
+            Err(InterpreterError::DomainError { .. }) => return Ok(Some(Vec::new())),
+            Err(e) => return Err(e),
+        },
+        None => {
+            let negative = radical::approximate(value).is_some_and(|v| v.is_real() && v.re < 0.0);
+            if negative && Symbol::imaginary().is_none() {
+                return Ok(Some(Vec::new()));
+            }
+            match radical::denest(value) {
+                Some(root) => root,
+                None => {
+                    let root = RationalFunction::from(value.clone())
+                        .power(&Polynomial::constant(half).into(), &Assumptions::new())?;
+                    // a root left as a power of a sum, such as (sqrt(2) - 1)^(1/2), is
+                    // not a number written in radicals
+                    let nested = root.numerator.terms().iter().any(|(monomial, _)| {
+                        monomial.symbols().any(|symbol| symbol.as_power().is_some())
+                    });
+                    match quotient(root.numerator, root.denominator) {
+                        Some(root) if !nested => root,
+                        _ => return Ok(None),
+                    }
+                }
+            }
+        }
+    };
+    Ok(Some(match root.is_zero() {
+        true => vec![root],
+        false => vec![root.scale(-1), root],
+    }))
+}
+
+/// The values of an unknown solving every one of its equations, whose coefficients are
+/// numbers written with constants, or None when they cannot be found in radicals
+///
+/// An equation linear in the unknown gives its value, as sqrt(2)y - sqrt(2) + 2 gives
+/// y = -sqrt(2) + 1; otherwise the one of lowest degree is solved by [`radical_roots`],
+/// after splitting it into factors when it is free of constants. A value is kept when
+/// every other equation holds there, numerically where it cannot be checked exactly.
+fn level_roots(
+    polynomials: &[Polynomial],
+    name: &str,
+) -> Result<Option<Vec<Polynomial>>, InterpreterError> {
+    let candidates = match polynomials.iter().find_map(|p| linear_root(p, name)) {
+        Some(root) => vec![root],
+        None => {
+            let Some(lowest) = polynomials
+                .iter()
+                .filter_map(|p| Some((p, coefficients_in(p, name)?)))
+                .min_by_key(|(_, coefficients)| coefficients.len())
+                .map(|(p, _)| p)
+            else {
+                return Ok(None);
+            };
+            let factors = match lowest.variables().len() {
+                1 => factor::factor(lowest)?.factors,
+                _ => vec![(lowest.clone(), 1)],
+            };
+            let mut candidates = Vec::new();
+            for (f, _) in factors {
+                let Some(coefficients) = coefficients_in(&f, name) else {
+                    return Ok(None);
+                };
+                match radical_roots(&coefficients)? {
+                    Some(roots) => candidates.extend(roots),
+                    None => return Ok(None),
+                }
+            }
+            candidates
+        }
+    };
+    Ok(Some(
+        candidates
+            .into_iter()
+            .filter(|root| {
+                polynomials.iter().all(|p| {
+                    let value = substitute_value(p, name, root);
+                    value.is_zero()
+                        || radical::approximate(&value).is_none_or(|v| v.modulus() <= ROUNDING)
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// The coefficients of a polynomial in one unknown from the constant term up, each a
+/// polynomial in the other symbols, or None for a negative or fractional power of it
+fn coefficients_in(p: &Polynomial, name: &str) -> Option<Vec<Polynomial>> {
+    let unknown = Symbol::intern(name);
+    let mut coefficients: Vec<Polynomial> = Vec::new();
+    for (monomial, coefficient) in p.terms() {
+        let exponent = monomial.exponent(unknown);
+        if exponent < 0.0 || exponent.fract() != 0.0 {
+            return None;
+        }
+        let degree = exponent as usize;
+        if coefficients.len() <= degree {
+            coefficients.resize(degree + 1, Polynomial::new());
+        }
+        coefficients[degree].add_term(
+            monomial * &Monomial::variable(name, -exponent),
+            coefficient.clone(),
+        );
+    }
+    Some(coefficients)
+}
+
+/// Finish a branch whose unknown at `index` has an irrational value, solving each earlier
+/// unknown from the basis elements at its level by [`level_roots`], e.g. x - y = 0 giving
+/// x = y, with one branch for each of its values
+fn back_substitute(
+    basis: &[SparsePolynomial],
+    values: &[Option<Rational>],
+    index: usize,
+    root: Polynomial,
+    variables: &[String],
+    order: MonomialOrder,
+) -> Result<Vec<PolynomialSolution>, InterpreterError> {
+    let mut branches = vec![vec![(variables[index].clone(), root)]];
+    for k in (0..index).rev() {
+        let mut next = Vec::new();
+        for roots in branches {
+            let mut level = Vec::new();
+            for g in basis.iter().filter(|g| g.main_variable() == Some(k)) {
+                let mut p = from_polynomial(&g.substitute(values, order), variables);
+                for (name, root) in roots.iter() {
+                    p = substitute_value(&p, name, root);
+                }
+                if !p.is_zero() {
+                    level.push(p);
+                }
+            }
+            if level.is_empty() {
+                return Err(InterpreterError::unsupported(format!(
+                    "{} is free once the other unknowns have irrational values",
+                    variables[k]
+                )));
+            }
+            let Some(values) = level_roots(&level, &variables[k])? else {
+                return Err(unsolvable(&level[0]));
+            };
+            for value in values {
+                let mut roots = roots.clone();
+                roots.push((variables[k].clone(), value));
+                next.push(roots);
+            }
+        }
+        branches = next;
+    }
+    Ok(branches
+        .into_iter()
+        .map(|mut roots| {
+            roots.reverse();
+            PolynomialSolution {
+                assignments: assignments(values, variables),
+                roots,
+                remaining: Vec::new(),
+            }
+        })
+        .collect())
+}
+
+/// The branch left once the unknown at `index` has no value that can be given, because
+/// nothing constrains it or it depends on a constant
+///
+/// An earlier unknown with a basis element linear in it over a number is solved in terms
+/// of the others, e.g. x = -y from x + y with y free, as a system of linear equations
+/// would be; the other elements are left as equations.
+fn unsolved(
+    basis: &[SparsePolynomial],
+    values: &[Option<Rational>],
+    index: usize,
+    variables: &[String],
+    order: MonomialOrder,
+) -> Result<PolynomialSolution, InterpreterError> {
+    let mut roots: Vec<(String, Polynomial)> = Vec::new();
+    let mut remaining = Vec::new();
+    for k in (0..=index).rev() {
+        let mut level = Vec::new();
+        for g in basis.iter().filter(|g| g.main_variable() == Some(k)) {
+            let mut p = from_polynomial(&g.substitute(values, order), variables);
+            for (name, root) in roots.iter() {
+                p = substitute_value(&p, name, root);
+            }
+            if !p.is_zero() {
+                level.push(p);
+            }
+        }
+        let name = &variables[k];
+        let root = level
+            .iter()
+            .find_map(|p| match coefficients_in(p, name)?.as_slice() {
+                [c, b] => Some(c.scale(-b.as_constant()?.recip())),
+                _ => None,
+            });
+        match root {
+            Some(root)
+                if level
+                    .iter()
+                    .all(|p| substitute_value(p, name, &root).is_zero()) =>
+            {
+                roots.push((name.clone(), root))
+            }
+            _ => {
+                for p in level {
+                    let cleared = to_polynomial(&p, variables, order)?.clear_denominators();
+                    remaining.push(from_polynomial(&cleared, variables));
+                }
+            }
+        }
+    }
+    Ok(PolynomialSolution {
+        assignments: assignments(values, variables),
+        roots,
+        remaining,
+    })
+}
+
+/// The value of an unknown from a polynomial linear in it, e.g. y = -sqrt(2) + 1 from
+/// sqrt(2)y - sqrt(2) + 2, or None when it is not linear or its coefficient, a number
+/// written with constants, does not divide out to a polynomial
+fn linear_root(p: &Polynomial, name: &str) -> Option<Polynomial> {
+    match coefficients_in(p, name)?.as_slice() {
+        [c, b] if !b.is_zero() => quotient(c.scale(-1), b.clone()),
+        _ => None,
+    }
+}
+
+/// A quotient of numbers written with constants as a polynomial, when what is left
+/// below once it is reduced is a single term, e.g. 1/sqrt(2) = sqrt(2)/2
+fn quotient(numerator: Polynomial, denominator: Polynomial) -> Option<Polynomial> {
+    let value = RationalFunction::from(numerator)
+        .div(&denominator.into())
+        .ok()?
+        .reduce();
+    let terms: Vec<_> = value.denominator.terms().iter().collect();
+    match terms.as_slice() {
+        [(monomial, coefficient)] => {
+            let mut reciprocal = Polynomial::new();
+            reciprocal.add_term(monomial.recip(), coefficient.recip());
+            Some(&value.numerator * &reciprocal)
+        }
+        _ => None,
+    }
+}
+
+/// Replace a variable by a value, e.g. y by sqrt(2) in x - y
+fn substitute_value(polynomial: &Polynomial, name: &str, value: &Polynomial) -> Polynomial {
+    let unknown = Symbol::intern(name);
+    let mut result = Polynomial::new();
+    for (monomial, coefficient) in polynomial.terms() {
+        let exponent = monomial.exponent(unknown);
+        let mut rest = Polynomial::new();
+        rest.add_term(
+            monomial * &Monomial::variable(name, -exponent),
+            coefficient.clone(),
+        );
+        result = &result + &(&rest * &value.pow(exponent as u32));
+    }
+    result
+}
+
+fn assignments(values: &[Option<Rational>], variables: &[String]) -> Vec<(String, Rational)> {
+    variables
+        .iter()
+        .zip(values)
+        .filter_map(|(name, value)| value.as_ref().map(|v| (name.clone(), v.clone())))
+        .collect()
+}
+
+/// Dense form of a polynomial that only depends on the variable at `index`
+fn to_univariate(polynomial: &SparsePolynomial, index: usize) -> UnivariatePolynomial {
+    let degree = polynomial
+        .terms
+        .iter()
+        .map(|(e, _)| e[index] as usize)
+        .max()
+        .unwrap_or(0);
+    let mut coefficients = vec![Rational::zero(); degree + 1];
+    for (exponents, coefficient) in polynomial.terms.iter() {
+        coefficients[exponents[index] as usize] = coefficient.clone();
+    }
+    UnivariatePolynomial::new(coefficients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polynomial(terms: &[(&[u32], i128)], order: MonomialOrder) -> SparsePolynomial {
+        SparsePolynomial::from_terms(
+            terms
+                .iter()
+                .map(|(e, c)| (e.to_vec(), Rational::from(*c)))
+                .collect(),
+            order,
+        )
+    }
+
+    #[test]
+    fn test_divide() {
+        // x^2 y + x y^2 + y^2 divided by x y - 1 and y^2 - 1 (Cox, Little & O'Shea)
+        let order = MonomialOrder::Lex;
+        let (quotients, remainder) = polynomial(&[(&[2, 1], 1), (&[1, 2], 1), (&[0, 2], 1)], order)
+            .divide(
+                &[
+                    polynomial(&[(&[1, 1], 1), (&[0, 0], -1)], order),
+                    polynomial(&[(&[0, 2], 1), (&[0, 0], -1)], order),
+                ],
+                order,
+            );
+        assert_eq!(
+            quotients,
+            vec![
+                polynomial(&[(&[1, 0], 1), (&[0, 1], 1)], order),
+                polynomial(&[(&[0, 0], 1)], order),
+            ]
+        );
+        assert_eq!(
+            remainder,
+            polynomial(&[(&[1, 0], 1), (&[0, 1], 1), (&[0, 0], 1)], order)
+        );
+    }
+
+    #[test]
+    fn test_buchberger() {
+        // x^2 + y^2 - 1, x - y under lex gives x - y, y^2 - 1/2
+        let order = MonomialOrder::Lex;
+        let basis = buchberger(
+            vec![
+                polynomial(&[(&[2, 0], 1), (&[0, 2], 1), (&[0, 0], -1)], order),
+                polynomial(&[(&[1, 0], 1), (&[0, 1], -1)], order),
+            ],
+            order,
+        )
+        .unwrap();
+        assert_eq!(
+            basis,
+            vec![
+                polynomial(&[(&[1, 0], 1), (&[0, 1], -1)], order),
+                SparsePolynomial::from_terms(
+                    vec![
+                        (vec![0, 2], Rational::one()),
+                        (vec![0, 0], Rational::new(-1, 2))
+                    ],
+                    order
+                ),
+            ]
+        );
+
+        // x y - 1, x - 1 and y - 2 generate the whole ring
+        let basis = buchberger(
+            vec![
+                polynomial(&[(&[1, 1], 1), (&[0, 0], -1)], order),
+                polynomial(&[(&[1, 0], 1), (&[0, 0], -1)], order),
+                polynomial(&[(&[0, 1], 1), (&[0, 0], -2)], order),
+            ],
+            order,
+        )
+        .unwrap();
+        assert_eq!(basis, vec![polynomial(&[(&[0, 0], 1)], order)]);
+    }
+}
@@ -0,0 +1,534 @@
+//! Structured output as JSON, for programs that would otherwise read the printed string
+//!
+//! Every document is an object carrying the schema `version` beside one of `tokens`,
+//! `ast`, `result` or `error`:
+//!
+//! ```json
+//! {"version": 1, "result": {"type": "sum", "terms": [...]}}
+//! ```
+//!
+//! The version is raised whenever a field is renamed or removed, or a value changes
+//! meaning; new fields and new `type`s may be added without raising it.
+//!
+//! **Tokens** are `{"type": "number", "value": 2.0}`, `{"type": "identifier", "value": "x"}`
+//! or `{"type": "symbol", "value": "+"}`. Numbers, in tokens, trees and results alike, are
+//! floats written with a decimal point, while the parts of a `rational` are integers.
+//!
+//! **Expression trees** are objects tagged with `type`, read back by [`read_ast`]:
+//!
+//! | `type` | fields |
+//! |---|---|
+//! | `number` | `value` |
+//! | `operation` | `operator` (`"*"`, `"/"` or `"^"`), `lhs`, `rhs` |
+//! | `variable` | `name`, `exponent` |
+//! | `function` | `name`, `arguments` |
+//! | `equation` | `lhs`, `rhs` |
+//! | `system` | `equations` |
+//! | `expression` | `terms`, a sum |
+//! | `term` | `coefficient`, `factors`, a product |
+//! | `assumption` | `names`, `property` (`positive`, `negative`, `nonzero`, `integer` or `real`) |
+//!
+//! **Results** are objects tagged with `type` as well, each value written as the
+//! operations it applies:
+//!
+//! | `type` | fields |
+//! |---|---|
+//! | `number` | `value`, null when not finite |
+//! | `rational` | `numerator`, `denominator` |
+//! | `variable` | `name` |
+//! | `sum` | `terms` |
+//! | `product` | `factors` |
+//! | `negation` | `value` |
+//! | `quotient` | `numerator`, `denominator` |
+//! | `power` | `base`, `exponent` |
+//! | `root` | `radicand`, `index` |
+//! | `function` | `name`, `arguments` |
+//! | `equation` | `lhs`, `rhs` |
+//! | `list` | `elements`, e.g. the branches of a solution set |
+//! | `set` | `elements` |
+//! | `text` | `text`, a phrase such as `No solution` |
+//! | `free` | `name`, an unknown left free in a solution |
+//! | `assumption` | `names`, `property` |
+//!
+//! **Errors** are `{"code", "kind", "message", "span"}`: the stable code of the kind of
+//! error such as `E202`, the kind in words such as `unbalanced_parenthesis`, the message,
+//! and the span of the input at fault as `[start, end)` character offsets, all of it for
+//! an error raised while evaluating, or null when the error is not tied to a place. See
+//! [`InterpreterError::code`] for the codes.
+//!
+//! **Evaluations**, one per input of the binary run with `--format json`, are written
+//! whole, each on a line of its own:
+//!
+//! ```json
+//! {"version": 1, "input": "x^2 + -1", "result": "x^2 - 1", "latex": "x^2 - 1",
+//!  "latex_error": null, "error": null, "microseconds": 87}
+//! ```
+//!
+//! `result` is the canonical text, which reads back in as the same value. When the
+//! input fails, `result` and `latex` are null and `error` is the error as above, with the
+//! `stage` that failed (`lex`, `parse` or `interpret`) first. When only writing the result
+//! as LaTeX fails, `latex` is null and `latex_error` is that error. `microseconds` is the time taken to lex, parse and interpret the input, including writing the result out as LaTeX.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property,
+    lexer::Token,
+    monomial::Monomial,
+    monomial_order::MonomialOrder,
+    parser::ASTNode,
+    polynomial::Polynomial,
+    render::{Factors, Notation},
+    InterpreterError, Span,
+};
+
+/// Version of the schema every document is written with
+pub const VERSION: u32 = 1;
+
+/// An expression tree node as it is written in JSON, with named fields in place of
+/// the positions `ASTNode` keeps them in
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Node {
+    Number {
+        value: f64,
+    },
+    Operation {
+        operator: String,
+        lhs: Box<ASTNode>,
+        rhs: Box<ASTNode>,
+    },
+    Variable {
+        name: String,
+        exponent: Box<ASTNode>,
+    },
+    Function {
+        name: String,
+        arguments: Vec<ASTNode>,
+    },
+    Equation {
+        lhs: Box<ASTNode>,
+        rhs: Box<ASTNode>,
+    },
+    System {
+        equations: Vec<ASTNode>,
+    },
+    Expression {
+        terms: Vec<ASTNode>,
+    },
+    Term {
+        coefficient: Box<ASTNode>,
+        factors: Vec<ASTNode>,
+    },
+    Assumption {
+        names: Vec<String>,
+        property: Property,
+    },
+}
+
+impl From<ASTNode> for Node {
+    fn from(node: ASTNode) -> Node {
+        match node {
+            ASTNode::Number(value) => Node::Number { value },
+            ASTNode::Operation(operator, lhs, rhs) => Node::Operation { operator, lhs, rhs },
+            ASTNode::Variable(name, exponent) => Node::Variable { name, exponent },
+            ASTNode::Function(name, arguments) => Node::Function { name, arguments },
+            ASTNode::Equation(lhs, rhs) => Node::Equation { lhs, rhs },
+            ASTNode::System(equations) => Node::System { equations },
+            ASTNode::Expression(terms) => Node::Expression { terms },
+            ASTNode::Term(coefficient, factors) => Node::Term {
+                coefficient,
+                factors,
+            },
+            ASTNode::Assumption(names, property) => Node::Assumption { names, property },
+        }
+    }
+}
+
+impl From<Node> for ASTNode {
+    fn from(node: Node) -> ASTNode {
+        match node {
+            Node::Number { value } => ASTNode::Number(value),
+            Node::Operation { operator, lhs, rhs } => ASTNode::Operation(operator, lhs, rhs),
+            Node::Variable { name, exponent } => ASTNode::Variable(name, exponent),
+            Node::Function { name, arguments } => ASTNode::Function(name, arguments),
+            Node::Equation { lhs, rhs } => ASTNode::Equation(lhs, rhs),
+            Node::System { equations } => ASTNode::System(equations),
+            Node::Expression { terms } => ASTNode::Expression(terms),
+            Node::Term {
+                coefficient,
+                factors,
+            } => ASTNode::Term(coefficient, factors),
+            Node::Assumption { names, property } => ASTNode::Assumption(names, property),
+        }
+    }
+}
+
+/// The stage of evaluation an input failed at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Lex,
+    Parse,
+    Interpret,
+}
+
+/// An input that failed to evaluate
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    pub stage: Stage,
+    pub code: &'static str,
+    pub kind: &'static str,
+    pub message: String,
+    /// the input at fault, when the error is tied to a place
+    pub span: Option<Span>,
+}
+
+impl Failure {
+    pub fn new(stage: Stage, error: &InterpreterError) -> Failure {
+        Failure {
+            stage,
+            code: error.code(),
+            kind: error.kind(),
+            message: error.message(),
+            span: error.span(),
+        }
+    }
+}
+
+/// One input evaluated, as the binary reports it
+#[derive(Debug, Serialize)]
+pub struct Evaluation {
+    pub version: u32,
+    pub input: String,
+    /// the result in canonical form
+    pub result: Option<String>,
+    pub latex: Option<String>,
+    /// why the result could not be written as LaTeX, when only that failed
+    pub latex_error: Option<Failure>,
+    pub error: Option<Failure>,
+    pub microseconds: u128,
+}
+
+impl Evaluation {
+    /// A document on a single line
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// The tokens of a lexed input as a document
+pub fn tokens(tokens: &VecDeque<Token>) -> String {
+    document("tokens", &serde_json::to_string(tokens).unwrap())
+}
+
+/// An expression tree as a document
+pub fn ast(node: &ASTNode) -> String {
+    document("ast", &serde_json::to_string(node).unwrap())
+}
+
+/// Read an expression tree back from a document written by [`ast`]
+pub fn read_ast(json: &str) -> Result<ASTNode, InterpreterError> {
+    #[derive(Deserialize)]
+    struct Document {
+        version: u32,
+        ast: ASTNode,
+    }
+    let document: Document = serde_json::from_str(json).map_err(|e| {
+        InterpreterError::unsupported(format!("Invalid JSON expression tree: {}", e))
+    })?;
+    match document.version {
+        VERSION => Ok(document.ast),
+        version => Err(InterpreterError::unsupported(format!(
+            "Unsupported JSON version {}, expected {}",
+            version, VERSION
+        ))),
+    }
+}
+
+/// An error as a document
+pub fn error(error: &InterpreterError) -> String {
+    document("error", &serde_json::to_string(error).unwrap())
+}
+
+/// A result as a document, null when there is none
+pub fn result(output: &str) -> String {
+    match output.is_empty() {
+        true => document("result", "null"),
+        false => document("result", output),
+    }
+}
+
+/// A versioned JSON document of the operations a result is made of
+pub struct Json;
+
+impl Notation for Json {
+    type Piece = String;
+
+    fn write(&self, piece: String) -> String {
+        piece
+    }
+
+    fn read(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// A variable
+    fn name(&self, name: &str) -> String {
+        object("variable", &[("name", string(name))])
+    }
+
+    /// A number, null when it is not finite
+    fn number(&self, n: f64) -> String {
+        object("number", &[("value", serde_json::to_string(&n).unwrap())])
+    }
+
+    /// An exact rational, e.g. `{"type": "rational", "numerator": -1, "denominator": 2}`
+    fn rational(&self, r: &Rational) -> String {
+        object(
+            "rational",
+            &[
+                ("numerator", r.numerator().to_string()),
+                ("denominator", r.denominator().to_string()),
+            ],
+        )
+    }
+
+    /// A single term as a product, with the coefficient 1 left out and -1 as a negation
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, order: MonomialOrder) -> String {
+        let factors = self.factors(monomial, order);
+        match coefficient {
+            c if factors.is_empty() => self.rational(c),
+            c if c.is_one() => times(factors),
+            c if *c == -1 => self.negate(times(factors)),
+            c => times([vec![self.rational(c)], factors].concat()),
+        }
+    }
+
+    /// Terms joined into a sum
+    fn sum(&self, terms: Vec<String>) -> String {
+        match terms.len() {
+            0 => self.rational(&Rational::zero()),
+            1 => terms.into_iter().next().unwrap(),
+            _ => object("sum", &[("terms", array(&terms))]),
+        }
+    }
+
+    /// A numeric power of a base, left off when it is 1
+    fn power(&self, base: String, exponent: f64) -> String {
+        match exponent {
+            1.0 => base,
+            e => object("power", &[("base", base), ("exponent", self.number(e))]),
+        }
+    }
+
+    /// A base as it is, as a power holds its base apart already
+    fn parenthesize(&self, piece: String) -> String {
+        piece
+    }
+
+    /// A product of powers, with the coefficient 1 left out
+    fn times(&self, coefficient: &Rational, factors: Vec<String>) -> String {
+        match coefficient.is_one() && !factors.is_empty() {
+            true => times(factors),
+            false => times([vec![self.rational(coefficient)], factors].concat()),
+        }
+    }
+
+    fn negate(&self, piece: String) -> String {
+        object("negation", &[("value", piece)])
+    }
+
+    fn over(&self, numerator: String, denominator: String) -> String {
+        object(
+            "quotient",
+            &[("numerator", numerator), ("denominator", denominator)],
+        )
+    }
+
+    /// An equation `lhs = rhs`
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        object(
+            "equation",
+            &[("lhs", lhs.to_string()), ("rhs", rhs.to_string())],
+        )
+    }
+
+    /// Several results, such as the assignments of a solution
+    fn list(&self, elements: Vec<String>) -> String {
+        object("list", &[("elements", array(&elements))])
+    }
+
+    /// Expressions listed as a set
+    fn set(&self, elements: Vec<String>) -> String {
+        object("set", &[("elements", array(&elements))])
+    }
+
+    /// A phrase such as `No solution` in place of a result
+    fn text(&self, words: &str) -> String {
+        object("text", &[("text", string(words))])
+    }
+
+    /// An unknown left free in a solution
+    fn free(&self, variable: &str) -> String {
+        object("free", &[("name", string(variable))])
+    }
+
+    /// An assumption on the listed variables
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        object(
+            "assumption",
+            &[
+                ("names", serde_json::to_string(names).unwrap()),
+                ("property", serde_json::to_string(&property).unwrap()),
+            ],
+        )
+    }
+
+    fn finish(&self, output: String) -> String {
+        result(&output)
+    }
+}
+
+impl Factors for Json {
+    fn root(&self, radicand: String, index: u32) -> String {
+        object(
+            "root",
+            &[("radicand", radicand), ("index", index.to_string())],
+        )
+    }
+
+    fn function(&self, name: &str, argument: String, exponent: f64) -> String {
+        let application = object(
+            "function",
+            &[("name", string(name)), ("arguments", array(&[argument]))],
+        );
+        self.power(application, exponent)
+    }
+
+    /// A base raised to a symbolic exponent, e.g. 2^x
+    fn raise(&self, _: &Polynomial, written: String, exponent: String) -> String {
+        object("power", &[("base", written), ("exponent", exponent)])
+    }
+}
+
+/// Factors multiplied together, a single factor standing alone
+fn times(factors: Vec<String>) -> String {
+    match factors.len() {
+        1 => factors.into_iter().next().unwrap(),
+        _ => object("product", &[("factors", array(&factors))]),
+    }
+}
+
+/// An object tagged with its type, from fields already written as JSON
+fn object(kind: &str, fields: &[(&str, String)]) -> String {
+    let mut members = vec![format!("\"type\":{}", string(kind))];
+    members.extend(
+        fields
+            .iter()
+            .map(|(field, value)| format!("{}:{}", string(field), value)),
+    );
+    format!("{{{}}}", members.join(","))
+}
+
+/// A document of the current version holding one field
+fn document(field: &str, value: &str) -> String {
+    format!("{{\"version\":{},{}:{}}}", VERSION, string(field), value)
+}
+
+fn array(elements: &[String]) -> String {
+    format!("[{}]", elements.join(","))
+}
+
+fn string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polynomial() {
+        let x = Polynomial::variable("x", 1.0);
+        let p = &x.pow(2).scale(3) - &Polynomial::constant(Rational::new(1, 2));
+        assert_eq!(
+            Json.polynomial(&p, MonomialOrder::Lex),
+            "{\"type\":\"sum\",\"terms\":[\
+             {\"type\":\"product\",\"factors\":[{\"type\":\"rational\",\"numerator\":3,\"denominator\":1},\
+             {\"type\":\"power\",\"base\":{\"type\":\"variable\",\"name\":\"x\"},\
+             \"exponent\":{\"type\":\"number\",\"value\":2.0}}]},\
+             {\"type\":\"rational\",\"numerator\":-1,\"denominator\":2}]}"
+        );
+        assert_eq!(
+            Json.polynomial(&x.scale(-1), MonomialOrder::Lex),
+            "{\"type\":\"negation\",\"value\":{\"type\":\"variable\",\"name\":\"x\"}}"
+        );
+        assert_eq!(
+            Json.number(f64::INFINITY),
+            "{\"type\":\"number\",\"value\":null}"
+        );
+    }
+
+    #[test]
+    fn test_ast() {
+        let ast = ASTNode::Equation(
+            Box::new(ASTNode::Variable(
+                "x".to_string(),
+                Box::new(ASTNode::Number(2.0)),
+            )),
+            Box::new(ASTNode::Number(4.0)),
+        );
+        let json = self::ast(&ast);
+        assert_eq!(
+            json,
+            "{\"version\":1,\"ast\":{\"type\":\"equation\",\
+             \"lhs\":{\"type\":\"variable\",\"name\":\"x\",\"exponent\":{\"type\":\"number\",\"value\":2.0}},\
+             \"rhs\":{\"type\":\"number\",\"value\":4.0}}}"
+        );
+        assert_eq!(read_ast(&json).unwrap(), ast);
+        assert!(read_ast(&json.replace("\"version\":1", "\"version\":2")).is_err());
+        assert!(read_ast("{\"version\":1,\"ast\":{\"type\":\"matrix\"}}").is_err());
+    }
+
+    #[test]
+    fn test_evaluation() {
+        let evaluation = Evaluation {
+            version: VERSION,
+            input: "(x".to_string(),
+            result: None,
+            latex: None,
+            latex_error: None,
+            error: Some(Failure::new(
+                Stage::Parse,
+                &InterpreterError::unbalanced("Expected ')' to close the group")
+                    .at(Some(Span::end_of(2))),
+            )),
+            microseconds: 7,
+        };
+        assert_eq!(
+            evaluation.to_json(),
+            "{\"version\":1,\"input\":\"(x\",\"result\":null,\"latex\":null,\
+             \"latex_error\":null,\"error\":{\"stage\":\"parse\",\"code\":\"E202\",\"kind\":\"unbalanced_parenthesis\",\
+             \"message\":\"Expected ')' to close the group\",\"span\":[2,2]},\"microseconds\":7}"
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        let tokens = VecDeque::from([
+            Token::Number(2.0),
+            Token::Identifier("x".to_string()),
+            Token::Symbol('+'),
+        ]);
+        assert_eq!(
+            self::tokens(&tokens),
+            "{\"version\":1,\"tokens\":[{\"type\":\"number\",\"value\":2.0},\
+             {\"type\":\"identifier\",\"value\":\"x\"},{\"type\":\"symbol\",\"value\":\"+\"}]}"
+        );
+    }
+}
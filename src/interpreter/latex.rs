@@ -0,0 +1,420 @@
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property,
+    monomial::Monomial,
+    monomial_order::MonomialOrder,
+    parser::ASTNode,
+    polynomial::Polynomial,
+    render::{self, Factors, Notation},
+    symbol::GREEK,
+};
+
+/// Functions with a LaTeX command of their own, by the name they are written with
+const COMMANDS: [(&str, &str); 14] = [
+    ("sin", "\\sin"),
+    ("cos", "\\cos"),
+    ("tan", "\\tan"),
+    ("csc", "\\csc"),
+    ("sec", "\\sec"),
+    ("cot", "\\cot"),
+    ("asin", "\\arcsin"),
+    ("acos", "\\arccos"),
+    ("atan", "\\arctan"),
+    ("ln", "\\ln"),
+    ("log", "\\log"),
+    ("log10", "\\log_{10}"),
+    ("arg", "\\arg"),
+    ("exp", "\\exp"),
+];
+
+/// LaTeX for typesetting, e.g. `x^{-2} + \frac{1}{2}`
+pub struct Latex;
+
+impl Notation for Latex {
+    type Piece = String;
+
+    const SIGN_BEFORE_FRACTION: bool = true;
+
+    fn write(&self, piece: String) -> String {
+        piece
+    }
+
+    fn read(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// A variable name, with Greek letters as commands, subscripts for trailing digits or
+    /// an underscore, and multi-letter names upright, e.g. `\theta`, `x_{10}` or `\mathrm{rate}`
+    fn name(&self, name: &str) -> String {
+        if let Some((base, subscript)) = name.split_once('_') {
+            return format!("{}_{}", self.name(base), group(&self.name(subscript)));
+        }
+        let digits = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+        if !digits.is_empty()
+            && digits.len() < name.len()
+            && digits.chars().all(|c| c.is_ascii_digit())
+        {
+            let base = &name[..name.len() - digits.len()];
+            return format!("{}_{}", self.name(base), group(digits));
+        }
+        if GREEK.iter().any(|(greek, _)| *greek == name) {
+            // a letter written by its command, `alpha` as `\alpha`
+            format!("\\{}", name)
+        } else if name.chars().count() > 1 && !name.chars().all(|c| c.is_ascii_digit()) {
+            format!("\\mathrm{{{}}}", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// A number, as a fraction when it is a rational with a small denominator, e.g. `\frac{1}{2}`
+    fn number(&self, n: f64) -> String {
+        match Rational::from_f64(n) {
+            Some(r) => coefficient(&r),
+            None => n.to_string(),
+        }
+    }
+
+    /// A rational, e.g. `3` or `-\frac{1}{2}`
+    fn rational(&self, r: &Rational) -> String {
+        if r.is_integer() {
+            return r.numerator().to_string();
+        }
+        let sign = if r.is_negative() { "-" } else { "" };
+        let r = r.abs();
+        format!("{}\\frac{{{}}}{{{}}}", sign, r.numerator(), r.denominator())
+    }
+
+    /// A single term, coefficient then monomial, with the coefficient 1 left out
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, order: MonomialOrder) -> String {
+        let factors = self
+            .factors(monomial, order)
+            .into_iter()
+            .fold(String::new(), |output, factor| juxtapose(&output, &factor));
+        if factors.is_empty() {
+            return self::coefficient(coefficient);
+        }
+        let coefficient = match coefficient {
+            c if c.is_one() => String::new(),
+            c if *c == -1 => "-".to_string(),
+            c => self::coefficient(c),
+        };
+        juxtapose(&coefficient, &factors)
+    }
+
+    /// Terms joined into a sum, with a negative term subtracted, e.g. `x - 1`
+    fn sum(&self, terms: Vec<String>) -> String {
+        if terms.is_empty() {
+            return "0".to_string();
+        }
+        let mut output = String::new();
+        for (i, term) in terms.into_iter().enumerate() {
+            match (i, term.strip_prefix('-')) {
+                (0, _) => output.push_str(&term),
+                (_, Some(rest)) => output.push_str(&format!(" - {}", rest)),
+                (_, None) => output.push_str(&format!(" + {}", term)),
+            }
+        }
+        output
+    }
+
+    /// A numeric exponent written on a base, e.g. `x^2` or `x^{-2}`, left off when it is 1
+    fn power(&self, base: String, exponent: f64) -> String {
+        if exponent == 1.0 {
+            base
+        } else {
+            format!("{}^{}", base, group(&self.number(exponent)))
+        }
+    }
+
+    fn parenthesize(&self, expression: String) -> String {
+        parenthesize(&expression)
+    }
+
+    /// Factors written side by side after the coefficient, e.g. `2x\left(x + 1\right)^2`
+    fn times(&self, coefficient: &Rational, factors: Vec<String>) -> String {
+        if coefficient.is_zero() {
+            return "0".to_string();
+        }
+        let output = if *coefficient == -1 && !factors.is_empty() {
+            "-".to_string()
+        } else if !coefficient.is_one() || factors.is_empty() {
+            self.rational(coefficient)
+        } else {
+            String::new()
+        };
+        factors
+            .iter()
+            .fold(output, |output, factor| juxtapose(&output, factor))
+    }
+
+    fn negate(&self, piece: String) -> String {
+        format!("-{}", piece)
+    }
+
+    fn over(&self, numerator: String, denominator: String) -> String {
+        format!("\\frac{{{}}}{{{}}}", numerator, denominator)
+    }
+
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        format!("{} = {}", lhs, rhs)
+    }
+
+    fn list(&self, elements: Vec<String>) -> String {
+        elements.join(", ")
+    }
+
+    /// Expressions listed in set braces, e.g. `\left\{x = 1, y = 2\right\}`
+    fn set(&self, elements: Vec<String>) -> String {
+        format!("\\left\\{{{}\\right\\}}", elements.join(", "))
+    }
+
+    /// Words written upright among the mathematics, e.g. `\text{No solution}`
+    fn text(&self, words: &str) -> String {
+        format!("\\text{{{}}}", words)
+    }
+
+    fn free(&self, variable: &str) -> String {
+        format!("{}{}", self.name(variable), self.text(" free"))
+    }
+
+    /// An assumption such as `x, y > 0` or `n \in \mathbb{Z}`
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        let names: Vec<String> = names.iter().map(|n| self.name(n)).collect();
+        let property = match property {
+            Property::Positive => "> 0",
+            Property::Negative => "< 0",
+            Property::Nonzero => "\\neq 0",
+            Property::Integer => "\\in \\mathbb{Z}",
+            Property::Real => "\\in \\mathbb{R}",
+        };
+        format!("{} {}", names.join(", "), property)
+    }
+}
+
+impl Factors for Latex {
+    const ROOTS: bool = true;
+
+    fn root(&self, radicand: String, index: u32) -> String {
+        match index {
+            2 => format!("\\sqrt{{{}}}", radicand),
+            q => format!("\\sqrt[{}]{{{}}}", q, radicand),
+        }
+    }
+
+    fn function(&self, name: &str, argument: String, exponent: f64) -> String {
+        if name == "abs" {
+            return self.power(format!("\\left|{}\\right|", argument), exponent);
+        }
+        let command = command(name);
+        match exponent {
+            1.0 => format!("{}{}", command, parenthesize(&argument)),
+            // sin^2(x) rather than sin(x)^2, as it is usually written
+            e => format!("{}{}", self.power(command, e), parenthesize(&argument)),
+        }
+    }
+
+    /// A base raised to a symbolic exponent, with a base other than a variable or
+    /// a non-negative number in parentheses, e.g. `2^{x}` or `\left(x + 1\right)^{n}`
+    fn raise(&self, base: &Polynomial, written: String, exponent: String) -> String {
+        let written = match render::is_bare(base) {
+            true => written,
+            false => parenthesize(&written),
+        };
+        format!("{}^{}", written, group(&exponent))
+    }
+}
+
+/// An exact coefficient, as a fraction when its denominator is small and a decimal otherwise
+fn coefficient(c: &Rational) -> String {
+    match render::is_small(c) {
+        true => Latex.rational(c),
+        false => c.to_f64().to_string(),
+    }
+}
+
+/// An expression tree as it was written, e.g. `\frac{x^2 + 1}{2}` for `(x^2 + 1)/2`
+pub fn ast(node: &ASTNode) -> String {
+    match node {
+        ASTNode::Number(n) => Latex.number(*n),
+        ASTNode::Variable(variable, exponent) => match exponent.as_ref() {
+            ASTNode::Number(e) if *e == 1.0 => Latex.name(variable),
+            exponent => format!("{}^{}", Latex.name(variable), group(&ast(exponent))),
+        },
+        ASTNode::Operation(operator, lhs, rhs) => match operator.as_str() {
+            "/" => format!("\\frac{{{}}}{{{}}}", operand(lhs), operand(rhs)),
+            "*" => format!("{} \\cdot {}", ast(lhs), ast(rhs)),
+            _ => raised(lhs, &ast(rhs)),
+        },
+        ASTNode::Function(function, arguments) => call(function, arguments, None),
+        ASTNode::Equation(lhs, rhs) => format!("{} = {}", ast(lhs), ast(rhs)),
+        ASTNode::System(equations) => format!(
+            "\\begin{{cases}} {} \\end{{cases}}",
+            equations.iter().map(ast).collect::<Vec<_>>().join(" \\\\ ")
+        ),
+        ASTNode::Expression(terms) => Latex.sum(terms.iter().map(ast).collect()),
+        ASTNode::Term(coefficient, factors) => {
+            let coefficient = match coefficient.as_ref() {
+                ASTNode::Number(c) if *c == 1.0 && !factors.is_empty() => String::new(),
+                ASTNode::Number(c) if *c == -1.0 && !factors.is_empty() => "-".to_string(),
+                coefficient => ast(coefficient),
+            };
+            factors.iter().fold(coefficient, |output, factor| {
+                let printed = match factor {
+                    ASTNode::Expression(_) => parenthesize(&ast(factor)),
+                    _ => ast(factor),
+                };
+                juxtapose(&output, &printed)
+            })
+        }
+        ASTNode::Assumption(names, property) => Latex.assumption(names, *property),
+    }
+}
+
+/// A function call from an expression tree, optionally raised to a power
+fn call(function: &str, arguments: &[ASTNode], exponent: Option<&str>) -> String {
+    let raise = |printed: String| match exponent {
+        Some(e) => format!("{}^{}", printed, group(e)),
+        None => printed,
+    };
+    let listed = || arguments.iter().map(operand).collect::<Vec<_>>().join(", ");
+    match (function, arguments) {
+        ("sqrt", [argument]) => raise(format!("\\sqrt{{{}}}", operand(argument))),
+        ("root", [argument, index]) => raise(format!(
+            "\\sqrt[{}]{{{}}}",
+            operand(index),
+            operand(argument)
+        )),
+        ("pow", [base, power]) => raised(base, &ast(power)),
+        ("abs", [argument]) => raise(format!("\\left|{}\\right|", operand(argument))),
+        ("conj", [argument]) => raise(format!("\\overline{{{}}}", operand(argument))),
+        ("exp", [argument]) => raise(format!("e^{}", group(&operand(argument)))),
+        ("log", [argument, base]) => format!(
+            "\\log_{}{}",
+            group(&operand(base)),
+            parenthesize(&operand(argument))
+        ),
+        _ => {
+            let command = command(function);
+            match exponent {
+                Some(e) if COMMANDS.iter().any(|(name, _)| *name == function) => {
+                    format!("{}^{}{}", command, group(e), parenthesize(&listed()))
+                }
+                _ => raise(format!("{}{}", command, parenthesize(&listed()))),
+            }
+        }
+    }
+}
+
+/// A base from an expression tree raised to a written exponent
+fn raised(base: &ASTNode, exponent: &str) -> String {
+    match base {
+        ASTNode::Number(n) if *n >= 0.0 && n.fract() == 0.0 => {
+            format!("{}^{}", Latex.number(*n), group(exponent))
+        }
+        ASTNode::Variable(..) => format!("{}^{}", ast(base), group(exponent)),
+        ASTNode::Function(function, arguments) => call(function, arguments, Some(exponent)),
+        _ => format!("{}^{}", parenthesize(&operand(base)), group(exponent)),
+    }
+}
+
+/// An operand of a fraction, root or function, without the parentheses that
+/// grouped it in the written form, so `(x + 1)/2` gives `x + 1` over 2
+fn operand(node: &ASTNode) -> String {
+    match node {
+        ASTNode::Term(coefficient, factors)
+            if **coefficient == ASTNode::Number(1.0) && factors.len() == 1 =>
+        {
+            match &factors[0] {
+                ASTNode::Expression(_) => ast(&factors[0]),
+                _ => ast(node),
+            }
+        }
+        ASTNode::Expression(terms) if terms.len() == 1 => operand(&terms[0]),
+        _ => ast(node),
+    }
+}
+
+/// The command for a function, e.g. `\sin`, or `\operatorname{name}` for one LaTeX lacks
+fn command(function: &str) -> String {
+    match COMMANDS.iter().find(|(name, _)| *name == function) {
+        Some((_, command)) => command.to_string(),
+        None => format!("\\operatorname{{{}}}", function.replace('_', "\\_")),
+    }
+}
+
+/// A superscript or subscript, in braces unless it is a single character
+fn group(script: &str) -> String {
+    if script.chars().count() == 1 {
+        script.to_string()
+    } else {
+        format!("{{{}}}", script)
+    }
+}
+
+fn parenthesize(expression: &str) -> String {
+    format!("\\left({}\\right)", expression)
+}
+
+/// Two factors written side by side, with a space after a command such as `\pi` so it does
+/// not run into a following letter, and a `\cdot` between a factor and a following number
+fn juxtapose(left: &str, right: &str) -> String {
+    if left.is_empty() || left == "-" {
+        return format!("{}{}", left, right);
+    }
+    let ends_in_command = left
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+        .ends_with('\\')
+        && left.ends_with(|c: char| c.is_ascii_alphabetic());
+    if right.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("{} \\cdot {}", left, right)
+    } else if ends_in_command && right.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        format!("{} {}", left, right)
+    } else {
+        format!("{}{}", left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        assert_eq!(Latex.name("x"), "x");
+        assert_eq!(Latex.name("theta"), "\\theta");
+        assert_eq!(Latex.name("x1"), "x_1");
+        assert_eq!(Latex.name("v_0"), "v_0");
+        assert_eq!(Latex.name("x_10"), "x_{10}");
+        assert_eq!(Latex.name("rate"), "\\mathrm{rate}");
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let x = Polynomial::variable("x", 1.0);
+        let p = &(&x.pow(2).scale(3) - &x) + &Polynomial::constant(Rational::new(-1, 2));
+        assert_eq!(
+            Latex.polynomial(&p, MonomialOrder::Lex),
+            "3x^2 - x - \\frac{1}{2}"
+        );
+        assert_eq!(
+            Latex.polynomial(&Polynomial::variable("x", -2.0), MonomialOrder::Lex),
+            "x^{-2}"
+        );
+        let pi = Polynomial::variable("pi", 1.0);
+        assert_eq!(
+            Latex.polynomial(&(&pi * &x).scale(2), MonomialOrder::Lex),
+            "2\\pi x"
+        );
+    }
+
+    #[test]
+    fn test_juxtapose() {
+        assert_eq!(juxtapose("2", "x"), "2x");
+        assert_eq!(juxtapose("2", "2^{x}"), "2 \\cdot 2^{x}");
+        assert_eq!(juxtapose("\\pi", "x"), "\\pi x");
+        assert_eq!(juxtapose("\\pi", "\\sqrt{2}"), "\\pi\\sqrt{2}");
+        assert_eq!(juxtapose("-", "x"), "-x");
+    }
+}
@@ -0,0 +1,618 @@
+use std::collections::VecDeque;
+
+use super::{lexer::Token, symbol::GREEK, InterpreterError, Span};
+
+/// Function commands and the names the parser knows them by
+const FUNCTIONS: [(&str, &str); 13] = [
+    ("sin", "sin"),
+    ("cos", "cos"),
+    ("tan", "tan"),
+    ("csc", "csc"),
+    ("sec", "sec"),
+    ("cot", "cot"),
+    ("arcsin", "asin"),
+    ("arccos", "acos"),
+    ("arctan", "atan"),
+    ("ln", "ln"),
+    ("log", "log"),
+    ("exp", "exp"),
+    ("arg", "arg"),
+];
+
+/// What ends a group being read
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Closer {
+    /// the end of the input
+    End,
+    /// a closing character such as `}` or `)`
+    Char(char),
+    /// `\right` followed by the given delimiter
+    Right(char),
+    /// `\end{cases}`
+    Cases,
+}
+
+impl Default for LatexLexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads LaTeX such as `\frac{x^2+1}{2}` into the tokens the plain lexer gives for
+/// `(x^2+1)/2`, so the parser builds the same tree from either
+pub struct LatexLexer {
+    pub input: String,
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl LatexLexer {
+    pub fn new() -> LatexLexer {
+        LatexLexer {
+            input: String::new(),
+            chars: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Translate LaTeX into tokens
+    ///
+    /// Commands are rewritten as the functions, operators and variables they stand for:
+    /// `\frac{a}{b}` is a quotient, `\sqrt[3]{x}` is root(x, 3), `\cdot` is '*', `\left(`
+    /// and `\right)` are parentheses and `\pi` is the variable pi. Braces only group, so
+    /// `x^{10}` is x^10. Errors give the position of the offending command in the input.
+    pub fn lex(&mut self, arg: &str) -> Result<VecDeque<Token>, InterpreterError> {
+        self.input = String::from(arg);
+        self.chars = arg.chars().collect();
+        self.position = 0;
+        Ok(self.group(Closer::End, 0)?.into())
+    }
+
+    /// Where the text from the given position up to the current one is, at least one
+    /// character long
+    fn span(&self, start: usize) -> Option<Span> {
+        Some(Span::new(start, self.position.max(start + 1)))
+    }
+
+    /// Read tokens up to the closer of a group opened at the given position
+    fn group(&mut self, closer: Closer, opened: usize) -> Result<Vec<Token>, InterpreterError> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            let Some(c) = self.peek() else {
+                return match closer {
+                    Closer::End => Ok(tokens),
+                    _ => Err(InterpreterError::unbalanced(format!(
+                        "Missing {} to close the group opened at position {}",
+                        describe(closer),
+                        opened
+                    ))
+                    .at(Some(Span::new(opened, opened + 1)))),
+                };
+            };
+            if closer == Closer::Char(c) {
+                self.position += 1;
+                return Ok(tokens);
+            }
+            match c {
+                '}' | ')' | ']' => {
+                    return Err(InterpreterError::unbalanced(format!(
+                        "Unexpected '{}' at position {}",
+                        c, start
+                    ))
+                    .at(self.span(start)))
+                }
+                '\\' if self.at_command("right") => {
+                    self.command();
+                    let delimiter = self.delimiter(start)?;
+                    return match closer {
+                        Closer::Right(expected) if expected == delimiter => Ok(tokens),
+                        _ => Err(InterpreterError::unbalanced(format!(
+                            "\\right at position {} does not match a \\left",
+                            start
+                        ))
+                        .at(self.span(start))),
+                    };
+                }
+                '\\' if self.at_command("end") => {
+                    self.command();
+                    let environment = self.environment(start)?;
+                    return match closer {
+                        Closer::Cases if environment == "cases" => Ok(tokens),
+                        _ => Err(InterpreterError::unbalanced(format!(
+                            "\\end{{{}}} at position {} does not match a \\begin",
+                            environment, start
+                        ))
+                        .at(self.span(start))),
+                    };
+                }
+                _ => self.element(&mut tokens, closer)?,
+            }
+        }
+    }
+
+    /// Read one number, variable, operator or command onto the tokens
+    fn element(&mut self, tokens: &mut Vec<Token>, closer: Closer) -> Result<(), InterpreterError> {
+        let start = self.position;
+        match self.next().unwrap_or(' ') {
+            c if c.is_ascii_digit() => tokens.push(Token::Number(c.to_digit(10).unwrap() as f64)),
+            c if c.is_alphabetic() => {
+                let mut identifier = c.to_string();
+                while let Some(c) = self.peek().filter(|c| c.is_alphanumeric()) {
+                    identifier.push(c);
+                    self.position += 1;
+                }
+                tokens.push(Token::Identifier(identifier));
+            }
+            '_' => {
+                let subscript = self.script_text(start)?;
+                match tokens.last_mut() {
+                    Some(Token::Identifier(name)) => {
+                        name.push('_');
+                        name.push_str(&subscript);
+                    }
+                    _ => {
+                        return Err(InterpreterError::unexpected_token(format!(
+                            "Subscript at position {} must follow a variable",
+                            start
+                        ))
+                        .at(self.span(start)))
+                    }
+                }
+            }
+            '^' => {
+                tokens.push(Token::Symbol('^'));
+                let exponent = self.argument(start)?;
+                tokens.extend(exponent_tokens(exponent));
+            }
+            '{' => tokens.extend(self.group(Closer::Char('}'), start)?),
+            '(' => {
+                tokens.push(Token::Symbol('('));
+                tokens.extend(self.group(Closer::Char(')'), start)?);
+                tokens.push(Token::Symbol(')'));
+            }
+            '&' if closer == Closer::Cases => {}
+            c @ ('+' | '-' | '*' | '/' | '=' | '<' | '>' | ',') => tokens.push(Token::Symbol(c)),
+            '\\' => self.command_element(tokens, start, closer)?,
+            c => {
+                return Err(InterpreterError::UnexpectedCharacter {
+                    character: c,
+                    span: self.span(start),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Translate the command starting at the given position
+    fn command_element(
+        &mut self,
+        tokens: &mut Vec<Token>,
+        start: usize,
+        closer: Closer,
+    ) -> Result<(), InterpreterError> {
+        self.position = start;
+        let command = self.command();
+        match command.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument(start)?;
+                let denominator = self.argument(start)?;
+                let mut quotient = operand_tokens(numerator);
+                quotient.push(Token::Symbol('/'));
+                quotient.extend(operand_tokens(denominator));
+                // a fraction among other factors is kept whole, so \frac{a}{b}c is (a/b)c
+                if stands_alone(tokens.last()) && self.ends_term() {
+                    tokens.extend(quotient);
+                } else {
+                    tokens.push(Token::Symbol('('));
+                    tokens.extend(quotient);
+                    tokens.push(Token::Symbol(')'));
+                }
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = match self.peek() {
+                    Some('[') => {
+                        let opened = self.position;
+                        self.position += 1;
+                        Some(self.group(Closer::Char(']'), opened)?)
+                    }
+                    _ => None,
+                };
+                let radicand = self.argument(start)?;
+                match index {
+                    None => tokens.extend(call("sqrt", radicand, None)),
+                    Some(index) => tokens.extend(call("root", radicand, Some(index))),
+                }
+            }
+            "cdot" | "times" => tokens.push(Token::Symbol('*')),
+            "div" => tokens.push(Token::Symbol('/')),
+            "left" => {
+                let delimiter = self.delimiter(start)?;
+                let closing = match delimiter {
+                    '(' => ')',
+                    '[' => ']',
+                    '|' => '|',
+                    _ => {
+                        return Err(InterpreterError::unexpected_token(format!(
+                            "Unsupported delimiter after \\left at position {}",
+                            start
+                        ))
+                        .at(self.span(start)))
+                    }
+                };
+                let inner = self.group(Closer::Right(closing), start)?;
+                if delimiter == '|' {
+                    tokens.extend(call("abs", inner, None));
+                } else {
+                    tokens.push(Token::Symbol('('));
+                    tokens.extend(inner);
+                    tokens.push(Token::Symbol(')'));
+                }
+            }
+            "begin" => {
+                let environment = self.environment(start)?;
+                if environment != "cases" {
+                    return Err(InterpreterError::unsupported(format!(
+                        "Unsupported environment {} at position {}",
+                        environment, start
+                    ))
+                    .at(self.span(start)));
+                }
+                tokens.push(Token::Symbol('{'));
+                tokens.extend(self.group(Closer::Cases, start)?);
+                tokens.push(Token::Symbol('}'));
+            }
+            "\\" if closer == Closer::Cases => tokens.push(Token::Symbol(',')),
+            "{" => tokens.push(Token::Symbol('{')),
+            "}" => tokens.push(Token::Symbol('}')),
+            "," | ";" | ":" | "!" | " " | "quad" | "qquad" => {}
+            "mathrm" | "operatorname" if self.peek() == Some('{') => {
+                let name = self.script_text(start)?;
+                match self.function_follows() {
+                    true => self.function(tokens, &name, start)?,
+                    false => tokens.push(Token::Identifier(name)),
+                }
+            }
+            // a letter is read as the variable of the same name, `\theta` as `theta`
+            name if GREEK.iter().any(|(greek, _)| *greek == name) => {
+                tokens.push(Token::Identifier(name.to_string()))
+            }
+            name => match FUNCTIONS.iter().find(|(command, _)| *command == name) {
+                Some((_, function)) => self.function(tokens, function, start)?,
+                None => {
+                    return Err(InterpreterError::UnknownCommand {
+                        command: name.to_string(),
+                        span: self.span(start),
+                    })
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// A function command with its optional base, power and argument,
+    /// e.g. `\sin^2 x` as sin(x)^2 or `\log_{2}\left(x\right)` as log(x, 2)
+    fn function(
+        &mut self,
+        tokens: &mut Vec<Token>,
+        name: &str,
+        start: usize,
+    ) -> Result<(), InterpreterError> {
+        let mut base = None;
+        let mut exponent = None;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('_') if base.is_none() => {
+                    self.position += 1;
+                    base = Some(self.argument(start)?);
+                }
+                Some('^') if exponent.is_none() => {
+                    self.position += 1;
+                    exponent = Some(self.argument(start)?);
+                }
+                _ => break,
+            }
+        }
+        self.skip_whitespace();
+        let opened = self.position;
+        let argument = match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                self.group(Closer::Char(')'), opened)?
+            }
+            Some('{') => {
+                self.position += 1;
+                self.group(Closer::Char('}'), opened)?
+            }
+            Some('\\') if self.at_command("left") => {
+                self.command();
+                match self.delimiter(opened)? {
+                    '(' => self.group(Closer::Right(')'), opened)?,
+                    '[' => self.group(Closer::Right(']'), opened)?,
+                    '|' => call("abs", self.group(Closer::Right('|'), opened)?, None),
+                    _ => {
+                        return Err(InterpreterError::unexpected_token(format!(
+                            "Unsupported delimiter after \\left at position {}",
+                            opened
+                        ))
+                        .at(self.span(opened)))
+                    }
+                }
+            }
+            // without parentheses the argument runs to the end of the next factor, \sin 2x
+            _ => {
+                let mut argument = Vec::new();
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+                    argument.push(Token::Number(c.to_digit(10).unwrap() as f64));
+                    self.position += 1;
+                }
+                if self.peek().is_some_and(|c| c.is_alphabetic() || c == '\\') {
+                    self.element(&mut argument, Closer::End)?;
+                }
+                if argument.is_empty() {
+                    return Err(InterpreterError::missing_operand(format!(
+                        "Missing an argument for \\{} at position {}",
+                        name, start
+                    ))
+                    .at(self.span(start)));
+                }
+                argument
+            }
+        };
+        tokens.extend(call(name, argument, base));
+        if let Some(exponent) = exponent {
+            tokens.push(Token::Symbol('^'));
+            tokens.extend(exponent_tokens(exponent));
+        }
+        Ok(())
+    }
+
+    /// The argument of a command: a braced group, or else a single character or command,
+    /// as in `\frac12` or `x^\pi`
+    fn argument(&mut self, start: usize) -> Result<Vec<Token>, InterpreterError> {
+        self.skip_whitespace();
+        let opened = self.position;
+        match self.next() {
+            Some('{') => self.group(Closer::Char('}'), opened),
+            Some(c) if c.is_ascii_digit() => {
+                Ok(vec![Token::Number(c.to_digit(10).unwrap() as f64)])
+            }
+            Some(c) if c.is_alphabetic() => Ok(vec![Token::Identifier(c.to_string())]),
+            Some('\\') => {
+                let mut tokens = Vec::new();
+                self.command_element(&mut tokens, opened, Closer::End)?;
+                Ok(tokens)
+            }
+            _ => Err(InterpreterError::missing_operand(format!(
+                "Missing an argument for the command at position {}",
+                start
+            ))
+            .at(self.span(start))),
+        }
+    }
+
+    /// The raw text of a braced or single character script, e.g. the `10` of `x_{10}`
+    fn script_text(&mut self, start: usize) -> Result<String, InterpreterError> {
+        match self.next() {
+            Some('{') => {
+                let mut text = String::new();
+                loop {
+                    match self.next() {
+                        Some('}') => return Ok(text),
+                        Some(c) if c.is_alphanumeric() || c == '_' => text.push(c),
+                        Some(c) if c.is_whitespace() => {}
+                        _ => {
+                            return Err(InterpreterError::unexpected_token(format!(
+                                "Expected a name in braces at position {}",
+                                start
+                            ))
+                            .at(self.span(start)))
+                        }
+                    }
+                }
+            }
+            Some(c) if c.is_alphanumeric() => Ok(c.to_string()),
+            _ => Err(InterpreterError::unexpected_token(format!(
+                "Expected a name in braces at position {}",
+                start
+            ))
+            .at(self.span(start))),
+        }
+    }
+
+    /// The delimiter after `\left` or `\right`
+    fn delimiter(&mut self, start: usize) -> Result<char, InterpreterError> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c @ ('(' | ')' | '[' | ']' | '|')) => Ok(c),
+            _ => Err(InterpreterError::unexpected_token(format!(
+                "Expected a delimiter after the command at position {}",
+                start
+            ))
+            .at(self.span(start))),
+        }
+    }
+
+    /// The environment name after `\begin` or `\end`
+    fn environment(&mut self, start: usize) -> Result<String, InterpreterError> {
+        self.skip_whitespace();
+        self.script_text(start)
+    }
+
+    /// Read a command name after its backslash: a run of letters, or a single other character
+    fn command(&mut self) -> String {
+        self.position += 1;
+        let mut name = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_alphabetic()) {
+            name.push(c);
+            self.position += 1;
+        }
+        if name.is_empty() {
+            if let Some(c) = self.next() {
+                name.push(c);
+            }
+        }
+        name
+    }
+
+    fn at_command(&self, name: &str) -> bool {
+        let rest: String = self.chars[self.position..].iter().collect();
+        rest.strip_prefix('\\')
+            .and_then(|rest| rest.strip_prefix(name))
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+    }
+
+    /// Whether what follows ends the current term, so a fraction there needs no parentheses
+    fn ends_term(&mut self) -> bool {
+        self.skip_whitespace();
+        match self.peek() {
+            None => true,
+            Some('+' | '-' | '*' | '/' | '=' | '<' | '>' | ',' | ')' | '}' | ']' | '&') => true,
+            Some('\\') => ["right", "end", "cdot", "times", "div", "\\"]
+                .iter()
+                .any(|command| self.at_command(command)),
+            _ => false,
+        }
+    }
+
+    /// Whether a call such as `\operatorname{f}(x)` follows
+    fn function_follows(&mut self) -> bool {
+        self.skip_whitespace();
+        self.peek() == Some('(') || self.at_command("left")
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+}
+
+/// What a closer looks like, for error messages
+fn describe(closer: Closer) -> String {
+    match closer {
+        Closer::End => "the end of the input".to_string(),
+        Closer::Char(c) => format!("'{}'", c),
+        Closer::Right(c) => format!("\\right{}", c),
+        Closer::Cases => "\\end{cases}".to_string(),
+    }
+}
+
+/// Whether the tokens so far end where a term starts, so a fraction there needs no parentheses
+fn stands_alone(last: Option<&Token>) -> bool {
+    matches!(
+        last,
+        None | Some(Token::Symbol(
+            '+' | '-' | '*' | '=' | '<' | '>' | '(' | '{' | ','
+        ))
+    )
+}
+
+/// A single number or one-letter variable is written bare, anything else in parentheses,
+/// since a longer name such as `ab` would be split into a·b outside of them
+fn is_atom(tokens: &[Token]) -> bool {
+    match tokens {
+        [Token::Identifier(name)] => name.chars().count() == 1,
+        digits => !digits.is_empty() && digits.iter().all(|t| matches!(t, Token::Number(_))),
+    }
+}
+
+/// The operand of a quotient, parenthesised unless it is a single number or variable
+fn operand_tokens(tokens: Vec<Token>) -> Vec<Token> {
+    if is_atom(&tokens) {
+        return tokens;
+    }
+    let mut operand = vec![Token::Symbol('(')];
+    operand.extend(tokens);
+    operand.push(Token::Symbol(')'));
+    operand
+}
+
+/// An exponent, bare if it is a signed number or a single variable, so `x^{-2}` is `x^-2`
+fn exponent_tokens(tokens: Vec<Token>) -> Vec<Token> {
+    match tokens.split_first() {
+        Some((Token::Symbol('-'), rest)) if rest.iter().all(|t| matches!(t, Token::Number(_))) => {
+            tokens
+        }
+        _ => operand_tokens(tokens),
+    }
+}
+
+/// A call of a known function, with an optional second argument
+fn call(name: &str, argument: Vec<Token>, second: Option<Vec<Token>>) -> Vec<Token> {
+    let mut tokens = vec![Token::Identifier(name.to_string()), Token::Symbol('(')];
+    tokens.extend(argument);
+    if let Some(second) = second {
+        tokens.push(Token::Symbol(','));
+        tokens.extend(second);
+    }
+    tokens.push(Token::Symbol(')'));
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::lexer::Lexer;
+
+    fn same_tokens(latex: &str, plain: &str) {
+        let translated = LatexLexer::new().lex(latex).unwrap();
+        let expected = Lexer::new().lex(plain).unwrap();
+        assert_eq!(translated, expected, "{} should read as {}", latex, plain);
+    }
+
+    #[test]
+    fn test_translation() {
+        same_tokens("\\frac{x^2+1}{2}", "(x^2+1)/2");
+        same_tokens("\\frac12 x", "(1/2)x");
+        same_tokens("\\sqrt[3]{x}", "root(x, 3)");
+        same_tokens("x^{10} \\cdot y", "x^10 * y");
+        same_tokens("x^{-2}", "x^-2");
+        same_tokens("x^{ab}", "x^(ab)");
+        same_tokens("2^{xy}z", "2^(xy)z");
+        same_tokens("\\left( x + 1 \\right)^{2}", "(x + 1)^2");
+        same_tokens("2\\pi x", "2pi x");
+        same_tokens("\\sin^2 x", "sin(x)^2");
+        same_tokens("\\log_{2}\\left(x\\right)", "log(x, 2)");
+        same_tokens("\\left|x - 1\\right|", "abs(x - 1)");
+        same_tokens("x_{10} + x_1", "x_10 + x_1");
+        same_tokens(
+            "\\begin{cases} x + y = 1 \\\\ x - y = 0 \\end{cases}",
+            "{x + y = 1, x - y = 0}",
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |latex: &str| LatexLexer::new().lex(latex).unwrap_err().to_string();
+        assert_eq!(
+            error("x + \\foo{y}"),
+            "InterpreterError: Unknown LaTeX command \\foo"
+        );
+        assert_eq!(
+            error("\\frac{1}{x"),
+            "InterpreterError: Missing '}' to close the group opened at position 8"
+        );
+        assert_eq!(
+            error("\\left(x + 1"),
+            "InterpreterError: Missing \\right) to close the group opened at position 0"
+        );
+        assert_eq!(
+            error("x\\right)"),
+            "InterpreterError: \\right at position 1 does not match a \\left"
+        );
+    }
+}
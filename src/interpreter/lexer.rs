@@ -1,6 +1,11 @@
 use std::collections::{HashSet, VecDeque};
 
-#[derive(Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use super::{InterpreterError, Span};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
 pub enum Token {
     Identifier(String),
     Number(f64),
@@ -16,30 +21,64 @@ impl Token {
     }
 }
 
+/// Written as it was typed, e.g. `x1`, `7` or `(`
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Symbol(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub struct Lexer {
     pub input: String,
+    /// where each token of the last input was, in characters
+    pub spans: Vec<Span>,
     symbols: HashSet<char>,
-    keywords: HashSet<String>,
 }
 
 impl Lexer {
     pub fn new() -> Lexer {
         Lexer {
             input: String::new(),
-            symbols: HashSet::from(['+', '-', '*', '/', '(', ')', '^', '=', '|']),
-            keywords: HashSet::from(
-                ["abs", "sqrt", "pow", "pi", "e"].map(|s: &str| -> String { s.to_string() }),
-            ),
+            spans: Vec::new(),
+            symbols: HashSet::from([
+                '+', '-', '*', '/', '(', ')', '^', '=', '<', '>', '|', ',', '{', '}', '.',
+            ]),
         }
     }
 
-    pub fn lex(&mut self, arg: &str) -> Result<VecDeque<Token>, super::InterpreterError> {
+    /// Split the input into numbers, symbols and identifiers
+    ///
+    /// Identifiers are kept whole, including Unicode letters such as `θ`, digits after the
+    /// first letter (`x1`) and underscore subscripts (`v_0`); the parser decides how they
+    /// split into variables. Whitespace ends an identifier, so `x y` is two identifiers, and
+    /// a number, which the parser tells from where the digits were, so `2 3` is two numbers.
+    /// A decimal point is a symbol of its own, joined to the digits around it by the parser.
+    /// Any other character, such as `$` or `;`, is an error. Where each token was is kept
+    /// in `spans`, for the parser to point at.
+    pub fn lex(&mut self, arg: &str) -> Result<VecDeque<Token>, InterpreterError> {
         self.input = String::from(arg);
+        self.spans.clear();
         let mut tokens: VecDeque<Token> = VecDeque::new();
         let mut current_token = String::new();
+        let mut start = 0;
 
-        for c in arg.chars() {
+        for (i, c) in arg.chars().enumerate() {
             if c.is_whitespace() {
+                if !current_token.is_empty() {
+                    tokens.push_back(Token::Identifier(current_token.clone()));
+                    self.spans.push(Span::new(start, i));
+                    current_token = String::new();
+                }
                 continue;
             }
 
@@ -47,36 +86,38 @@ impl Lexer {
                 Some(_) => {
                     if !current_token.is_empty() {
                         tokens.push_back(Token::Identifier(current_token.clone()));
+                        self.spans.push(Span::new(start, i));
                         current_token = String::new();
                     }
                     tokens.push_back(Token::Symbol(c));
+                    self.spans.push(Span::new(i, i + 1));
                 }
-                None => {
-                    if self.keywords.contains(&current_token) {
-                        if !current_token.is_empty() {
-                            tokens.push_back(Token::Identifier(current_token.clone()));
-                            current_token = String::new();
-                        }
-                        current_token.push(c);
-                    } else if c.is_numeric() {
-                        if !current_token.is_empty() {
-                            tokens.push_back(Token::Identifier(current_token.clone()));
-                            current_token = String::new();
-                        }
-                        tokens.push_back(Token::Number(c.to_digit(10).unwrap() as f64));
-                    } else {
-                        current_token.push(c);
+                // digits only start a number outside an identifier
+                None if c.is_ascii_digit() && current_token.is_empty() => {
+                    tokens.push_back(Token::Number(c.to_digit(10).unwrap() as f64));
+                    self.spans.push(Span::new(i, i + 1));
+                }
+                None if c.is_alphanumeric() || c == '_' => {
+                    if current_token.is_empty() {
+                        start = i;
                     }
+                    current_token.push(c)
+                }
+                None => {
+                    return Err(InterpreterError::UnexpectedCharacter {
+                        character: c,
+                        span: Some(Span::new(i, i + 1)),
+                    })
                 }
             }
         }
 
         // add last token if it exists
         // this is to handle where the last token is an identifier because this is an edge case
-        if current_token.len() > 0 {
+        if !current_token.is_empty() {
             tokens.push_back(Token::Identifier(current_token.clone()));
+            self.spans.push(Span::new(start, arg.chars().count()));
         }
-        //println!("{:#?}", tokens);
         Ok(tokens)
     }
 }
@@ -0,0 +1,172 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::One;
+
+use crate::util::{hashable_float::HashableFloat, rational::Rational};
+
+use super::{
+    log, monomial::Monomial, polynomial::Polynomial, rational_function::RationalFunction,
+    symbol::Symbol, trig, InterpreterError,
+};
+
+/// The solution set of a system of linear equations
+#[derive(Debug)]
+pub enum LinearSolution {
+    /// Each pivot unknown is assigned a value in terms of the free unknowns,
+    /// so the solution is unique exactly when there are no free unknowns
+    Solved {
+        assignments: Vec<(String, RationalFunction)>,
+        free: Vec<String>,
+    },
+    /// The equations contradict each other
+    Inconsistent,
+}
+
+/// Solve a system of linear equations by exact Gaussian elimination over the rationals
+///
+/// Each equation is given as `lhs - rhs`, to be solved for zero. The unknowns are
+/// every variable that appears, in alphabetical order, other than the constants e, pi,
+/// the imaginary unit and radicals, which may appear on their own, as in x + y = sqrt(2),
+/// but not multiplying an unknown.
+pub fn solve_linear_system(
+    equations: &[RationalFunction],
+) -> Result<LinearSolution, InterpreterError> {
+    let mut unknowns: Vec<String> = Vec::new();
+    // the constant monomials, each a column of its own on the right hand side
+    let mut constants = vec![Monomial::one()];
+    for equation in equations.iter() {
+        if equation.denominator.as_constant().is_none() {
+            return Err(InterpreterError::unsupported(
+                "Equation is not linear: unknowns appear in a denominator".to_string(),
+            ));
+        }
+        for monomial in equation.numerator.terms().keys() {
+            let powers = monomial.powers();
+            if powers.iter().all(|(name, _)| is_constant(name)) {
+                if !constants.contains(monomial) {
+                    constants.push(monomial.clone());
+                }
+                continue;
+            }
+            match powers.as_slice() {
+                [(name, exponent)] if *exponent == HashableFloat::new(1.0) => {
+                    if !unknowns.contains(name) {
+                        unknowns.push(name.clone());
+                    }
+                }
+                _ => {
+                    return Err(InterpreterError::unsupported(format!(
+                        "Equation is not linear: {}",
+                        monomial
+                    )))
+                }
+            }
+        }
+    }
+    unknowns.sort();
+
+    // augmented matrix, with the constants moved to the right hand side
+    let columns = unknowns.len();
+    let mut matrix = Vec::new();
+    for equation in equations.iter() {
+        let mut row = vec![Rational::zero(); columns + constants.len()];
+        for (monomial, coefficient) in equation.numerator.terms().iter() {
+            match constants.iter().position(|c| c == monomial) {
+                Some(k) => row[columns + k] -= coefficient,
+                None => {
+                    let (name, _) = &monomial.powers()[0];
+                    let column = unknowns.binary_search(name).unwrap();
+                    row[column] += coefficient;
+                }
+            }
+        }
+        matrix.push(row);
+    }
+
+    let pivots = row_reduce(&mut matrix, columns);
+
+    if matrix[pivots.len()..]
+        .iter()
+        .any(|row| row[columns..].iter().any(|c| !c.is_zero()))
+    {
+        return Ok(LinearSolution::Inconsistent);
+    }
+
+    let free: Vec<usize> = (0..columns).filter(|c| !pivots.contains(c)).collect();
+    let assignments = pivots
+        .iter()
+        .enumerate()
+        .map(|(row, column)| {
+            // x_pivot = rhs - sum(a_f * x_f) over the free unknowns
+            let mut terms: Vec<(Monomial, Rational)> = constants
+                .iter()
+                .zip(&matrix[row][columns..])
+                .map(|(monomial, c)| (monomial.clone(), c.clone()))
+                .collect();
+            for f in free.iter() {
+                terms.push((Monomial::variable(&unknowns[*f], 1.0), -&matrix[row][*f]));
+            }
+            (unknowns[*column].clone(), to_rational_function(terms))
+        })
+        .collect();
+
+    Ok(LinearSolution::Solved {
+        assignments,
+        free: free.iter().map(|f| unknowns[*f].clone()).collect(),
+    })
+}
+
+/// Whether a variable stands for a number: e, pi, the imaginary unit or a radical
+pub(crate) fn is_constant(name: &str) -> bool {
+    name == log::E
+        || name == trig::PI
+        || Symbol::lookup(name)
+            .is_some_and(|symbol| symbol.as_radical().is_some() || symbol.is_imaginary())
+}
+
+/// Reduce the first `columns` columns to reduced row echelon form in place,
+/// returning the pivot column of each leading row
+fn row_reduce(matrix: &mut [Vec<Rational>], columns: usize) -> Vec<usize> {
+    let mut pivots = Vec::new();
+    for column in 0..columns {
+        let row = pivots.len();
+        let Some(pivot) = (row..matrix.len()).find(|&r| !matrix[r][column].is_zero()) else {
+            continue;
+        };
+        matrix.swap(row, pivot);
+
+        let inverse = matrix[row][column].recip();
+        for entry in matrix[row].iter_mut() {
+            *entry *= &inverse;
+        }
+        let pivot_row = matrix[row].clone();
+        for (r, current) in matrix.iter_mut().enumerate() {
+            let factor = current[column].clone();
+            if r != row && !factor.is_zero() {
+                for (entry, pivot_entry) in current.iter_mut().zip(pivot_row.iter()) {
+                    *entry -= &(&factor * pivot_entry);
+                }
+            }
+        }
+        pivots.push(column);
+    }
+    pivots
+}
+
+/// Build an exact value from rational terms, keeping integer coefficients over their
+/// least common denominator, which leaves the fraction in lowest terms
+fn to_rational_function(terms: Vec<(Monomial, Rational)>) -> RationalFunction {
+    let denominator = Rational::from(
+        terms
+            .iter()
+            .fold(BigInt::one(), |acc, (_, c)| acc.lcm(c.denominator())),
+    );
+    let mut numerator = Polynomial::new();
+    for (monomial, coefficient) in terms {
+        numerator.add_term(monomial, coefficient * &denominator);
+    }
+    RationalFunction {
+        numerator,
+        denominator: Polynomial::constant(denominator),
+    }
+}
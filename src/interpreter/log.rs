@@ -0,0 +1,421 @@
+use num_bigint::BigInt;
+use num_traits::{One, ToPrimitive, Zero};
+
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::{Assumptions, Property},
+    monomial::{self, Monomial},
+    polynomial::Polynomial,
+    rational_function::RationalFunction,
+    symbol::Symbol,
+    trig, InterpreterError,
+};
+
+/// The exponential and logarithm functions, as written in expressions
+pub const FUNCTIONS: [&str; 4] = ["exp", "ln", "log", "log10"];
+
+/// Euler's number, kept as a symbol so that exp(x) is the power e^x
+pub const E: &str = "e";
+
+/// Largest prime tried when writing the logarithm of an integer as a sum, ln(12) = 2ln(2) + ln(3)
+const TRIAL_DIVISION_LIMIT: i128 = 1_000_000;
+
+/// exp(x) = e^x, with integer multiples of logarithms folded back, so exp(2ln(x)) = x^2,
+/// and imaginary multiples of pi at the special angles taken round the unit circle, so
+/// exp(i·pi) = -1 and exp(i·pi/2) = i
+pub fn exp(
+    exponent: &Polynomial,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    let mut rest = exponent.clone();
+    let mut value = RationalFunction::from(Polynomial::constant(1));
+    for (monomial, coefficient) in exponent.terms() {
+        if is_imaginary_pi(monomial) {
+            if let Some(point) = unit_circle(coefficient, assumptions)? {
+                value = value.mul(&point);
+                rest.add_term(monomial.clone(), -coefficient);
+            }
+            continue;
+        }
+        let (Some(argument), Some(power)) = (logarithm(monomial), coefficient.to_i64()) else {
+            continue;
+        };
+        value = value.mul(&RationalFunction::from(argument).pow(power)?);
+        rest.add_term(monomial.clone(), -coefficient);
+    }
+    if rest.is_zero() {
+        return Ok(value);
+    }
+    let power =
+        RationalFunction::from(Polynomial::variable(E, 1.0)).power(&rest.into(), assumptions)?;
+    Ok(value.mul(&power))
+}
+
+/// The natural logarithm of a polynomial
+///
+/// Powers of e and other positive symbolic powers with a real exponent come out as their
+/// exponents, so ln(e^3) = 3 and ln(2^x·y) = x·ln(2) + ln(y) for a real x. A power of e
+/// whose exponent is also an imaginary multiple of pi gives the principal value, with the
+/// multiple brought into (-1, 1], so ln(e^(2i·pi)) = 0, and a negative number gives
+/// ln(-2) = ln(2) + i·pi while there is an imaginary unit. Anything else is kept as a symbol
+/// such as ln(x) or ln(e^x).
+pub fn ln(
+    argument: &Polynomial,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    if argument.is_zero() {
+        return Err(InterpreterError::domain("ln is undefined at 0".to_string()));
+    }
+    match argument.as_constant() {
+        // the principal value ln(-c) = ln(c) + i·pi, where there is an imaginary unit
+        Some(c) if c.is_negative() && Symbol::imaginary_unit().is_some() => {
+            let magnitude = ln(&Polynomial::constant(-c), assumptions)?;
+            return Ok(magnitude.add(&principal_turns(&Rational::one()).into()));
+        }
+        Some(c) if c.is_negative() => {
+            return Err(InterpreterError::domain(
+                "ln is undefined for negative numbers".to_string(),
+            ))
+        }
+        Some(c) if c.is_one() => return Ok(Polynomial::new().into()),
+        _ => {}
+    }
+
+    let terms: Vec<_> = argument.terms().iter().collect();
+    let [(monomial, coefficient)] = terms.as_slice() else {
+        return Ok(function("ln", argument.clone()).into());
+    };
+    let mut value = Polynomial::new();
+    let mut kept = Vec::new();
+    for (name, exponent) in monomial.powers() {
+        let symbol = Symbol::intern(&name);
+        match symbol.as_power() {
+            _ if name == E => value = &value + &Polynomial::constant(monomial::exact(exponent.0)),
+            Some(power) if is_positive_base(&power.base) => {
+                let exponent_of = power.exponent.scale(monomial::exact(exponent.0));
+                match imaginary_turns(&exponent_of, assumptions) {
+                    Some((real, turns)) if turns.is_zero() => {
+                        value = &value + &(&real * &ln(&power.base, assumptions)?.numerator);
+                    }
+                    Some((real, turns)) if power.base == Polynomial::variable(E, 1.0) => {
+                        value = &(&value + &real) + &principal_turns(&turns);
+                    }
+                    _ => kept.push((name, exponent.0)),
+                }
+            }
+            _ => kept.push((name, exponent.0)),
+        }
+    }
+    let mut rest = Polynomial::new();
+    rest.add_term(Monomial::new(kept), (*coefficient).clone());
+    if !rest.as_constant().is_some_and(|c| c.is_one()) {
+        value = &value + &function("ln", rest);
+    }
+    Ok(value.into())
+}
+
+/// An exponent as its real part and the multiple of i·pi beside it, if the rest is known real
+fn imaginary_turns(
+    exponent: &Polynomial,
+    assumptions: &Assumptions,
+) -> Option<(Polynomial, Rational)> {
+    let mut real = exponent.clone();
+    let mut turns = Rational::zero();
+    for (monomial, coefficient) in exponent.terms() {
+        if is_imaginary_pi(monomial) {
+            turns = turns + coefficient.clone();
+            real.add_term(monomial.clone(), -coefficient);
+        }
+    }
+    assumptions
+        .is_real(&real)
+        .is_true()
+        .then_some((real, turns))
+}
+
+/// Whether a monomial is i·pi
+fn is_imaginary_pi(monomial: &Monomial) -> bool {
+    let symbols: Vec<Symbol> = monomial.symbols().collect();
+    symbols.len() == 2
+        && symbols.iter().all(|s| monomial.exponent(*s) == 1.0)
+        && symbols.iter().any(|s| s.is_imaginary())
+        && symbols.iter().any(|s| s.name() == trig::PI)
+}
+
+/// e^(c·i·pi) = cos(c·pi) + i·sin(c·pi), when both are exact numbers
+fn unit_circle(
+    turns: &Rational,
+    assumptions: &Assumptions,
+) -> Result<Option<RationalFunction>, InterpreterError> {
+    let angle = Polynomial::variable(trig::PI, 1.0).scale(turns.clone());
+    let cosine = trig::apply("cos", &angle, assumptions)?;
+    let sine = trig::apply("sin", &angle, assumptions)?;
+    // a special angle gives numbers and radicals, any other a symbol such as cos(pi/5)
+    let exact = |value: &RationalFunction| {
+        value.denominator.as_constant().is_some()
+            && value
+                .numerator
+                .symbols()
+                .iter()
+                .all(|symbol| symbol.as_radical().is_some())
+    };
+    let Some(unit) = Symbol::imaginary_unit().filter(|_| exact(&cosine) && exact(&sine)) else {
+        return Ok(None);
+    };
+    let unit = RationalFunction::from(Polynomial::variable(&unit, 1.0));
+    Ok(Some(cosine.add(&unit.mul(&sine))))
+}
+
+/// c·i·pi with c brought into (-1, 1] by whole turns of 2i·pi
+fn principal_turns(turns: &Rational) -> Polynomial {
+    // the least whole number of turns at or above (c - 1)/2
+    let whole = -(-(turns - Rational::one()) * Rational::new(1, 2)).floor();
+    let turns = turns - Rational::from(whole * 2);
+    match Symbol::imaginary_unit() {
+        Some(unit) => {
+            &Polynomial::variable(&unit, 1.0) * &Polynomial::variable(trig::PI, 1.0).scale(turns)
+        }
+        None => Polynomial::new(),
+    }
+}
+
+/// The logarithm of x to base b, ln(x)/ln(b), exact when x is a rational power of b
+pub fn log(
+    argument: &Polynomial,
+    base: &Polynomial,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    match base.as_constant() {
+        Some(b) if b <= 0 || b.is_one() => {
+            return Err(InterpreterError::domain(
+                "log expects a positive base other than 1".to_string(),
+            ))
+        }
+        _ => {}
+    }
+    let exact = argument
+        .as_constant()
+        .zip(base.as_constant())
+        .and_then(|(x, b)| rational_log(x, b));
+    if let Some(k) = exact {
+        return RationalFunction::new(
+            Polynomial::constant(k.numerator().clone()),
+            Polynomial::constant(k.denominator().clone()),
+        );
+    }
+    ln(argument, assumptions)?.div(&ln(base, assumptions)?)
+}
+
+/// k with b^k = x, if k is a rational with a small denominator
+fn rational_log(x: Rational, b: Rational) -> Option<Rational> {
+    if x <= 0 || b <= 0 {
+        return None;
+    }
+    let estimate = x.to_f64().ln() / b.to_f64().ln();
+    let k = Rational::from_f64(estimate)?;
+    let (p, q) = (k.numerator().to_i128()?, k.denominator().to_i128()?);
+    if q > 12 || p.abs() > 64 {
+        return None;
+    }
+    // x^q = b^p
+    (x.pow(q as i32)? == b.pow(p as i32)?).then_some(k)
+}
+
+/// Write logarithms of products and powers as sums, ln(x^2 y) = 2ln(x) + ln(y)
+///
+/// Only factors known to be positive, from the assumptions or because they are constants
+/// such as e, are split off, as the rules fail otherwise: ln((-1)(-1)) is not
+/// ln(-1) + ln(-1). An even power of a variable assumed real is positive through its
+/// absolute value, ln(x^2) = 2ln(abs(x)). With `force` every factor is split.
+pub fn expand_log(
+    value: &RationalFunction,
+    force: bool,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    let expand_polynomial = |polynomial: &Polynomial| -> Result<Polynomial, InterpreterError> {
+        let mut result = Polynomial::new();
+        for (monomial, coefficient) in polynomial.terms() {
+            let mut term = Polynomial::constant(coefficient.clone());
+            let mut kept = Vec::new();
+            for (name, exponent) in monomial.powers() {
+                match logarithm(&Monomial::variable(&name, 1.0)) {
+                    Some(argument) if exponent.0 == 1.0 => {
+                        term = &term * &expand(&argument, force, assumptions)?;
+                    }
+                    _ => kept.push((name, exponent.0)),
+                }
+            }
+            let mut rest = Polynomial::new();
+            rest.add_term(Monomial::new(kept), Rational::one());
+            result = &result + &(&term * &rest);
+        }
+        Ok(result)
+    };
+    RationalFunction::new(
+        expand_polynomial(&value.numerator)?,
+        expand_polynomial(&value.denominator)?,
+    )
+}
+
+/// The expanded logarithm of a single argument
+fn expand(
+    argument: &Polynomial,
+    force: bool,
+    assumptions: &Assumptions,
+) -> Result<Polynomial, InterpreterError> {
+    let terms: Vec<_> = argument.terms().iter().collect();
+    let [(monomial, coefficient)] = terms.as_slice() else {
+        return Ok(function("ln", argument.clone()));
+    };
+    let mut value = Polynomial::new();
+    let mut kept = Vec::new();
+    for (name, exponent) in monomial.powers() {
+        let symbol = Symbol::intern(&name);
+        if let Some((radicand, index)) = symbol.as_radical() {
+            let power = monomial::exact(exponent.0) / Rational::from(index);
+            value = &value + &integer_log(&BigInt::from(radicand)).scale(power);
+        } else if force || assumptions.ask(&name, Property::Positive).is_true() {
+            let factor = ln(&Polynomial::variable(&name, 1.0), assumptions)?.numerator;
+            value = &value + &factor.scale(monomial::exact(exponent.0));
+        } else if exponent.0.rem_euclid(2.0) == 0.0
+            && assumptions.ask(&name, Property::Real).is_true()
+        {
+            // an even power of a real x is positive, ln(x^2) = 2ln(abs(x))
+            let absolute = function("abs", Polynomial::variable(&name, 1.0));
+            value = &value + &function("ln", absolute).scale(monomial::exact(exponent.0));
+        } else {
+            kept.push((name, exponent.0));
+        }
+    }
+    let mut rest = Polynomial::new();
+    if coefficient.is_negative() {
+        rest.add_term(Monomial::new(kept), (*coefficient).clone());
+    } else {
+        value = &(&value + &integer_log(coefficient.numerator()))
+            - &integer_log(coefficient.denominator());
+        rest.add_term(Monomial::new(kept), Rational::one());
+    }
+    if !rest.as_constant().is_some_and(|c| c.is_one()) {
+        value = &value + &function("ln", rest);
+    }
+    Ok(value)
+}
+
+/// ln(n) as a sum over the prime factors of n, ln(12) = 2ln(2) + ln(3)
+fn integer_log(n: &BigInt) -> Polynomial {
+    let mut value = Polynomial::new();
+    let mut rest = n.clone();
+    let mut p: i128 = 2;
+    while p <= TRIAL_DIVISION_LIMIT && BigInt::from(p * p) <= rest {
+        let divisor = BigInt::from(p);
+        let mut count = 0;
+        while (&rest % &divisor).is_zero() {
+            rest /= &divisor;
+            count += 1;
+        }
+        if count > 0 {
+            value = &value + &function("ln", Polynomial::constant(p)).scale(count);
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+    if rest > BigInt::one() {
+        value = &value + &function("ln", Polynomial::constant(rest));
+    }
+    value
+}
+
+/// Combine sums of logarithms into one, 2ln(x) + ln(y) = ln(x^2 y)
+///
+/// Integer multiples are gathered into a product of powers, with the negative ones
+/// going into a second logarithm that is subtracted, as in ln(x) - ln(y).
+/// Only logarithms of arguments known to be positive are combined, as on the principal
+/// branch the rules fail otherwise: ln(-1) + ln(-1) is 2iπ, not ln(1). With `force` every
+/// logarithm is combined.
+pub fn contract_log(
+    value: &RationalFunction,
+    force: bool,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    let contract_polynomial = |polynomial: &Polynomial| -> Result<Polynomial, InterpreterError> {
+        let mut rest = polynomial.clone();
+        let mut numerator = Polynomial::constant(1);
+        let mut denominator = Polynomial::constant(1);
+        for (monomial, coefficient) in polynomial.terms() {
+            let multiple = coefficient
+                .abs()
+                .to_i64()
+                .and_then(|m| u32::try_from(m).ok());
+            let (Some(argument), Some(multiple)) = (logarithm(monomial), multiple) else {
+                continue;
+            };
+            if !force && !assumptions.is_positive(&argument).is_true() {
+                continue;
+            }
+            let power = argument.pow(multiple);
+            if *coefficient > 0 {
+                numerator = &numerator * &power;
+            } else {
+                denominator = &denominator * &power;
+            }
+            rest.add_term(monomial.clone(), -coefficient);
+        }
+        // a constant denominator divides through, ln(x) - ln(2) = ln(x/2)
+        if let Some(d) = denominator.as_constant() {
+            numerator = numerator.scale(d.recip());
+            denominator = Polynomial::constant(1);
+        }
+        let contracted =
+            &ln(&numerator, assumptions)?.numerator - &ln(&denominator, assumptions)?.numerator;
+        Ok(&rest + &contracted)
+    };
+    RationalFunction::new(
+        contract_polynomial(&value.numerator)?,
+        contract_polynomial(&value.denominator)?,
+    )
+}
+
+/// The argument of a monomial that is a single logarithm, ln(x) giving x
+fn logarithm(monomial: &Monomial) -> Option<Polynomial> {
+    let symbols: Vec<Symbol> = monomial.symbols().collect();
+    let [symbol] = symbols.as_slice() else {
+        return None;
+    };
+    match symbol.as_function() {
+        Some(f) if f.name == "ln" && monomial.exponent(*symbol) == 1.0 => Some(f.argument),
+        _ => None,
+    }
+}
+
+/// A positive number or e, whose symbolic powers are positive
+pub(crate) fn is_positive_base(base: &Polynomial) -> bool {
+    base.as_constant().is_some_and(|c| c > 0) || *base == Polynomial::variable(E, 1.0)
+}
+
+/// The function kept whole as a symbol
+fn function(name: &str, argument: Polynomial) -> Polynomial {
+    let mut value = Polynomial::new();
+    value.add_term(Monomial::function(name, argument), Rational::one());
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rational_log() {
+        let r = Rational::from_integer;
+        assert_eq!(rational_log(r(8), r(2)), Some(r(3)));
+        assert_eq!(rational_log(Rational::new(1, 8), r(2)), Some(r(-3)));
+        assert_eq!(rational_log(r(4), r(8)), Some(Rational::new(2, 3)));
+        assert_eq!(rational_log(r(3), r(2)), None);
+    }
+
+    #[test]
+    fn test_integer_log() {
+        let ln = |n: i32| function("ln", Polynomial::constant(n));
+        assert_eq!(integer_log(&BigInt::from(12)), &ln(2).scale(2) + &ln(3));
+        assert_eq!(integer_log(&BigInt::one()), Polynomial::new());
+    }
+}
@@ -0,0 +1,505 @@
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property,
+    monomial::Monomial,
+    monomial_order::MonomialOrder,
+    parser::ASTNode,
+    polynomial::Polynomial,
+    render::{self, Factors, Notation},
+    symbol::GREEK,
+};
+
+/// Functions written under another name, `asin` as arcsin
+const FUNCTION_NAMES: [(&str, &str); 3] =
+    [("asin", "arcsin"), ("acos", "arccos"), ("atan", "arctan")];
+
+/// The invisible operator between factors written side by side
+const INVISIBLE_TIMES: &str = "<mo>&#x2062;</mo>";
+
+/// The invisible operator between a function and its argument
+const APPLY_FUNCTION: &str = "<mo>&#x2061;</mo>";
+
+/// A whole result as a MathML document fragment
+pub fn math(body: &str) -> String {
+    format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+        body
+    )
+}
+
+/// Presentation MathML, laid out for display and screen readers
+pub struct MathML;
+
+impl Notation for MathML {
+    type Piece = String;
+
+    const SIGN_BEFORE_FRACTION: bool = true;
+
+    fn write(&self, piece: String) -> String {
+        piece
+    }
+
+    fn read(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    /// A variable name, with Greek letters as their characters and subscripts for trailing
+    /// digits or an underscore, e.g. `<msub><mi>x</mi><mn>1</mn></msub>` for x_1
+    fn name(&self, name: &str) -> String {
+        let script = |base: &str, subscript: &str| {
+            format!("<msub>{}{}</msub>", self.name(base), self.name(subscript))
+        };
+        if let Some((base, subscript)) = name.split_once('_') {
+            return script(base, subscript);
+        }
+        let digits = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+        if digits.chars().all(|c| c.is_ascii_digit()) && !digits.is_empty() {
+            if digits.len() == name.len() {
+                return format!("<mn>{}</mn>", digits);
+            }
+            return script(&name[..name.len() - digits.len()], digits);
+        }
+        match GREEK.iter().find(|(greek, _)| *greek == name) {
+            Some((_, letter)) => format!("<mi>{}</mi>", letter),
+            None => format!("<mi>{}</mi>", escape(name)),
+        }
+    }
+
+    /// A number, as a fraction when it is a rational with a small denominator
+    fn number(&self, n: f64) -> String {
+        match Rational::from_f64(n) {
+            Some(r) if render::is_small(&r) => self.rational(&r),
+            _ if n < 0.0 => format!("<mo>-</mo><mn>{}</mn>", -n),
+            _ => format!("<mn>{}</mn>", n),
+        }
+    }
+
+    /// A rational, e.g. `<mn>3</mn>` or `<mo>-</mo><mfrac><mn>1</mn><mn>2</mn></mfrac>`
+    fn rational(&self, r: &Rational) -> String {
+        let sign = if r.is_negative() { "<mo>-</mo>" } else { "" };
+        let r = r.abs();
+        if r.is_integer() {
+            return format!("{}<mn>{}</mn>", sign, r.numerator());
+        }
+        format!(
+            "{}<mfrac><mn>{}</mn><mn>{}</mn></mfrac>",
+            sign,
+            r.numerator(),
+            r.denominator()
+        )
+    }
+
+    /// A single term, coefficient then monomial, with the coefficient 1 left out
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, order: MonomialOrder) -> String {
+        let factors = self
+            .factors(monomial, order)
+            .into_iter()
+            .fold(String::new(), |output, factor| juxtapose(&output, &factor));
+        if factors.is_empty() {
+            return self::coefficient(coefficient);
+        }
+        let coefficient = match coefficient {
+            c if c.is_one() => String::new(),
+            c if *c == -1 => "<mo>-</mo>".to_string(),
+            c => self::coefficient(c),
+        };
+        juxtapose(&coefficient, &factors)
+    }
+
+    /// Terms joined into a sum, with a negative term subtracted
+    fn sum(&self, terms: Vec<String>) -> String {
+        if terms.is_empty() {
+            return "<mn>0</mn>".to_string();
+        }
+        let mut output = String::new();
+        for (i, term) in terms.into_iter().enumerate() {
+            match (i, term.strip_prefix("<mo>-</mo>")) {
+                (0, _) => output.push_str(&term),
+                (_, Some(rest)) => output.push_str(&format!("<mo>-</mo>{}", rest)),
+                (_, None) => output.push_str(&format!("<mo>+</mo>{}", term)),
+            }
+        }
+        row(&output)
+    }
+
+    /// A numeric exponent written on a base, left off when it is 1
+    fn power(&self, base: String, exponent: f64) -> String {
+        if exponent == 1.0 {
+            base
+        } else {
+            format!("<msup>{}{}</msup>", row(&base), row(&self.number(exponent)))
+        }
+    }
+
+    fn parenthesize(&self, expression: String) -> String {
+        parenthesize(&expression)
+    }
+
+    /// Factors joined by invisible times after the coefficient, e.g. 2x(x + 1)^2
+    fn times(&self, coefficient: &Rational, factors: Vec<String>) -> String {
+        if coefficient.is_zero() {
+            return "<mn>0</mn>".to_string();
+        }
+        let output = if *coefficient == -1 && !factors.is_empty() {
+            "<mo>-</mo>".to_string()
+        } else if !coefficient.is_one() || factors.is_empty() {
+            self.rational(coefficient)
+        } else {
+            String::new()
+        };
+        factors
+            .iter()
+            .fold(output, |output, factor| juxtapose(&output, factor))
+    }
+
+    fn negate(&self, piece: String) -> String {
+        format!("<mo>-</mo>{}", piece)
+    }
+
+    fn over(&self, numerator: String, denominator: String) -> String {
+        format!("<mfrac>{}{}</mfrac>", row(&numerator), row(&denominator))
+    }
+
+    /// An equation `lhs = rhs`
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        format!("<mrow>{}<mo>=</mo>{}</mrow>", lhs, rhs)
+    }
+
+    /// Expressions separated by commas
+    fn list(&self, elements: Vec<String>) -> String {
+        format!("<mrow>{}</mrow>", elements.join("<mo>,</mo>"))
+    }
+
+    /// Expressions listed in set braces
+    fn set(&self, elements: Vec<String>) -> String {
+        format!(
+            "<mrow><mo>{{</mo>{}<mo>}}</mo></mrow>",
+            elements.join("<mo>,</mo>")
+        )
+    }
+
+    /// Words written among the mathematics, e.g. No solution
+    fn text(&self, words: &str) -> String {
+        format!("<mtext>{}</mtext>", escape(words))
+    }
+
+    /// An unknown left free in a solution, e.g. y free
+    fn free(&self, variable: &str) -> String {
+        format!(
+            "<mrow>{}<mtext>&#xA0;free</mtext></mrow>",
+            self.name(variable)
+        )
+    }
+
+    /// An assumption such as x, y > 0 or n ∈ ℤ
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        let names: Vec<String> = names.iter().map(|n| self.name(n)).collect();
+        let property = match property {
+            Property::Positive => "<mo>&gt;</mo><mn>0</mn>",
+            Property::Negative => "<mo>&lt;</mo><mn>0</mn>",
+            Property::Nonzero => "<mo>&#x2260;</mo><mn>0</mn>",
+            Property::Integer => "<mo>&#x2208;</mo><mi mathvariant=\"double-struck\">Z</mi>",
+            Property::Real => "<mo>&#x2208;</mo><mi mathvariant=\"double-struck\">R</mi>",
+        };
+        format!("<mrow>{}{}</mrow>", names.join("<mo>,</mo>"), property)
+    }
+
+    fn finish(&self, output: String) -> String {
+        match output.is_empty() {
+            true => output,
+            false => math(&output),
+        }
+    }
+}
+
+impl Factors for MathML {
+    const ROOTS: bool = true;
+
+    fn root(&self, radicand: String, index: u32) -> String {
+        match index {
+            2 => format!("<msqrt>{}</msqrt>", radicand),
+            q => format!("<mroot>{}<mn>{}</mn></mroot>", row(&radicand), q),
+        }
+    }
+
+    fn function(&self, name: &str, argument: String, exponent: f64) -> String {
+        if name == "abs" {
+            return self.power(absolute(&argument), exponent);
+        }
+        format!(
+            "<mrow>{}{}{}</mrow>",
+            self.power(function_name(name), exponent),
+            APPLY_FUNCTION,
+            parenthesize(&argument)
+        )
+    }
+
+    /// A base raised to a symbolic exponent, with a base other than a variable or
+    /// a non-negative number in parentheses
+    fn raise(&self, base: &Polynomial, written: String, exponent: String) -> String {
+        let written = match render::is_bare(base) {
+            true => written,
+            false => parenthesize(&written),
+        };
+        format!("<msup>{}{}</msup>", row(&written), row(&exponent))
+    }
+}
+
+/// An exact coefficient, as a fraction when its denominator is small and a decimal otherwise
+fn coefficient(c: &Rational) -> String {
+    match render::is_small(c) {
+        true => MathML.rational(c),
+        false => MathML.number(c.to_f64()),
+    }
+}
+
+/// An expression tree as it was written, e.g. an `<mfrac>` for `(x^2 + 1)/2`
+pub fn ast(node: &ASTNode) -> String {
+    match node {
+        ASTNode::Number(n) => MathML.number(*n),
+        ASTNode::Variable(variable, exponent) => match exponent.as_ref() {
+            ASTNode::Number(e) if *e == 1.0 => MathML.name(variable),
+            exponent => format!(
+                "<msup>{}{}</msup>",
+                MathML.name(variable),
+                row(&ast(exponent))
+            ),
+        },
+        ASTNode::Operation(operator, lhs, rhs) => match operator.as_str() {
+            "/" => format!(
+                "<mfrac>{}{}</mfrac>",
+                row(&operand(lhs)),
+                row(&operand(rhs))
+            ),
+            "*" => format!("<mrow>{}<mo>&#xB7;</mo>{}</mrow>", ast(lhs), ast(rhs)),
+            _ => raised(lhs, &ast(rhs)),
+        },
+        ASTNode::Function(function, arguments) => call(function, arguments, None),
+        ASTNode::Equation(lhs, rhs) => MathML.equation(&ast(lhs), &ast(rhs)),
+        ASTNode::System(equations) => format!(
+            "<mrow><mo>{{</mo><mtable>{}</mtable></mrow>",
+            equations
+                .iter()
+                .map(|e| format!("<mtr><mtd>{}</mtd></mtr>", ast(e)))
+                .collect::<String>()
+        ),
+        ASTNode::Expression(terms) => MathML.sum(terms.iter().map(ast).collect()),
+        ASTNode::Term(coefficient, factors) => {
+            let coefficient = match coefficient.as_ref() {
+                ASTNode::Number(c) if *c == 1.0 && !factors.is_empty() => String::new(),
+                ASTNode::Number(c) if *c == -1.0 && !factors.is_empty() => "<mo>-</mo>".to_string(),
+                coefficient => ast(coefficient),
+            };
+            factors.iter().fold(coefficient, |output, factor| {
+                let printed = match factor {
+                    ASTNode::Expression(_) => parenthesize(&ast(factor)),
+                    _ => ast(factor),
+                };
+                juxtapose(&output, &printed)
+            })
+        }
+        ASTNode::Assumption(names, property) => MathML.assumption(names, *property),
+    }
+}
+
+/// A function call from an expression tree, optionally raised to a power
+fn call(function: &str, arguments: &[ASTNode], exponent: Option<&str>) -> String {
+    let raise = |printed: String| match exponent {
+        Some(e) => format!("<msup>{}{}</msup>", row(&printed), row(e)),
+        None => printed,
+    };
+    match (function, arguments) {
+        ("sqrt", [argument]) => raise(format!("<msqrt>{}</msqrt>", operand(argument))),
+        ("root", [argument, index]) => raise(format!(
+            "<mroot>{}{}</mroot>",
+            row(&operand(argument)),
+            row(&operand(index))
+        )),
+        ("pow", [base, power]) => raised(base, &ast(power)),
+        ("abs", [argument]) => raise(absolute(&operand(argument))),
+        ("conj", [argument]) => raise(format!(
+            "<mover>{}<mo>&#xAF;</mo></mover>",
+            row(&operand(argument))
+        )),
+        ("exp", [argument]) => raise(format!(
+            "<msup><mi>e</mi>{}</msup>",
+            row(&operand(argument))
+        )),
+        ("log", [argument, base]) => format!(
+            "<mrow><msub><mi>log</mi>{}</msub>{}{}</mrow>",
+            row(&operand(base)),
+            APPLY_FUNCTION,
+            parenthesize(&operand(argument))
+        ),
+        ("log10", [argument]) => format!(
+            "<mrow><msub><mi>log</mi><mn>10</mn></msub>{}{}</mrow>",
+            APPLY_FUNCTION,
+            parenthesize(&operand(argument))
+        ),
+        _ => {
+            let listed: Vec<String> = arguments.iter().map(operand).collect();
+            let function = function_name(function);
+            let function = match exponent {
+                Some(e) => format!("<msup>{}{}</msup>", function, row(e)),
+                None => function,
+            };
+            format!(
+                "<mrow>{}{}{}</mrow>",
+                function,
+                APPLY_FUNCTION,
+                parenthesize(&listed.join("<mo>,</mo>"))
+            )
+        }
+    }
+}
+
+/// A base from an expression tree raised to a written exponent
+fn raised(base: &ASTNode, exponent: &str) -> String {
+    match base {
+        ASTNode::Number(n) if *n >= 0.0 && n.fract() == 0.0 => {
+            format!("<msup>{}{}</msup>", MathML.number(*n), row(exponent))
+        }
+        ASTNode::Variable(..) => format!("<msup>{}{}</msup>", row(&ast(base)), row(exponent)),
+        ASTNode::Function(function, arguments) => call(function, arguments, Some(exponent)),
+        _ => format!(
+            "<msup>{}{}</msup>",
+            parenthesize(&operand(base)),
+            row(exponent)
+        ),
+    }
+}
+
+/// An operand of a fraction, root or function, without the parentheses that
+/// grouped it in the written form
+fn operand(node: &ASTNode) -> String {
+    match node {
+        ASTNode::Term(coefficient, factors)
+            if **coefficient == ASTNode::Number(1.0) && factors.len() == 1 =>
+        {
+            match &factors[0] {
+                ASTNode::Expression(_) => ast(&factors[0]),
+                _ => ast(node),
+            }
+        }
+        ASTNode::Expression(terms) if terms.len() == 1 => operand(&terms[0]),
+        _ => ast(node),
+    }
+}
+
+/// The name a function is written with, upright as an identifier of several letters
+fn function_name(function: &str) -> String {
+    let written = FUNCTION_NAMES
+        .iter()
+        .find(|(name, _)| *name == function)
+        .map_or(function, |(_, written)| written);
+    format!("<mi>{}</mi>", escape(written))
+}
+
+fn absolute(argument: &str) -> String {
+    format!("<mrow><mo>|</mo>{}<mo>|</mo></mrow>", argument)
+}
+
+fn parenthesize(expression: &str) -> String {
+    format!("<mrow><mo>(</mo>{}<mo>)</mo></mrow>", expression)
+}
+
+/// Group markup into one element, as scripts, fractions and roots take single children
+fn row(markup: &str) -> String {
+    if is_one_element(markup) {
+        markup.to_string()
+    } else {
+        format!("<mrow>{}</mrow>", markup)
+    }
+}
+
+/// Whether markup is a single element rather than several siblings
+fn is_one_element(markup: &str) -> bool {
+    let Some(rest) = markup.strip_prefix('<') else {
+        return false;
+    };
+    let tag: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    if tag.is_empty() {
+        return false;
+    }
+    let (open, close) = (format!("<{}", tag), format!("</{}>", tag));
+    let mut depth = 0;
+    let mut i = 0;
+    while i < markup.len() {
+        let rest = &markup[i..];
+        if rest.starts_with(&close) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return i == markup.len();
+            }
+        } else if rest.starts_with(&open) && rest[open.len()..].starts_with(['>', ' ']) {
+            depth += 1;
+            i += open.len();
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    false
+}
+
+/// Two factors written side by side, joined by invisible times
+fn juxtapose(left: &str, right: &str) -> String {
+    if left.is_empty() || left == "<mo>-</mo>" {
+        format!("{}{}", left, right)
+    } else {
+        format!("{}{}{}", left, INVISIBLE_TIMES, right)
+    }
+}
+
+/// Escape the characters with a meaning in XML
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        assert_eq!(MathML.name("x"), "<mi>x</mi>");
+        assert_eq!(MathML.name("theta"), "<mi>θ</mi>");
+        assert_eq!(MathML.name("x_10"), "<msub><mi>x</mi><mn>10</mn></msub>");
+        assert_eq!(MathML.name("a<b"), "<mi>a&lt;b</mi>");
+    }
+
+    #[test]
+    fn test_row() {
+        assert_eq!(row("<mi>x</mi>"), "<mi>x</mi>");
+        assert_eq!(
+            row("<mo>-</mo><mn>2</mn>"),
+            "<mrow><mo>-</mo><mn>2</mn></mrow>"
+        );
+        assert_eq!(
+            row("<mrow><mi>x</mi></mrow><mi>y</mi>"),
+            "<mrow><mrow><mi>x</mi></mrow><mi>y</mi></mrow>"
+        );
+        assert_eq!(
+            row("<mrow><mrow><mi>x</mi></mrow><mi>y</mi></mrow>"),
+            "<mrow><mrow><mi>x</mi></mrow><mi>y</mi></mrow>"
+        );
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let x = Polynomial::variable("x", 1.0);
+        let p = &x.pow(2).scale(3) - &Polynomial::constant(Rational::new(1, 2));
+        assert_eq!(
+            MathML.polynomial(&p, MonomialOrder::Lex),
+            "<mrow><mn>3</mn><mo>&#x2062;</mo><msup><mi>x</mi><mn>2</mn></msup>\
+             <mo>-</mo><mfrac><mn>1</mn><mn>2</mn></mfrac></mrow>"
+        );
+    }
+}
@@ -1,15 +1,115 @@
-use std::{cmp::Ordering, collections::VecDeque};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
-use parser::ParsedExpression;
+use apart::PartialFractions;
+use assumptions::Assumptions;
+use canonical::Canonical;
+use content_mathml::ContentMathML;
+use groebner::PolynomialSolution;
+use json::Json;
+use latex::Latex;
+use linear_system::LinearSolution;
+use mathml::MathML;
+use monomial_order::MonomialOrder;
+use number_format::NumberFormat;
+use plain::Plain;
+use polynomial::Polynomial;
+use pretty::Pretty;
+use rational_function::RationalFunction;
+use render::Renderer;
+use symbol::{Symbol, SymbolTable};
 
-use crate::util::hashable_float::HashableFloat;
+use crate::util::rational::Rational;
 
+pub mod apart;
+pub mod assumptions;
+pub mod canonical;
+pub mod content_mathml;
+mod error;
+pub mod factor;
+pub mod groebner;
+pub mod json;
+pub mod latex;
+pub mod latex_lexer;
 pub mod lexer;
+pub mod linear_system;
+pub mod log;
+pub mod mathml;
+pub mod monomial;
+pub mod monomial_order;
+pub mod number_format;
 pub mod parser;
+pub mod plain;
+pub mod polynomial;
+pub mod pretty;
+pub mod radical;
+pub mod rational_function;
+pub mod render;
+pub mod symbol;
+pub mod trig;
+pub mod univariate;
+
+pub use error::{InterpreterError, Span};
+
+/// How results are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// plain text as it would be typed back in, e.g. `x^-2 + 1/2`
+    #[default]
+    Plain,
+    /// LaTeX for typesetting, e.g. `x^{-2} + \frac{1}{2}`
+    Latex,
+    /// presentation MathML, laid out for display and screen readers
+    MathML,
+    /// content MathML, the operations a result is made of, for interchange
+    ContentMathML,
+    /// two-dimensional text for a terminal, with fraction bars, raised exponents and
+    /// radical signs drawn in Unicode or plain ASCII
+    Pretty(pretty::Glyphs),
+    /// plain text that reads back in as the same value, every product written with `*`
+    /// and every exponent but a non-negative integer parenthesised, e.g. `3*x^(-2) - 1`
+    Canonical,
+    /// a versioned JSON document of the operations a result is made of, see [`json`]
+    Json,
+}
+
+/// What solving one system gives, from whichever solver took it
+enum Solutions {
+    Linear(LinearSolution),
+    Polynomial(Vec<PolynomialSolution>),
+}
 
 pub struct Interpreter {
     pub parser: parser::Parser,
     pub lexer: lexer::Lexer,
+    pub latex_lexer: latex_lexer::LatexLexer,
+    /// order terms of a polynomial are printed in, leading term first
+    pub order: MonomialOrder,
+    /// what is known about each variable, from `assume x > 0` and the like
+    pub assumptions: Assumptions,
+    /// how results are written out
+    pub format: OutputFormat,
+    /// how numbers are written in plain output
+    pub numbers: NumberFormat,
+    /// how long an interpretation may take before it fails with a timeout
+    ///
+    /// The limit is checked between evaluation steps, so a single step such as factoring
+    /// a large polynomial can run past it.
+    pub time_limit: Option<Duration>,
+    /// when the current interpretation runs out of time
+    deadline: Cell<Option<Instant>>,
+    /// the variables, radicals and powers interned by this interpreter, entered as the
+    /// thread's symbol table while it interprets
+    symbols: SymbolTable,
+    /// the whole of the last input lexed, where errors raised while evaluating it point
+    input: Option<Span>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
@@ -17,164 +117,768 @@ impl Interpreter {
         Interpreter {
             parser: parser::Parser::new(),
             lexer: lexer::Lexer::new(),
+            latex_lexer: latex_lexer::LatexLexer::new(),
+            order: MonomialOrder::default(),
+            assumptions: Assumptions::new(),
+            format: OutputFormat::default(),
+            numbers: NumberFormat::default(),
+            time_limit: None,
+            deadline: Cell::new(None),
+            symbols: SymbolTable::new(),
+            input: None,
         }
     }
 
+    /// Write the imaginary unit as another name, e.g. `j`, or turn complex numbers off
+    /// with None so `i` is an ordinary variable
+    pub fn set_imaginary_unit(&mut self, unit: Option<&str>) {
+        self.symbols.set_imaginary_unit(unit);
+    }
+
+    /// Run with this interpreter's symbol table entered, so the polynomials built share
+    /// its symbols and no other interpreter's
+    fn with_symbols<T>(&mut self, run: impl FnOnce(&mut Self) -> T) -> T {
+        let mut symbols = std::mem::take(&mut self.symbols);
+        let result = symbols.enter(|| run(self));
+        self.symbols = symbols;
+        result
+    }
+
     pub fn lex(&mut self, arg: &str) -> Result<VecDeque<lexer::Token>, InterpreterError> {
+        self.input = Some(Span::new(0, arg.chars().count()));
         self.lexer.lex(arg)
     }
 
+    /// Read LaTeX such as `\frac{x^2+1}{2}` into the same tokens as the plain form
+    pub fn lex_latex(&mut self, arg: &str) -> Result<VecDeque<lexer::Token>, InterpreterError> {
+        // LaTeX tokens do not line up with the input, so errors from them have no span
+        self.lexer.spans.clear();
+        self.input = Some(Span::new(0, arg.chars().count()));
+        self.latex_lexer.lex(arg)
+    }
+
+    /// Parse tokens into an expression tree; tokens from the last `lex` point errors at
+    /// where they were in the input
     pub fn parse(
         &mut self,
         tokens: VecDeque<lexer::Token>,
     ) -> Result<parser::ASTNode, InterpreterError> {
-        self.parser.parse(tokens)
+        let spans = std::mem::take(&mut self.lexer.spans);
+        self.parser.parse_with_spans(tokens, spans)
     }
 
-    pub(crate) fn interpret(&self, ast_head: parser::ASTNode) -> Result<String, InterpreterError> {
-        let mut output = String::new();
-        match ast_head {
-            parser::ASTNode::Number(n) => {
-                output.push_str(n.to_string().as_str());
-                Ok(output)
+    /// Evaluate an expression tree and write out the result; an error from evaluating the
+    /// last input lexed spans all of it, as the tree does not keep where its parts were
+    pub fn interpret(&mut self, ast_head: parser::ASTNode) -> Result<String, InterpreterError> {
+        let input = self.input.take();
+        self.with_symbols(|interpreter| interpreter.interpret_entered(ast_head))
+            .map_err(|error| error.within(input))
+    }
+
+    fn interpret_entered(&mut self, ast_head: parser::ASTNode) -> Result<String, InterpreterError> {
+        self.deadline
+            .set(self.time_limit.map(|limit| Instant::now() + limit));
+        let output = match &ast_head {
+            parser::ASTNode::Number(n) => Ok(self.renderer().number(*n)),
+            parser::ASTNode::Expression(terms) => match terms.as_slice() {
+                [parser::ASTNode::Term(coefficient, factors)]
+                    if **coefficient == parser::ASTNode::Number(1.0) =>
+                {
+                    match factors.as_slice() {
+                        // only commands are called; value functions such as sqrt are simplified like any term
+                        [parser::ASTNode::Function(name, arguments)]
+                            if matches!(name.as_str(), "factor" | "apart" | "groebner") =>
+                        {
+                            self.call_function(name, arguments)
+                        }
+                        _ => self.solve(&ast_head),
+                    }
+                }
+                [] => Ok("".to_string()),
+                _ => self.solve(&ast_head),
+            },
+            parser::ASTNode::Equation(_, _) => self.solve_system(std::slice::from_ref(&ast_head)),
+            parser::ASTNode::System(branches)
+                if branches
+                    .iter()
+                    .all(|branch| matches!(branch, parser::ASTNode::System(_))) =>
+            {
+                self.solve_branches(branches)
+            }
+            parser::ASTNode::System(equations) => self.solve_system(equations),
+            parser::ASTNode::Assumption(names, property) => {
+                for name in names {
+                    self.assumptions.assume(name, *property)?;
+                }
+                Ok(self.renderer().assumption(names, *property))
             }
-            parser::ASTNode::Expression(terms) => Ok(self.solve(terms)?),
-            _ => Err(InterpreterError::new(
+            _ => Err(InterpreterError::unsupported(
                 "Invalid interpretation input".to_string(),
             )),
+        }?;
+        Ok(self.renderer().finish(output))
+    }
+
+    /// Evaluate a top level function call such as `factor(x^2 - 1)`
+    fn call_function(
+        &self,
+        name: &str,
+        arguments: &[parser::ASTNode],
+    ) -> Result<String, InterpreterError> {
+        match (name, arguments) {
+            ("factor", [argument]) => {
+                let value = self.evaluate(argument)?.reduce();
+                if let Some(denominator) = value.constant_denominator() {
+                    let mut product = factor::factor(&value.numerator)?;
+                    product.coefficient = product.coefficient / denominator;
+                    return Ok(self.renderer().product(&product, self.order));
+                }
+                Ok(self.renderer().factored_fraction(
+                    &factor::factor(&value.numerator)?,
+                    &factor::factor(&value.denominator)?,
+                    self.order,
+                ))
+            }
+            ("factor", _) => Err(InterpreterError::unsupported(
+                "factor expects a single expression".to_string(),
+            )),
+            ("apart", [argument, variable]) => {
+                let variable = variable_name(variable).ok_or_else(|| {
+                    InterpreterError::unsupported(
+                        "apart expects a variable to decompose in".to_string(),
+                    )
+                })?;
+                let value = self.evaluate(argument)?.reduce();
+                self.print_out_partial_fractions(apart::apart(&value, &variable)?)
+            }
+            ("apart", _) => Err(InterpreterError::unsupported(
+                "apart expects an expression and a variable".to_string(),
+            )),
+            ("groebner", [parser::ASTNode::System(equations), rest @ ..]) if rest.len() <= 1 => {
+                let order = match rest.first() {
+                    None => MonomialOrder::Lex,
+                    Some(order) => word(order)
+                        .and_then(|name| MonomialOrder::from_name(&name))
+                        .ok_or_else(|| {
+                            InterpreterError::unsupported(
+                                "groebner expects the order lex, grlex or grevlex".to_string(),
+                            )
+                        })?,
+                };
+                let polynomials = self
+                    .evaluate_equations(equations)?
+                    .into_iter()
+                    .map(|equation| {
+                        if equation.denominator.as_constant().is_none() {
+                            return Err(InterpreterError::unsupported(
+                                "groebner expects polynomial equations".to_string(),
+                            ));
+                        }
+                        Ok(equation.numerator)
+                    })
+                    .collect::<Result<Vec<_>, InterpreterError>>()?;
+                let renderer = self.renderer();
+                let basis: Vec<String> = groebner::groebner_basis(&polynomials, order)?
+                    .iter()
+                    .map(|g| renderer.polynomial(g, self.order))
+                    .collect();
+                Ok(renderer.basis(basis, order))
+            }
+            ("groebner", _) => Err(InterpreterError::unsupported(
+                "groebner expects a system of equations and an optional monomial order".to_string(),
+            )),
+            _ => Err(InterpreterError::unsupported(format!(
+                "Unknown function: {}",
+                name
+            ))),
         }
     }
 
-    /// Combine like terms
-    /// example: 2x^2 + 2x^2 -> 4x^2
+    /// Evaluate an expression tree into a single rational function
+    /// example: 2x^2 + 2x^2 -> 4x^2, 1/x + 1/y -> (x + y)/xy
     ///
-    /// adds up the coefficients for each type of term, and adds up constants
+    /// like terms are combined as sums are folded together, and quotients are
+    /// brought over a common denominator
     ///
-    fn combine_like_terms(
-        &self,
-        terms: &mut Vec<parser::ASTNode>,
-    ) -> Result<ParsedExpression, InterpreterError> {
-        //Expression is a mapping of unique term signatures to their coefficients
-        let mut accumulator = ParsedExpression::new();
-
-        for term in terms.iter() {
-            match term {
-                parser::ASTNode::Term(coefficient, variables) => {
-                    let coefficient = match **coefficient {
-                        parser::ASTNode::Number(n) => n,
-                        _ => 0.0,
-                    };
-
-                    // combine constants
-                    if variables.len() == 0 {
-                        accumulator.add_term(Vec::new(), coefficient);
-                    } else {
-                        // combine variables
-                        let mut term_identifier: Vec<(String, HashableFloat)> = Vec::new();
-                        for variable in variables.iter() {
-                            let var_identifier = match variable {
-                                parser::ASTNode::Variable(name, exponent) => {
-                                    let e = match **exponent {
-                                        parser::ASTNode::Number(n) => n,
-                                        _ => 0.0,
-                                    };
-                                    (name.clone(), HashableFloat::new(e))
-                                }
-                                _ => (String::new(), HashableFloat::new(1.0)),
-                            };
-                            //TODO: add handling for variables written out as multiples of the same variable i.e. 'xxx' => x^3
-                            term_identifier.push(var_identifier);
+    fn evaluate(&self, node: &parser::ASTNode) -> Result<RationalFunction, InterpreterError> {
+        if self
+            .deadline
+            .get()
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(InterpreterError::Timeout { span: None });
+        }
+        match node {
+            parser::ASTNode::Number(n) => match Rational::from_f64(*n) {
+                Some(n) => Ok(Polynomial::constant(n).into()),
+                None => Err(InterpreterError::overflow(format!(
+                    "Unsupported number: {}",
+                    n
+                ))),
+            },
+            parser::ASTNode::Variable(name, exponent) => {
+                // the imaginary unit is registered on first use so that i^2 reduces to -1
+                if Symbol::imaginary_unit().as_deref() == Some(name.as_str()) {
+                    Symbol::imaginary();
+                }
+                match **exponent {
+                    parser::ASTNode::Number(e) => Ok(Polynomial::variable(name, e).into()),
+                    // e^x is exp(x), so that e^ln(x) is x
+                    _ if name == log::E => log::exp(
+                        &self.polynomial_argument(exponent, "exp")?,
+                        &self.assumptions,
+                    ),
+                    _ => {
+                        let exponent = self.evaluate(exponent)?;
+                        // x^(-2) is x^-2, the same power however the exponent is written,
+                        // within the integer powers RationalFunction::pow would take
+                        let integer = exponent
+                            .numerator
+                            .as_constant()
+                            .filter(|e| exponent.is_polynomial() && e.is_integer())
+                            .and_then(|e| e.to_i64())
+                            .filter(|e| u32::try_from(e.unsigned_abs()).is_ok());
+                        match integer {
+                            Some(e) => Ok(Polynomial::variable(name, e as f64).into()),
+                            _ => RationalFunction::from(Polynomial::variable(name, 1.0))
+                                .power(&exponent, &self.assumptions),
                         }
+                    }
+                }
+            }
+            parser::ASTNode::Term(coefficient, factors) => factors
+                .iter()
+                .try_fold(self.evaluate(coefficient)?, |acc, f| {
+                    Ok(acc.mul(&self.evaluate(f)?))
+                }),
+            parser::ASTNode::Expression(terms) => terms
+                .iter()
+                .try_fold(RationalFunction::from(Polynomial::new()), |acc, t| {
+                    Ok(acc.add(&self.evaluate(t)?))
+                }),
+            parser::ASTNode::Operation(operator, lhs, rhs) => {
+                let lhs = self.evaluate(lhs)?;
+                match (operator.as_str(), &**rhs) {
+                    ("*", rhs) => Ok(lhs.mul(&self.evaluate(rhs)?)),
+                    ("/", rhs) => lhs.div(&self.evaluate(rhs)?),
+                    ("^", parser::ASTNode::Number(e)) if e.fract() == 0.0 => lhs.pow(*e as i64),
+                    ("^", rhs) => lhs.power(&self.evaluate(rhs)?, &self.assumptions),
+                    _ => Err(InterpreterError::unsupported(format!(
+                        "Unknown operator: {}",
+                        operator
+                    ))),
+                }
+            }
+            parser::ASTNode::Function(name, arguments) => self.evaluate_function(name, arguments),
+            parser::ASTNode::Equation(_, _) | parser::ASTNode::System(_) => Err(
+                InterpreterError::unsupported("Equations cannot be evaluated".to_string()),
+            ),
+            parser::ASTNode::Assumption(_, _) => Err(InterpreterError::unsupported(
+                "Assumptions cannot be evaluated".to_string(),
+            )),
+        }
+    }
 
-                        // sort by variable, then exponent
-                        term_identifier.sort_by(|a, b| {
-                            a.0.cmp(&b.0).then_with(|| {
-                                a.1 .0.partial_cmp(&b.1 .0).unwrap_or(Ordering::Equal)
-                            })
-                        });
-
-                        accumulator.add_term(term_identifier, coefficient);
+    /// Evaluate a function that stands for a value, such as `sqrt(12)` or `conj(1 + 2i)`
+    fn evaluate_function(
+        &self,
+        name: &str,
+        arguments: &[parser::ASTNode],
+    ) -> Result<RationalFunction, InterpreterError> {
+        match (name, arguments) {
+            ("sqrt", [argument]) => {
+                let value = self.evaluate(argument)?.reduce();
+                if value.is_polynomial() {
+                    if let Some(denested) = radical::denest(&value.numerator) {
+                        return Ok(denested.into());
                     }
                 }
-                _ => (),
+                value.power(
+                    &Polynomial::constant(Rational::new(1, 2)).into(),
+                    &self.assumptions,
+                )
+            }
+            ("root", [argument, index]) => {
+                let (value, index) = (self.evaluate(argument)?, self.evaluate(index)?);
+                match index.numerator.as_constant() {
+                    Some(n) if index.is_polynomial() && n >= 1 && n.is_integer() => {
+                        value.power(&Polynomial::constant(n.recip()).into(), &self.assumptions)
+                    }
+                    _ => Err(InterpreterError::unsupported(
+                        "root expects a positive integer index".to_string(),
+                    )),
+                }
+            }
+            ("pow", [base, exponent]) => self
+                .evaluate(base)?
+                .power(&self.evaluate(exponent)?, &self.assumptions),
+            (name, [argument]) if trig::FUNCTIONS.contains(&name) => trig::apply(
+                name,
+                &self.polynomial_argument(argument, name)?,
+                &self.assumptions,
+            ),
+            ("exp", [argument]) => log::exp(
+                &self.polynomial_argument(argument, name)?,
+                &self.assumptions,
+            ),
+            ("ln" | "log", [argument]) => log::ln(
+                &self.polynomial_argument(argument, name)?,
+                &self.assumptions,
+            ),
+            ("log", [argument, base]) => log::log(
+                &self.polynomial_argument(argument, name)?,
+                &self.polynomial_argument(base, name)?,
+                &self.assumptions,
+            ),
+            ("log10", [argument]) => log::log(
+                &self.polynomial_argument(argument, name)?,
+                &Polynomial::constant(10),
+                &self.assumptions,
+            ),
+            ("expand_log", [argument]) => {
+                log::expand_log(&self.evaluate(argument)?, false, &self.assumptions)
+            }
+            ("expand_log", [argument, option]) => match word(option).as_deref() {
+                Some("force") => {
+                    log::expand_log(&self.evaluate(argument)?, true, &self.assumptions)
+                }
+                _ => Err(InterpreterError::unsupported(
+                    "expand_log expects an expression and optionally force".to_string(),
+                )),
+            },
+            ("contract_log", [argument]) => {
+                log::contract_log(&self.evaluate(argument)?, false, &self.assumptions)
+            }
+            ("contract_log", [argument, option]) => match word(option).as_deref() {
+                Some("force") => {
+                    log::contract_log(&self.evaluate(argument)?, true, &self.assumptions)
+                }
+                _ => Err(InterpreterError::unsupported(
+                    "contract_log expects an expression and optionally force".to_string(),
+                )),
+            },
+            ("expand_trig", [argument]) => {
+                trig::expand_trig(&self.evaluate(argument)?, &self.assumptions)
+            }
+            ("simplify_trig", [argument]) => trig::simplify_trig(&self.evaluate(argument)?),
+            ("conj", [argument]) => {
+                let value = self.evaluate(argument)?;
+                RationalFunction::new(value.numerator.conj(), value.denominator.conj())
             }
+            ("abs", [argument]) => {
+                let value = self.evaluate(argument)?.reduce();
+                if value.numerator.as_complex().is_none() {
+                    return self.absolute_value(&self.polynomial_argument(argument, name)?);
+                }
+                // |a + bi| = sqrt(a^2 + b^2)
+                let (a, b) = self.complex_value(argument, "abs")?;
+                Ok(radical::rational_power(&a * &a + &b * &b, Rational::new(1, 2))?.into())
+            }
+            ("arg", [argument]) => {
+                let (a, b) = self.complex_value(argument, "arg")?;
+                if a.is_zero() && b.is_zero() {
+                    return Err(InterpreterError::domain(
+                        "arg is undefined at 0".to_string(),
+                    ));
+                }
+                // exact on the axes and the diagonals, in quarter turns of pi
+                let sign = match b.is_negative() {
+                    true => -1,
+                    false => 1,
+                };
+                let quarters = match (a.is_zero(), b.is_zero()) {
+                    (_, true) if a.is_negative() => Some(4),
+                    (_, true) => Some(0),
+                    (true, _) => Some(2 * sign),
+                    _ if a.abs() == b.abs() && a.is_negative() => Some(3 * sign),
+                    _ if a.abs() == b.abs() => Some(sign),
+                    _ => None,
+                };
+                if let Some(quarters) = quarters {
+                    let angle =
+                        Polynomial::variable(trig::PI, 1.0).scale(Rational::new(quarters, 4));
+                    return Ok(trig::exact(angle));
+                }
+                // otherwise kept as arg(a + bi), b only being nonzero when there is an
+                // imaginary unit
+                let unit = Symbol::imaginary_unit()
+                    .map_or_else(Polynomial::new, |i| Polynomial::variable(&i, 1.0));
+                let z = &Polynomial::constant(a) + &unit.scale(b);
+                let mut value = Polynomial::new();
+                value.add_term(monomial::Monomial::function("arg", z), Rational::one());
+                Ok(value.into())
+            }
+            (
+                "sqrt" | "conj" | "abs" | "arg" | "expand_trig" | "simplify_trig" | "exp" | "ln"
+                | "log10",
+                _,
+            ) => Err(InterpreterError::unsupported(format!(
+                "{} expects a single expression",
+                name
+            ))),
+            ("root", _) => Err(InterpreterError::unsupported(
+                "root expects an expression and an index".to_string(),
+            )),
+            ("pow", _) => Err(InterpreterError::unsupported(
+                "pow expects a base and an exponent".to_string(),
+            )),
+            ("log", _) => Err(InterpreterError::unsupported(
+                "log expects an expression and optionally a base".to_string(),
+            )),
+            ("expand_log" | "contract_log", _) => Err(InterpreterError::unsupported(format!(
+                "{} expects an expression and optionally force",
+                name
+            ))),
+            (name, _) if trig::FUNCTIONS.contains(&name) => Err(InterpreterError::unsupported(
+                format!("{} expects a single expression", name),
+            )),
+            _ => Err(InterpreterError::unsupported(format!(
+                "{}() cannot be combined with other terms",
+                name
+            ))),
         }
+    }
 
-        Ok(accumulator)
+    /// |p| for a polynomial that is not a number: p itself when the assumptions make it
+    /// at least zero, as for x > 0 or x^2 with x real, -p when they make it at most zero,
+    /// otherwise abs(p) with the sign taken out, so abs(-x) is abs(x)
+    fn absolute_value(
+        &self,
+        polynomial: &Polynomial,
+    ) -> Result<RationalFunction, InterpreterError> {
+        let negated = -polynomial;
+        if self.assumptions.is_nonnegative(polynomial).is_true() {
+            return Ok(polynomial.clone().into());
+        }
+        if self.assumptions.is_nonnegative(&negated).is_true() {
+            return Ok(negated.into());
+        }
+        let leading = polynomial
+            .sorted_monomials(self.order)
+            .first()
+            .copied()
+            .cloned();
+        let argument = match leading {
+            Some(monomial) if polynomial.coefficient(&monomial).is_negative() => negated,
+            _ => polynomial.clone(),
+        };
+        let mut value = Polynomial::new();
+        value.add_term(
+            monomial::Monomial::function("abs", argument),
+            Rational::one(),
+        );
+        Ok(value.into())
     }
 
-    fn print_out_expression(&self, expression: ParsedExpression) -> String {
-        let mut output_string = String::new();
+    /// Evaluate a function argument that must be a polynomial, dividing through by a
+    /// constant denominator so that pi/6 is accepted
+    fn polynomial_argument(
+        &self,
+        argument: &parser::ASTNode,
+        function: &str,
+    ) -> Result<Polynomial, InterpreterError> {
+        let value = self.evaluate(argument)?.reduce();
+        match value.denominator.as_constant() {
+            Some(d) => Ok(value.numerator.scale(d.recip())),
+            None => Err(InterpreterError::unsupported(format!(
+                "{} expects a polynomial argument",
+                function
+            ))),
+        }
+    }
 
-        // sort by first exponent
-        let keys = expression.get_sorted_term_sigs();
+    /// Evaluate an argument that must be a complex number a + bi
+    fn complex_value(
+        &self,
+        argument: &parser::ASTNode,
+        function: &str,
+    ) -> Result<(Rational, Rational), InterpreterError> {
+        let value = self.evaluate(argument)?.reduce();
+        match (
+            value.numerator.as_gaussian(),
+            value.denominator.as_gaussian(),
+        ) {
+            // (a + bi)/(c + di) = ((ac + bd) + (bc - ad)i)/(c^2 + d^2)
+            (Some((a, b)), Some((c, d))) => {
+                let norm = &c * &c + &d * &d;
+                let real = (&a * &c + &b * &d) / &norm;
+                let imaginary = (&b * &c - &a * &d) / &norm;
+                Ok((real, imaginary))
+            }
+            _ => Err(InterpreterError::unsupported(format!(
+                "{} expects a number",
+                function
+            ))),
+        }
+    }
 
-        //zero cases
-        let mut zero_flag = false;
-        for signature in keys.clone().iter() {
-            if expression.get_term(signature.to_vec()).unwrap() != 0.0 {
-                zero_flag = false;
-                break;
-            } else {
-                zero_flag = true;
+    /// Solve one or more equations for every variable they contain
+    ///
+    /// Linear systems are solved by Gaussian elimination, which also describes parametric
+    /// families; anything else falls through to the Gröbner basis solver.
+    fn solve_system(&self, equations: &[parser::ASTNode]) -> Result<String, InterpreterError> {
+        Ok(match self.solve_equations(equations)? {
+            Solutions::Linear(solution) => self.print_out_linear_solution(solution),
+            Solutions::Polynomial(solutions) => self.print_out_polynomial_solutions(solutions),
+        })
+    }
+
+    /// Solve each of several systems, e.g. `{{x = -1}, {x = 1}}`, and print every solution
+    /// of any of them, the way a solution set with several branches is written in canonical
+    /// output
+    fn solve_branches(&self, branches: &[parser::ASTNode]) -> Result<String, InterpreterError> {
+        let renderer = self.renderer();
+        let mut sets = Vec::new();
+        for branch in branches {
+            let parser::ASTNode::System(equations) = branch else {
+                continue;
+            };
+            match self.solve_equations(equations)? {
+                Solutions::Linear(LinearSolution::Inconsistent) => {}
+                Solutions::Linear(LinearSolution::Solved { assignments, free }) => {
+                    if assignments.is_empty() && free.is_empty() {
+                        return Ok(renderer.always_true());
+                    }
+                    sets.push(renderer.set(self.linear_equations(assignments, &free)));
+                }
+                Solutions::Polynomial(solutions) => sets.extend(self.polynomial_sets(solutions)),
             }
         }
-        if keys.len() == 0 || zero_flag {
-            return "0".to_string();
+        match sets.is_empty() {
+            true => Ok(renderer.no_solution()),
+            false => Ok(renderer.list(sets)),
         }
+    }
 
-        //then, access the terms in order and output to a string
-        for (i, signature) in keys.iter().enumerate() {
-            let coefficient = expression.get_term(signature.to_vec()).unwrap();
-            if HashableFloat::new(coefficient) != HashableFloat::new(1.0) || signature.len() == 0 {
-                output_string.push_str(&format!("{}", coefficient));
+    /// Solve the equations of a single system, keeping only solutions the assumptions allow
+    fn solve_equations(
+        &self,
+        equations: &[parser::ASTNode],
+    ) -> Result<Solutions, InterpreterError> {
+        let differences = self.evaluate_equations(equations)?;
+        match linear_system::solve_linear_system(&differences) {
+            Ok(solution) => Ok(Solutions::Linear(self.admitted_linear(solution))),
+            Err(_) => {
+                let solutions = groebner::solve_polynomial_system(&differences)?
+                    .into_iter()
+                    .filter(|solution| {
+                        solution
+                            .assignments
+                            .iter()
+                            .all(|(name, value)| self.assumptions.admits(name, value.clone()))
+                            && solution.roots.iter().all(|(name, value)| {
+                                radical::approximate(value).is_none_or(|value| {
+                                    self.assumptions.admits_approximately(name, value)
+                                })
+                            })
+                    })
+                    .collect();
+                Ok(Solutions::Polynomial(solutions))
             }
-            for (variable, exponent) in signature.iter() {
-                if *exponent != HashableFloat::new(1.0) {
-                    output_string.push_str(&format!("{}^{}", variable, exponent));
-                } else {
-                    output_string.push_str(variable);
+        }
+    }
+
+    /// A linear solution with a numeric value the assumptions rule out has no solution
+    fn admitted_linear(&self, solution: LinearSolution) -> LinearSolution {
+        let LinearSolution::Solved { assignments, .. } = &solution else {
+            return solution;
+        };
+        let ruled_out = assignments.iter().any(|(name, value)| {
+            let value = value.clone().reduce();
+            let number = value
+                .numerator
+                .as_constant()
+                .zip(value.constant_denominator())
+                .map(|(n, d)| n / d);
+            number.is_some_and(|number| !self.assumptions.admits(name, number))
+        });
+        match ruled_out {
+            true => LinearSolution::Inconsistent,
+            false => solution,
+        }
+    }
+
+    /// Evaluate each equation as `lhs - rhs`
+    fn evaluate_equations(
+        &self,
+        equations: &[parser::ASTNode],
+    ) -> Result<Vec<RationalFunction>, InterpreterError> {
+        equations
+            .iter()
+            .map(|equation| match equation {
+                parser::ASTNode::Equation(lhs, rhs) => {
+                    Ok(self.evaluate(lhs)?.sub(&self.evaluate(rhs)?))
                 }
-            }
-            if i < keys.len() - 1 {
-                output_string.push_str(" + ");
-            }
+                _ => Err(InterpreterError::unsupported(
+                    "Expected an equation in the system".to_string(),
+                )),
+            })
+            .collect()
+    }
+
+    /// The notation results are written in, chosen once from the output format
+    fn renderer(&self) -> Box<dyn Renderer> {
+        match self.format {
+            OutputFormat::Plain => Box::new(Plain(self.numbers)),
+            OutputFormat::Latex => Box::new(Latex),
+            OutputFormat::MathML => Box::new(MathML),
+            OutputFormat::ContentMathML => Box::new(ContentMathML),
+            OutputFormat::Pretty(glyphs) => Box::new(Pretty(glyphs)),
+            OutputFormat::Canonical => Box::new(Canonical),
+            OutputFormat::Json => Box::new(Json),
         }
+    }
 
-        output_string
+    /// Print a partial fraction decomposition as a sum, polynomial part first,
+    /// e.g. `x + 1/(2(x - 1)) - 1/(2(x + 1))`
+    fn print_out_partial_fractions(
+        &self,
+        decomposition: PartialFractions,
+    ) -> Result<String, InterpreterError> {
+        let renderer = self.renderer();
+        let mut parts = Vec::new();
+        if !decomposition.polynomial.is_zero() {
+            parts.push(renderer.polynomial(&decomposition.polynomial, self.order));
+        }
+        for fraction in decomposition.fractions {
+            let mut numerator = factor::factor(&fraction.numerator)?;
+            let mut denominator = factor::factor(&fraction.denominator.pow(fraction.power as u32))?;
+            // keep the numerator integral by moving the coefficient's denominator down
+            denominator.coefficient = Rational::from(numerator.coefficient.denominator().clone());
+            numerator.coefficient = Rational::from(numerator.coefficient.numerator().clone());
+            parts.push(renderer.factored_fraction(&numerator, &denominator, self.order));
+        }
+        Ok(renderer.sum(parts))
     }
 
-    fn solve(&self, mut terms: Vec<parser::ASTNode>) -> Result<String, InterpreterError> {
-        if terms.len() == 0 {
-            return Ok("".to_string());
+    /// Print the solution of a linear system, e.g. `x = 2, y = 1`
+    ///
+    /// Parametric families assign each pivot unknown in terms of the free ones,
+    /// which are listed afterwards, e.g. `x = -y + 1, y free`.
+    fn print_out_linear_solution(&self, solution: LinearSolution) -> String {
+        let renderer = self.renderer();
+        match solution {
+            LinearSolution::Inconsistent => renderer.no_solution(),
+            LinearSolution::Solved { assignments, free } => {
+                if assignments.is_empty() && free.is_empty() {
+                    return renderer.always_true();
+                }
+                renderer.list(self.linear_equations(assignments, &free))
+            }
         }
-        let expression = self.combine_like_terms(&mut terms)?;
-        Ok(self.print_out_expression(expression))
     }
-}
 
-#[derive(Debug)]
-pub struct InterpreterError {
-    message: String,
-}
+    /// The assignments of a linear solution as equations, then its free unknowns
+    fn linear_equations(
+        &self,
+        assignments: Vec<(String, RationalFunction)>,
+        free: &[String],
+    ) -> Vec<String> {
+        let renderer = self.renderer();
+        assignments
+            .into_iter()
+            .map(|(name, value)| {
+                renderer.equation(
+                    &renderer.name(&name),
+                    &renderer.fraction(&value, self.order),
+                )
+            })
+            .chain(free.iter().map(|name| renderer.free(name)))
+            .collect()
+    }
+
+    /// Print each branch of a polynomial system's solution set, e.g. `{x = -1, y = -1}, {x = 1, y = 1}`
+    ///
+    /// Equations left in free unknowns are printed as `p = 0` after the assignments.
+    fn print_out_polynomial_solutions(&self, solutions: Vec<PolynomialSolution>) -> String {
+        let renderer = self.renderer();
+        if solutions.is_empty() {
+            return renderer.no_solution();
+        }
+        renderer.list(self.polynomial_sets(solutions))
+    }
 
-impl InterpreterError {
-    fn new(message: String) -> InterpreterError {
-        InterpreterError { message }
+    /// Each solution of a polynomial system as the set of equations it satisfies
+    fn polynomial_sets(&self, solutions: Vec<PolynomialSolution>) -> Vec<String> {
+        let renderer = self.renderer();
+        solutions
+            .into_iter()
+            .map(|solution| {
+                // every unknown with a value, in the order of their names
+                let mut values: Vec<(String, String)> = solution
+                    .assignments
+                    .iter()
+                    .map(|(name, value)| (name.clone(), renderer.rational(value)))
+                    .chain(solution.roots.into_iter().map(|(name, value)| {
+                        (name, renderer.fraction(&trig::exact(value), self.order))
+                    }))
+                    .collect();
+                values.sort_by(|(a, _), (b, _)| a.cmp(b));
+                let equations: Vec<String> = values
+                    .into_iter()
+                    .map(|(name, value)| renderer.equation(&renderer.name(&name), &value))
+                    .chain(solution.remaining.into_iter().map(|p| {
+                        renderer
+                            .equation(&renderer.polynomial(&p, self.order), &renderer.number(0.0))
+                    }))
+                    .collect();
+                renderer.set(equations)
+            })
+            .collect()
     }
 
-    fn unsupported_number(accumulator: f64, n: f64) -> InterpreterError {
-        InterpreterError::new(format!("Unsupported number: {}{}", accumulator, n))
+    fn solve(&self, expression: &parser::ASTNode) -> Result<String, InterpreterError> {
+        let value = self.evaluate(expression)?;
+        Ok(self.renderer().fraction(&value.reduce(), self.order))
     }
 }
 
-impl std::fmt::Display for InterpreterError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "InterpreterError: {}", self.message)
+/// The name of a bare variable argument such as the `x` in `apart(1/(x^2 - 1), x)`
+fn variable_name(argument: &parser::ASTNode) -> Option<String> {
+    match argument {
+        parser::ASTNode::Expression(terms) => match terms.as_slice() {
+            [parser::ASTNode::Term(coefficient, factors)]
+                if **coefficient == parser::ASTNode::Number(1.0) =>
+            {
+                match factors.as_slice() {
+                    [parser::ASTNode::Variable(name, exponent)]
+                        if **exponent == parser::ASTNode::Number(1.0) =>
+                    {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
     }
 }
 
-impl std::error::Error for InterpreterError {}
+/// Read a run of single letter variables back as the word they spell, e.g. an option like `lex`
+fn word(argument: &parser::ASTNode) -> Option<String> {
+    match argument {
+        parser::ASTNode::Expression(terms) => match terms.as_slice() {
+            [parser::ASTNode::Term(coefficient, factors)]
+                if **coefficient == parser::ASTNode::Number(1.0) =>
+            {
+                factors
+                    .iter()
+                    .map(|factor| match factor {
+                        parser::ASTNode::Variable(name, exponent)
+                            if **exponent == parser::ASTNode::Number(1.0) =>
+                        {
+                            Some(name.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
 #[cfg(test)]
 pub mod tests;
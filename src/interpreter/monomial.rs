@@ -0,0 +1,544 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Mul,
+};
+
+use num_traits::ToPrimitive;
+
+use crate::util::{
+    hashable_float::HashableFloat,
+    rational::{self, Rational, ROUNDING},
+};
+
+use super::{
+    polynomial::Polynomial,
+    radical,
+    symbol::{power_name, Power, Symbol},
+};
+
+/// A factor of a monomial as it is written out
+#[derive(Debug, Clone, PartialEq)]
+pub enum Factor {
+    /// a variable, radical or function application to a numeric power
+    Symbol(Symbol, f64),
+    /// a symbolic power with the numeric power of its base folded in, e.g. x^(n + 2)
+    Power(Power),
+}
+
+/// A product of variables raised to powers, e.g. x^2y
+///
+/// Stored as a packed vector of exponents indexed by each variable's interned
+/// symbol, with trailing zeros trimmed, so multiplying is an element-wise sum and
+/// hashing never touches the variable names. Exponents may be negative or fractional.
+///
+/// Symbolic powers such as x^n are symbols of their own, and powers sharing a base are
+/// always folded into one, so x^m·x^n is stored as x^(m + n).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Monomial {
+    exponents: Vec<HashableFloat>,
+}
+
+impl Monomial {
+    /// The empty product, 1
+    pub fn one() -> Self {
+        Monomial::default()
+    }
+
+    /// Build from named powers, summing the exponents of repeated variables (xx -> x^2)
+    pub fn new<S: AsRef<str>>(powers: impl IntoIterator<Item = (S, f64)>) -> Self {
+        let mut exponents = Vec::new();
+        for (name, exponent) in powers {
+            let index = Symbol::intern(name.as_ref()).index();
+            if exponents.len() <= index {
+                exponents.resize(index + 1, HashableFloat::new(0.0));
+            }
+            exponents[index] = HashableFloat::new(exponents[index].0 + exponent);
+        }
+        Monomial::from_exponents(exponents)
+    }
+
+    /// A single variable raised to a power, e.g. x^2
+    pub fn variable(name: &str, exponent: f64) -> Self {
+        Monomial::new([(name, exponent)])
+    }
+
+    /// A base raised to a symbolic exponent with no constant term, e.g. 2^x
+    pub fn power(base: Polynomial, exponent: Polynomial) -> Self {
+        let index = Symbol::power(base, exponent).index();
+        let mut exponents = vec![HashableFloat::new(0.0); index + 1];
+        exponents[index] = HashableFloat::new(1.0);
+        Monomial::from_exponents(exponents)
+    }
+
+    /// The root of a simplified radicand, e.g. sqrt(3), or 1 for a radicand of 1
+    pub fn radical(radicand: i128, index: u32) -> Self {
+        if radicand == 1 {
+            return Monomial::one();
+        }
+        let index = Symbol::radical(radicand, index).index();
+        let mut exponents = vec![HashableFloat::new(0.0); index + 1];
+        exponents[index] = HashableFloat::new(1.0);
+        Monomial::from_exponents(exponents)
+    }
+
+    /// A function applied to an argument, e.g. sin(x)
+    pub fn function(name: &str, argument: Polynomial) -> Self {
+        let index = Symbol::function(name, argument).index();
+        let mut exponents = vec![HashableFloat::new(0.0); index + 1];
+        exponents[index] = HashableFloat::new(1.0);
+        Monomial::from_exponents(exponents)
+    }
+
+    fn from_exponents(mut exponents: Vec<HashableFloat>) -> Self {
+        combine_powers(&mut exponents);
+        // zero powers are stored exactly so equal monomials stay equal element by element
+        for e in exponents.iter_mut() {
+            if *e == HashableFloat::new(0.0) {
+                *e = HashableFloat::new(0.0);
+            }
+        }
+        while exponents.last().is_some_and(|e| e.0 == 0.0) {
+            exponents.pop();
+        }
+        Monomial { exponents }
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.exponents.is_empty()
+    }
+
+    /// The power of a variable, 0 if it does not appear
+    pub fn exponent(&self, symbol: Symbol) -> f64 {
+        self.exponents.get(symbol.index()).map_or(0.0, |e| e.0)
+    }
+
+    /// The variables with a non-zero power
+    pub fn symbols(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.exponents
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.0 != 0.0)
+            .map(|(i, _)| Symbol::from_index(i))
+    }
+
+    /// Number of variables with a non-zero power
+    pub fn len(&self) -> usize {
+        self.symbols().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.is_one()
+    }
+
+    /// Sum of all the exponents
+    pub fn degree(&self) -> f64 {
+        self.exponents.iter().map(|e| e.0).sum()
+    }
+
+    /// Only the radicals and imaginary unit of this monomial, e.g. sqrt(2) from 3sqrt(2)x
+    pub fn radicals(&self) -> Monomial {
+        let mut exponents = self.exponents.clone();
+        for (index, e) in exponents.iter_mut().enumerate() {
+            if !is_radical(Symbol::from_index(index)) {
+                *e = HashableFloat::new(0.0);
+            }
+        }
+        Monomial::from_exponents(exponents)
+    }
+
+    /// This monomial with the imaginary unit taken out, and whether it was there, e.g. (x, true) from ix
+    pub fn split_imaginary(&self) -> (Monomial, bool) {
+        match Symbol::imaginary() {
+            Some(i) if self.exponent(i) == 1.0 => {
+                let mut exponents = self.exponents.clone();
+                exponents[i.index()] = HashableFloat::new(0.0);
+                (Monomial::from_exponents(exponents), true)
+            }
+            _ => (self.clone(), false),
+        }
+    }
+
+    /// The monomial with every power negated, e.g. x^-2y^-1 from x^2y
+    pub fn recip(&self) -> Monomial {
+        Monomial::from_exponents(
+            self.exponents
+                .iter()
+                .map(|e| HashableFloat::new(-e.0))
+                .collect(),
+        )
+    }
+
+    /// Multiply out the radicals, returning the simplified monomial and the rational
+    /// factor that comes out of them, e.g. sqrt(2)·sqrt(6) = 2sqrt(3), sqrt(2)^2 = 2 and i^2 = -1
+    ///
+    /// Radicals of the same index share one radicand, while the imaginary unit is kept
+    /// apart so that i·i is -1 rather than sqrt(1). Radicands too large to simplify
+    /// are left as they are.
+    pub fn reduce_radicals(self) -> (Monomial, Rational) {
+        if !Symbol::has_radicals() {
+            return (self, Rational::one());
+        }
+        // each index with the product of its radicands, and the radical it came from if unchanged
+        let mut groups: Vec<(u32, i128, i128, Option<usize>)> = Vec::new();
+        let mut imaginary = None;
+        for (position, e) in self.exponents.iter().enumerate() {
+            if e.0 == 0.0 {
+                continue;
+            }
+            let symbol = Symbol::from_index(position);
+            if symbol.is_imaginary() {
+                if e.0 != 1.0 && e.0.fract() == 0.0 {
+                    imaginary = Some((position, e.0.rem_euclid(4.0)));
+                }
+                continue;
+            }
+            let Some((radicand, index)) = symbol.as_radical() else {
+                continue;
+            };
+            let Some(power) = Rational::from_f64(e.0) else {
+                return (self, Rational::one());
+            };
+            let exponent = power / Rational::from(index);
+            let (Some(index), Some(magnitude)) = (
+                exponent.denominator().to_u32(),
+                exponent.numerator().magnitude().to_u32(),
+            ) else {
+                return (self, Rational::one());
+            };
+            let Some(value) = radicand.checked_pow(magnitude) else {
+                return (self, Rational::one());
+            };
+            let (numerator, denominator) = match exponent.is_negative() {
+                true => (1, value),
+                false => (value, 1),
+            };
+            let unchanged = (e.0 == 1.0).then_some(position);
+            match groups.iter_mut().find(|group| group.0 == index) {
+                Some(group) => {
+                    let (Some(n), Some(d)) = (
+                        group.1.checked_mul(numerator),
+                        group.2.checked_mul(denominator),
+                    ) else {
+                        return (self, Rational::one());
+                    };
+                    *group = (index, n, d, None);
+                }
+                None => groups.push((index, numerator, denominator, unchanged)),
+            }
+        }
+        if groups.iter().all(|group| group.3.is_some()) && imaginary.is_none() {
+            return (self, Rational::one());
+        }
+
+        let mut reduced = self.clone();
+        let mut coefficient = Rational::one();
+        if let Some((position, power)) = imaginary {
+            // i^2 = -1, i^3 = -i, i^4 = 1
+            reduced.exponents[position] = HashableFloat::new(power % 2.0);
+            if power >= 2.0 {
+                coefficient = Rational::from(-1);
+            }
+        }
+        for (index, e) in reduced.exponents.iter_mut().enumerate() {
+            if Symbol::from_index(index).as_radical().is_some() {
+                *e = HashableFloat::new(0.0);
+            }
+        }
+        for (index, numerator, denominator, _) in groups {
+            let Some((factor, radicand, index)) =
+                radical::simplify_root(numerator, denominator, index)
+            else {
+                return (self, Rational::one());
+            };
+            coefficient *= &factor;
+            reduced = &reduced * &Monomial::radical(radicand, index);
+        }
+        (Monomial::from_exponents(reduced.exponents), coefficient)
+    }
+
+    /// Each variable with its power, sorted by variable name
+    pub fn powers(&self) -> Vec<(String, HashableFloat)> {
+        let mut powers: Vec<(String, HashableFloat)> = self
+            .symbols()
+            .map(|symbol| (symbol.name(), self.exponents[symbol.index()]))
+            .collect();
+        powers.sort_by(|a, b| a.0.cmp(&b.0));
+        powers
+    }
+
+    /// The factors in the order they are written, alphabetically by name, with a numeric
+    /// power of a variable folded into its symbolic power, so x^2·x^n is the one factor x^(n + 2)
+    pub fn factors(&self) -> Vec<Factor> {
+        let powers = self.powers();
+        let mut merged: HashMap<Symbol, Power> = HashMap::new();
+        let mut absorbed: HashSet<String> = HashSet::new();
+        for symbol in self.symbols() {
+            let Some(power) = symbol.as_power() else {
+                continue;
+            };
+            let Some((base, numeric)) = powers.iter().find_map(|(name, e)| {
+                (power.base == Polynomial::variable(name, 1.0)).then(|| (name.clone(), e.0))
+            }) else {
+                continue;
+            };
+            let exponent = &power.exponent + &Polynomial::constant(exact(numeric));
+            merged.insert(
+                symbol,
+                Power {
+                    base: power.base,
+                    exponent,
+                },
+            );
+            absorbed.insert(base);
+        }
+        powers
+            .into_iter()
+            .filter(|(name, _)| !absorbed.contains(name))
+            .map(|(name, e)| {
+                let symbol = Symbol::intern(&name);
+                match merged.remove(&symbol) {
+                    Some(power) => Factor::Power(power),
+                    None => Factor::Symbol(symbol, e.0),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Mul for &Monomial {
+    type Output = Monomial;
+
+    fn mul(self, other: &Monomial) -> Monomial {
+        let (long, short) = if self.exponents.len() >= other.exponents.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut exponents = long.exponents.clone();
+        for (e, f) in exponents.iter_mut().zip(short.exponents.iter()) {
+            e.0 += f.0;
+            // fractional powers add up to the fraction they stand for, so x^(1/729)
+            // cubed is x^(1/243) rather than a unit in the last place off it
+            if e.0.fract() != 0.0 {
+                e.0 = rational::round(e.0);
+            }
+        }
+        Monomial::from_exponents(exponents)
+    }
+}
+
+/// A numeric exponent as an exact coefficient, for when it becomes part of a symbolic
+/// exponent, e.g. the 2 of x^2 when x^2·x^n is written x^(n + 2)
+pub fn exact(exponent: f64) -> Rational {
+    Rational::from_f64(exponent).unwrap_or_default()
+}
+
+fn is_radical(symbol: Symbol) -> bool {
+    symbol.as_radical().is_some() || symbol.is_imaginary()
+}
+
+/// Fold symbolic powers sharing a base into a single power, so x^m·x^n is x^(m + n),
+/// (x^n)^2 is x^(2n) and 2^x·2^-x is 1
+fn combine_powers(exponents: &mut Vec<HashableFloat>) {
+    if !Symbol::has_powers() {
+        return;
+    }
+    // each base with its summed exponent, and the one power it came from if it is unchanged
+    let mut groups: Vec<(Polynomial, Polynomial, Option<usize>)> = Vec::new();
+    for (index, e) in exponents.iter_mut().enumerate() {
+        if e.0 == 0.0 {
+            continue;
+        }
+        let Some(power) = Symbol::from_index(index).as_power() else {
+            continue;
+        };
+        let unchanged = (e.0 == 1.0).then_some(index);
+        let exponent = power.exponent.scale(exact(e.0));
+        *e = HashableFloat::new(0.0);
+        match groups.iter_mut().find(|(base, _, _)| *base == power.base) {
+            Some(group) => {
+                group.1 = &group.1 + &exponent;
+                group.2 = None;
+            }
+            None => groups.push((power.base, exponent, unchanged)),
+        }
+    }
+    for (base, exponent, unchanged) in groups {
+        if exponent.is_zero() {
+            continue;
+        }
+        let index = unchanged.unwrap_or_else(|| Symbol::power(base, exponent).index());
+        if exponents.len() <= index {
+            exponents.resize(index + 1, HashableFloat::new(0.0));
+        }
+        exponents[index] = HashableFloat::new(exponents[index].0 + 1.0);
+    }
+}
+
+/// A name to a numeric power, a power of 1/2 as a square root and other fractions
+/// exactly, e.g. `x^2`, `x^-1`, `sqrt(x)` or `x^(2/3)`
+fn numeric_power(name: &str, exponent: f64) -> String {
+    if HashableFloat::new(exponent) == HashableFloat::new(1.0) {
+        return name.to_string();
+    }
+    if exponent.fract() == 0.0 {
+        return format!("{}^{}", name, exponent);
+    }
+    match Rational::from_f64_within(exponent, ROUNDING) {
+        Some(e) if e == Rational::new(1, 2) => format!("sqrt({})", name),
+        Some(e) => format!("{}^({})", name, e),
+        None => format!("{}^{}", name, exponent),
+    }
+}
+
+/// Whether a name reads back as one variable when written next to others:
+/// a single letter, optionally with a digit suffix or subscript, e.g. `x`, `x1`, `v_0`, `θ`
+fn is_compact_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first_is_letter = chars.next().is_some_and(|c| c.is_alphabetic());
+    let rest: String = chars.collect();
+    first_is_letter && (rest.chars().all(|c| c.is_ascii_digit()) || rest.starts_with('_'))
+}
+
+/// Variables are written side by side, e.g. `x^2y`, unless a multi-letter name would
+/// run into its neighbours, in which case they are joined with `*`, e.g. `rate*time`.
+/// A numeric power of a variable is written into its symbolic power, so x^2·x^n is `x^(n + 2)`.
+impl std::fmt::Display for Monomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.written(!self.is_compact()))
+    }
+}
+
+impl Monomial {
+    /// Whether the factors can be written side by side without running together, as in
+    /// `x^2y`, but not `rate*time` or `cos(x)*sin(x)`
+    pub fn is_compact(&self) -> bool {
+        let names = self.written_factors();
+        names.len() < 2 || names.iter().all(|(name, _)| is_compact_name(name))
+    }
+
+    /// The factors written out, joined by `*` when `separated`, e.g. `x*y^2` or `xy^2`
+    pub fn written(&self, separated: bool) -> String {
+        let separator = match separated {
+            true => "*",
+            false => "",
+        };
+        self.written_factors()
+            .into_iter()
+            .map(|(name, exponent)| numeric_power(&name, exponent))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Each factor's name with the numeric power it is raised to
+    fn written_factors(&self) -> Vec<(String, f64)> {
+        self.factors()
+            .into_iter()
+            .map(|factor| match factor {
+                Factor::Symbol(symbol, exponent) => (symbol.name(), exponent),
+                Factor::Power(power) => (power_name(&power.base, &power.exponent), 1.0),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing() {
+        let xy = Monomial::new([("y", 1.0), ("x", 1.0)]);
+        assert_eq!(xy, Monomial::new([("x", 1.0), ("y", 1.0)]));
+        assert_eq!(
+            Monomial::new([("x", 1.0), ("x", 1.0)]),
+            Monomial::variable("x", 2.0)
+        );
+        assert_eq!(Monomial::new([("x", 1.0), ("x", -1.0)]), Monomial::one());
+        assert_eq!(xy.len(), 2);
+        assert_eq!(xy.to_string(), "xy");
+    }
+
+    #[test]
+    fn test_multiplication() {
+        let x = Monomial::variable("x", 1.0);
+        let y2 = Monomial::variable("y", 2.0);
+        let product = &x * &y2;
+
+        assert_eq!(product.to_string(), "xy^2");
+        assert_eq!(product.degree(), 3.0);
+        assert_eq!(&product * &Monomial::variable("y", -2.0), x);
+        assert_eq!(&x * &Monomial::variable("x", -1.0), Monomial::one());
+        assert!((&x * &Monomial::variable("x", -1.0)).is_one());
+    }
+
+    #[test]
+    fn test_power_laws() {
+        let n = Polynomial::variable("n", 1.0);
+        let x = Polynomial::variable("x", 1.0);
+        let x_n = Monomial::power(x.clone(), n.clone());
+        let x_minus_n = Monomial::power(x.clone(), -&n);
+
+        assert_eq!(&x_n * &x_minus_n, Monomial::one());
+        assert_eq!(&x_n * &x_n, Monomial::power(x, n.scale(2)));
+        assert_eq!(
+            (&x_n * &Monomial::variable("x", 2.0)).to_string(),
+            "x^(n + 2)"
+        );
+    }
+
+    #[test]
+    fn test_radicals() {
+        let root_2 = Monomial::radical(2, 2);
+        let root_6 = Monomial::radical(6, 2);
+
+        assert_eq!(
+            (&root_2 * &root_6).reduce_radicals(),
+            (Monomial::radical(3, 2), Rational::from(2))
+        );
+        assert_eq!(
+            (&root_2 * &root_2).reduce_radicals(),
+            (Monomial::one(), Rational::from(2))
+        );
+        assert_eq!(
+            root_2.recip().reduce_radicals(),
+            (root_2.clone(), Rational::new(1, 2))
+        );
+        let x = Monomial::variable("x", 1.0);
+        assert_eq!((&x * &root_2).radicals(), root_2);
+    }
+
+    #[test]
+    fn test_imaginary_unit() {
+        let i = Monomial::variable("i", 1.0);
+        Symbol::imaginary();
+
+        assert_eq!(
+            (&i * &i).reduce_radicals(),
+            (Monomial::one(), Rational::from(-1))
+        );
+        assert_eq!(
+            Monomial::variable("i", 3.0).reduce_radicals(),
+            (i.clone(), Rational::from(-1))
+        );
+        assert_eq!(
+            Monomial::variable("i", 8.0).reduce_radicals(),
+            (Monomial::one(), Rational::one())
+        );
+        let x = Monomial::variable("x", 1.0);
+        assert_eq!((&x * &i).split_imaginary(), (x.clone(), true));
+        assert_eq!(x.split_imaginary(), (x, false));
+    }
+
+    #[test]
+    fn test_display_names() {
+        assert_eq!(
+            Monomial::new([("x1", 2.0), ("v_0", 1.0), ("θ", 1.0)]).to_string(),
+            "v_0x1^2θ"
+        );
+        assert_eq!(
+            Monomial::new([("rate", 1.0), ("time", 2.0)]).to_string(),
+            "rate*time^2"
+        );
+    }
+}
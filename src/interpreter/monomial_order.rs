@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+
+use crate::util::rational;
+
+use super::{monomial::Monomial, symbol::Symbol};
+
+/// Order used to rank the terms of a multivariate polynomial, both for picking leading
+/// terms in division and Gröbner bases and for the order terms are printed in
+///
+/// Variables are compared in alphabetical order, so under `Lex` x > y > z, with the
+/// constants e, pi, i and radicals ranked below every variable. Every order
+/// is total: two monomials only compare equal when all of their exponents match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonomialOrder {
+    /// lexicographic: compare exponents variable by variable
+    #[default]
+    Lex,
+    /// graded lexicographic: total degree first, then lex
+    GrLex,
+    /// graded reverse lexicographic: total degree first, then the smaller power of the last variable wins
+    GrevLex,
+    /// lowest total degree first, constants leading, e.g. 1 + x + x^2
+    ///
+    /// This is not a well-ordering, so it is only used for output.
+    AscendingPowers,
+}
+
+impl MonomialOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lex" => Some(MonomialOrder::Lex),
+            "grlex" => Some(MonomialOrder::GrLex),
+            "grevlex" => Some(MonomialOrder::GrevLex),
+            "ascending" => Some(MonomialOrder::AscendingPowers),
+            _ => None,
+        }
+    }
+
+    /// The name an order is read from, e.g. `grlex`
+    pub fn name(&self) -> &'static str {
+        match self {
+            MonomialOrder::Lex => "lex",
+            MonomialOrder::GrLex => "grlex",
+            MonomialOrder::GrevLex => "grevlex",
+            MonomialOrder::AscendingPowers => "ascending",
+        }
+    }
+
+    /// Whether every monomial ranks above 1, as division and Gröbner bases require
+    pub fn is_well_ordering(&self) -> bool {
+        *self != MonomialOrder::AscendingPowers
+    }
+
+    /// Compare two exponent vectors over the same variable list
+    pub fn compare(&self, a: &[u32], b: &[u32]) -> Ordering {
+        self.compare_exponents(a, b)
+    }
+
+    /// Compare two monomials, ranking their variables as [`Symbol::ranked`] does
+    ///
+    /// Variables missing from one monomial count as a power of 0 there.
+    pub fn compare_monomials(&self, a: &Monomial, b: &Monomial) -> Ordering {
+        let symbols = Symbol::ranked(a.symbols().chain(b.symbols()));
+        let dense = |monomial: &Monomial| -> Vec<f64> {
+            symbols.iter().map(|s| monomial.exponent(*s)).collect()
+        };
+        self.compare_exponents(&dense(a), &dense(b))
+    }
+
+    /// Compare two dense exponent vectors, given in alphabetical order of their variables
+    pub fn compare_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering {
+        debug_assert_eq!(a.len(), b.len());
+        let lex = || {
+            a.iter().zip(b).fold(Ordering::Equal, |order, (x, y)| {
+                order.then_with(|| x.compare(y))
+            })
+        };
+        match self {
+            MonomialOrder::Lex => lex(),
+            MonomialOrder::GrLex => E::degree(a).compare(&E::degree(b)).then_with(lex),
+            MonomialOrder::GrevLex => E::degree(a).compare(&E::degree(b)).then_with(|| {
+                a.iter()
+                    .zip(b)
+                    .rev()
+                    .fold(Ordering::Equal, |order, (x, y)| {
+                        order.then_with(|| y.compare(x))
+                    })
+            }),
+            MonomialOrder::AscendingPowers => MonomialOrder::GrLex.compare_exponents(b, a),
+        }
+    }
+}
+
+/// Exponents that can be compared exactly: integer powers in algorithms, float powers when printing
+pub trait Exponent: Copy {
+    fn compare(&self, other: &Self) -> Ordering;
+    fn degree(exponents: &[Self]) -> Self;
+}
+
+impl Exponent for u32 {
+    fn compare(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+
+    fn degree(exponents: &[Self]) -> Self {
+        exponents.iter().sum()
+    }
+}
+
+impl Exponent for f64 {
+    fn compare(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
+    }
+
+    /// The sum rounded to the fraction it stands for, so 1/10 + 1/5 ties with 3/10
+    fn degree(exponents: &[Self]) -> Self {
+        rational::round(exponents.iter().sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::symbol::SymbolTable;
+
+    fn monomial(terms: &[(&str, f64)]) -> Monomial {
+        Monomial::new(terms.iter().copied())
+    }
+
+    #[test]
+    fn test_exponent_orders() {
+        // x^2 z vs x y^2 over (x, y, z)
+        let (a, b) = ([2, 0, 1], [1, 2, 0]);
+        assert_eq!(MonomialOrder::Lex.compare(&a, &b), Ordering::Greater);
+        assert_eq!(MonomialOrder::GrLex.compare(&a, &b), Ordering::Greater);
+        assert_eq!(MonomialOrder::GrevLex.compare(&a, &b), Ordering::Less);
+        assert_eq!(
+            MonomialOrder::AscendingPowers.compare(&a, &b),
+            Ordering::Less
+        );
+        // total degree decides first under graded orders
+        assert_eq!(
+            MonomialOrder::Lex.compare(&[1, 0, 0], &[0, 3, 0]),
+            Ordering::Greater
+        );
+        assert_eq!(
+            MonomialOrder::GrLex.compare(&[1, 0, 0], &[0, 3, 0]),
+            Ordering::Less
+        );
+        assert_eq!(
+            MonomialOrder::AscendingPowers.compare(&[0, 0, 0], &[1, 0, 0]),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_monomial_orders() {
+        let x = monomial(&[("x", 1.0)]);
+        let xy = monomial(&[("x", 1.0), ("y", 1.0)]);
+        let y2 = monomial(&[("y", 2.0)]);
+        let one = monomial(&[]);
+
+        assert_eq!(
+            MonomialOrder::Lex.compare_monomials(&xy, &x),
+            Ordering::Greater
+        );
+        assert_eq!(
+            MonomialOrder::Lex.compare_monomials(&x, &y2),
+            Ordering::Greater
+        );
+        assert_eq!(
+            MonomialOrder::GrLex.compare_monomials(&x, &y2),
+            Ordering::Less
+        );
+        assert_eq!(
+            MonomialOrder::Lex.compare_monomials(&one, &x),
+            Ordering::Less
+        );
+        // fractional and negative powers still order totally
+        let root = monomial(&[("x", 0.5)]);
+        let inverse = monomial(&[("x", -1.0)]);
+        assert_eq!(
+            MonomialOrder::Lex.compare_monomials(&root, &inverse),
+            Ordering::Greater
+        );
+        assert_eq!(
+            MonomialOrder::Lex.compare_monomials(&inverse, &one),
+            Ordering::Less
+        );
+        assert_eq!(
+            MonomialOrder::GrevLex.compare_monomials(&xy, &xy),
+            Ordering::Equal
+        );
+        // degrees that differ only by rounding tie, leaving x^(3/10) ahead on x
+        let tenths = monomial(&[("y", 0.1), ("z", 0.2)]);
+        let x_tenths = monomial(&[("x", 0.3)]);
+        assert_eq!(
+            MonomialOrder::GrLex.compare_monomials(&tenths, &x_tenths),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_alphabetical_order() {
+        // variables rank by name whatever order they were first seen in
+        let mut table = SymbolTable::new();
+        let (b, a) = table.enter(|| (monomial(&[("b", 1.0)]), monomial(&[("a", 1.0)])));
+        assert_eq!(
+            table.enter(|| MonomialOrder::Lex.compare_monomials(&a, &b)),
+            Ordering::Greater
+        );
+    }
+}
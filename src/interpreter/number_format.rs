@@ -0,0 +1,167 @@
+use crate::util::{hashable_float::HashableFloat, rational::Rational};
+
+use super::render;
+
+/// How numbers are written in plain output
+///
+/// Exact rationals such as solutions and factored coefficients are fractions unless a
+/// decimal format is asked for; floating point values are decimals unless `Fraction` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// the shortest decimal that reads back as the same value, e.g. 0.1, 2, 1/3 for a rational
+    #[default]
+    Shortest,
+    /// rounded to at most this many digits after the point, e.g. 0.333 for 3
+    Fixed(usize),
+    /// rounded to this many significant figures, e.g. 0.000123 or 12300 for 3
+    Significant(usize),
+    /// as a fraction wherever the value has a small denominator, e.g. 1/3
+    Fraction,
+}
+
+impl NumberFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (kind, digits) = match name.split_once(':') {
+            Some((kind, digits)) => (kind, Some(digits.parse().ok()?)),
+            None => (name, None),
+        };
+        match (kind, digits) {
+            ("shortest", None) => Some(NumberFormat::Shortest),
+            ("fixed", Some(digits)) => Some(NumberFormat::Fixed(digits)),
+            ("significant", Some(digits)) if digits > 0 => Some(NumberFormat::Significant(digits)),
+            ("fraction", None) => Some(NumberFormat::Fraction),
+            _ => None,
+        }
+    }
+
+    /// Whether values are written as decimals, so fractions are divided out
+    pub fn is_decimal(&self) -> bool {
+        matches!(self, NumberFormat::Fixed(_) | NumberFormat::Significant(_))
+    }
+
+    /// Whether a non-zero rational is written as 0, as 0.001 is to 2 places
+    pub fn rounds_to_zero(&self, r: &Rational) -> bool {
+        self.is_decimal() && self.rational(r) == "0"
+    }
+
+    /// Write a floating point value
+    ///
+    /// Integer values never carry a trailing `.0`, and values within rounding error of an
+    /// integer are written as that integer.
+    pub fn number(&self, n: f64) -> String {
+        if !n.is_finite() {
+            return n.to_string();
+        }
+        match self {
+            NumberFormat::Shortest => {
+                match HashableFloat::new(n) == HashableFloat::new(n.round()) {
+                    true => integer(n.round()),
+                    false => n.to_string(),
+                }
+            }
+            NumberFormat::Fixed(digits) => decimal(n, *digits as i32),
+            NumberFormat::Significant(digits) => {
+                let magnitude = match n {
+                    0.0 => 0,
+                    n => n.abs().log10().floor() as i32,
+                };
+                decimal(n, *digits as i32 - 1 - magnitude)
+            }
+            NumberFormat::Fraction => match Rational::from_f64(n) {
+                Some(r) if render::is_small(&r) => r.to_string(),
+                _ => NumberFormat::Shortest.number(n),
+            },
+        }
+    }
+
+    /// Write an exact rational, as a fraction unless a decimal format is asked for
+    ///
+    /// Integers keep every digit with a fixed number of places, but are rounded to
+    /// significant figures like any other value.
+    pub fn rational(&self, r: &Rational) -> String {
+        match self {
+            NumberFormat::Shortest | NumberFormat::Fraction => r.to_string(),
+            NumberFormat::Fixed(_) if r.is_integer() => r.to_string(),
+            format => format.number(r.to_f64()),
+        }
+    }
+}
+
+/// An integer-valued float without a trailing `.0` or a negative zero
+fn integer(n: f64) -> String {
+    match n {
+        0.0 => "0".to_string(),
+        n => format!("{:.0}", n),
+    }
+}
+
+/// A value rounded to the given number of decimal places, trailing zeros dropped
+///
+/// A negative number of places rounds to tens, hundreds and so on.
+fn decimal(n: f64, places: i32) -> String {
+    if places <= 0 {
+        let scale = 10f64.powi(-places);
+        return integer((n / scale).round() * scale);
+    }
+    let written = format!("{:.*}", places as usize, n);
+    let written = written.trim_end_matches('0').trim_end_matches('.');
+    match written {
+        "-0" => "0".to_string(),
+        written => written.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number() {
+        assert_eq!(NumberFormat::Shortest.number(2.0), "2");
+        assert_eq!(
+            NumberFormat::Shortest.number(0.1 + 0.2),
+            "0.30000000000000004"
+        );
+        assert_eq!(NumberFormat::Shortest.number(3.0 - 4e-16), "3");
+        assert_eq!(NumberFormat::Shortest.number(-0.0), "0");
+        assert_eq!(NumberFormat::Fixed(3).number(1.0 / 3.0), "0.333");
+        assert_eq!(NumberFormat::Fixed(3).number(2.5), "2.5");
+        assert_eq!(NumberFormat::Fixed(2).number(-0.001), "0");
+        assert_eq!(NumberFormat::Significant(3).number(0.000123456), "0.000123");
+        assert_eq!(NumberFormat::Significant(3).number(12345.0), "12300");
+        assert_eq!(NumberFormat::Fraction.number(-0.75), "-3/4");
+        assert_eq!(
+            NumberFormat::Fraction.number(2.0f64.sqrt()),
+            "1.4142135623730951"
+        );
+    }
+
+    #[test]
+    fn test_rational() {
+        let third = Rational::new(1, 3);
+        assert_eq!(NumberFormat::Shortest.rational(&third), "1/3");
+        assert_eq!(NumberFormat::Fixed(4).rational(&third), "0.3333");
+        assert_eq!(NumberFormat::Significant(2).rational(&-third), "-0.33");
+        assert_eq!(NumberFormat::Fixed(4).rational(&Rational::from(5)), "5");
+        assert_eq!(
+            NumberFormat::Significant(2).rational(&Rational::from(12345)),
+            "12000"
+        );
+        assert!(NumberFormat::Fixed(2).rounds_to_zero(&Rational::new(-1, 1000)));
+        assert!(!NumberFormat::Fraction.rounds_to_zero(&Rational::new(1, 1000)));
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            NumberFormat::from_name("fixed:2"),
+            Some(NumberFormat::Fixed(2))
+        );
+        assert_eq!(
+            NumberFormat::from_name("fraction"),
+            Some(NumberFormat::Fraction)
+        );
+        assert_eq!(NumberFormat::from_name("significant:0"), None);
+        assert_eq!(NumberFormat::from_name("fixed"), None);
+    }
+}
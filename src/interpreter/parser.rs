@@ -1,104 +1,310 @@
-use std::{
-    cmp::Ordering,
-    collections::{HashMap, HashSet, VecDeque},
-};
+use std::collections::{HashSet, VecDeque};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
 
-use crate::util::hashable_float::HashableFloat;
+use super::{
+    assumptions::Property, json, lexer::Token, log, symbol::GREEK, trig, InterpreterError, Span,
+};
 
-use super::{lexer::Token, InterpreterError};
+/// Power of ten splitting an integer too long for a float into chunks that each fit one
+const CHUNK: i64 = 1_000_000_000_000_000;
 
-#[derive(Debug, PartialEq)]
+/// Written to JSON with named fields, see [`json`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "json::Node", from = "json::Node")]
 pub enum ASTNode {
     Number(f64),
     Operation(String, Box<ASTNode>, Box<ASTNode>),
     Variable(String, Box<ASTNode>),
     Function(String, Vec<ASTNode>),
     Equation(Box<ASTNode>, Box<ASTNode>),
+    System(Vec<ASTNode>),
     Expression(Vec<ASTNode>),
     Term(Box<ASTNode>, Vec<ASTNode>),
+    /// `assume x, y > 0` gives every listed variable the property
+    Assumption(Vec<String>, Property),
+}
+
+/// How a run of letters such as `xy` or `rate` is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VariableMode {
+    /// every letter is its own variable, so `2xy` is 2·x·y, apart from declared
+    /// multi-letter symbols and Greek letter names such as `theta`
+    #[default]
+    SingleLetter,
+    /// a whole identifier is one variable, so `rate` is a single symbol and `xy` is not x·y
+    MultiLetter,
+}
+
+/// How factors written side by side, such as `2(x + 1)` or `x(x + 2)`, are read
+///
+/// Juxtaposition binds tighter than '/', so `1/2x` is 1/(2x), the way it is written by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MultiplicationMode {
+    /// any run of numbers, variables, function calls and groups is a product
+    #[default]
+    Implicit,
+    /// juxtaposition that could be read more than one way is rejected: a product after '/',
+    /// a number after a group as in `(x + 1)2`, and a variable before a group as in `f(x)`
+    Strict,
 }
 
-pub struct ParsedExpression {
-    pub terms: HashMap<Vec<(String, HashableFloat)>, f64>,
-    variables: HashSet<String>,
+pub struct Parser {
+    functions: HashSet<String>,
+    /// how identifiers are split into variables
+    pub variable_mode: VariableMode,
+    /// whether ambiguous implicit multiplication is accepted
+    pub multiplication_mode: MultiplicationMode,
+    /// multi-letter names kept whole even when splitting into single letters
+    symbols: HashSet<String>,
+    /// where each token of the input being parsed was, empty when not known
+    spans: Vec<Span>,
+    /// how many tokens the input being parsed started with
+    total: usize,
+    /// how many absolute value bars are open, so a '|' after a factor closes one
+    bars: usize,
 }
 
-impl ParsedExpression {
-    pub fn new() -> Self {
-        ParsedExpression {
-            terms: HashMap::new(),
-            variables: HashSet::new(),
-        }
-    }
-
-    pub fn add_term(&mut self, term: Vec<(String, HashableFloat)>, coefficient: f64) {
-        if coefficient != 0.0 {
-            if term.len() > 0 {
-                self.variables.insert(term[0].0.clone());
-                self.terms
-                    .entry(term)
-                    .and_modify(|e| *e += coefficient)
-                    .or_insert(coefficient);
-            } else {
-                self.terms
-                    .entry(Vec::new())
-                    .and_modify(|e| *e += coefficient)
-                    .or_insert(coefficient);
+impl Parser {
+    pub fn parse(&mut self, tokens: VecDeque<Token>) -> Result<ASTNode, InterpreterError> {
+        self.parse_with_spans(tokens, Vec::new())
+    }
+
+    /// Parse tokens along with where each was in the input, as recorded by the lexer, so
+    /// errors can point at the token at fault
+    pub fn parse_with_spans(
+        &mut self,
+        mut tokens: VecDeque<Token>,
+        spans: Vec<Span>,
+    ) -> Result<ASTNode, InterpreterError> {
+        self.total = tokens.len();
+        self.bars = 0;
+        self.spans = if spans.len() == tokens.len() {
+            spans
+        } else {
+            Vec::new()
+        };
+        let ast = match tokens.front() {
+            Some(Token::Symbol('{')) => self.parse_system(&mut tokens),
+            Some(Token::Identifier(word)) if word == "assume" => {
+                tokens.pop_front();
+                self.parse_assumption(&mut tokens)
+            }
+            _ => self.parse_equation(&mut tokens),
+        }?;
+        let at = self.span(&tokens);
+        match tokens.front() {
+            None => Ok(ast),
+            Some(Token::Symbol(c @ (')' | '}'))) => Err(InterpreterError::unbalanced(format!(
+                "Unexpected '{}' without a matching opening bracket",
+                c
+            ))
+            .at(at)),
+            Some(token) => {
+                Err(InterpreterError::unexpected_token(format!("Unexpected '{}'", token)).at(at))
             }
         }
     }
 
-    pub fn get_term(&self, term: Vec<(String, HashableFloat)>) -> Option<f64> {
-        self.terms.get(&term).cloned()
+    /// Where the next token is in the input, an empty span at the end when there are no
+    /// more tokens, or None when the spans are not known
+    fn span(&self, tokens: &VecDeque<Token>) -> Option<Span> {
+        let index = self.total.saturating_sub(tokens.len());
+        match self.spans.get(index) {
+            Some(span) => Some(*span),
+            None => self.spans.last().map(|last| Span::end_of(last.end)),
+        }
+    }
+
+    /// Narrow the next token's span to its last characters once the first `skipped` of them
+    /// have been read off as a token of their own, as the `x` of `xy` in `2^xy`
+    fn skip_chars(&mut self, tokens: &VecDeque<Token>, skipped: usize) {
+        let index = self.total.saturating_sub(tokens.len());
+        if let Some(span) = self.spans.get_mut(index) {
+            span.start = (span.start + skipped).min(span.end);
+        }
+    }
+
+    /// Whether each of the next `count` tokens starts where the one before it ends, so two
+    /// digits with a space between them are two numbers, `2 3` being 2·3; true when the
+    /// spans are not known
+    fn touching(&self, tokens: &VecDeque<Token>, count: usize) -> bool {
+        let index = self.total.saturating_sub(tokens.len());
+        (index.max(1)..index + count).all(|i| match (self.spans.get(i - 1), self.spans.get(i)) {
+            (Some(previous), Some(next)) => previous.end == next.start,
+            _ => true,
+        })
     }
 
-    pub fn get_sorted_term_sigs(&self) -> Vec<&Vec<(String, HashableFloat)>> {
-        let mut keys = self
-            .terms
-            .keys()
-            .collect::<Vec<&Vec<(String, HashableFloat)>>>();
-        keys.sort_by(|a, b| {
-            // Handle empty vectors: constants move to the right
-            if a.is_empty() || b.is_empty() {
-                return b.len().cmp(&a.len());
+    /// Accumulates consecutive digit tokens into a single integer, stopping at whitespace,
+    /// or returns None if there are none
+    fn parse_digits(&self, tokens: &mut VecDeque<Token>) -> Option<BigInt> {
+        let mut accumulator = None;
+        while let Some(Token::Number(n)) = tokens.front() {
+            if accumulator.is_some() && !self.touching(tokens, 1) {
+                break;
             }
+            let value = accumulator.unwrap_or_else(BigInt::zero);
+            accumulator = Some(value * 10 + *n as u32);
+            tokens.pop_front();
+        }
+        accumulator
+    }
 
-            // Iterate over each index in both vectors for comparison
-            for (elem_a, elem_b) in a.iter().zip(b.iter()) {
-                // Sort by variable names alphabetically
-                match elem_a.0.partial_cmp(&elem_b.0).unwrap_or(Ordering::Equal) {
-                    Ordering::Equal => {}
-                    non_equal => return non_equal,
-                }
+    /// Reads digits with an optional decimal part as the digits without the point and the
+    /// power of ten they are over, so `2.25` is (225, 100) and read exactly as 225/100.
+    /// Returns None if there are no digits.
+    fn parse_number(&self, tokens: &mut VecDeque<Token>) -> Option<(BigInt, BigInt)> {
+        let mut value = self.parse_digits(tokens)?;
+        let mut scale = BigInt::one();
+        if let (Some(Token::Symbol('.')), Some(Token::Number(_)), true) =
+            (tokens.front(), tokens.get(1), self.touching(tokens, 2))
+        {
+            tokens.pop_front();
+            let before = tokens.len();
+            let decimals = self.parse_digits(tokens)?;
+            scale = BigInt::from(10).pow((before - tokens.len()) as u32);
+            value = value * &scale + decimals;
+        }
+        Some((value, scale))
+    }
 
-                // Sort by exponent values descending
-                match elem_b
-                    .1
-                     .0
-                    .partial_cmp(&elem_a.1 .0)
-                    .unwrap_or(Ordering::Equal)
-                {
-                    Ordering::Equal => {}
-                    non_equal => return non_equal,
+    /// parse_assumption
+    ///
+    /// Parses the rest of `assume x > 0`, `assume x, y < 0` or `assume n integer`. The property
+    /// is a strict comparison with zero or one of the words positive, negative, nonzero,
+    /// integer or real. Other bounds such as `x >= 0` or `x > 1` are not properties a
+    /// variable can have, and are rejected rather than weakened to a sign.
+    fn parse_assumption(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<ASTNode, InterpreterError> {
+        let mut names = Vec::new();
+        while let Some(Token::Identifier(identifier)) = tokens.front() {
+            if let Some(property) = Property::from_name(identifier) {
+                if !names.is_empty() {
+                    tokens.pop_front();
+                    return self.end_assumption(tokens, names, property);
                 }
             }
-
-            // If all elements are equal so far, fall back to comparing lengths
-            a.len().cmp(&b.len())
-        });
-        keys
+            names.extend(self.split_identifier(identifier, self.span(tokens))?);
+            tokens.pop_front();
+            if tokens.front() == Some(&Token::Symbol(',')) {
+                tokens.pop_front();
+            }
+        }
+        if names.is_empty() {
+            return Err(InterpreterError::unexpected_token(
+                "Expected a variable to make an assumption about".to_string(),
+            )
+            .at(self.span(tokens)));
+        }
+        let at = self.span(tokens);
+        let property = match tokens.pop_front() {
+            Some(Token::Symbol('>')) => Property::Positive,
+            Some(Token::Symbol('<')) => Property::Negative,
+            _ => {
+                return Err(InterpreterError::unexpected_token(
+                    "Expected > 0, < 0, positive, negative, nonzero, integer or real".to_string(),
+                )
+                .at(at))
+            }
+        };
+        let at = self.span(tokens);
+        if tokens.front() == Some(&Token::Symbol('=')) {
+            return Err(InterpreterError::unsupported(
+                "Assumptions are strict, as in x > 0; x >= 0 cannot be assumed".to_string(),
+            )
+            .at(at));
+        }
+        if !self.parse_digits(tokens).is_some_and(|n| n.is_zero()) {
+            return Err(InterpreterError::unsupported(
+                "Assumptions compare with 0, as in x > 0; other bounds cannot be assumed"
+                    .to_string(),
+            )
+            .at(at));
+        }
+        self.end_assumption(tokens, names, property)
     }
 
-    pub fn get_variables(&self) -> HashSet<String> {
-        self.variables.clone()
+    /// parse_system
+    ///
+    /// Parses a brace delimited, comma separated list of equations, e.g. `{2x + y = 5, x - y = 1}`.
+    /// The equations may themselves be systems, `{{x = -1}, {x = 1}}` being the solutions
+    /// of either one, as a solution set with several branches is written in canonical output.
+    fn parse_system(&mut self, tokens: &mut VecDeque<Token>) -> Result<ASTNode, InterpreterError> {
+        tokens.pop_front();
+        let mut equations = Vec::new();
+        loop {
+            let at = self.span(tokens);
+            let equation = match tokens.front() {
+                Some(Token::Symbol('{')) => self.parse_system(tokens)?,
+                _ => self.parse_equation(tokens)?,
+            };
+            if is_empty(&equation) {
+                return Err(InterpreterError::missing_operand(
+                    "Expected an equation in the system".to_string(),
+                )
+                .at(at));
+            }
+            equations.push(equation);
+            let at = self.span(tokens);
+            match tokens.pop_front() {
+                Some(Token::Symbol(',')) => continue,
+                Some(Token::Symbol('}')) => break,
+                _ => {
+                    return Err(InterpreterError::unbalanced(
+                        "Expected '}' to close the system of equations".to_string(),
+                    )
+                    .at(at))
+                }
+            }
+        }
+        Ok(ASTNode::System(equations))
     }
-}
 
-pub struct Parser {}
+    /// parse_equation
+    ///
+    /// Parses an expression, and if it is followed by '=', the right hand side of an equation.
+    /// Neither side of an equation may be empty.
+    fn parse_equation(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<ASTNode, InterpreterError> {
+        let at = self.span(tokens);
+        let lhs = self.parse_expression(tokens)?;
+        match tokens.front() {
+            Some(Token::Symbol('=')) if is_empty(&lhs) => Err(InterpreterError::missing_operand(
+                "Expected an expression before '='".to_string(),
+            )
+            .at(at)),
+            Some(Token::Symbol('=')) => {
+                tokens.pop_front();
+                let rhs = self.parse_operand(tokens, "Expected an expression after '='")?;
+                Ok(ASTNode::Equation(Box::new(lhs), Box::new(rhs)))
+            }
+            _ => Ok(lhs),
+        }
+    }
 
-impl Parser {
-    pub fn parse(&mut self, mut tokens: VecDeque<Token>) -> Result<ASTNode, InterpreterError> {
-        self.parse_expression(&mut tokens)
+    /// parse_operand
+    ///
+    /// Parses an expression that has to be there, such as a side of an equation or the inside
+    /// of a group, failing with `missing` at where it should start when it is empty.
+    fn parse_operand(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+        missing: &str,
+    ) -> Result<ASTNode, InterpreterError> {
+        let at = self.span(tokens);
+        let operand = self.parse_expression(tokens)?;
+        if is_empty(&operand) {
+            return Err(InterpreterError::missing_operand(missing.to_string()).at(at));
+        }
+        Ok(operand)
     }
 
     pub fn parse_expression(
@@ -106,161 +312,664 @@ impl Parser {
         tokens: &mut VecDeque<Token>,
     ) -> Result<ASTNode, InterpreterError> {
         let mut terms = Vec::new();
-        while tokens.len() > 0 {
-            let term = self.parse_term(tokens)?;
-            //println!("{:#?}", term);
-            match term { None => break, Some(t) => terms.push(t) } //terms.push(term);
+        while !tokens.is_empty() {
+            // a bar after a term closes the absolute value it is in
+            if self.bars > 0 && !terms.is_empty() && tokens.front() == Some(&Token::Symbol('|')) {
+                break;
+            }
+            let remaining = tokens.len();
+            match self.parse_term(tokens)? {
+                // a sign with nothing following it, as in `x +` or a lone `-`
+                None if tokens.len() < remaining => {
+                    return Err(InterpreterError::missing_operand(
+                        "Missing operand after a sign".to_string(),
+                    )
+                    .at(self.span(tokens)))
+                }
+                None => break,
+                Some(t) => terms.push(t),
+            }
         }
 
-        println!("{:#?}", terms);
-
         Ok(ASTNode::Expression(terms))
     }
 
-    fn parse_term(&self, mut tokens: &mut VecDeque<Token>) -> Result<Option<ASTNode>, InterpreterError> {
-        let before_constant_length = tokens.len();
-        let coefficient = parse_constant(&mut tokens)?;
-        let after_constant_length = tokens.len();
-        let variables = parse_optional_variables(&mut tokens)?;
-
-        //println!("{:#?}, {} - {}", coefficient, before_constant_length, after_constant_length);
+    /// parse_term
+    ///
+    /// Parses a product or quotient of simple terms joined by '*' or '/', e.g. `(x^2 - 1)/(x - 1)`.
+    /// The operators are left associative, so `a/b/c` is `(a/b)/c`, while a juxtaposed
+    /// product after '/' is a single operand, so `1/2x` is `1/(2x)`.
+    /// If no simple term is found, None is returned.
+    fn parse_term(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<Option<ASTNode>, InterpreterError> {
+        let mut term = match self.parse_simple_term(tokens)? {
+            None => return Ok(None),
+            Some((t, _)) => t,
+        };
 
-        //pop that pesky term operator for now, if it's addition
-        if let Some(Token::Symbol('+')) = tokens.front() {
+        while let Some(Token::Symbol(operator @ ('*' | '/'))) = tokens.front() {
+            let operator = *operator;
             tokens.pop_front();
+            let at = self.span(tokens);
+            let (operand, juxtaposed) = self.parse_simple_term(tokens)?.ok_or_else(|| {
+                InterpreterError::missing_operand(format!("Missing operand after '{}'", operator))
+                    .at(at)
+            })?;
+            if operator == '/'
+                && juxtaposed
+                && self.multiplication_mode == MultiplicationMode::Strict
+            {
+                return Err(InterpreterError::unexpected_token(
+                    "Ambiguous implicit multiplication after '/': write a/(bc) or (a/b)c"
+                        .to_string(),
+                )
+                .at(at));
+            }
+            term = ASTNode::Operation(operator.to_string(), Box::new(term), Box::new(operand));
         }
 
-        let zero = Box::new(ASTNode::Number(0.0));
-        let coefficient = match coefficient {
-            Some(c) => c,
-            None => return Ok(None),
+        Ok(Some(term))
+    }
+
+    /// parse_simple_term
+    ///
+    /// Parses an optionally signed term: a coefficient followed by any run of variables,
+    /// function calls and parenthesised groups, all multiplied together, e.g. `3sqrt(2)`.
+    /// Leading '+' and '-' symbols are folded into the sign of the coefficient, which also consumes
+    /// the operator joining this term to the previous one.
+    /// Also returns whether the term is a juxtaposed product of more than one part.
+    /// If neither a coefficient nor any factors are found, None is returned.
+    fn parse_simple_term(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<Option<(ASTNode, bool)>, InterpreterError> {
+        let sign = if get_sign(tokens) { 1 } else { -1 };
+        let mut constant = self.parse_number(tokens);
+        let mut factors = Vec::new();
+        // a power of a number, e.g. 2^x, is a factor rather than the coefficient
+        if let (Some((base, scale)), Some(Token::Symbol('^'))) = (&constant, tokens.front()) {
+            let exponent = self.parse_optional_exponent(tokens)?;
+            factors.push(ASTNode::Operation(
+                "^".to_string(),
+                Box::new(number(base.clone(), scale.clone())),
+                exponent,
+            ));
+            constant = None;
+        }
+        let preceded = constant.is_some() || !factors.is_empty();
+        factors.append(&mut self.parse_factors(tokens, preceded)?);
+        let juxtaposed = constant.is_some() as usize + factors.len() > 1;
+
+        let term = match constant {
+            None if factors.is_empty() => return Ok(None),
+            // no written coefficient on a non-constant term is an implicit coefficient of 1
+            None => ASTNode::Term(Box::new(ASTNode::Number(sign as f64)), factors),
+            Some((c, scale)) => ASTNode::Term(Box::new(number(sign * c, scale)), factors),
         };
+        Ok(Some((term, juxtaposed)))
+    }
 
-        //check for if this is a non-constant term that has a constant of 0, (implicit coefficient of 1)
-        //  -> but how do we differentiate this from a zero constant?
-        //     to convert into a negative coefficient
-        if variables.len() > 0 && coefficient == zero {
-            if before_constant_length == after_constant_length {
-                return Ok(Some(ASTNode::Term(Box::new(ASTNode::Number(1.0)), variables)));
-            } else {
-                return Ok(Some(ASTNode::Term(Box::new(ASTNode::Number(0.0)), variables)));
+    /// parse_factors
+    ///
+    /// Reads variables, function calls, parenthesised groups and numbers following them,
+    /// until something else is found. `preceded` is whether the term already has a
+    /// coefficient, after which a bar closes an open absolute value.
+    fn parse_factors(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+        preceded: bool,
+    ) -> Result<Vec<ASTNode>, InterpreterError> {
+        let strict = self.multiplication_mode == MultiplicationMode::Strict;
+        let mut factors = Vec::new();
+        loop {
+            match tokens.front() {
+                Some(Token::Identifier(_)) => match self.parse_optional_function(tokens)? {
+                    Some(function) => factors.push(match tokens.front() {
+                        Some(Token::Symbol('^')) => ASTNode::Operation(
+                            "^".to_string(),
+                            Box::new(function),
+                            self.parse_optional_exponent(tokens)?,
+                        ),
+                        _ => function,
+                    }),
+                    None => {
+                        factors.append(&mut self.parse_optional_variables(tokens)?);
+                        if let (true, Some(Token::Symbol('(')), Some(ASTNode::Variable(name, _))) =
+                            (strict, tokens.front(), factors.last())
+                        {
+                            return Err(InterpreterError::unexpected_token(format!(
+                                "Ambiguous implicit multiplication: {}(...) could be a function call, write {}*(...) to multiply",
+                                name, name
+                            ))
+                            .at(self.span(tokens)));
+                        }
+                    }
+                },
+                Some(Token::Symbol('(')) => factors.push(self.parse_group(tokens)?),
+                Some(Token::Symbol('|')) if self.bars == 0 || (!preceded && factors.is_empty()) => {
+                    factors.push(self.parse_absolute_value(tokens)?)
+                }
+                Some(Token::Number(_)) if preceded || !factors.is_empty() => {
+                    if strict {
+                        return Err(InterpreterError::unexpected_token(
+                            "Ambiguous implicit multiplication by a trailing number: write the number first, e.g. 2(x + 1)"
+                                .to_string(),
+                        )
+                        .at(self.span(tokens)));
+                    }
+                    if let Some((n, scale)) = self.parse_number(tokens) {
+                        factors.push(match tokens.front() {
+                            Some(Token::Symbol('^')) => ASTNode::Operation(
+                                "^".to_string(),
+                                Box::new(number(n, scale)),
+                                self.parse_optional_exponent(tokens)?,
+                            ),
+                            _ => number(n, scale),
+                        });
+                    }
+                }
+                _ => return Ok(factors),
             }
         }
-        Ok(Some(ASTNode::Term(coefficient, variables)))
     }
 
-    pub(crate) fn new() -> Self {
-        Parser {}
+    /// parse_group
+    ///
+    /// Parses a parenthesised expression and an optional exponent applied to the whole group.
+    fn parse_group(&mut self, tokens: &mut VecDeque<Token>) -> Result<ASTNode, InterpreterError> {
+        tokens.pop_front();
+        let group = self.parse_operand(tokens, "Expected an expression inside '(' and ')'")?;
+        let at = self.span(tokens);
+        match tokens.pop_front() {
+            Some(Token::Symbol(')')) => {}
+            _ => {
+                return Err(InterpreterError::unbalanced(
+                    "Expected ')' to close the group".to_string(),
+                )
+                .at(at))
+            }
+        }
+
+        match tokens.front() {
+            Some(Token::Symbol('^')) => Ok(ASTNode::Operation(
+                "^".to_string(),
+                Box::new(group),
+                self.parse_optional_exponent(tokens)?,
+            )),
+            _ => Ok(group),
+        }
     }
-}
 
-/// parse_optional_exponent
-///
-/// This function takes a stream of tokens and tries to parse an optional exponent.
-/// If an exponent is found, it is parsed and returned as a boxed ASTNode.
-/// If an exponent is not found, a boxed ASTNode representing the number 1 is returned.
-fn parse_optional_exponent(
-    mut tokens: &mut VecDeque<Token>,
-) -> Result<Box<ASTNode>, InterpreterError> {
-    match tokens.front() {
-        Some(Token::Symbol('^')) => {
-            tokens.pop_front();
-            Ok(match parse_constant(&mut tokens)? {
-                None => Box::new(ASTNode::Number(1.0)),
-                Some(n) => n,
-            })
+    /// parse_absolute_value
+    ///
+    /// Parses `|x - 1|` as abs(x - 1), with an optional exponent applied to it. Inside an
+    /// absolute value a bar after a factor closes it and any other bar opens another, so
+    /// `||x| - 1|` nests, while `|x||y|` is a product outside of one.
+    fn parse_absolute_value(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<ASTNode, InterpreterError> {
+        tokens.pop_front();
+        self.bars += 1;
+        let argument = self.parse_operand(tokens, "Expected an expression inside '|' and '|'");
+        self.bars -= 1;
+        let argument = argument?;
+        let at = self.span(tokens);
+        if tokens.pop_front() != Some(Token::Symbol('|')) {
+            return Err(InterpreterError::unbalanced(
+                "Expected '|' to close the absolute value".to_string(),
+            )
+            .at(at));
+        }
+        let absolute = ASTNode::Function("abs".to_string(), vec![argument]);
+
+        match tokens.front() {
+            Some(Token::Symbol('^')) => Ok(ASTNode::Operation(
+                "^".to_string(),
+                Box::new(absolute),
+                self.parse_optional_exponent(tokens)?,
+            )),
+            _ => Ok(absolute),
         }
-        _ => Ok(Box::new(ASTNode::Number(1.0))),
     }
-}
 
-/// parse_optional_variables
-///
-/// This function takes a stream of tokens and tries to parse a variable.
-/// If a variable is found, it is added to a vector, and then any optional exponents are parsed and added to the vector.
-/// If a variable is not found, an empty vector is returned.
-///
-fn parse_optional_variables(
-    tokens: &mut VecDeque<Token>,
-) -> Result<Vec<ASTNode>, InterpreterError> {
-    let option = match tokens.front() {
-        Some(Token::Identifier(_)) => true,
-        _ => false,
-    };
-
-    if option {
-        match tokens.pop_front() {
-            Some(Token::Identifier(s)) => {
-                let var_string = s.clone();
-                let mut variables = Vec::new();
-                //keep reading variables and optional exponents until we hit something else (identifier has a length greater than 1)
-                if var_string.len() > 1 {
-                    for c in var_string.chars().take(var_string.len() - 1) {
-                        variables.push(ASTNode::Variable(
-                            c.to_string(),
-                            Box::new(ASTNode::Number(1.0)),
-                        ));
+    /// parse_optional_function
+    ///
+    /// Parses a call like `factor(x^2 - 1)` if the next tokens are a known function name followed by '('.
+    /// Arguments are comma separated expressions, or a brace delimited system of equations.
+    fn parse_optional_function(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<Option<ASTNode>, InterpreterError> {
+        let name = match (tokens.front(), tokens.get(1)) {
+            (Some(Token::Identifier(name)), Some(Token::Symbol('(')))
+                if self.functions.contains(name) =>
+            {
+                name.clone()
+            }
+            _ => return Ok(None),
+        };
+        tokens.pop_front();
+        tokens.pop_front();
+
+        let mut arguments = Vec::new();
+        loop {
+            arguments.push(match tokens.front() {
+                Some(Token::Symbol('{')) => self.parse_system(tokens)?,
+                _ => self.parse_operand(tokens, &format!("Expected an argument to {}", name))?,
+            });
+            let at = self.span(tokens);
+            match tokens.pop_front() {
+                Some(Token::Symbol(',')) => continue,
+                Some(Token::Symbol(')')) => break,
+                _ => {
+                    return Err(InterpreterError::unbalanced(format!(
+                        "Expected ')' to close the arguments of {}",
+                        name
+                    ))
+                    .at(at))
+                }
+            }
+        }
+
+        Ok(Some(ASTNode::Function(name, arguments)))
+    }
+
+    /// parse_optional_variables
+    ///
+    /// Reads identifiers as variables, each followed by an optional exponent.
+    /// An identifier is split into variables according to the variable mode, and the
+    /// exponent applies to the last of them, so `xy^2` is x·y^2.
+    /// Reading stops at a function call, so `x sin(x)` is x·sin(x), and in single letter mode
+    /// a function name glued to the end of a call's identifier is split off, so `xsin(x)`
+    /// is also x·sin(x), which strict multiplication rejects as ambiguous.
+    /// If no identifier is found, an empty vector is returned.
+    fn parse_optional_variables(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<Vec<ASTNode>, InterpreterError> {
+        let mut variables = Vec::new();
+        while let Some(Token::Identifier(identifier)) = tokens.front() {
+            let called = tokens.get(1) == Some(&Token::Symbol('('));
+            if called && self.functions.contains(identifier) {
+                break;
+            }
+            let identifier = identifier.clone();
+            let function = match (called, self.variable_mode) {
+                (true, VariableMode::SingleLetter) => self
+                    .functions
+                    .iter()
+                    .filter(|f| identifier.len() > f.len() && identifier.ends_with(f.as_str()))
+                    .max_by_key(|f| f.len())
+                    .cloned(),
+                _ => None,
+            };
+            let mut names = match function {
+                Some(function) => {
+                    let prefix = &identifier[..identifier.len() - function.len()];
+                    if self.multiplication_mode == MultiplicationMode::Strict {
+                        return Err(InterpreterError::unexpected_token(format!(
+                            "Ambiguous implicit multiplication: {}(...) could be a function call, write {}*{}(...) to multiply",
+                            identifier, prefix, function
+                        ))
+                        .at(self.span(tokens)));
                     }
-                    let last = var_string.chars().last().unwrap();
-                    let optional_exponent = match *parse_optional_exponent(tokens)? {
-                        ASTNode::Number(n) => Box::new(ASTNode::Number(n)) as Box<ASTNode>,
-                        _ => Box::new(ASTNode::Number(1.0)),
-                    };
-                    variables.push(ASTNode::Variable(last.to_string(), optional_exponent));
-                } else {
-                    variables.push(ASTNode::Variable(
-                        s.clone(),
-                        parse_optional_exponent(tokens)?,
-                    ));
+                    // the function is left behind as a call of its own
+                    reject_unsupported_inverse(&identifier, self.span(tokens))?;
+                    let names = self.split_identifier(prefix, self.span(tokens))?;
+                    tokens[0] = Token::Identifier(function);
+                    self.skip_chars(tokens, prefix.chars().count());
+                    variables.extend(
+                        names
+                            .into_iter()
+                            .map(|name| ASTNode::Variable(name, Box::new(ASTNode::Number(1.0)))),
+                    );
+                    break;
                 }
-                return Ok(variables);
+                None => self.split_identifier(&identifier, self.span(tokens))?,
+            };
+            tokens.pop_front();
+
+            let last = names.pop().unwrap();
+            for name in names {
+                variables.push(ASTNode::Variable(name, Box::new(ASTNode::Number(1.0))));
             }
-            _ => return Ok(Vec::new()),
+            variables.push(ASTNode::Variable(
+                last,
+                self.parse_optional_exponent(tokens)?,
+            ));
         }
+        Ok(variables)
     }
 
-    Ok(Vec::new())
-}
+    /// Split an identifier into variable names
+    ///
+    /// In single letter mode each letter starts a new variable, carrying along any digits
+    /// (`x1`) or an underscore subscript (`v_0`, `x_i`, `x_a1`) that follows it, while declared
+    /// symbols and Greek letter names are matched whole, longest first, so `theta` is one
+    /// variable. The Greek xi is left out, `xi` being x·i. A function name found along the
+    /// way is an error, as `sinx` is more likely a call missing its parentheses than s·i·n·x.
+    /// A subscript is a single letter or digit, so `x_`, `x__1` and `_x` are errors, and a
+    /// Greek letter typed as its character is the variable of its name, `θ` being `theta`.
+    /// The unsupported inverse reciprocal functions are errors too, so `acot` is not a·cot.
+    /// Errors point at the piece of the identifier at fault, the `sin` of `xsin`.
+    fn split_identifier(
+        &self,
+        identifier: &str,
+        at: Option<Span>,
+    ) -> Result<Vec<String>, InterpreterError> {
+        Ok(self
+            .split_pieces(identifier, at)?
+            .iter()
+            .map(|piece| spelled(piece))
+            .collect())
+    }
+
+    /// The pieces an identifier splits into as they were typed, before Greek letters are
+    /// spelled out, see [`Parser::split_identifier`]
+    fn split_pieces(
+        &self,
+        identifier: &str,
+        at: Option<Span>,
+    ) -> Result<Vec<String>, InterpreterError> {
+        let chars: Vec<char> = identifier.chars().collect();
+        // the characters from `start` to `end` of the identifier's span
+        let piece =
+            |start: usize, end: usize| at.map(|at| Span::new(at.start + start, at.start + end));
+        let bad_subscript = chars.iter().enumerate().find(|(i, c)| {
+            **c == '_' && (*i == 0 || !chars.get(i + 1).is_some_and(|c| c.is_alphanumeric()))
+        });
+        if let Some((i, _)) = bad_subscript {
+            return Err(InterpreterError::unexpected_token(format!(
+                "{} has an empty subscript, '_' goes between a name and a letter or digit",
+                identifier
+            ))
+            .at(piece(i, i + 1)));
+        }
+        if self.variable_mode == VariableMode::MultiLetter {
+            return Ok(vec![identifier.to_string()]);
+        }
+        reject_unsupported_inverse(identifier, at)?;
 
-fn parse_constant(mut tokens: &mut VecDeque<Token>) -> Result<Option<Box<ASTNode>>, InterpreterError> {
-    let sign = get_sign(&mut tokens);
-    let mut accumulator = 0.0;
-    loop {
-        if let Some(Token::Number(n)) = tokens.front() {
-            if accumulator >= f64::MAX / 10.0 {
-                return Err(InterpreterError::unsupported_number(accumulator, *n));
+        let mut names = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let rest: String = chars[i..].iter().collect();
+            let whole = self
+                .symbols
+                .iter()
+                .map(String::as_str)
+                .chain(
+                    GREEK
+                        .iter()
+                        .map(|(letter, _)| *letter)
+                        .filter(|letter| *letter != "xi"),
+                )
+                .chain(self.functions.iter().map(String::as_str))
+                .filter(|name| rest.starts_with(name))
+                .max_by_key(|name| name.chars().count());
+            if let Some(name) =
+                whole.filter(|name| self.functions.contains(*name) && !self.symbols.contains(*name))
+            {
+                return Err(InterpreterError::unexpected_token(format!(
+                    "{} is a function, write {}(...) to call it",
+                    name, name
+                ))
+                .at(piece(i, i + name.chars().count())));
             }
-            accumulator *= 10.0;
-            accumulator += n;
-            tokens.pop_front();
+            let mut end = match whole {
+                Some(name) => i + name.chars().count(),
+                None => i + 1,
+            };
+            while end < chars.len() && chars[end].is_ascii_digit() {
+                end += 1;
+            }
+            // the subscript character, checked above, and any digits after it
+            if end < chars.len() && chars[end] == '_' {
+                end += 2;
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+            }
+            names.push(chars[i..end].iter().collect());
+            i = end;
+        }
+        Ok(names)
+    }
+
+    /// parse_optional_exponent
+    ///
+    /// Parses an optional '^' followed by an optionally signed exponent: a number, a single
+    /// variable, a function call or a parenthesised expression, e.g. `x^-2`, `2^x`,
+    /// `x^sqrt(2)`, `x^(1/2)` or `e^(-x^2)`.
+    /// An exponent variable takes only the first variable of an identifier, so `2^xy` is 2^x·y.
+    /// Any exponent may carry its own exponent, so `2^x^2` is 2^(x^2) and `x^2^3` and
+    /// `x^(2)^3` are x^8.
+    /// If no '^' is found, a boxed ASTNode representing the number 1 is returned.
+    fn parse_optional_exponent(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<Box<ASTNode>, InterpreterError> {
+        if tokens.front() != Some(&Token::Symbol('^')) {
+            return Ok(Box::new(ASTNode::Number(1.0)));
+        }
+        tokens.pop_front();
+
+        let sign = if get_sign(tokens) { 1 } else { -1 };
+        let exponent = match tokens.front() {
+            Some(Token::Number(_)) => {
+                let (n, scale) = self
+                    .parse_number(tokens)
+                    .unwrap_or((BigInt::one(), BigInt::one()));
+                if tokens.front() != Some(&Token::Symbol('^')) {
+                    return Ok(Box::new(number(sign * n, scale)));
+                }
+                ASTNode::Operation(
+                    "^".to_string(),
+                    Box::new(number(n, scale)),
+                    self.parse_optional_exponent(tokens)?,
+                )
+            }
+            Some(Token::Symbol('(')) => {
+                tokens.pop_front();
+                let exponent =
+                    self.parse_operand(tokens, "Expected an exponent inside '(' and ')'")?;
+                let at = self.span(tokens);
+                if tokens.pop_front() != Some(Token::Symbol(')')) {
+                    return Err(InterpreterError::unbalanced(
+                        "Expected ')' to close the exponent".to_string(),
+                    )
+                    .at(at));
+                }
+                self.parse_tower(tokens, exponent)?
+            }
+            Some(Token::Identifier(_)) => match self.parse_optional_function(tokens)? {
+                Some(function) => self.parse_tower(tokens, function)?,
+                None => self.parse_exponent_variable(tokens)?,
+            },
+            _ => {
+                return Err(InterpreterError::missing_operand(
+                    "Expected an exponent after '^'".to_string(),
+                )
+                .at(self.span(tokens)))
+            }
+        };
+
+        Ok(Box::new(if sign < 0 {
+            ASTNode::Term(Box::new(ASTNode::Number(-1.0)), vec![exponent])
+        } else {
+            exponent
+        }))
+    }
+
+    /// The first variable of an identifier in an exponent, with its own optional exponent
+    /// when it is the whole identifier; the rest of the identifier is left to be read next
+    fn parse_exponent_variable(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+    ) -> Result<ASTNode, InterpreterError> {
+        let Some(Token::Identifier(identifier)) = tokens.front() else {
+            return Ok(ASTNode::Number(1.0));
+        };
+        let mut pieces = self
+            .split_pieces(identifier, self.span(tokens))?
+            .into_iter();
+        tokens.pop_front();
+        let first = pieces.next().unwrap_or_default();
+        let rest: String = pieces.collect();
+        if rest.is_empty() {
+            Ok(ASTNode::Variable(
+                spelled(&first),
+                self.parse_optional_exponent(tokens)?,
+            ))
         } else {
-            break;
+            tokens.push_front(Token::Identifier(rest));
+            self.skip_chars(tokens, first.chars().count());
+            Ok(ASTNode::Variable(
+                spelled(&first),
+                Box::new(ASTNode::Number(1.0)),
+            ))
+        }
+    }
+
+    /// A base raised to the exponent that follows it, if any, so an exponent can be a
+    /// tower of its own such as the (x)^2 of `e^(x)^2`
+    fn parse_tower(
+        &mut self,
+        tokens: &mut VecDeque<Token>,
+        base: ASTNode,
+    ) -> Result<ASTNode, InterpreterError> {
+        match tokens.front() {
+            Some(Token::Symbol('^')) => Ok(ASTNode::Operation(
+                "^".to_string(),
+                Box::new(base),
+                self.parse_optional_exponent(tokens)?,
+            )),
+            _ => Ok(base),
+        }
+    }
+
+    /// Keep a multi-letter name such as `rate` whole when splitting identifiers into single letters
+    pub fn declare_symbol(&mut self, name: &str) {
+        self.symbols.insert(name.to_string());
+    }
+
+    pub(crate) fn new() -> Self {
+        Parser {
+            functions: [
+                "factor",
+                "apart",
+                "groebner",
+                "sqrt",
+                "root",
+                "pow",
+                "conj",
+                "abs",
+                "arg",
+                "expand_trig",
+                "simplify_trig",
+                "expand_log",
+                "contract_log",
+            ]
+            .into_iter()
+            .chain(trig::FUNCTIONS)
+            .chain(log::FUNCTIONS)
+            .map(|s: &str| -> String { s.to_string() })
+            .collect(),
+            variable_mode: VariableMode::default(),
+            multiplication_mode: MultiplicationMode::default(),
+            symbols: HashSet::from([trig::PI.to_string()]),
+            spans: Vec::new(),
+            total: 0,
+            bars: 0,
         }
     }
-    if accumulator == 0.0 && !sign {
-        return Ok(None);
+
+    /// An assumption must be the whole input
+    fn end_assumption(
+        &self,
+        tokens: &mut VecDeque<Token>,
+        names: Vec<String>,
+        property: Property,
+    ) -> Result<ASTNode, InterpreterError> {
+        match tokens.front() {
+            None => Ok(ASTNode::Assumption(names, property)),
+            Some(_) => Err(InterpreterError::unexpected_token(
+                "Unexpected input after the assumption".to_string(),
+            )
+            .at(self.span(tokens))),
+        }
+    }
+}
+
+/// An error for an identifier holding acot, asec or acsc, which would otherwise split
+/// into a·cot and so on, a wrong answer with no error
+fn reject_unsupported_inverse(identifier: &str, at: Option<Span>) -> Result<(), InterpreterError> {
+    for (inverse, equal) in trig::UNSUPPORTED_INVERSES {
+        if let Some(start) = identifier.find(inverse) {
+            let start = identifier[..start].chars().count();
+            return Err(InterpreterError::unsupported(format!(
+                "{} is not supported, write {}(1/x) for {}(x)",
+                inverse, equal, inverse
+            ))
+            .at(at.map(|at| Span::new(at.start + start, at.start + start + inverse.len()))));
+        }
+    }
+    Ok(())
+}
+
+/// A name with a leading Greek letter character written out, so `θ` is `theta` and `θ_0`
+/// is `theta_0`, the same variable however it was typed
+fn spelled(name: &str) -> String {
+    let mut chars = name.chars();
+    let letter = chars
+        .next()
+        .and_then(|first| GREEK.iter().find(|(_, letter)| *letter == first));
+    match letter {
+        Some((spelling, _)) => format!("{}{}", spelling, chars.as_str()),
+        None => name.to_string(),
     }
-    return Ok(Some(Box::new(ASTNode::Number(
-        accumulator * if sign { 1.0 } else { -1.0 },
-    ))));
+}
+
+/// A number read by [`parse_number`], a quotient when it had decimal places
+fn number(value: BigInt, scale: BigInt) -> ASTNode {
+    if scale.is_one() {
+        return integer(value);
+    }
+    ASTNode::Operation(
+        "/".to_string(),
+        Box::new(integer(value)),
+        Box::new(integer(scale)),
+    )
+}
+
+/// An integer as a number node, or past the integers a float holds exactly, 2^53, as
+/// its 15 digit chunks summed, `high * 10^15 + low`, so it still evaluates exactly
+fn integer(n: BigInt) -> ASTNode {
+    if n.magnitude().bits() <= 53 {
+        return ASTNode::Number(n.to_f64().unwrap_or_default());
+    }
+    let (high, low) = n.div_rem(&BigInt::from(CHUNK));
+    ASTNode::Expression(vec![
+        ASTNode::Term(Box::new(ASTNode::Number(CHUNK as f64)), vec![integer(high)]),
+        ASTNode::Term(Box::new(integer(low)), Vec::new()),
+    ])
+}
+
+/// Whether an expression has no terms, as read from nothing at all
+fn is_empty(node: &ASTNode) -> bool {
+    matches!(node, ASTNode::Expression(terms) if terms.is_empty())
 }
 
 fn get_sign(tokens: &mut VecDeque<Token>) -> bool {
     let mut sign = true;
-    loop { 
-        let front = tokens.front();
-        match front {
-            Some(Token::Symbol('-')) => {
-                sign = !sign;
-                tokens.pop_front();
-                continue;
-            }
-            Some(Token::Symbol('+')) => {
-                tokens.pop_front();
-                continue;
-            }
-            _ => return sign,
+    while let Some(Token::Symbol(symbol @ ('-' | '+'))) = tokens.front() {
+        if *symbol == '-' {
+            sign = !sign;
         }
+        tokens.pop_front();
     }
-    //return sign;
+    sign
 }
@@ -0,0 +1,227 @@
+use num_bigint::BigInt;
+use num_traits::One;
+
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property, factor::Factorization, monomial::Monomial,
+    monomial_order::MonomialOrder, number_format::NumberFormat, polynomial::Polynomial,
+    rational_function::RationalFunction, render::Notation,
+};
+
+/// Plain text as it would be typed back in, with numbers written in the given format,
+/// e.g. `x^-2 + 1/2`
+pub struct Plain(pub NumberFormat);
+
+impl Notation for Plain {
+    type Piece = String;
+
+    fn write(&self, piece: String) -> String {
+        piece
+    }
+
+    fn read(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn name(&self, name: &str) -> String {
+        name.to_string()
+    }
+
+    fn number(&self, n: f64) -> String {
+        self.0.number(n)
+    }
+
+    fn rational(&self, r: &Rational) -> String {
+        self.0.rational(r)
+    }
+
+    /// A single term, its sign then the magnitude of its coefficient and its monomial
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, _order: MonomialOrder) -> String {
+        self.written_term(coefficient, monomial.to_string())
+    }
+
+    /// A polynomial with its products joined the same way in every term, so when one
+    /// needs `*`, as cos(x)*sin(x) does, the others have it too, `2cos(x)*sin(x) + x*y`
+    ///
+    /// Terms whose coefficients round to 0 in a decimal format are left out, so
+    /// x + 0.001y to 2 places is `x` rather than `x + 0y`.
+    fn polynomial(&self, polynomial: &Polynomial, order: MonomialOrder) -> String {
+        let separated = !polynomial.terms().keys().all(Monomial::is_compact);
+        let terms = polynomial
+            .sorted_monomials(order)
+            .into_iter()
+            .filter(|m| !self.0.rounds_to_zero(&polynomial.coefficient(m)))
+            .map(|m| self.written_term(&polynomial.coefficient(m), m.written(separated)))
+            .collect();
+        self.sum(terms)
+    }
+
+    /// Terms joined into a sum, with a negative term subtracted, e.g. `x - 1`
+    fn sum(&self, terms: Vec<String>) -> String {
+        if terms.is_empty() {
+            return "0".to_string();
+        }
+        let mut output = String::new();
+        for (i, term) in terms.iter().enumerate() {
+            match (i, term.strip_prefix('-')) {
+                (0, _) => output.push_str(term),
+                (_, Some(negated)) => output.push_str(&format!(" - {}", negated)),
+                (_, None) => output.push_str(&format!(" + {}", term)),
+            }
+        }
+        output
+    }
+
+    fn power(&self, base: String, exponent: f64) -> String {
+        match exponent {
+            1.0 => base,
+            e => format!("{}^{}", base, e),
+        }
+    }
+
+    fn parenthesize(&self, expression: String) -> String {
+        format!("({})", expression)
+    }
+
+    /// Factors written side by side after the coefficient, e.g. `2x(x + 1)^2`
+    ///
+    /// A fractional coefficient puts its denominator last, `(x + 1)/2` rather than
+    /// `1/2(x + 1)`, which would read as 1/(2(x + 1)).
+    fn times(&self, coefficient: &Rational, factors: Vec<String>) -> String {
+        if coefficient.is_zero() || self.0.rounds_to_zero(coefficient) {
+            return "0".to_string();
+        }
+        let has_factors = !factors.is_empty();
+        let denominator = match (has_factors, self.0.is_decimal()) {
+            (true, false) => coefficient.denominator().clone(),
+            _ => BigInt::one(),
+        };
+        let coefficient = coefficient * Rational::from(denominator.clone());
+        let mut output = if coefficient == -1 && has_factors {
+            "-".to_string()
+        } else if !coefficient.is_one() || !has_factors {
+            self.0.rational(&coefficient)
+        } else {
+            String::new()
+        };
+        output.push_str(&factors.concat());
+        if !denominator.is_one() {
+            output.push_str(&format!("/{}", denominator));
+        }
+        output
+    }
+
+    fn negate(&self, piece: String) -> String {
+        format!("-{}", piece)
+    }
+
+    fn over(&self, numerator: String, denominator: String) -> String {
+        format!("{}/{}", numerator, denominator)
+    }
+
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        format!("{} = {}", lhs, rhs)
+    }
+
+    fn list(&self, elements: Vec<String>) -> String {
+        elements.join(", ")
+    }
+
+    fn set(&self, elements: Vec<String>) -> String {
+        format!("{{{}}}", elements.join(", "))
+    }
+
+    fn text(&self, words: &str) -> String {
+        words.to_string()
+    }
+
+    fn free(&self, variable: &str) -> String {
+        format!("{} free", variable)
+    }
+
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        format!("{} {}", names.join(", "), property)
+    }
+
+    /// A rational function as a single fraction, e.g. `(x + y)/xy`
+    ///
+    /// Numerators with several terms are parenthesised, as are denominators
+    /// other than a single number or variable power.
+    fn fraction(&self, value: &RationalFunction, order: MonomialOrder) -> String {
+        if value.is_polynomial() {
+            return self.polynomial(&value.numerator, order);
+        }
+        // a decimal format writes (3x + 4)/12 as 0.25x + 0.333
+        if let (true, Some(denominator)) = (self.0.is_decimal(), value.denominator.as_constant()) {
+            return self.polynomial(&value.numerator.scale(denominator.recip()), order);
+        }
+        let numerator = match value.numerator.terms().len() > 1 {
+            true => self.parenthesize(self.polynomial(&value.numerator, order)),
+            false => self.polynomial(&value.numerator, order),
+        };
+        let denominator = match value.is_simple_denominator() {
+            true => self.polynomial(&value.denominator, order),
+            false => self.parenthesize(self.polynomial(&value.denominator, order)),
+        };
+        self.over(numerator, denominator)
+    }
+
+    /// A factored numerator over a factored denominator
+    fn factored_fraction(
+        &self,
+        numerator: &Factorization,
+        denominator: &Factorization,
+        order: MonomialOrder,
+    ) -> String {
+        let lone_factor = |product: &Factorization| {
+            product.coefficient.is_one()
+                && product.monomial.is_empty()
+                && product.factors.len() == 1
+                && product.factors[0].1 == 1
+        };
+        // a number, a single variable power, or a single parenthesised factor power
+        let simple_denominator = if denominator.factors.is_empty() {
+            denominator.monomial.is_empty()
+                || (denominator.coefficient.is_one() && denominator.monomial.len() == 1)
+        } else {
+            denominator.coefficient.is_one()
+                && denominator.monomial.is_empty()
+                && denominator.factors.len() == 1
+                && denominator.factors[0].1 > 1
+        };
+        let written = match lone_factor(numerator) {
+            true => self.parenthesize(self.product(numerator, order)),
+            false => self.product(numerator, order),
+        };
+        let divisor = match lone_factor(denominator) || !simple_denominator {
+            true => self.parenthesize(self.product(denominator, order)),
+            false => self.product(denominator, order),
+        };
+        self.over(written, divisor)
+    }
+}
+
+impl Plain {
+    /// A term with its monomial written out already
+    fn written_term(&self, coefficient: &Rational, monomial: String) -> String {
+        let sign = if coefficient.is_negative() { "-" } else { "" };
+        let magnitude = self.0.rational(&coefficient.abs());
+        if monomial.is_empty() {
+            return format!("{}{}", sign, magnitude);
+        }
+        let magnitude = match magnitude.as_str() {
+            "1" => String::new(),
+            // a fraction is kept apart from the monomial it multiplies, (1/2)x
+            m if m.contains('/') => format!("({})", m),
+            m => m.to_string(),
+        };
+        // keep a numeric base such as 2^x apart from the coefficient
+        let times =
+            match !magnitude.is_empty() && monomial.starts_with(|c: char| c.is_ascii_digit()) {
+                true => "*",
+                false => "",
+            };
+        format!("{}{}{}{}", sign, magnitude, times, monomial)
+    }
+}
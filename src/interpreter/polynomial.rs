@@ -0,0 +1,397 @@
+use std::{
+    collections::{hash_map::Entry, BTreeSet, HashMap},
+    hash::{Hash, Hasher},
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use crate::util::{complex::Complex, rational::Rational};
+
+use super::{
+    monomial::Monomial, monomial_order::MonomialOrder, number_format::NumberFormat, plain::Plain,
+    render::Notation, symbol::Symbol,
+};
+
+/// Sparse multivariate polynomial, mapping each monomial to its coefficient
+///
+/// Exponents may be negative or fractional, so Laurent polynomials such as x^-2 + 1
+/// are represented directly. Terms that cancel are removed, so equal polynomials
+/// always have the same terms.
+///
+/// Polynomials combine with the usual operators on values or references:
+/// `&(&x + &y) * &x - Polynomial::constant(1)` is x^2 + xy - 1. Coefficients are exact
+/// rationals of any size.
+#[derive(Debug, Clone, Default)]
+pub struct Polynomial {
+    terms: HashMap<Monomial, Rational>,
+}
+
+impl Polynomial {
+    pub fn new() -> Self {
+        Polynomial {
+            terms: HashMap::new(),
+        }
+    }
+
+    pub fn constant(coefficient: impl Into<Rational>) -> Self {
+        let mut polynomial = Polynomial::new();
+        polynomial.add_term(Monomial::one(), coefficient.into());
+        polynomial
+    }
+
+    /// A single variable raised to a power, e.g. x^2
+    pub fn variable(name: &str, exponent: f64) -> Self {
+        let mut polynomial = Polynomial::new();
+        polynomial.add_term(Monomial::variable(name, exponent), Rational::one());
+        polynomial
+    }
+
+    /// add_term
+    ///
+    /// Adds a coefficient to the term with the given monomial, multiplying out its radicals.
+    /// Terms whose coefficients cancel out are removed.
+    pub fn add_term(&mut self, monomial: Monomial, coefficient: Rational) {
+        if coefficient.is_zero() {
+            return;
+        }
+        // powers of radicals come out as numbers, e.g. sqrt(2)^2 = 2
+        let (monomial, factor) = monomial.reduce_radicals();
+        let coefficient = coefficient * factor;
+        match self.terms.entry(monomial) {
+            Entry::Occupied(mut entry) => {
+                *entry.get_mut() += &coefficient;
+                if entry.get().is_zero() {
+                    entry.remove();
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(coefficient);
+            }
+        }
+    }
+
+    /// Every term, keyed by its normalized monomial
+    pub fn terms(&self) -> &HashMap<Monomial, Rational> {
+        &self.terms
+    }
+
+    /// The coefficient of a monomial, 0 if it does not appear
+    pub fn coefficient(&self, monomial: &Monomial) -> Rational {
+        self.terms.get(monomial).cloned().unwrap_or_default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The value of this polynomial if it has no variables
+    pub fn as_constant(&self) -> Option<Rational> {
+        match self.terms.len() {
+            0 => Some(Rational::zero()),
+            1 => self.terms.get(&Monomial::one()).cloned(),
+            _ => None,
+        }
+    }
+
+    /// The terms with the imaginary unit folded into complex coefficients, so 2x + 3ix
+    /// is (2 + 3i)x
+    pub fn complex_terms(&self) -> HashMap<Monomial, Complex> {
+        let mut terms: HashMap<Monomial, Complex> = HashMap::new();
+        for (monomial, coefficient) in self.terms.iter() {
+            let (monomial, imaginary) = monomial.split_imaginary();
+            let coefficient = match imaginary {
+                true => Complex::new(0.0, coefficient.to_f64()),
+                false => Complex::from(coefficient.to_f64()),
+            };
+            let entry = terms.entry(monomial).or_default();
+            *entry = *entry + coefficient;
+        }
+        terms
+    }
+
+    /// The value of this polynomial if it is a complex number a + bi
+    pub fn as_complex(&self) -> Option<Complex> {
+        let terms = self.complex_terms();
+        match terms.len() {
+            0 => Some(Complex::default()),
+            1 => terms.get(&Monomial::one()).copied(),
+            _ => None,
+        }
+    }
+
+    /// The exact value of this polynomial as `(a, b)` if it is a complex number a + bi
+    /// with rational parts
+    pub fn as_gaussian(&self) -> Option<(Rational, Rational)> {
+        let (mut real, mut imaginary) = (Rational::zero(), Rational::zero());
+        for (monomial, coefficient) in self.terms.iter() {
+            match monomial.split_imaginary() {
+                (rest, false) if rest.is_one() => real += coefficient,
+                (rest, true) if rest.is_one() => imaginary += coefficient,
+                _ => return None,
+            }
+        }
+        Some((real, imaginary))
+    }
+
+    /// The complex conjugate, replacing i with -i and treating variables as real
+    pub fn conj(&self) -> Polynomial {
+        let mut conjugate = Polynomial::new();
+        for (monomial, coefficient) in self.terms.iter() {
+            let coefficient = match monomial.split_imaginary().1 {
+                true => -coefficient,
+                false => coefficient.clone(),
+            };
+            conjugate.add_term(monomial.clone(), coefficient);
+        }
+        conjugate
+    }
+
+    /// Every variable appearing in any term, in alphabetical order
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.symbols().iter().map(|symbol| symbol.name()).collect()
+    }
+
+    /// Every variable appearing in any term, as interned symbols
+    pub fn symbols(&self) -> BTreeSet<Symbol> {
+        self.terms
+            .keys()
+            .flat_map(|monomial| monomial.symbols())
+            .collect()
+    }
+
+    pub fn scale(&self, factor: impl Into<Rational>) -> Polynomial {
+        let factor = factor.into();
+        let mut scaled = Polynomial::new();
+        for (monomial, coefficient) in self.terms.iter() {
+            scaled.add_term(monomial.clone(), coefficient * &factor);
+        }
+        scaled
+    }
+
+    /// Raise to a non-negative integer power by repeated squaring
+    pub fn pow(&self, exponent: u32) -> Polynomial {
+        let mut result = Polynomial::constant(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// Monomials from the leading term down under the given monomial order
+    pub fn sorted_monomials(&self, order: MonomialOrder) -> Vec<&Monomial> {
+        // unpack each monomial once over the variables in ranked order
+        let symbols = Symbol::ranked(self.symbols());
+        let mut keyed: Vec<(Vec<f64>, &Monomial)> = self
+            .terms
+            .keys()
+            .map(|monomial| {
+                let key = symbols.iter().map(|s| monomial.exponent(*s)).collect();
+                (key, monomial)
+            })
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| order.compare_exponents(b, a));
+        keyed.into_iter().map(|(_, monomial)| monomial).collect()
+    }
+
+    /// Write the terms out under the given monomial order, e.g. `x^2 - 2xy + 1`
+    pub fn format(&self, order: MonomialOrder) -> String {
+        self.format_with(order, NumberFormat::default())
+    }
+
+    /// Write the terms out with coefficients in the given number format
+    ///
+    /// Negative terms after the first are subtracted, and a coefficient of 1 or -1 is
+    /// left as just its sign, e.g. `2x^2 - 3x - 1` and `-x + y`.
+    pub fn format_with(&self, order: MonomialOrder, numbers: NumberFormat) -> String {
+        Plain(numbers).polynomial(self, order)
+    }
+}
+
+impl PartialEq for Polynomial {
+    fn eq(&self, other: &Self) -> bool {
+        self.terms == other.terms
+    }
+}
+
+impl Eq for Polynomial {}
+
+impl Hash for Polynomial {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // hash in a fixed term order so equal polynomials hash alike
+        for monomial in self.sorted_monomials(MonomialOrder::Lex) {
+            monomial.hash(state);
+            self.terms[monomial].hash(state);
+        }
+    }
+}
+
+impl std::fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(MonomialOrder::default()))
+    }
+}
+
+impl Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, other: &Polynomial) -> Polynomial {
+        let mut sum = self.clone();
+        for (monomial, coefficient) in other.terms.iter() {
+            sum.add_term(monomial.clone(), coefficient.clone());
+        }
+        sum
+    }
+}
+
+impl Sub for &Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, other: &Polynomial) -> Polynomial {
+        self + &-other
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, other: &Polynomial) -> Polynomial {
+        let mut product = Polynomial::new();
+        for (a, coefficient_a) in self.terms.iter() {
+            for (b, coefficient_b) in other.terms.iter() {
+                product.add_term(a * b, coefficient_a * coefficient_b);
+            }
+        }
+        product
+    }
+}
+
+impl Neg for &Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        self.scale(-1)
+    }
+}
+
+impl Add for Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, other: Polynomial) -> Polynomial {
+        &self + &other
+    }
+}
+
+impl Sub for Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, other: Polynomial) -> Polynomial {
+        &self - &other
+    }
+}
+
+impl Mul for Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, other: Polynomial) -> Polynomial {
+        &self * &other
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        -&self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn x() -> Polynomial {
+        Polynomial::variable("x", 1.0)
+    }
+
+    fn y() -> Polynomial {
+        Polynomial::variable("y", 1.0)
+    }
+
+    #[test]
+    fn test_operators() {
+        let one = Polynomial::constant(1);
+        let sum = x() + y();
+
+        assert_eq!((&sum * &sum).to_string(), "x^2 + 2xy + y^2");
+        assert_eq!((&sum * &(x() - y())), &x().pow(2) - &y().pow(2));
+        assert_eq!(sum.pow(3), &(&sum * &sum) * &sum);
+        assert_eq!(-(x() - one.clone()), one - x());
+        assert!((x() - x()).is_zero());
+        assert_eq!(sum.pow(0), Polynomial::constant(1));
+    }
+
+    #[test]
+    fn test_format() {
+        let p = &(&x().pow(2).scale(2) - &x().scale(3)) - &Polynomial::constant(1);
+        assert_eq!(p.to_string(), "2x^2 - 3x - 1");
+        assert_eq!((y() - x()).to_string(), "-x + y");
+
+        let half = &x().scale(Rational::new(1, 2)) - &Polynomial::constant(Rational::new(1, 4));
+        assert_eq!(
+            half.format_with(MonomialOrder::Lex, NumberFormat::Fraction),
+            "(1/2)x - 1/4"
+        );
+        assert_eq!(
+            x().scale(Rational::new(1, 3))
+                .format_with(MonomialOrder::Lex, NumberFormat::Fixed(2)),
+            "0.33x"
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let product = &(&x() * &y()) + &Polynomial::variable("z", 2.0);
+        assert_eq!(
+            product.variables().into_iter().collect::<Vec<_>>(),
+            vec!["x", "y", "z"]
+        );
+        assert!(Polynomial::constant(3).variables().is_empty());
+    }
+
+    #[test]
+    fn test_equality_and_hashing() {
+        let a = &x() + &y();
+        let b = &y() + &x();
+        assert_eq!(a, b);
+        assert_ne!(a, &x() - &y());
+
+        let set: HashSet<Polynomial> = [a, b, x()].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_large_expansion() {
+        let sum = &(&x() + &y()) + &(&Polynomial::variable("z", 1.0) + &Polynomial::constant(1));
+        let power = sum.pow(20);
+
+        assert_eq!(power.terms().len(), 1771);
+        // multinomial coefficient 20!/(5!5!5!5!)
+        let monomial = Monomial::new([("x", 5.0), ("y", 5.0), ("z", 5.0)]);
+        assert_eq!(power.coefficient(&monomial), Rational::from(11732745024i64));
+        assert_eq!(power.coefficient(&Monomial::one()), Rational::one());
+
+        // exact past the integers an f64 holds
+        assert_eq!(
+            Polynomial::constant(3).pow(40).to_string(),
+            "12157665459056928801"
+        );
+    }
+}
@@ -0,0 +1,806 @@
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property,
+    monomial::{Factor, Monomial},
+    monomial_order::MonomialOrder,
+    parser::ASTNode,
+    polynomial::Polynomial,
+    render::{self, Factors, Notation},
+    symbol::GREEK,
+};
+
+/// The characters two-dimensional output is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Glyphs {
+    /// superscript digits, fraction bars and radical signs, e.g. `3x⁻²`, `x·y`, `√2`
+    #[default]
+    Unicode,
+    /// plain ASCII, with exponents raised onto the line above
+    Ascii,
+}
+
+/// Characters with a superscript form, written inline in Unicode, `x²`
+const SUPERSCRIPTS: [(char, char); 17] = [
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('-', '⁻'),
+    ('+', '⁺'),
+    ('=', '⁼'),
+    ('(', '⁽'),
+    (')', '⁾'),
+    ('n', 'ⁿ'),
+    ('i', 'ⁱ'),
+];
+
+/// Digits as subscripts, written inline in Unicode, `x₁`
+const SUBSCRIPTS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+
+/// A rectangle of text with a baseline, the row its neighbours line up with
+///
+/// Blocks are passed around as strings padded to the same number of rows above and below
+/// the baseline, so the baseline is always the middle row and can be read back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    lines: Vec<Vec<char>>,
+    baseline: usize,
+}
+
+impl Block {
+    fn text(text: &str) -> Block {
+        Block {
+            lines: vec![text.chars().collect()],
+            baseline: 0,
+        }
+    }
+
+    /// Read back a block written out with [`Block::write`]
+    fn read(text: &str) -> Block {
+        let mut lines: Vec<Vec<char>> = text.split('\n').map(|l| l.chars().collect()).collect();
+        let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+        for line in lines.iter_mut() {
+            line.resize(width, ' ');
+        }
+        Block {
+            baseline: (lines.len() - 1) / 2,
+            lines,
+        }
+    }
+
+    /// Write the block out, padded so the baseline is the middle row
+    fn write(&self) -> String {
+        let above = self.baseline;
+        let below = self.height() - self.baseline - 1;
+        let blank = " ".repeat(self.width());
+        let padding = above.max(below);
+        let mut lines = vec![blank.clone(); padding - above];
+        lines.extend(self.lines.iter().map(|l| l.iter().collect::<String>()));
+        lines.extend(vec![blank; padding - below]);
+        lines.join("\n")
+    }
+
+    fn width(&self) -> usize {
+        self.lines.first().map_or(0, Vec::len)
+    }
+
+    fn height(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.width() == 0
+    }
+
+    /// The row at the given offset from the top, blank outside the block
+    fn row(&self, row: isize) -> Vec<char> {
+        usize::try_from(row)
+            .ok()
+            .and_then(|r| self.lines.get(r))
+            .cloned()
+            .unwrap_or_else(|| vec![' '; self.width()])
+    }
+
+    /// Two blocks side by side with their baselines lined up
+    fn beside(&self, other: &Block) -> Block {
+        let above = self.baseline.max(other.baseline);
+        let below = (self.height() - self.baseline).max(other.height() - other.baseline);
+        let lines = (0..above + below)
+            .map(|r| {
+                let r = r as isize;
+                let mut line = self.row(r - (above - self.baseline) as isize);
+                line.extend(other.row(r - (above - other.baseline) as isize));
+                line
+            })
+            .collect();
+        Block {
+            lines,
+            baseline: above,
+        }
+    }
+
+    /// A numerator over a denominator with a bar between them on the baseline
+    fn over(&self, denominator: &Block, glyphs: Glyphs) -> Block {
+        let width = self.width().max(denominator.width());
+        let bar = match glyphs {
+            Glyphs::Unicode => '─',
+            Glyphs::Ascii => '-',
+        };
+        let mut lines = self.centered(width).lines;
+        lines.push(vec![bar; width]);
+        lines.extend(denominator.centered(width).lines);
+        Block {
+            lines,
+            baseline: self.height(),
+        }
+    }
+
+    /// An exponent raised onto the line above the top right of this block
+    fn raised(&self, exponent: &Block) -> Block {
+        let mut lines: Vec<Vec<char>> = exponent
+            .lines
+            .iter()
+            .map(|l| {
+                let mut line = vec![' '; self.width()];
+                line.extend(l);
+                line
+            })
+            .collect();
+        lines.extend(self.lines.iter().map(|l| {
+            let mut line = l.clone();
+            line.extend(vec![' '; exponent.width()]);
+            line
+        }));
+        Block {
+            lines,
+            baseline: exponent.height() + self.baseline,
+        }
+    }
+
+    fn centered(&self, width: usize) -> Block {
+        let left = (width - self.width()) / 2;
+        let right = width - self.width() - left;
+        Block {
+            lines: self
+                .lines
+                .iter()
+                .map(|l| {
+                    let mut line = vec![' '; left];
+                    line.extend(l);
+                    line.extend(vec![' '; right]);
+                    line
+                })
+                .collect(),
+            baseline: self.baseline,
+        }
+    }
+
+    /// The block between delimiters drawn to its full height
+    fn delimited(&self, left: [char; 4], right: [char; 4]) -> Block {
+        let edge = |glyphs: [char; 4]| -> Block {
+            let lines = match self.height() {
+                1 => vec![vec![glyphs[0]]],
+                h => (0..h)
+                    .map(|r| match r {
+                        0 => vec![glyphs[1]],
+                        r if r == h - 1 => vec![glyphs[3]],
+                        _ => vec![glyphs[2]],
+                    })
+                    .collect(),
+            };
+            Block {
+                lines,
+                baseline: self.baseline,
+            }
+        };
+        edge(left).beside(self).beside(&edge(right))
+    }
+
+    /// Whether the block is a negative term, a minus sign on the baseline before the rest
+    fn negated(&self) -> Option<Block> {
+        let first = |r: usize| self.lines[r].first().copied();
+        let negative = first(self.baseline) == Some('-')
+            && (0..self.height()).all(|r| r == self.baseline || first(r) == Some(' '));
+        // the space a sign is kept apart from a fraction with goes too
+        let sign = match self.descends() && self.lines.iter().all(|l| l.get(1) == Some(&' ')) {
+            true => 2,
+            false => 1,
+        };
+        negative.then(|| Block {
+            lines: self.lines.iter().map(|l| l[sign..].to_vec()).collect(),
+            baseline: self.baseline,
+        })
+    }
+
+    /// Whether anything is drawn below the baseline, as in a fraction
+    fn descends(&self) -> bool {
+        self.height() > self.baseline + 1
+    }
+
+    /// The character at the left of the baseline
+    fn first(&self) -> Option<char> {
+        self.lines[self.baseline].first().copied()
+    }
+}
+
+/// Finish two-dimensional output for printing, trimming blank rows and trailing spaces
+pub fn finish(output: &str) -> String {
+    let lines: Vec<&str> = output.split('\n').map(str::trim_end).collect();
+    let start = lines.iter().position(|l| !l.is_empty()).unwrap_or(0);
+    let end = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(0, |i| i + 1);
+    lines[start..end.max(start)].join("\n")
+}
+
+/// Two-dimensional text for a terminal, drawn with the given glyphs
+pub struct Pretty(pub Glyphs);
+
+impl Notation for Pretty {
+    type Piece = Block;
+
+    const SIGN_BEFORE_FRACTION: bool = true;
+
+    fn write(&self, piece: Block) -> String {
+        piece.write()
+    }
+
+    fn read(&self, text: &str) -> Block {
+        Block::read(text)
+    }
+
+    /// A variable name, with Greek letters and digit subscripts as their Unicode characters
+    fn name(&self, name: &str) -> Block {
+        named(name, self.0)
+    }
+
+    /// A number, with a fraction drawn over a bar
+    fn number(&self, n: f64) -> Block {
+        numeral(n, self.0)
+    }
+
+    /// A rational, with a fraction drawn over a bar and its sign in front
+    fn rational(&self, r: &Rational) -> Block {
+        ratio(r, self.0)
+    }
+
+    /// A single term, coefficient then monomial, with the coefficient 1 left out
+    fn term(&self, coefficient: &Rational, monomial: &Monomial, order: MonomialOrder) -> Block {
+        // as in plain output, a multi-letter name keeps every factor apart, rate·time
+        let words = monomial.factors().iter().any(|factor| match factor {
+            Factor::Symbol(symbol, _) => {
+                let name = symbol.name();
+                symbol.as_power().is_none()
+                    && symbol.as_radical().is_none()
+                    && symbol.as_function().is_none()
+                    && name.chars().count() > 1
+                    && name.chars().skip(1).any(|c| c.is_alphabetic())
+                    && !GREEK.iter().any(|(greek, _)| *greek == name)
+            }
+            Factor::Power(_) => false,
+        });
+        let factors = self
+            .factors(monomial, order)
+            .into_iter()
+            .fold(Block::text(""), |output, factor| {
+                juxtapose(&output, &factor, words, self.0)
+            });
+        if factors.is_empty() {
+            return self::coefficient(coefficient, self.0);
+        }
+        let (negative, magnitude) = (coefficient.is_negative(), coefficient.abs());
+        let term = match magnitude {
+            c if c.is_one() => factors,
+            c => juxtapose(&self::coefficient(&c, self.0), &factors, false, self.0),
+        };
+        signed(negative, term)
+    }
+
+    /// Terms joined into a sum, with a negative term subtracted
+    fn sum(&self, terms: Vec<Block>) -> Block {
+        added(terms)
+    }
+
+    fn power(&self, base: Block, exponent: f64) -> Block {
+        match exponent {
+            1.0 => base,
+            e => power(base, &numeral(e, self.0), self.0),
+        }
+    }
+
+    fn parenthesize(&self, block: Block) -> Block {
+        parenthesize(&block, self.0)
+    }
+
+    /// Factors side by side after the coefficient, e.g. 2x(x + 1)²
+    fn times(&self, coefficient: &Rational, factors: Vec<Block>) -> Block {
+        if coefficient.is_zero() {
+            return Block::text("0");
+        }
+        let output = if *coefficient == -1 && !factors.is_empty() {
+            Block::text("-")
+        } else if !coefficient.is_one() || factors.is_empty() {
+            ratio(coefficient, self.0)
+        } else {
+            Block::text("")
+        };
+        factors.iter().fold(output, |output, factor| {
+            juxtapose(&output, factor, false, self.0)
+        })
+    }
+
+    fn negate(&self, block: Block) -> Block {
+        signed(true, block)
+    }
+
+    fn over(&self, numerator: Block, denominator: Block) -> Block {
+        numerator.over(&denominator, self.0)
+    }
+
+    /// An equation `lhs = rhs`
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        joined(vec![lhs.to_string(), rhs.to_string()], " = ")
+    }
+
+    /// Results separated by commas
+    fn list(&self, elements: Vec<String>) -> String {
+        joined(elements, ", ")
+    }
+
+    /// Expressions listed in set braces
+    fn set(&self, elements: Vec<String>) -> String {
+        let inside = Block::read(&joined(elements, ", "));
+        brace(&inside, true, self.0)
+            .beside(&inside)
+            .beside(&brace(&inside, false, self.0))
+            .write()
+    }
+
+    fn text(&self, words: &str) -> String {
+        words.to_string()
+    }
+
+    /// An unknown left free in a solution, e.g. y free
+    fn free(&self, variable: &str) -> String {
+        named(variable, self.0)
+            .beside(&Block::text(" free"))
+            .write()
+    }
+
+    /// An assumption such as x, y > 0 or n ∈ ℤ
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        let names: Vec<String> = names.iter().map(|n| named(n, self.0).write()).collect();
+        let property = match (property, self.0) {
+            (Property::Nonzero, Glyphs::Unicode) => "≠ 0".to_string(),
+            (Property::Integer, Glyphs::Unicode) => "∈ ℤ".to_string(),
+            (Property::Real, Glyphs::Unicode) => "∈ ℝ".to_string(),
+            (property, _) => property.to_string(),
+        };
+        format!("{} {}", names.join(", "), property)
+    }
+
+    fn finish(&self, output: String) -> String {
+        finish(&output)
+    }
+}
+
+impl Factors for Pretty {
+    const ROOTS: bool = true;
+
+    fn root(&self, radicand: Block, index: u32) -> Block {
+        let index = (index != 2).then(|| Block::text(&index.to_string()));
+        radical(&radicand, index.as_ref(), self.0)
+    }
+
+    fn function(&self, name: &str, argument: Block, exponent: f64) -> Block {
+        if name == "abs" {
+            return self.power(absolute(&argument, self.0), exponent);
+        }
+        // sin²(x) rather than sin(x)², as it is usually written
+        self.power(Block::text(name), exponent)
+            .beside(&parenthesize(&argument, self.0))
+    }
+
+    /// A base raised to a symbolic exponent, with a base other than a variable or
+    /// a non-negative number in parentheses
+    fn raise(&self, base: &Polynomial, written: Block, exponent: Block) -> Block {
+        let written = match render::is_bare(base) {
+            true => written,
+            false => parenthesize(&written, self.0),
+        };
+        power(written, &exponent, self.0)
+    }
+}
+
+fn named(name: &str, glyphs: Glyphs) -> Block {
+    if glyphs == Glyphs::Ascii {
+        return Block::text(name);
+    }
+    let (base, subscript) = match name.split_once('_') {
+        Some((base, subscript)) => (base, Some(subscript)),
+        None => {
+            let digits = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+            match digits.chars().all(|c| c.is_ascii_digit()) && digits.len() < name.len() {
+                true if !digits.is_empty() => (&name[..name.len() - digits.len()], Some(digits)),
+                _ => (name, None),
+            }
+        }
+    };
+    let mut written = match GREEK.iter().find(|(greek, _)| *greek == base) {
+        Some((_, letter)) => letter.to_string(),
+        None => base.to_string(),
+    };
+    match subscript {
+        Some(s) if s.chars().all(|c| c.is_ascii_digit()) => {
+            written.extend(
+                s.chars()
+                    .map(|c| SUBSCRIPTS[c.to_digit(10).unwrap() as usize]),
+            );
+        }
+        Some(s) => written.push_str(&format!("_{}", s)),
+        None => {}
+    }
+    Block::text(&written)
+}
+
+fn numeral(n: f64, glyphs: Glyphs) -> Block {
+    match Rational::from_f64(n) {
+        Some(r) => coefficient(&r, glyphs),
+        None => Block::text(&n.to_string()),
+    }
+}
+
+/// An exact coefficient, as a fraction when its denominator is small and a decimal otherwise
+fn coefficient(c: &Rational, glyphs: Glyphs) -> Block {
+    match render::is_small(c) {
+        true => ratio(c, glyphs),
+        false => Block::text(&c.to_f64().to_string()),
+    }
+}
+
+fn ratio(r: &Rational, glyphs: Glyphs) -> Block {
+    if r.is_integer() {
+        return Block::text(&r.numerator().to_string());
+    }
+    let magnitude = r.abs();
+    let fraction = Block::text(&magnitude.numerator().to_string())
+        .over(&Block::text(&magnitude.denominator().to_string()), glyphs);
+    signed(r.is_negative(), fraction)
+}
+
+fn added(terms: Vec<Block>) -> Block {
+    let mut terms = terms.into_iter();
+    let Some(mut output) = terms.next() else {
+        return Block::text("0");
+    };
+    for term in terms {
+        let (operator, term) = match term.negated() {
+            Some(rest) => (" - ", rest),
+            None => (" + ", term),
+        };
+        output = output.beside(&Block::text(operator)).beside(&term);
+    }
+    output
+}
+
+fn joined(elements: Vec<String>, separator: &str) -> String {
+    let mut output: Option<Block> = None;
+    for element in elements {
+        let element = Block::read(&element);
+        output = Some(match output {
+            None => element,
+            Some(o) => o.beside(&Block::text(separator)).beside(&element),
+        });
+    }
+    output.unwrap_or_else(|| Block::text("")).write()
+}
+
+/// An expression tree drawn as it was written
+pub fn ast(node: &ASTNode, glyphs: Glyphs) -> String {
+    tree(node, glyphs).write()
+}
+
+fn tree(node: &ASTNode, glyphs: Glyphs) -> Block {
+    match node {
+        ASTNode::Number(n) => Block::text(&n.to_string()),
+        ASTNode::Variable(variable, exponent) => match exponent.as_ref() {
+            ASTNode::Number(e) if *e == 1.0 => named(variable, glyphs),
+            exponent => power(named(variable, glyphs), &tree(exponent, glyphs), glyphs),
+        },
+        ASTNode::Operation(operator, lhs, rhs) => match operator.as_str() {
+            "/" => operand(lhs, glyphs).over(&operand(rhs, glyphs), glyphs),
+            "*" => {
+                let times = match glyphs {
+                    Glyphs::Unicode => "·",
+                    Glyphs::Ascii => "*",
+                };
+                tree(lhs, glyphs)
+                    .beside(&Block::text(times))
+                    .beside(&tree(rhs, glyphs))
+            }
+            _ => raised(lhs, &tree(rhs, glyphs), glyphs),
+        },
+        ASTNode::Function(function, arguments) => call(function, arguments, None, glyphs),
+        ASTNode::Equation(lhs, rhs) => Block::read(
+            &Pretty(glyphs).equation(&tree(lhs, glyphs).write(), &tree(rhs, glyphs).write()),
+        ),
+        ASTNode::System(equations) => {
+            let rows: Vec<Block> = equations.iter().map(|e| tree(e, glyphs)).collect();
+            let width = rows.iter().map(Block::width).max().unwrap_or(0);
+            let mut lines = Vec::new();
+            for row in rows {
+                lines.extend(row.lines.into_iter().map(|mut l| {
+                    l.resize(width, ' ');
+                    l
+                }));
+            }
+            let stacked = Block {
+                baseline: (lines.len() - 1) / 2,
+                lines,
+            };
+            brace(&stacked, true, glyphs)
+                .beside(&Block::text(" "))
+                .beside(&stacked)
+        }
+        ASTNode::Expression(terms) => added(terms.iter().map(|t| tree(t, glyphs)).collect()),
+        ASTNode::Term(coefficient, factors) => {
+            let coefficient = match coefficient.as_ref() {
+                ASTNode::Number(c) if *c == 1.0 && !factors.is_empty() => Block::text(""),
+                ASTNode::Number(c) if *c == -1.0 && !factors.is_empty() => Block::text("-"),
+                coefficient => tree(coefficient, glyphs),
+            };
+            factors.iter().fold(coefficient, |output, factor| {
+                let printed = match factor {
+                    ASTNode::Expression(_) => parenthesize(&tree(factor, glyphs), glyphs),
+                    _ => tree(factor, glyphs),
+                };
+                juxtapose(&output, &printed, false, glyphs)
+            })
+        }
+        ASTNode::Assumption(names, property) => {
+            Block::text(&Pretty(glyphs).assumption(names, *property))
+        }
+    }
+}
+
+/// A function call from an expression tree, optionally raised to a power
+fn call(function: &str, arguments: &[ASTNode], exponent: Option<&Block>, glyphs: Glyphs) -> Block {
+    let raise = |printed: Block| match exponent {
+        Some(e) => power(printed, e, glyphs),
+        None => printed,
+    };
+    match (function, arguments) {
+        ("sqrt", [argument]) => raise(radical(&operand(argument, glyphs), None, glyphs)),
+        ("root", [argument, index]) => raise(radical(
+            &operand(argument, glyphs),
+            Some(&operand(index, glyphs)),
+            glyphs,
+        )),
+        ("pow", [base, power]) => raised(base, &tree(power, glyphs), glyphs),
+        ("abs", [argument]) => raise(absolute(&operand(argument, glyphs), glyphs)),
+        ("exp", [argument]) => raise(power(Block::text("e"), &operand(argument, glyphs), glyphs)),
+        _ => {
+            let mut listed = Block::text("");
+            for (i, argument) in arguments.iter().enumerate() {
+                if i > 0 {
+                    listed = listed.beside(&Block::text(", "));
+                }
+                listed = listed.beside(&operand(argument, glyphs));
+            }
+            let name = match exponent {
+                Some(e) => power(Block::text(function), e, glyphs),
+                None => Block::text(function),
+            };
+            name.beside(&parenthesize(&listed, glyphs))
+        }
+    }
+}
+
+/// A base from an expression tree raised to a written exponent
+fn raised(base: &ASTNode, exponent: &Block, glyphs: Glyphs) -> Block {
+    match base {
+        ASTNode::Number(n) if *n >= 0.0 && n.fract() == 0.0 => {
+            power(Block::text(&n.to_string()), exponent, glyphs)
+        }
+        ASTNode::Variable(..) => power(tree(base, glyphs), exponent, glyphs),
+        ASTNode::Function(function, arguments) => call(function, arguments, Some(exponent), glyphs),
+        _ => power(
+            parenthesize(&operand(base, glyphs), glyphs),
+            exponent,
+            glyphs,
+        ),
+    }
+}
+
+/// An operand of a fraction, root or function, without the parentheses that
+/// grouped it in the written form
+fn operand(node: &ASTNode, glyphs: Glyphs) -> Block {
+    match node {
+        ASTNode::Term(coefficient, factors)
+            if **coefficient == ASTNode::Number(1.0) && factors.len() == 1 =>
+        {
+            match &factors[0] {
+                ASTNode::Expression(_) => tree(&factors[0], glyphs),
+                _ => tree(node, glyphs),
+            }
+        }
+        ASTNode::Expression(terms) if terms.len() == 1 => operand(&terms[0], glyphs),
+        _ => tree(node, glyphs),
+    }
+}
+
+/// A base with an exponent, written in superscript characters on the same line when
+/// every character has one in Unicode, and raised onto the line above otherwise
+fn power(base: Block, exponent: &Block, glyphs: Glyphs) -> Block {
+    if glyphs == Glyphs::Unicode && exponent.height() == 1 && base.height() == 1 {
+        let superscript: Option<Vec<char>> = exponent.lines[0]
+            .iter()
+            .map(|c| SUPERSCRIPTS.iter().find(|(p, _)| p == c).map(|(_, s)| *s))
+            .collect();
+        if let Some(superscript) = superscript {
+            let mut lines = base.lines;
+            lines[0].extend(superscript);
+            return Block { lines, baseline: 0 };
+        }
+    }
+    base.raised(exponent)
+}
+
+/// The root of a block, a radical sign with a bar over the radicand and any index
+/// written at the top left
+fn radical(radicand: &Block, index: Option<&Block>, glyphs: Glyphs) -> Block {
+    let (sign, stem) = match glyphs {
+        Glyphs::Unicode => (vec!['√'], vec!['│']),
+        Glyphs::Ascii => (vec!['\\', '/'], vec![' ', '|']),
+    };
+    let h = radicand.height();
+    let mut lines = vec![];
+    let mut top = vec![' '; sign.len()];
+    top.extend(vec!['_'; radicand.width()]);
+    lines.push(top);
+    for (r, line) in radicand.lines.iter().enumerate() {
+        let mut row = if r == h - 1 {
+            sign.clone()
+        } else {
+            stem.clone()
+        };
+        row.extend(line);
+        lines.push(row);
+    }
+    let root = Block {
+        lines,
+        baseline: radicand.baseline + 1,
+    };
+    match index {
+        // lined up along the top, the index sits just above the radical sign
+        Some(index) => {
+            let top = |block: &Block| Block {
+                lines: block.lines.clone(),
+                baseline: 0,
+            };
+            Block {
+                baseline: root.baseline,
+                lines: top(index).beside(&top(&root)).lines,
+            }
+        }
+        None => root,
+    }
+}
+
+/// A brace as tall as a block, its point on the baseline
+fn brace(block: &Block, left: bool, glyphs: Glyphs) -> Block {
+    let [single, top, point, stem, bottom] = match (glyphs, left) {
+        (Glyphs::Unicode, true) => ['{', '⎧', '⎨', '⎪', '⎩'],
+        (Glyphs::Unicode, false) => ['}', '⎫', '⎬', '⎪', '⎭'],
+        (Glyphs::Ascii, true) => ['{', '/', '<', '|', '\\'],
+        (Glyphs::Ascii, false) => ['}', '\\', '>', '|', '/'],
+    };
+    let h = block.height();
+    let lines = (0..h)
+        .map(|r| match r {
+            _ if h == 1 => vec![single],
+            0 => vec![top],
+            r if r == h - 1 => vec![bottom],
+            r if r == block.baseline => vec![point],
+            _ => vec![stem],
+        })
+        .collect();
+    Block {
+        lines,
+        baseline: block.baseline,
+    }
+}
+
+fn absolute(argument: &Block, glyphs: Glyphs) -> Block {
+    let bar = match glyphs {
+        Glyphs::Unicode => '│',
+        Glyphs::Ascii => '|',
+    };
+    let bars = ['|', bar, bar, bar];
+    argument.delimited(bars, bars)
+}
+
+fn parenthesize(block: &Block, glyphs: Glyphs) -> Block {
+    match glyphs {
+        Glyphs::Unicode => block.delimited(['(', '⎛', '⎜', '⎝'], [')', '⎞', '⎟', '⎠']),
+        Glyphs::Ascii => block.delimited(['(', '/', '|', '\\'], [')', '\\', '|', '/']),
+    }
+}
+
+/// A block with a minus sign in front when it is negative
+fn signed(negative: bool, block: Block) -> Block {
+    match (negative, block.descends()) {
+        // kept apart from a fraction bar, which it would run into in ASCII
+        (true, true) => Block::text("- ").beside(&block),
+        (true, false) => Block::text("-").beside(&block),
+        (false, _) => block,
+    }
+}
+
+/// Two factors side by side, with a multiplication sign before a number or between
+/// words, and a space beside a fraction so it does not run into the next factor
+fn juxtapose(left: &Block, right: &Block, words: bool, glyphs: Glyphs) -> Block {
+    if left.is_empty() || left.lines == [vec!['-']] {
+        return left.beside(right);
+    }
+    let times = match glyphs {
+        Glyphs::Unicode => "·",
+        Glyphs::Ascii => "*",
+    };
+    if words || right.first().is_some_and(|c| c.is_ascii_digit()) {
+        left.beside(&Block::text(times)).beside(right)
+    } else if left.descends() || right.descends() {
+        left.beside(&Block::text(" ")).beside(right)
+    } else {
+        left.beside(right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_layout() {
+        let fraction = Block::text("x + 1").over(&Block::text("2"), Glyphs::Unicode);
+        let written = fraction.beside(&Block::text(" = y")).write();
+        assert_eq!(written, "x + 1    \n───── = y\n  2      ");
+        assert_eq!(Block::read(&written).baseline, 1);
+
+        let raised = Block::text("x").raised(&Block::text("2"));
+        assert_eq!(finish(&raised.write()), " 2\nx");
+    }
+
+    #[test]
+    fn test_power() {
+        let power = |exponent: &str, glyphs| {
+            finish(&super::power(Block::text("x"), &Block::text(exponent), glyphs).write())
+        };
+        assert_eq!(power("-2", Glyphs::Unicode), "x⁻²");
+        assert_eq!(power("y", Glyphs::Unicode), " y\nx");
+        assert_eq!(power("2", Glyphs::Ascii), " 2\nx");
+    }
+
+    #[test]
+    fn test_radical() {
+        let root = radical(&Block::text("x + 1"), None, Glyphs::Unicode);
+        assert_eq!(finish(&root.write()), " _____\n√x + 1");
+        let root = radical(&Block::text("2"), Some(&Block::text("3")), Glyphs::Ascii);
+        assert_eq!(finish(&root.write()), "3  _\n \\/2");
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!(named("theta", Glyphs::Unicode).write(), "θ");
+        assert_eq!(named("x_10", Glyphs::Unicode).write(), "x₁₀");
+        assert_eq!(named("x_10", Glyphs::Ascii).write(), "x_10");
+    }
+}
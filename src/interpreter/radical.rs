@@ -0,0 +1,303 @@
+use std::f64::consts;
+
+use num_integer::Integer;
+use num_traits::ToPrimitive;
+
+use crate::util::{complex::Complex, rational::Rational};
+
+use super::{
+    log, monomial::Monomial, polynomial::Polynomial, rational_function::RationalFunction,
+    symbol::Symbol, trig, InterpreterError,
+};
+
+/// Largest trial divisor used when taking perfect powers out of a radicand
+const TRIAL_DIVISION_LIMIT: i128 = 1_000_000;
+
+/// Largest common root index of a binomial denominator that is rationalised, since the
+/// multiplier it takes has that many terms
+const MAX_CONJUGATE_INDEX: u32 = 12;
+
+/// The exact value of a rational raised to a rational power, e.g. 12^(1/2) = 2sqrt(3)
+///
+/// The result is a rational coefficient times at most one radical, with perfect powers
+/// taken out of the radicand and any denominator rationalised, so (1/2)^(1/2) is sqrt(2)/2.
+///
+/// Roots are taken before powers. An odd root of a negative number is the real one, so
+/// (-8)^(1/3) is -2 and (-8)^(2/3) is 4, the convention [`Assumptions::is_real`] follows
+/// for odd roots of real variables. A square root of a negative number is the principal
+/// one, so sqrt(-4) is 2i and (-4)^(3/2) is (2i)^3 = -8i.
+///
+/// [`Assumptions::is_real`]: super::assumptions::Assumptions::is_real
+pub fn rational_power(base: Rational, exponent: Rational) -> Result<Polynomial, InterpreterError> {
+    let too_large = || InterpreterError::overflow("Number too large for an exact root".to_string());
+    if base.is_zero() && exponent.is_negative() {
+        return Err(InterpreterError::division_by_zero());
+    }
+    // 0 and 1 are their own powers, however large the exponent
+    if base.is_one() || (base.is_zero() && exponent > Rational::zero()) {
+        return Ok(Polynomial::constant(base));
+    }
+    let power = exponent.numerator().to_i32().ok_or_else(too_large)?;
+    let index = exponent.denominator().to_u32().ok_or_else(too_large)?;
+    if base.is_negative() && index.is_multiple_of(2) {
+        // (-n)^(p/2) = (i·sqrt(n))^p when complex numbers are on
+        return match Symbol::imaginary() {
+            Some(i) if index == 2 => {
+                let root = rational_power(-base, exponent)?;
+                let (sign, imaginary) = match power.rem_euclid(4) {
+                    0 => (1, false),
+                    1 => (1, true),
+                    2 => (-1, false),
+                    _ => (-1, true),
+                };
+                let mut unit = Polynomial::new();
+                match imaginary {
+                    true => unit.add_term(Monomial::variable(&i.name(), 1.0), Rational::from(sign)),
+                    false => unit.add_term(Monomial::one(), Rational::from(sign)),
+                }
+                Ok(&root * &unit)
+            }
+            _ => Err(InterpreterError::domain(
+                "Even roots of negative numbers are not supported".to_string(),
+            )),
+        };
+    }
+    let value = base.pow(power).ok_or_else(too_large)?;
+    if index == 1 {
+        return Ok(Polynomial::constant(value));
+    }
+    let (Some(numerator), Some(denominator)) =
+        (value.numerator().to_i128(), value.denominator().to_i128())
+    else {
+        return Err(too_large());
+    };
+    let (coefficient, radicand, index) =
+        simplify_root(numerator, denominator, index).ok_or_else(too_large)?;
+    let mut root = Polynomial::new();
+    root.add_term(Monomial::radical(radicand, index), coefficient);
+    Ok(root)
+}
+
+/// The numeric value of a number written with radicals, e, pi and the imaginary unit,
+/// e.g. 2.414 for 1 + sqrt(2), or None if it has a variable
+pub fn approximate(value: &Polynomial) -> Option<Complex> {
+    value
+        .complex_terms()
+        .iter()
+        .try_fold(Complex::default(), |sum, (monomial, coefficient)| {
+            let magnitude = monomial.symbols().try_fold(1.0, |product, symbol| {
+                let (base, exponent) = match symbol.as_radical() {
+                    Some((radicand, index)) => {
+                        (radicand as f64, monomial.exponent(symbol) / index as f64)
+                    }
+                    None => match symbol.name().as_str() {
+                        trig::PI => (consts::PI, monomial.exponent(symbol)),
+                        log::E => (consts::E, monomial.exponent(symbol)),
+                        _ => return None,
+                    },
+                };
+                Some(product * base.powf(exponent))
+            })?;
+            Some(sum + *coefficient * Complex::from(magnitude))
+        })
+}
+
+/// Simplify the q-th root of n/d into a coefficient and a radical `(c, r, q)` meaning c·r^(1/q)
+///
+/// The radicand is a positive integer with no q-th power factors and the index is as small
+/// as possible, so root(4, 4) becomes sqrt(2). A radicand of 1 means there is no radical.
+/// Returns None for even roots of negative numbers or on overflow.
+pub(crate) fn simplify_root(
+    numerator: i128,
+    denominator: i128,
+    index: u32,
+) -> Option<(Rational, i128, u32)> {
+    if numerator == 0 || index == 1 {
+        return Some((Rational::new(numerator, denominator), 1, 1));
+    }
+    let sign = match numerator < 0 {
+        true if index.is_multiple_of(2) => return None,
+        true => -1,
+        false => 1,
+    };
+    let (a, n) = extract_powers(numerator.checked_abs()?, index);
+    let (b, d) = extract_powers(denominator, index);
+    // (n/d)^(1/q) = (n·d^(q-1))^(1/q)/d
+    let (c, radicand) = extract_powers(n.checked_mul(d.checked_pow(index - 1)?)?, index);
+    let (radicand, index) = lower_index(radicand, index);
+    let coefficient = Rational::new(sign * a.checked_mul(c)?, b.checked_mul(d)?);
+    Some((coefficient, radicand, if radicand == 1 { 1 } else { index }))
+}
+
+/// Split n into a·m where a^q·m = n and m has no q-th power factors among small primes
+fn extract_powers(n: i128, index: u32) -> (i128, i128) {
+    let (mut outside, mut inside, mut rest) = (1, 1, n);
+    let mut p = 2;
+    while p <= TRIAL_DIVISION_LIMIT && p * p <= rest {
+        let mut count = 0;
+        while rest % p == 0 {
+            rest /= p;
+            count += 1;
+        }
+        outside *= p.pow(count / index);
+        inside *= p.pow(count % index);
+        p += if p == 2 { 1 } else { 2 };
+    }
+    (outside, inside * rest)
+}
+
+/// Write a radical with the smallest index, e.g. root(8, 6) = sqrt(2)
+fn lower_index(radicand: i128, index: u32) -> (i128, u32) {
+    (2..=index)
+        .rev()
+        .filter(|d| index.is_multiple_of(*d))
+        .find_map(|d| exact_root(radicand, d).map(|root| (root, index / d)))
+        .unwrap_or((radicand, index))
+}
+
+/// The integer d-th root of n, if n is a perfect d-th power
+fn exact_root(n: i128, d: u32) -> Option<i128> {
+    if n < 0 {
+        return None;
+    }
+    let estimate = (n as f64).powf(1.0 / d as f64).round() as i128;
+    (estimate.saturating_sub(1)..=estimate.saturating_add(1))
+        .find(|r| *r >= 0 && r.checked_pow(d) == Some(n))
+}
+
+/// Denest sqrt(a + b·sqrt(c)) as sqrt(x) ± sqrt(y) when a^2 - b^2·c is a perfect square,
+/// e.g. sqrt(3 + 2sqrt(2)) = 1 + sqrt(2)
+pub fn denest(radicand: &Polynomial) -> Option<Polynomial> {
+    let mut a = Rational::zero();
+    let mut surd = None;
+    for (monomial, coefficient) in radicand.terms() {
+        let coefficient = coefficient.clone();
+        if monomial.is_one() {
+            a = coefficient;
+            continue;
+        }
+        let symbols: Vec<Symbol> = monomial.symbols().collect();
+        match (symbols.as_slice(), surd) {
+            ([symbol], None) if monomial.exponent(*symbol) == 1.0 => match symbol.as_radical() {
+                Some((c, 2)) => surd = Some((coefficient, c)),
+                _ => return None,
+            },
+            _ => return None,
+        }
+    }
+    let (b, c) = surd?;
+
+    let square = &a * &a - &b * &b * Rational::from_integer(c);
+    let d = Rational::new(
+        exact_root(square.numerator().to_i128()?, 2)?,
+        exact_root(square.denominator().to_i128()?, 2)?,
+    );
+    let two = Rational::from_integer(2);
+    let (x, y) = ((&a + &d) / &two, (&a - &d) / &two);
+    if x <= Rational::zero() || y.is_negative() {
+        return None;
+    }
+    let half = Rational::new(1, 2);
+    let (x, y) = (
+        rational_power(x, half.clone()).ok()?,
+        rational_power(y, half).ok()?,
+    );
+    Some(if b.is_negative() { &x - &y } else { &x + &y })
+}
+
+/// Move radicals out of a denominator
+///
+/// A single term is multiplied through by the radicals that complete it to a rational,
+/// e.g. 1/sqrt(2) = sqrt(2)/2. A binomial a + b whose roots all have indices dividing n
+/// is multiplied by the sum of a^(n - 1 - k)·(-b)^k over k < n, leaving a^n - (-b)^n.
+/// For square roots that is the conjugate, e.g. 1/(sqrt(2) + sqrt(3)) = sqrt(3) - sqrt(2)
+/// and 1/(1 + i) = (1 - i)/2, and for cube roots the sum of cubes, e.g.
+/// 1/(1 + root(2, 3)) = (root(2, 3)^2 - root(2, 3) + 1)/3. Binomials needing an n past
+/// [`MAX_CONJUGATE_INDEX`] and other denominators are left as they are.
+pub fn rationalise(fraction: RationalFunction) -> RationalFunction {
+    if !Symbol::has_radicals() {
+        return fraction;
+    }
+    let terms: Vec<(&Monomial, &Rational)> = fraction.denominator.terms().iter().collect();
+    let term = |monomial: Monomial, coefficient: Rational| {
+        let mut polynomial = Polynomial::new();
+        polynomial.add_term(monomial, coefficient);
+        polynomial
+    };
+
+    let multiplier = match terms.as_slice() {
+        [(monomial, _)] if !monomial.radicals().is_one() => {
+            // the reciprocal of the radicals, without the rational factor it brings out
+            let reciprocal = term(monomial.radicals().recip(), Rational::one());
+            match reciprocal.terms().keys().next() {
+                Some(radicals) => term(radicals.clone(), Rational::one()),
+                None => return fraction,
+            }
+        }
+        [(a, ca), (b, cb)] => {
+            let n = root_index(a).lcm(&root_index(b));
+            if n == 1 || n > MAX_CONJUGATE_INDEX {
+                return fraction;
+            }
+            let a = term((*a).clone(), (*ca).clone());
+            let minus_b = term((*b).clone(), -(*cb).clone());
+            (0..n).fold(Polynomial::new(), |sum, k| {
+                &sum + &(&a.pow(n - 1 - k) * &minus_b.pow(k))
+            })
+        }
+        _ => return fraction,
+    };
+
+    RationalFunction {
+        numerator: &fraction.numerator * &multiplier,
+        denominator: &fraction.denominator * &multiplier,
+    }
+}
+
+/// The least n taking every root in a monomial to a rational by its n-th power, 2 for
+/// sqrt(2)·i and 6 for sqrt(2)·root(3, 3)
+fn root_index(monomial: &Monomial) -> u32 {
+    monomial.radicals().symbols().fold(1, |n, symbol| {
+        let index = match symbol.as_radical() {
+            Some((_, index)) => index,
+            None => 2,
+        };
+        n.lcm(&index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplify_root() {
+        assert_eq!(
+            simplify_root(12, 1, 2),
+            Some((Rational::from_integer(2), 3, 2))
+        );
+        assert_eq!(
+            simplify_root(16, 1, 2),
+            Some((Rational::from_integer(4), 1, 1))
+        );
+        assert_eq!(simplify_root(1, 2, 2), Some((Rational::new(1, 2), 2, 2)));
+        assert_eq!(simplify_root(4, 1, 4), Some((Rational::one(), 2, 2)));
+        assert_eq!(
+            simplify_root(-27, 1, 3),
+            Some((Rational::from_integer(-3), 1, 1))
+        );
+        assert_eq!(simplify_root(-4, 1, 2), None);
+    }
+
+    #[test]
+    fn test_denest() {
+        let root = |n: i128| rational_power(Rational::from_integer(n), Rational::new(1, 2));
+        let radicand = &Polynomial::constant(3) + &root(2).unwrap().scale(2);
+        assert_eq!(
+            denest(&radicand),
+            Some(&Polynomial::constant(1) + &root(2).unwrap())
+        );
+        let radicand = &Polynomial::constant(2) + &root(2).unwrap();
+        assert_eq!(denest(&radicand), None);
+    }
+}
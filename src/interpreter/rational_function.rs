@@ -0,0 +1,617 @@
+use num_traits::{ToPrimitive, Zero};
+
+use crate::util::rational::{self, Rational, MAX_POWER_BITS, ROUNDING};
+
+use super::{
+    assumptions::{Assumptions, Property},
+    factor,
+    monomial::{self, Monomial},
+    polynomial::Polynomial,
+    radical,
+    symbol::Symbol,
+    InterpreterError,
+};
+
+/// Most bits of coefficients an integer power of a sum may expand to, some 4 million,
+/// see [`RationalFunction::pow`]
+pub const MAX_EXPANDED_BITS: u128 = 1 << 22;
+
+/// A quotient of two polynomials, e.g. (x^2 - 1)/(x - 1)
+///
+/// Polynomials with no division are kept with a denominator of 1, so negative
+/// exponents written directly (x^-2) stay in the numerator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RationalFunction {
+    pub numerator: Polynomial,
+    pub denominator: Polynomial,
+}
+
+impl From<Polynomial> for RationalFunction {
+    fn from(numerator: Polynomial) -> Self {
+        RationalFunction {
+            numerator,
+            denominator: Polynomial::constant(1),
+        }
+    }
+}
+
+impl RationalFunction {
+    pub fn new(numerator: Polynomial, denominator: Polynomial) -> Result<Self, InterpreterError> {
+        if denominator.is_zero() {
+            return Err(InterpreterError::division_by_zero());
+        }
+        Ok(RationalFunction {
+            numerator,
+            denominator,
+        })
+    }
+
+    pub fn is_polynomial(&self) -> bool {
+        self.denominator.as_constant().is_some_and(|c| c.is_one())
+    }
+
+    /// a/b + c/d = (ad + cb)/bd, sharing the denominator when both are equal
+    pub fn add(&self, other: &RationalFunction) -> RationalFunction {
+        if self.denominator == other.denominator {
+            return RationalFunction {
+                numerator: &self.numerator + &other.numerator,
+                denominator: self.denominator.clone(),
+            };
+        }
+        RationalFunction {
+            numerator: &(&self.numerator * &other.denominator)
+                + &(&other.numerator * &self.denominator),
+            denominator: &self.denominator * &other.denominator,
+        }
+    }
+
+    pub fn sub(&self, other: &RationalFunction) -> RationalFunction {
+        self.add(&other.negate())
+    }
+
+    pub fn negate(&self) -> RationalFunction {
+        RationalFunction {
+            numerator: -&self.numerator,
+            denominator: self.denominator.clone(),
+        }
+    }
+
+    pub fn mul(&self, other: &RationalFunction) -> RationalFunction {
+        RationalFunction {
+            numerator: &self.numerator * &other.numerator,
+            denominator: &self.denominator * &other.denominator,
+        }
+    }
+
+    pub fn div(&self, other: &RationalFunction) -> Result<RationalFunction, InterpreterError> {
+        RationalFunction::new(
+            &self.numerator * &other.denominator,
+            &self.denominator * &other.numerator,
+        )
+    }
+
+    /// Raise to an integer power, taking the reciprocal for negative exponents
+    ///
+    /// Powers of sums are expanded, so a power whose expansion would hold more than
+    /// [`MAX_EXPANDED_BITS`] bits of coefficients on either side is refused as an overflow,
+    /// as are exponents beyond the range of a u32 and coefficients of more than
+    /// [`MAX_POWER_BITS`] bits. A single term such as (xy)^100000 only has its exponents
+    /// and its coefficient raised, so just the coefficient is bounded, counting the
+    /// radicals that turn into coefficient as they are raised, sqrt(3)^2 being 3.
+    pub fn pow(&self, exponent: i64) -> Result<RationalFunction, InterpreterError> {
+        let too_large =
+            || InterpreterError::overflow(format!("Power {} too large to expand", exponent));
+        let magnitude = u32::try_from(exponent.unsigned_abs()).map_err(|_| too_large())?;
+        if [&self.numerator, &self.denominator].iter().any(|side| {
+            let terms: Vec<_> = side.terms().iter().collect();
+            if let [(monomial, coefficient)] = terms.as_slice() {
+                // a single term only has its exponents and its coefficient grow
+                let bits = coefficient
+                    .numerator()
+                    .bits()
+                    .max(coefficient.denominator().bits())
+                    + radical_bits(monomial);
+                return !(coefficient.abs().is_one() && radical_bits(monomial) == 0)
+                    && bits.saturating_mul(magnitude as u64) > MAX_POWER_BITS;
+            }
+            let bits = coefficient_bits(side).saturating_mul(magnitude as u64);
+            bits > MAX_POWER_BITS
+                || expanded_terms(side.terms().len(), magnitude).saturating_mul(bits as u128)
+                    > MAX_EXPANDED_BITS
+        }) {
+            return Err(too_large());
+        }
+        let power = RationalFunction {
+            numerator: self.numerator.pow(magnitude),
+            denominator: self.denominator.pow(magnitude),
+        };
+        if exponent < 0 {
+            RationalFunction::new(power.denominator, power.numerator)
+        } else {
+            Ok(power)
+        }
+    }
+
+    /// Raise to a polynomial exponent, e.g. x^n, 2^x or x^(1/2)
+    ///
+    /// An exponent divided by a single term is taken as a polynomial with negative powers,
+    /// so x^(1/n) is x^(n^-1), while x^(1/(n + 1)) is unsupported.
+    /// The constant part of the exponent is applied numerically and the rest is kept as a
+    /// symbolic power, so x^(n + 1) is x·x^n. Products are raised factor by factor, (2x)^n
+    /// being 2^n·x^n, while sums stay whole under the power, e.g. (x + 1)^n, and keep
+    /// the fractional part of a constant exponent, e.g. (x + 1)^(3/2) = (x + 1)·sqrt(x + 1).
+    /// An even power of a variable assumed real taken to an odd or fractional one goes
+    /// through the absolute value, (x^2)^(1/2) being abs(x), or x itself when the assumptions
+    /// fix its sign. For a variable that may be complex it is kept whole, as sqrt(x^2).
+    pub fn power(
+        &self,
+        exponent: &RationalFunction,
+        assumptions: &Assumptions,
+    ) -> Result<RationalFunction, InterpreterError> {
+        // a single term below divides through as a negative power, 1/n being n^-1, once
+        // radicals are moved up so the same exponent written either way is the same power
+        let RationalFunction {
+            numerator,
+            denominator,
+        } = radical::rationalise(exponent.clone());
+        let terms: Vec<_> = denominator.terms().iter().collect();
+        let [(monomial, coefficient)] = terms.as_slice() else {
+            return Err(InterpreterError::unsupported(
+                "Exponents must be polynomials or divided by a single term".to_string(),
+            ));
+        };
+        let mut reciprocal = Polynomial::new();
+        reciprocal.add_term(monomial.recip(), Rational::one());
+        let exponent = &numerator.scale(coefficient.recip()) * &reciprocal;
+        let constant = exponent.coefficient(&Monomial::one());
+        let symbolic = &exponent - &Polynomial::constant(constant.clone());
+
+        let numeric = if constant.is_integer() {
+            self.pow(constant.to_i64().ok_or_else(|| {
+                InterpreterError::overflow(format!("Power {} too large to expand", constant))
+            })?)?
+        } else {
+            match (
+                monomial_power(&self.numerator, &constant, assumptions)?,
+                monomial_power(&self.denominator, &constant, assumptions)?,
+            ) {
+                (Some(numerator), Some(denominator)) => {
+                    RationalFunction::new(numerator, denominator)?
+                }
+                // a sum below is raised to the negated power on top, keeping its root there
+                (numerator, denominator) => {
+                    let numerator = match numerator {
+                        Some(numerator) => numerator.into(),
+                        None => fractional_power(&self.numerator, &constant)?,
+                    };
+                    let denominator = match denominator {
+                        Some(denominator) => {
+                            RationalFunction::new(Polynomial::constant(1), denominator)?
+                        }
+                        None => fractional_power(&self.denominator, &-&constant)?,
+                    };
+                    numerator.mul(&denominator)
+                }
+            }
+        };
+        if symbolic.is_zero() {
+            return Ok(numeric);
+        }
+        // a/b to the n is a^n·b^-n, keeping symbolic powers in the numerator
+        let power = symbolic_power(&self.numerator, &symbolic)?
+            .mul(&symbolic_power(&self.denominator, &-&symbolic)?);
+        Ok(numeric.mul(&power))
+    }
+
+    /// Cancel common factors between the numerator and denominator
+    ///
+    /// Both sides are factored over the integers and the shared factors removed,
+    /// leaving a primitive denominator with a positive leading coefficient.
+    /// Polynomials are returned unchanged, as are quotients too large to factor.
+    /// Radicals are first moved out of the denominator, so 1/sqrt(2) becomes sqrt(2)/2.
+    pub fn reduce(self) -> RationalFunction {
+        radical::rationalise(self.whole_powers()).cancel()
+    }
+
+    /// Multiply out the whole part of the constant in each symbolic power, so
+    /// sqrt(x + 1)^2 is x + 1, after moving the powers of a single term below up top,
+    /// so 1/sqrt(x + 1) is sqrt(x + 1)/(x + 1)
+    fn whole_powers(self) -> RationalFunction {
+        if !Symbol::has_powers() {
+            return self;
+        }
+        let mut value = self;
+        let terms: Vec<_> = value.denominator.terms().keys().collect();
+        if let [monomial] = terms.as_slice() {
+            let powers = monomial
+                .symbols()
+                .filter(|symbol| symbol.as_power().is_some())
+                .fold(Monomial::one(), |powers, symbol| {
+                    &powers * &Monomial::variable(&symbol.name(), monomial.exponent(symbol))
+                });
+            if !powers.is_one() {
+                let mut reciprocal = Polynomial::new();
+                reciprocal.add_term(powers.recip(), Rational::one());
+                value = RationalFunction {
+                    numerator: &value.numerator * &reciprocal,
+                    denominator: &value.denominator * &reciprocal,
+                };
+            }
+        }
+
+        let mut kept = Polynomial::new();
+        let mut expanded = RationalFunction::from(Polynomial::new());
+        for (monomial, coefficient) in value.numerator.terms() {
+            match whole_power(monomial) {
+                Some(Ok(term)) => {
+                    let coefficient = Polynomial::constant(coefficient.clone());
+                    expanded = expanded.add(&term.mul(&coefficient.into()));
+                }
+                _ => kept.add_term(monomial.clone(), coefficient.clone()),
+            }
+        }
+        if expanded.numerator.is_zero() {
+            return value;
+        }
+        let denominator = RationalFunction {
+            numerator: Polynomial::constant(1),
+            denominator: value.denominator,
+        };
+        RationalFunction::from(kept)
+            .add(&expanded)
+            .mul(&denominator)
+    }
+
+    fn cancel(self) -> RationalFunction {
+        if self.is_polynomial() {
+            return self;
+        }
+        // only factors of the side with fewer terms can be shared, so the other side is
+        // just divided by them rather than factored in full
+        let split = |smaller: &Polynomial, larger: &Polynomial| {
+            let smaller = factor::factor(smaller)?;
+            let larger = factor::factor_by(larger, &smaller.factors)?;
+            Ok::<_, InterpreterError>((smaller, larger))
+        };
+        let split = match self.numerator.terms().len() < self.denominator.terms().len() {
+            true => split(&self.numerator, &self.denominator),
+            false => split(&self.denominator, &self.numerator).map(|(d, n)| (n, d)),
+        };
+        let Ok((numerator, denominator)) = split else {
+            return self;
+        };
+        if numerator.coefficient.is_zero() {
+            return RationalFunction::from(Polynomial::new());
+        }
+
+        let Some(coefficient) = numerator.coefficient.checked_div(&denominator.coefficient) else {
+            return self;
+        };
+        let mut reduced_numerator = Polynomial::constant(coefficient.numerator().clone());
+        let mut reduced_denominator = Polynomial::constant(coefficient.denominator().clone());
+
+        // monomial content cancels by exponent
+        let mut monomial: Vec<(String, i64)> = numerator.monomial.clone();
+        for (name, exponent) in denominator.monomial.iter() {
+            match monomial.iter_mut().find(|(n, _)| n == name) {
+                Some((_, e)) => *e -= exponent,
+                None => monomial.push((name.clone(), -exponent)),
+            }
+        }
+        for (name, exponent) in monomial {
+            let side = if exponent > 0 {
+                &mut reduced_numerator
+            } else {
+                &mut reduced_denominator
+            };
+            *side = &*side * &Polynomial::variable(&name, exponent.abs() as f64);
+        }
+
+        // irreducible factors cancel by multiplicity
+        let mut denominator_factors = denominator.factors;
+        for (f, multiplicity) in numerator.factors {
+            let shared = match denominator_factors.iter_mut().find(|(g, _)| *g == f) {
+                Some((_, m)) => {
+                    let shared = multiplicity.min(*m);
+                    *m -= shared;
+                    shared
+                }
+                None => 0,
+            };
+            reduced_numerator = &reduced_numerator * &f.pow((multiplicity - shared) as u32);
+        }
+        for (g, multiplicity) in denominator_factors {
+            reduced_denominator = &reduced_denominator * &g.pow(multiplicity as u32);
+        }
+
+        RationalFunction {
+            numerator: reduced_numerator,
+            denominator: reduced_denominator,
+        }
+    }
+
+    /// The constant denominator as an exact rational, if the denominator has no variables
+    pub fn constant_denominator(&self) -> Option<Rational> {
+        self.denominator.as_constant().filter(|d| !d.is_zero())
+    }
+
+    /// Whether the printed denominator can be written without parentheses: a single number or variable power
+    pub fn is_simple_denominator(&self) -> bool {
+        match self
+            .denominator
+            .terms()
+            .iter()
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [(monomial, coefficient)] => {
+                monomial.is_one() || (monomial.len() == 1 && coefficient.is_one())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Raise a single term to a fractional power exactly, e.g. (12x^2)^(1/2) = 2sqrt(3)x,
+/// or None for sums
+///
+/// The power is taken factor by factor only when at most one factor may be other than
+/// a nonnegative real, as (ab)^r = a^r·b^r can fail otherwise: sqrt(xy) and sqrt(-x) are
+/// kept whole, while sqrt(-x) is i·sqrt(x) once x is assumed positive.
+fn monomial_power(
+    polynomial: &Polynomial,
+    exponent: &Rational,
+    assumptions: &Assumptions,
+) -> Result<Option<Polynomial>, InterpreterError> {
+    if polynomial.is_zero() {
+        if exponent.is_negative() {
+            return Err(InterpreterError::division_by_zero());
+        }
+        return Ok(Some(Polynomial::new()));
+    }
+    let terms: Vec<_> = polynomial.terms().iter().collect();
+    let [(monomial, coefficient)] = terms.as_slice() else {
+        return Ok(None);
+    };
+    let nonnegative = |name: &str, e: f64| {
+        assumptions.ask(name, Property::Positive).is_true()
+            || (e.rem_euclid(2.0) == 0.0 && assumptions.ask(name, Property::Real).is_true())
+    };
+    let unknown = monomial
+        .powers()
+        .iter()
+        .filter(|(name, e)| !nonnegative(name, e.0))
+        .count()
+        + usize::from(coefficient.is_negative());
+    if unknown > 1 {
+        return Ok(None);
+    }
+    let mut sign = Rational::one();
+    let mut powers = Vec::new();
+    let mut whole = Vec::new();
+    for (name, e) in monomial.powers() {
+        let power = exact_power(e.0, exponent)?;
+        // (x^2)^(1/2) is ±x for a complex x, so the power is kept whole unless x is real
+        if e.0 != 1.0 && e.0.fract() == 0.0 && !assumptions.ask(&name, Property::Real).is_true() {
+            whole.push((name, e.0));
+        } else if e.0.rem_euclid(2.0) != 0.0
+            || power.rem_euclid(2.0) == 0.0
+            || assumptions.ask(&name, Property::Positive).is_true()
+        {
+            powers.push((name, power));
+        } else if power.fract() == 0.0 && assumptions.ask(&name, Property::Negative).is_true() {
+            // (x^2)^(1/2) = -x for negative x
+            sign = -sign;
+            powers.push((name, power));
+        } else {
+            let absolute = Symbol::function("abs", Polynomial::variable(&name, 1.0));
+            powers.push((absolute.name(), power));
+        }
+    }
+    // a positive coefficient comes out of the root, sqrt(4x^2) = 2sqrt(x^2)
+    let kept = match whole.is_empty() {
+        true => Polynomial::constant(1),
+        false if coefficient.is_negative() => return Ok(None),
+        false => {
+            let mut base = Polynomial::new();
+            base.add_term(Monomial::new(whole), Rational::one());
+            match fractional_power(&base, exponent)? {
+                kept if kept.is_polynomial() => kept.numerator,
+                _ => return Ok(None),
+            }
+        }
+    };
+    let monomial = Monomial::new(powers);
+    let coefficient = radical::rational_power((*coefficient).clone(), exponent.clone())?;
+    let mut power = Polynomial::new();
+    power.add_term(monomial, sign);
+    Ok(Some(&(&coefficient * &power) * &kept))
+}
+
+/// A monomial with the whole part of the constant in each of its symbolic powers
+/// multiplied out, e.g. x·(x + 1)^(n + 1) = x(x + 1)·(x + 1)^n, or None when there is none
+fn whole_power(monomial: &Monomial) -> Option<Result<RationalFunction, InterpreterError>> {
+    let mut whole = RationalFunction::from(Polynomial::constant(1));
+    let mut rest = Monomial::one();
+    let mut changed = false;
+    for symbol in monomial.symbols() {
+        let e = monomial.exponent(symbol);
+        let split = symbol.as_power().and_then(|power| {
+            let exponent = power.exponent.scale(monomial::exact(e));
+            let floor = exponent.coefficient(&Monomial::one()).floor();
+            (!floor.is_zero()).then_some((power.base, exponent, floor))
+        });
+        let Some((base, exponent, floor)) = split else {
+            rest = &rest * &Monomial::variable(&symbol.name(), e);
+            continue;
+        };
+        changed = true;
+        let power = floor
+            .to_i64()
+            .ok_or_else(|| InterpreterError::overflow("Power too large to expand".to_string()))
+            .and_then(|floor| RationalFunction::from(base.clone()).pow(floor));
+        match power {
+            Ok(power) => whole = whole.mul(&power),
+            Err(e) => return Some(Err(e)),
+        }
+        let fraction = &exponent - &Polynomial::constant(Rational::from(floor));
+        if !fraction.is_zero() {
+            rest = &rest * &Monomial::power(base, fraction);
+        }
+    }
+    let mut rest_polynomial = Polynomial::new();
+    rest_polynomial.add_term(rest, Rational::one());
+    changed.then(|| Ok(whole.mul(&rest_polynomial.into())))
+}
+
+/// A sum to a constant power, the whole part multiplied out and the fraction left kept as
+/// a symbolic power of the sum, e.g. (x + 1)^(-1/2) = sqrt(x + 1)/(x + 1)
+fn fractional_power(
+    base: &Polynomial,
+    exponent: &Rational,
+) -> Result<RationalFunction, InterpreterError> {
+    let floor = exponent.floor();
+    let fraction = exponent - &Rational::from(floor.clone());
+    let floor = floor.to_i64().ok_or_else(|| {
+        InterpreterError::overflow(format!("Power {} too large to expand", exponent))
+    })?;
+    let power = RationalFunction::from(base.clone()).pow(floor)?;
+    if fraction.is_zero() {
+        return Ok(power);
+    }
+    let mut root = Polynomial::new();
+    root.add_term(
+        Monomial::power(base.clone(), Polynomial::constant(fraction)),
+        Rational::one(),
+    );
+    Ok(power.mul(&root.into()))
+}
+
+/// Raise to an exponent with no constant term, one factor at a time
+fn symbolic_power(
+    base: &Polynomial,
+    exponent: &Polynomial,
+) -> Result<RationalFunction, InterpreterError> {
+    let symbolic = |base: Polynomial, exponent: Polynomial| {
+        let mut power = Polynomial::new();
+        power.add_term(Monomial::power(base, exponent), Rational::one());
+        power
+    };
+    if base.is_zero() {
+        return Err(InterpreterError::domain(
+            "Zero cannot be raised to a symbolic power".to_string(),
+        ));
+    }
+    let terms: Vec<_> = base.terms().iter().collect();
+    let [(monomial, coefficient)] = terms.as_slice() else {
+        return Ok(symbolic(base.clone(), exponent.clone()).into());
+    };
+    if coefficient.is_negative() {
+        return Err(InterpreterError::unsupported(
+            "Only positive numbers can be raised to symbolic powers".to_string(),
+        ));
+    }
+
+    let mut power = RationalFunction::from(Polynomial::constant(1));
+    if !coefficient.is_one() {
+        let base = Polynomial::constant((*coefficient).clone());
+        power = power.mul(&symbolic(base, exponent.clone()).into());
+    }
+    for (name, e) in monomial.powers() {
+        // (x^m)^n is x^(mn)
+        let factor = match Symbol::intern(&name).as_power() {
+            Some(inner) => {
+                // the product of exponents may leave a constant, (x^(1/n))^n being x
+                let product = &inner.exponent * &exponent.scale(monomial::exact(e.0));
+                let constant = product.coefficient(&Monomial::one());
+                let rest = &product - &Polynomial::constant(constant.clone());
+                let numeric = numeric_power(&inner.base, &constant)?;
+                match rest.is_zero() {
+                    true => numeric,
+                    false => numeric.mul(&symbolic(inner.base, rest).into()),
+                }
+            }
+            None => {
+                let exponent = exponent.scale(monomial::exact(e.0));
+                symbolic(Polynomial::variable(&name, 1.0), exponent).into()
+            }
+        };
+        power = power.mul(&factor);
+    }
+    Ok(power)
+}
+
+/// The base of a symbolic power to a numeric exponent, exact for a variable base and
+/// keeping the fractional part of the exponent on a sum
+fn numeric_power(
+    base: &Polynomial,
+    exponent: &Rational,
+) -> Result<RationalFunction, InterpreterError> {
+    let terms: Vec<_> = base.terms().iter().collect();
+    if let [(monomial, coefficient)] = terms.as_slice() {
+        if let [(name, e)] = monomial.powers().as_slice() {
+            if coefficient.is_one() {
+                let power = exact_power(e.0, exponent)?;
+                return Ok(Polynomial::variable(name, power).into());
+            }
+        }
+    }
+    fractional_power(base, exponent)
+}
+
+/// A stored exponent times a rational power, e.g. 2/3 for (x^2)^(1/3), or an error when
+/// the float it is stored as would not read back as the exact fraction, as for 1/1000001
+fn exact_power(e: f64, exponent: &Rational) -> Result<f64, InterpreterError> {
+    let power = e * rational::round(exponent.to_f64());
+    let exact = Rational::from_f64_within(e, ROUNDING).map(|e| e * exponent);
+    let read_back = match power.fract() == 0.0 {
+        true => Rational::from_f64(power),
+        false => Rational::from_f64_within(power, ROUNDING),
+    };
+    match exact.is_some() && read_back == exact {
+        true => Ok(power),
+        false => Err(InterpreterError::unsupported(format!(
+            "Exponent {} cannot be kept exactly",
+            exact.map_or_else(|| exponent.to_string(), |exact| exact.to_string())
+        ))),
+    }
+}
+
+/// Bits in the largest numerator or denominator of a polynomial's coefficients, plus
+/// enough for the sums of products a power adds up
+fn coefficient_bits(polynomial: &Polynomial) -> u64 {
+    let largest = polynomial
+        .terms()
+        .iter()
+        .map(|(m, c)| c.numerator().bits().max(c.denominator().bits()) + radical_bits(m))
+        .max()
+        .unwrap_or(0);
+    largest + u64::from(usize::BITS - polynomial.terms().len().leading_zeros())
+}
+
+/// Bits a monomial's radicals add to the coefficient for each power it is raised to,
+/// as their powers reduce into it, e.g. half the bits of 3 for sqrt(3)
+fn radical_bits(monomial: &Monomial) -> u64 {
+    monomial
+        .symbols()
+        .filter_map(|symbol| {
+            let (radicand, index) = symbol.as_radical()?;
+            let bits = (u128::BITS - radicand.unsigned_abs().leading_zeros()) as f64;
+            Some(bits * monomial.exponent(symbol).abs() / index as f64)
+        })
+        .sum::<f64>()
+        .ceil() as u64
+}
+
+/// Upper bound on the terms of a polynomial with `terms` terms raised to the `exponent`,
+/// the number of monomials of that degree in as many variables, capped past the limit
+fn expanded_terms(terms: usize, exponent: u32) -> u128 {
+    let mut count: u128 = 1;
+    for i in 1..terms.max(1) as u128 {
+        count = count * (exponent as u128 + i) / i;
+        if count > MAX_EXPANDED_BITS {
+            break;
+        }
+    }
+    count
+}
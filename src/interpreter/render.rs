@@ -0,0 +1,383 @@
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::Property,
+    factor::Factorization,
+    monomial::{self, Factor, Monomial},
+    monomial_order::MonomialOrder,
+    polynomial::Polynomial,
+    rational_function::RationalFunction,
+    symbol::Symbol,
+};
+
+/// Largest denominator a coefficient is written over as a fraction, beyond which it is a decimal
+pub const MAX_DENOMINATOR: i128 = 10_000;
+
+/// Whether a rational is written as a fraction rather than a decimal
+pub fn is_small(r: &Rational) -> bool {
+    *r.denominator() <= MAX_DENOMINATOR.into()
+}
+
+/// How an output format writes results, from names and numbers up
+///
+/// A notation writes the pieces: a term, a sum, a power, a quotient. How a factorisation
+/// or a fraction is put together from them is shared, so every format lays a result out
+/// the same way.
+pub trait Notation {
+    /// What results are built from, text or a block of it
+    type Piece;
+
+    /// Whether a lone negative term over a denominator has its sign written in front of
+    /// the fraction, so -x/2 is minus x over 2
+    const SIGN_BEFORE_FRACTION: bool = false;
+
+    /// A piece written out as text
+    fn write(&self, piece: Self::Piece) -> String;
+
+    /// A piece read back from the text [`Notation::write`] gave
+    fn read(&self, text: &str) -> Self::Piece;
+
+    fn name(&self, name: &str) -> Self::Piece;
+
+    fn number(&self, n: f64) -> Self::Piece;
+
+    fn rational(&self, r: &Rational) -> Self::Piece;
+
+    /// A term of a polynomial, its coefficient times its monomial
+    fn term(
+        &self,
+        coefficient: &Rational,
+        monomial: &Monomial,
+        order: MonomialOrder,
+    ) -> Self::Piece;
+
+    /// Terms added together, with a negative term subtracted
+    fn sum(&self, terms: Vec<Self::Piece>) -> Self::Piece;
+
+    /// A base to a numeric power, left off when it is 1
+    fn power(&self, base: Self::Piece, exponent: f64) -> Self::Piece;
+
+    /// A piece in parentheses, where the notation needs them to raise it to a power
+    fn parenthesize(&self, piece: Self::Piece) -> Self::Piece;
+
+    /// Factors multiplied together with an exact coefficient in front
+    fn times(&self, coefficient: &Rational, factors: Vec<Self::Piece>) -> Self::Piece;
+
+    fn negate(&self, piece: Self::Piece) -> Self::Piece;
+
+    fn over(&self, numerator: Self::Piece, denominator: Self::Piece) -> Self::Piece;
+
+    /// An equation `lhs = rhs` between results written out already
+    fn equation(&self, lhs: &str, rhs: &str) -> String;
+
+    /// Several results, such as the assignments of a solution
+    fn list(&self, elements: Vec<String>) -> String;
+
+    /// Results listed as a set
+    fn set(&self, elements: Vec<String>) -> String;
+
+    /// A phrase such as `No solution` in place of a result
+    fn text(&self, words: &str) -> String;
+
+    /// The result of a system with no solution
+    fn no_solution(&self) -> String {
+        self.text("No solution")
+    }
+
+    /// The result of a system every value solves
+    fn always_true(&self) -> String {
+        self.text("Always true")
+    }
+
+    /// A Gröbner basis under the order it was computed in, its elements written already
+    fn basis(&self, elements: Vec<String>, _order: MonomialOrder) -> String {
+        self.set(elements)
+    }
+
+    /// An unknown left free in a solution
+    fn free(&self, variable: &str) -> String;
+
+    /// An assumption on the listed variables
+    fn assumption(&self, names: &[String], property: Property) -> String;
+
+    /// A whole result, ready to print
+    fn finish(&self, output: String) -> String {
+        output
+    }
+
+    /// A polynomial under a monomial order, leading term first
+    fn polynomial(&self, polynomial: &Polynomial, order: MonomialOrder) -> Self::Piece {
+        let terms = polynomial
+            .sorted_monomials(order)
+            .into_iter()
+            .map(|m| self.term(&polynomial.coefficient(m), m, order))
+            .collect();
+        self.sum(terms)
+    }
+
+    /// A factor of a factorisation to its multiplicity, bare when it is all there is
+    fn multiple(&self, factor: Self::Piece, multiplicity: usize, alone: bool) -> Self::Piece {
+        match (alone, multiplicity) {
+            (true, 1) => factor,
+            (_, m) => self.power(self.parenthesize(factor), m as f64),
+        }
+    }
+
+    /// A factorisation as a product, e.g. 2x(x + 1)^2
+    fn product(&self, product: &Factorization, order: MonomialOrder) -> Self::Piece {
+        let mut factors: Vec<Self::Piece> = product
+            .monomial
+            .iter()
+            .map(|(variable, exponent)| self.power(self.name(variable), *exponent as f64))
+            .collect();
+        let alone =
+            product.coefficient.is_one() && factors.is_empty() && product.factors.len() == 1;
+        for (factor, multiplicity) in &product.factors {
+            factors.push(self.multiple(self.polynomial(factor, order), *multiplicity, alone));
+        }
+        self.times(&product.coefficient, factors)
+    }
+
+    /// A rational function as a quotient, or a polynomial when the denominator is 1
+    fn fraction(&self, value: &RationalFunction, order: MonomialOrder) -> Self::Piece {
+        if value.is_polynomial() {
+            return self.polynomial(&value.numerator, order);
+        }
+        let numerator = &value.numerator;
+        let negative = Self::SIGN_BEFORE_FRACTION
+            && numerator.terms().len() == 1
+            && numerator.terms().values().all(Rational::is_negative);
+        let denominator = self.polynomial(&value.denominator, order);
+        match negative {
+            true => self.negate(self.over(self.polynomial(&-numerator, order), denominator)),
+            false => self.over(self.polynomial(numerator, order), denominator),
+        }
+    }
+
+    /// A factored numerator over a factored denominator
+    fn factored_fraction(
+        &self,
+        numerator: &Factorization,
+        denominator: &Factorization,
+        order: MonomialOrder,
+    ) -> Self::Piece {
+        let denominator = self.product(denominator, order);
+        if Self::SIGN_BEFORE_FRACTION && numerator.coefficient.is_negative() {
+            let numerator = Factorization {
+                coefficient: -&numerator.coefficient,
+                ..numerator.clone()
+            };
+            return self.negate(self.over(self.product(&numerator, order), denominator));
+        }
+        self.over(self.product(numerator, order), denominator)
+    }
+}
+
+/// A notation that writes each factor of a monomial out itself, the variables,
+/// radicals, function applications and symbolic powers
+pub trait Factors: Notation {
+    /// Whether a power to 1/n is written as an nth root, so x^(1/2) is the square root of x
+    const ROOTS: bool = false;
+
+    /// The index-th root of a radicand, the square root when the index is 2
+    fn root(&self, radicand: Self::Piece, index: u32) -> Self::Piece;
+
+    /// A function applied to an argument, to a numeric power
+    fn function(&self, name: &str, argument: Self::Piece, exponent: f64) -> Self::Piece;
+
+    /// A base raised to a symbolic exponent, given the base both as a polynomial and written
+    fn raise(&self, base: &Polynomial, written: Self::Piece, exponent: Self::Piece) -> Self::Piece;
+
+    /// The factors of a monomial in the order they are written
+    fn factors(&self, monomial: &Monomial, order: MonomialOrder) -> Vec<Self::Piece> {
+        monomial
+            .factors()
+            .into_iter()
+            .map(|factor| match factor {
+                Factor::Symbol(symbol, exponent) => symbol_power(self, symbol, exponent, order),
+                Factor::Power(p) => symbolic_power(self, &p.base, &p.exponent, order),
+            })
+            .collect()
+    }
+}
+
+/// Whether a base is written without parentheses under a symbolic exponent: a
+/// non-negative number or a single product of variables, e.g. `2^x` or `xy^n`
+pub fn is_bare(base: &Polynomial) -> bool {
+    match base.as_constant() {
+        Some(c) => !c.is_negative(),
+        None => base.terms().len() == 1 && base.terms().values().all(|c| c.is_one()),
+    }
+}
+
+/// A variable, radical or function application to a numeric power
+fn symbol_power<F: Factors + ?Sized>(
+    format: &F,
+    symbol: Symbol,
+    exponent: f64,
+    order: MonomialOrder,
+) -> F::Piece {
+    if let Some(p) = symbol.as_power() {
+        let exponent = p.exponent.scale(monomial::exact(exponent));
+        return symbolic_power(format, &p.base, &exponent, order);
+    }
+    if let Some((radicand, index)) = symbol.as_radical() {
+        let root = format.root(format.rational(&Rational::from(radicand)), index);
+        return match exponent {
+            1.0 => root,
+            e => format.power(format.parenthesize(root), e),
+        };
+    }
+    let index = root_index::<F>(&monomial::exact(exponent));
+    if let Some(application) = symbol.as_function() {
+        let argument = format.polynomial(&application.argument, order);
+        return match index {
+            Some(n) => format.root(format.function(&application.name, argument, 1.0), n),
+            None => format.function(&application.name, argument, exponent),
+        };
+    }
+    match index {
+        Some(n) => format.root(format.name(&symbol.name()), n),
+        None => format.power(format.name(&symbol.name()), exponent),
+    }
+}
+
+/// A base raised to a symbolic exponent
+fn symbolic_power<F: Factors + ?Sized>(
+    format: &F,
+    base: &Polynomial,
+    exponent: &Polynomial,
+    order: MonomialOrder,
+) -> F::Piece {
+    let written = format.polynomial(base, order);
+    match exponent.as_constant().and_then(|e| root_index::<F>(&e)) {
+        Some(n) => format.root(written, n),
+        None => format.raise(base, written, format.polynomial(exponent, order)),
+    }
+}
+
+/// The index n of an exponent 1/n, n at least 2, in a notation that writes roots
+fn root_index<F: Factors + ?Sized>(exponent: &Rational) -> Option<u32> {
+    match F::ROOTS && exponent.numerator() == &1.into() {
+        true => u32::try_from(exponent.denominator())
+            .ok()
+            .filter(|n| *n >= 2),
+        false => None,
+    }
+}
+
+/// A notation chosen as the program runs, writing whole results out as text
+///
+/// Every [`Notation`] is one, so the interpreter picks its format once and writes each
+/// result through it.
+pub trait Renderer {
+    fn name(&self, name: &str) -> String;
+    fn number(&self, n: f64) -> String;
+    fn rational(&self, r: &Rational) -> String;
+    fn polynomial(&self, polynomial: &Polynomial, order: MonomialOrder) -> String;
+    fn fraction(&self, value: &RationalFunction, order: MonomialOrder) -> String;
+    fn product(&self, product: &Factorization, order: MonomialOrder) -> String;
+    fn factored_fraction(
+        &self,
+        numerator: &Factorization,
+        denominator: &Factorization,
+        order: MonomialOrder,
+    ) -> String;
+    /// Results written out already, added together
+    fn sum(&self, terms: Vec<String>) -> String;
+    fn equation(&self, lhs: &str, rhs: &str) -> String;
+    fn list(&self, elements: Vec<String>) -> String;
+    fn set(&self, elements: Vec<String>) -> String;
+    fn text(&self, words: &str) -> String;
+    fn no_solution(&self) -> String;
+    fn always_true(&self) -> String;
+    fn basis(&self, elements: Vec<String>, order: MonomialOrder) -> String;
+    fn free(&self, variable: &str) -> String;
+    fn assumption(&self, names: &[String], property: Property) -> String;
+    fn finish(&self, output: String) -> String;
+}
+
+impl<N: Notation> Renderer for N {
+    fn name(&self, name: &str) -> String {
+        self.write(Notation::name(self, name))
+    }
+
+    fn number(&self, n: f64) -> String {
+        self.write(Notation::number(self, n))
+    }
+
+    fn rational(&self, r: &Rational) -> String {
+        self.write(Notation::rational(self, r))
+    }
+
+    fn polynomial(&self, polynomial: &Polynomial, order: MonomialOrder) -> String {
+        self.write(Notation::polynomial(self, polynomial, order))
+    }
+
+    fn fraction(&self, value: &RationalFunction, order: MonomialOrder) -> String {
+        self.write(Notation::fraction(self, value, order))
+    }
+
+    fn product(&self, product: &Factorization, order: MonomialOrder) -> String {
+        self.write(Notation::product(self, product, order))
+    }
+
+    fn factored_fraction(
+        &self,
+        numerator: &Factorization,
+        denominator: &Factorization,
+        order: MonomialOrder,
+    ) -> String {
+        self.write(Notation::factored_fraction(
+            self,
+            numerator,
+            denominator,
+            order,
+        ))
+    }
+
+    fn sum(&self, terms: Vec<String>) -> String {
+        let terms = terms.iter().map(|t| self.read(t)).collect();
+        self.write(Notation::sum(self, terms))
+    }
+
+    fn equation(&self, lhs: &str, rhs: &str) -> String {
+        Notation::equation(self, lhs, rhs)
+    }
+
+    fn list(&self, elements: Vec<String>) -> String {
+        Notation::list(self, elements)
+    }
+
+    fn set(&self, elements: Vec<String>) -> String {
+        Notation::set(self, elements)
+    }
+
+    fn text(&self, words: &str) -> String {
+        Notation::text(self, words)
+    }
+
+    fn no_solution(&self) -> String {
+        Notation::no_solution(self)
+    }
+
+    fn always_true(&self) -> String {
+        Notation::always_true(self)
+    }
+
+    fn basis(&self, elements: Vec<String>, order: MonomialOrder) -> String {
+        Notation::basis(self, elements, order)
+    }
+
+    fn free(&self, variable: &str) -> String {
+        Notation::free(self, variable)
+    }
+
+    fn assumption(&self, names: &[String], property: Property) -> String {
+        Notation::assumption(self, names, property)
+    }
+
+    fn finish(&self, output: String) -> String {
+        Notation::finish(self, output)
+    }
+}
@@ -0,0 +1,526 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::One;
+
+use crate::util::rational::Rational;
+
+use super::{log, polynomial::Polynomial, trig};
+
+/// Greek letter names and the characters they are written as, `theta` as `θ`, a variable
+/// typed either way being the one named `theta`
+pub const GREEK: [(&str, char); 34] = [
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("pi", 'π'),
+    ("rho", 'ρ'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("upsilon", 'υ'),
+    ("phi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Theta", 'Θ'),
+    ("Lambda", 'Λ'),
+    ("Xi", 'Ξ'),
+    ("Pi", 'Π'),
+    ("Sigma", 'Σ'),
+    ("Upsilon", 'Υ'),
+    ("Phi", 'Φ'),
+    ("Psi", 'Ψ'),
+    ("Omega", 'Ω'),
+];
+
+/// A variable name interned into the current symbol table, see [`SymbolTable::enter`]
+///
+/// Symbols are numbered in the order they are first seen, and the number indexes
+/// the packed exponent vector of a monomial. Comparing symbols compares their ids,
+/// not their names; [`Symbol::alphabetical`] puts them in the order of their names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Look up a name in the session's table, adding it if it is new
+    pub fn intern(name: &str) -> Symbol {
+        SYMBOLS.with(|symbols| symbols.borrow_mut().intern(name))
+    }
+
+    /// The symbol already interned under a name, if any
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        SYMBOLS.with(|symbols| symbols.borrow().lookup(name))
+    }
+
+    pub fn name(&self) -> String {
+        SYMBOLS.with(|symbols| symbols.borrow().name(*self).to_string())
+    }
+
+    /// The distinct symbols given, in alphabetical order of their names
+    pub fn alphabetical(symbols: impl IntoIterator<Item = Symbol>) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = symbols.into_iter().collect();
+        SYMBOLS.with(|table| {
+            let table = table.borrow();
+            symbols.sort_unstable_by_key(|symbol| table.ranks[symbol.index()]);
+        });
+        symbols.dedup();
+        symbols
+    }
+
+    /// The distinct symbols given alphabetically, with the constants e, pi, the imaginary
+    /// unit and radicals after every variable, so that x leads pi*x and sqrt(2)*x
+    pub fn ranked(symbols: impl IntoIterator<Item = Symbol>) -> Vec<Symbol> {
+        let mut symbols = Symbol::alphabetical(symbols);
+        symbols.sort_by_cached_key(Symbol::is_constant);
+        symbols
+    }
+
+    /// Position of this symbol in a packed exponent vector
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn from_index(index: usize) -> Symbol {
+        Symbol(index as u32)
+    }
+
+    /// The symbol standing for a base raised to a symbolic exponent, e.g. `x^n`
+    pub fn power(base: Polynomial, exponent: Polynomial) -> Symbol {
+        let name = power_name(&base, &exponent);
+        SYMBOLS.with(|symbols| {
+            symbols
+                .borrow_mut()
+                .intern_power(&name, Power { base, exponent })
+        })
+    }
+
+    /// The base and exponent this symbol stands for, if it is a symbolic power
+    pub fn as_power(&self) -> Option<Power> {
+        SYMBOLS.with(|symbols| symbols.borrow().power(*self).cloned())
+    }
+
+    /// Whether any symbolic power has been interned this session
+    pub fn has_powers() -> bool {
+        SYMBOLS.with(|symbols| symbols.borrow().has_powers())
+    }
+
+    /// The symbol standing for the simplified root of a positive integer, e.g. `sqrt(3)`
+    pub fn radical(radicand: i128, index: u32) -> Symbol {
+        let name = match index {
+            2 => format!("sqrt({})", radicand),
+            _ => format!("root({}, {})", radicand, index),
+        };
+        SYMBOLS.with(|symbols| symbols.borrow_mut().intern_radical(&name, radicand, index))
+    }
+
+    /// The radicand and index this symbol stands for, if it is a radical
+    pub fn as_radical(&self) -> Option<(i128, u32)> {
+        SYMBOLS.with(|symbols| symbols.borrow().radical(*self))
+    }
+
+    /// Whether any radical or the imaginary unit has been interned this session
+    pub fn has_radicals() -> bool {
+        SYMBOLS.with(|symbols| symbols.borrow().has_radicals())
+    }
+
+    /// The session's imaginary unit, or None if complex numbers are turned off
+    pub fn imaginary() -> Option<Symbol> {
+        SYMBOLS.with(|symbols| symbols.borrow_mut().imaginary())
+    }
+
+    /// The name written for the imaginary unit, `i` unless configured otherwise
+    pub fn imaginary_unit() -> Option<String> {
+        SYMBOLS.with(|symbols| symbols.borrow().imaginary_unit.clone())
+    }
+
+    pub fn is_imaginary(&self) -> bool {
+        SYMBOLS.with(|symbols| symbols.borrow().imaginary == Some(*self))
+    }
+
+    /// Whether this symbol stands for a number: e, pi, the imaginary unit or a radical
+    pub fn is_constant(&self) -> bool {
+        self.as_radical().is_some() || self.is_imaginary() || {
+            let name = self.name();
+            name == log::E || name == trig::PI
+        }
+    }
+
+    /// The symbol standing for a function applied to an argument, e.g. `sin(2x)`
+    pub fn function(name: &str, argument: Polynomial) -> Symbol {
+        let written = function_name(name, &argument);
+        let application = Application {
+            name: name.to_string(),
+            argument,
+        };
+        SYMBOLS.with(|symbols| symbols.borrow_mut().intern_function(&written, application))
+    }
+
+    /// The function and argument this symbol stands for, if it is a function application
+    pub fn as_function(&self) -> Option<Application> {
+        SYMBOLS.with(|symbols| symbols.borrow().function(*self).cloned())
+    }
+}
+
+/// A function such as sin applied to a polynomial argument, kept whole as one symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct Application {
+    pub name: String,
+    pub argument: Polynomial,
+}
+
+/// A base raised to a symbolic exponent, such as x^n, 2^x or (x + 1)^n
+///
+/// Each distinct power is interned as one symbol, so polynomial algorithms treat it as
+/// an opaque variable, while multiplying monomials folds powers of the same base together.
+/// The exponent never has a constant term: x^(n + 2) is stored as x^2·x^n.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Power {
+    pub base: Polynomial,
+    pub exponent: Polynomial,
+}
+
+/// How a power is written, e.g. `x^n`, `2^(2n)`, `(x + 1)^n` or `sqrt(x + 1)`
+pub fn power_name(base: &Polynomial, exponent: &Polynomial) -> String {
+    match exponent.as_constant() {
+        Some(e) if e == Rational::new(1, 2) => format!("sqrt({})", base),
+        _ => format!("{}^{}", power_operand(base), power_operand(exponent)),
+    }
+}
+
+/// How a function application is written, with fractional arguments over a common
+/// denominator, e.g. `sin(2x)`, `sin(x/2)` or `cos((x + 1)/3)`
+pub fn function_name(name: &str, argument: &Polynomial) -> String {
+    let denominator = argument
+        .terms()
+        .values()
+        .fold(BigInt::one(), |d, c| d.lcm(c.denominator()));
+    match (denominator.is_one(), argument.terms().len()) {
+        (true, _) => format!("{}({})", name, argument),
+        (false, 1) => format!(
+            "{}({}/{})",
+            name,
+            argument.scale(denominator.clone()),
+            denominator
+        ),
+        (false, _) => format!(
+            "{}(({})/{})",
+            name,
+            argument.scale(denominator.clone()),
+            denominator
+        ),
+    }
+}
+
+/// Write a base or exponent bare if it is a single variable or number, else in parentheses
+fn power_operand(polynomial: &Polynomial) -> String {
+    let bare = match polynomial.as_constant() {
+        Some(c) => c.is_integer() && !c.is_negative(),
+        None => {
+            polynomial.variables().len() == 1
+                && polynomial
+                    .variables()
+                    .first()
+                    .is_some_and(|name| *polynomial == Polynomial::variable(name, 1.0))
+        }
+    };
+    if bare {
+        polynomial.to_string()
+    } else {
+        format!("({})", polynomial)
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SYMBOLS.with(|symbols| write!(f, "{}", symbols.borrow().name(*self)))
+    }
+}
+
+/// Two way map between variable names and their symbols
+///
+/// Names are only ever added, so a symbol stays valid for the whole session.
+/// The imaginary unit is interned under its configured name the first time it is used.
+/// Each interpreter owns a table of its own and enters it while it runs.
+#[derive(Debug)]
+pub struct SymbolTable {
+    names: Vec<String>,
+    /// the position of each symbol's name in alphabetical order
+    ranks: Vec<u32>,
+    ids: HashMap<String, Symbol>,
+    powers: HashMap<Symbol, Power>,
+    radicals: HashMap<Symbol, (i128, u32)>,
+    functions: HashMap<Symbol, Application>,
+    imaginary_unit: Option<String>,
+    imaginary: Option<Symbol>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        SymbolTable {
+            names: Vec::new(),
+            ranks: Vec::new(),
+            ids: HashMap::new(),
+            powers: HashMap::new(),
+            radicals: HashMap::new(),
+            functions: HashMap::new(),
+            imaginary_unit: Some("i".to_string()),
+            imaginary: None,
+        }
+    }
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    pub fn imaginary(&mut self) -> Option<Symbol> {
+        if self.imaginary.is_none() {
+            let name = self.imaginary_unit.clone()?;
+            self.imaginary = Some(self.intern(&name));
+        }
+        self.imaginary
+    }
+
+    /// Rename the imaginary unit, keeping the interned unit when the name is unchanged
+    pub fn set_imaginary_unit(&mut self, name: Option<&str>) {
+        if self.imaginary_unit.as_deref() == name {
+            return;
+        }
+        self.imaginary_unit = name.map(str::to_string);
+        self.imaginary = None;
+    }
+
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.ids.get(name) {
+            return *symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        let rank = self.names.iter().filter(|n| n.as_str() < name).count() as u32;
+        for r in self.ranks.iter_mut().filter(|r| **r >= rank) {
+            *r += 1;
+        }
+        self.ranks.push(rank);
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), symbol);
+        symbol
+    }
+
+    /// Intern a symbolic power under its written name, e.g. `2^x`
+    pub fn intern_power(&mut self, name: &str, power: Power) -> Symbol {
+        let symbol = self.intern(name);
+        self.powers.entry(symbol).or_insert(power);
+        symbol
+    }
+
+    pub fn power(&self, symbol: Symbol) -> Option<&Power> {
+        self.powers.get(&symbol)
+    }
+
+    pub fn has_powers(&self) -> bool {
+        !self.powers.is_empty()
+    }
+
+    /// Intern a radical under its written name, e.g. `root(2, 3)`
+    pub fn intern_radical(&mut self, name: &str, radicand: i128, index: u32) -> Symbol {
+        let symbol = self.intern(name);
+        self.radicals.insert(symbol, (radicand, index));
+        symbol
+    }
+
+    pub fn radical(&self, symbol: Symbol) -> Option<(i128, u32)> {
+        self.radicals.get(&symbol).copied()
+    }
+
+    /// Intern a function application under its written name, e.g. `cos(x + 1)`
+    pub fn intern_function(&mut self, name: &str, application: Application) -> Symbol {
+        let symbol = self.intern(name);
+        self.functions.entry(symbol).or_insert(application);
+        symbol
+    }
+
+    pub fn function(&self, symbol: Symbol) -> Option<&Application> {
+        self.functions.get(&symbol)
+    }
+
+    pub fn has_radicals(&self) -> bool {
+        !self.radicals.is_empty() || self.imaginary.is_some()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Symbol> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Run with this as the table symbols on this thread are interned in, handing it
+    /// back afterwards, and the table it stood in for back to the thread
+    pub fn enter<T>(&mut self, run: impl FnOnce() -> T) -> T {
+        /// Swaps the tables back even if `run` panics
+        struct Entered<'a>(&'a mut SymbolTable);
+
+        impl Drop for Entered<'_> {
+            fn drop(&mut self) {
+                SYMBOLS.with(|symbols| std::mem::swap(&mut *symbols.borrow_mut(), self.0));
+            }
+        }
+
+        SYMBOLS.with(|symbols| std::mem::swap(&mut *symbols.borrow_mut(), self));
+        let _entered = Entered(self);
+        run()
+    }
+}
+
+thread_local! {
+    /// The table entered on this thread, or one of its own outside any interpreter
+    static SYMBOLS: RefCell<SymbolTable> = RefCell::new(SymbolTable::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::rational::Rational;
+
+    #[test]
+    fn test_symbol_table() {
+        let mut table = SymbolTable::new();
+        let x = table.intern("x");
+        let theta = table.intern("theta");
+
+        assert_eq!(table.intern("x"), x);
+        assert_ne!(x, theta);
+        assert_eq!(table.name(theta), "theta");
+        assert_eq!(table.lookup("y"), None);
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn test_alphabetical() {
+        let mut table = SymbolTable::new();
+        let (z, a, m) = table.enter(|| {
+            (
+                Symbol::intern("z"),
+                Symbol::intern("a"),
+                Symbol::intern("m"),
+            )
+        });
+        assert!(z < a && a < m);
+        assert_eq!(
+            table.enter(|| Symbol::alphabetical([z, m, a, z])),
+            vec![a, m, z]
+        );
+        let (pi, root) = table.enter(|| (Symbol::intern("pi"), Symbol::radical(2, 2)));
+        assert_eq!(
+            table.enter(|| Symbol::ranked([root, z, pi, a])),
+            vec![a, z, pi, root]
+        );
+    }
+
+    #[test]
+    fn test_enter() {
+        let mut outer = SymbolTable::new();
+        let mut inner = SymbolTable::new();
+        outer.enter(|| {
+            Symbol::intern("x");
+            inner.enter(|| {
+                assert_eq!(Symbol::lookup("x"), None);
+                Symbol::intern("y");
+            });
+            assert_eq!(Symbol::lookup("y"), None);
+            assert!(Symbol::lookup("x").is_some());
+        });
+        assert!(outer.lookup("x").is_some() && outer.lookup("y").is_none());
+        assert!(inner.lookup("y").is_some() && inner.lookup("x").is_none());
+    }
+
+    #[test]
+    fn test_session_symbols() {
+        let x = Symbol::intern("x");
+        assert_eq!(Symbol::intern("x"), x);
+        assert_eq!(Symbol::lookup("x"), Some(x));
+        assert_eq!(x.name(), "x");
+        assert_eq!(x.to_string(), "x");
+    }
+
+    #[test]
+    fn test_powers() {
+        let x = Polynomial::variable("x", 1.0);
+        let n = Polynomial::variable("n", 1.0);
+        let power = Symbol::power(x.clone(), n.clone());
+
+        assert_eq!(power.name(), "x^n");
+        assert_eq!(Symbol::power(x.clone(), n.clone()), power);
+        assert_eq!(power.as_power().map(|p| p.base), Some(x.clone()));
+        assert_eq!(Symbol::intern("x").as_power(), None);
+        assert_eq!(
+            Symbol::power(Polynomial::constant(2), n.scale(2)).name(),
+            "2^(2n)"
+        );
+        assert_eq!(Symbol::power(&x + &n, n).name(), "(n + x)^n");
+    }
+
+    #[test]
+    fn test_radicals() {
+        let root = Symbol::radical(3, 2);
+        assert_eq!(root.name(), "sqrt(3)");
+        assert_eq!(root.as_radical(), Some((3, 2)));
+        assert_eq!(Symbol::radical(2, 3).name(), "root(2, 3)");
+        assert_eq!(Symbol::intern("x").as_radical(), None);
+    }
+
+    #[test]
+    fn test_functions() {
+        let x = Polynomial::variable("x", 1.0);
+        let sine = Symbol::function("sin", x.scale(2));
+
+        assert_eq!(sine.name(), "sin(2x)");
+        assert_eq!(Symbol::function("sin", x.scale(2)), sine);
+        assert_eq!(
+            sine.as_function().map(|f| (f.name, f.argument)),
+            Some(("sin".to_string(), x.scale(2)))
+        );
+        assert_eq!(Symbol::intern("x").as_function(), None);
+        assert_eq!(
+            function_name("sin", &x.scale(Rational::new(1, 2))),
+            "sin(x/2)"
+        );
+    }
+
+    #[test]
+    fn test_imaginary_unit() {
+        let mut table = SymbolTable::new();
+        let i = table.imaginary();
+        assert_eq!(i, table.lookup("i"));
+
+        table.set_imaginary_unit(Some("j"));
+        let j = table.imaginary();
+        assert_ne!(i, j);
+        assert_eq!(j.map(|j| table.name(j).to_string()), Some("j".to_string()));
+        table.set_imaginary_unit(None);
+        assert_eq!(table.imaginary(), None);
+    }
+}
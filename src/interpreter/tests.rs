@@ -1,30 +1,23 @@
 use super::*;
 
 fn lex(input: &str) -> Result<VecDeque<lexer::Token>, InterpreterError> {
-    let mut interpreter = Interpreter::new();
-    match interpreter.lex(input) {
-        Ok(tokens) => Ok(tokens),
-        Err(e) => Err(InterpreterError::new(format!("Lexing failed: {}", e))),
-    }
+    Interpreter::new().lex(input)
 }
 
 fn parse(tokens: VecDeque<lexer::Token>) -> Result<parser::ASTNode, InterpreterError> {
-    let mut interpreter = Interpreter::new();
-    match interpreter.parse(tokens) {
-        Ok(ast) => Ok(ast),
-        Err(e) => Err(InterpreterError::new(format!("Parsing failed: {}", e))),
-    }
+    Interpreter::new().parse(tokens)
 }
 
 fn interpret(input: &str) -> Result<String, InterpreterError> {
-    let interpreter = Interpreter::new();
-    match interpreter.interpret(parse(lex(input)?)?) {
-        Ok(output) => Ok(output),
-        Err(e) => Err(InterpreterError::new(format!(
-            "Interpretation failed: {}",
-            e
-        ))),
-    }
+    interpret_with(&mut Interpreter::new(), input)
+}
+
+/// Runs an input through an interpreter configured by the test, which keeps its
+/// assumptions between calls
+fn interpret_with(interpreter: &mut Interpreter, input: &str) -> Result<String, InterpreterError> {
+    let tokens = interpreter.lex(input)?;
+    let ast = interpreter.parse(tokens)?;
+    interpreter.interpret(ast)
 }
 
 #[test]
@@ -175,6 +168,10 @@ fn sort_alphabetically_test() -> Result<(), InterpreterError> {
     assert_eq!(output1, input);
     assert_eq!(output2, input);
     assert_eq!(output3, input);
+    // constants rank below every variable, so the highest power of x leads
+    assert_eq!(interpret("x^2 + pi x + 1")?, "x^2 + pi*x + 1");
+    assert_eq!(interpret("x^2 + 2sqrt(2)x + 2")?, "x^2 + 2sqrt(2)*x + 2");
+    assert_eq!(interpret("e y + y^2")?, "y^2 + ey");
 
     Ok(())
 }
@@ -274,11 +271,1818 @@ fn error_test() -> Result<(), InterpreterError> {
     max_string.push('0');
     let input1 = max_string.as_str();
 
-    let output = interpret(input)?;
+    let output = interpret(input);
     let output1 = interpret(input1);
 
-    assert!(output.is_empty());
-    assert!(output1.is_err());
+    assert!(output.is_err());
+    // past the largest float, read exactly since coefficients became exact rationals
+    assert_eq!(output1?, max_string);
+    // still too large to take a root of, which overflows rather than rounding
+    let root = format!("sqrt({})", max_string);
+    assert_eq!(interpret(&root).unwrap_err().code(), "E401");
+
+    Ok(())
+}
+#[test]
+fn factor_univariate_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("factor(x^4 - 1)")?, "(x - 1)(x + 1)(x^2 + 1)");
+    assert_eq!(interpret("factor(x^2 + 2x + 1)")?, "(x + 1)^2");
+    assert_eq!(interpret("factor(6x^2 - 6)")?, "6(x - 1)(x + 1)");
+    assert_eq!(interpret("factor(-x^3 + x)")?, "-x(x - 1)(x + 1)");
+    assert_eq!(interpret("factor(x^4 + 1)")?, "x^4 + 1");
+    assert_eq!(
+        interpret("factor(6x^4 - x^3 - 8x^2 + x + 2)")?,
+        "(x - 1)(x + 1)(2x + 1)(3x - 2)"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn factor_multivariate_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("factor(x^2 - y^2)")?, "(x - y)(x + y)");
+    assert_eq!(interpret("factor(x^2 + 2xy + y^2)")?, "(x + y)^2");
+    assert_eq!(interpret("factor(x^2y + xy^2)")?, "xy(x + y)");
+    // images with a high power of the substitution variable, which took minutes
+    assert_eq!(interpret("factor(ax^2 - by)")?, "ax^2 - by");
+    assert_eq!(interpret("factor((xy + 1)(x^2 - y))")?, "(xy + 1)(x^2 - y)");
+    // the sign follows the leading power of x rather than of the constant
+    assert_eq!(
+        interpret("factor(x^2 - 2sqrt(2)x + 2)")?,
+        interpret("x^2 - 2sqrt(2)x + 2")?
+    );
+    assert_eq!(interpret("factor(x^2 - pi x)")?, "x(x - pi)");
+    assert_eq!(interpret("factor(2 - sqrt(2)x)")?, "-(sqrt(2)*x - 2)");
+
+    Ok(())
+}
+
+#[test]
+fn factor_constant_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("factor(0)")?, "0");
+    assert_eq!(interpret("factor(-12)")?, "-12");
+    assert_eq!(interpret("factor(2x)")?, "2x");
+
+    Ok(())
+}
+
+#[test]
+fn factor_error_test() {
+    assert!(interpret("factor(x^2 - 1").is_err());
+    assert!(interpret("1 + factor(x)").is_err());
+}
+
+#[test]
+fn factor_large_coefficients_test() -> Result<(), InterpreterError> {
+    // the gcds of the square-free step overflowed an i128 over the rationals
+    assert_eq!(
+        interpret("factor((x^5+3x+7)(x^5-2x+11))")?,
+        "(x^5 - 2x + 11)(x^5 + 3x + 7)"
+    );
+    assert_eq!(
+        interpret("factor((x^8+3x+1)(x^8-x+1))")?,
+        "(x^2 - x + 1)(x^6 + x^5 - x^3 - x^2 + 1)(x^8 + 3x + 1)"
+    );
+    assert_eq!(
+        interpret("factor((x^3+3x+7)(x^4-2x+11)(x^2+5))")?,
+        "(x^2 + 5)(x^3 + 3x + 7)(x^4 - 2x + 11)"
+    );
+
+    // coefficients past an i64, which overflowed before they were exact
+    assert_eq!(interpret("factor((x+1)^60)")?, "(x + 1)^60");
+    // Mignotte bounds past 2^62, which a single lifting modulus could not reach
+    assert_eq!(
+        interpret("factor(x^64 - 1)")?,
+        "(x - 1)(x + 1)(x^2 + 1)(x^4 + 1)(x^8 + 1)(x^16 + 1)(x^32 + 1)"
+    );
+    assert_eq!(
+        interpret("factor(x^4y^2z^2 - 1)")?,
+        "(x^2yz - 1)(x^2yz + 1)"
+    );
+    assert_eq!(
+        interpret("factor(15x^4y^2z^2 + 9x^3y^2z + 5x^2yz + 3xy + 10x^2y^2z + 6xy^2)")?,
+        "xy(5xz + 3)(3x^2yz + 2y + 1)"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn parser_division_test() -> Result<(), InterpreterError> {
+    let ast = parse(lex("1/x")?)?;
+
+    assert_eq!(
+        ast,
+        parser::ASTNode::Expression(vec![parser::ASTNode::Operation(
+            "/".to_string(),
+            Box::new(parser::ASTNode::Term(
+                Box::new(parser::ASTNode::Number(1.0)),
+                Vec::new()
+            )),
+            Box::new(parser::ASTNode::Term(
+                Box::new(parser::ASTNode::Number(1.0)),
+                vec![parser::ASTNode::Variable(
+                    "x".to_string(),
+                    Box::new(parser::ASTNode::Number(1.0))
+                )]
+            )),
+        )])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rational_function_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("(x^2 - 1)/(x - 1)")?, "x + 1");
+    assert_eq!(interpret("1/x + 1/y")?, "(x + y)/(xy)");
+    assert_eq!(interpret("1/x + 1/x")?, "2/x");
+    assert_eq!(interpret("(x + 1)/(x^2 + 2x + 1)")?, "1/(x + 1)");
+    assert_eq!(interpret("x * y / z")?, "xy/z");
+    assert_eq!(interpret("(1/x)^-2")?, "x^2");
+    assert_eq!(
+        interpret("(x^5+3x+7)/(x^5-2x+11)*(x^3+1)/(x^4+2x+9)")?,
+        "(x^8 + x^5 + 3x^4 + 7x^3 + 3x + 7)/(x^9 + 2x^6 + 7x^5 + 11x^4 - 4x^2 + 4x + 99)"
+    );
+    // the numerator is only divided by the factors of the denominator, not factored
+    assert_eq!(
+        interpret("sqrt(3)/(x - y - 2^y) + 9 - (i + y) - pi")?,
+        "(-2^y*y - 2^y*i - 2^y*pi + 9*2^y + x*y + i*x + pi*x - 9x - y^2 - i*y - pi*y + 9y - sqrt(3))/(2^y - x + y)"
+    );
+    // nothing to cancel, so the expanded numerator stays over the denominator
+    assert!(interpret("(x + 1)^60/(x + 2)")?.ends_with("/(x + 2)"));
+
+    Ok(())
+}
+
+#[test]
+fn rational_constant_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("6/4")?, "3/2");
+    assert_eq!(interpret("x/2 + x/2")?, "x");
+    assert_eq!(interpret("factor(x/2 + 1/2)")?, "(x + 1)/2");
+    assert_eq!(interpret("factor(-x/2 - 1/2)")?, "-(x + 1)/2");
+    assert_eq!(interpret("factor(3x^2/4 + 3x/4)")?, "3x(x + 1)/4");
+    assert_eq!(interpret("factor(x/2)")?, "x/2");
+
+    Ok(())
+}
+
+#[test]
+fn division_by_zero_test() {
+    assert!(interpret("1/0").is_err());
+    assert!(interpret("x/(x - x)").is_err());
+    assert!(interpret("x/").is_err());
+}
+
+#[test]
+fn apart_linear_factors_test() -> Result<(), InterpreterError> {
+    assert_eq!(
+        interpret("apart(1/(x^2 - 1), x)")?,
+        "1/(2(x - 1)) - 1/(2(x + 1))"
+    );
+    assert_eq!(
+        interpret("apart(x^3/(x^2 - 1), x)")?,
+        "x + 1/(2(x - 1)) + 1/(2(x + 1))"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apart_repeated_factors_test() -> Result<(), InterpreterError> {
+    assert_eq!(
+        interpret("apart(1/(x^2(x + 1)), x)")?,
+        "-1/x + 1/x^2 + 1/(x + 1)"
+    );
+    assert_eq!(
+        interpret("apart((x^2 + 1)/((x - 1)^2(x + 2)), x)")?,
+        "4/(9(x - 1)) + 2/(3(x - 1)^2) + 5/(9(x + 2))"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apart_quadratic_factors_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("apart(1/(x(x^2 + 1)), x)")?, "1/x - x/(x^2 + 1)");
+    assert_eq!(
+        interpret("apart((2x + 3)/(x^2 + 1)^2, x)")?,
+        "(2x + 3)/(x^2 + 1)^2"
+    );
+    assert_eq!(
+        interpret("apart(1/(x^3 - 1), x)")?,
+        "1/(3(x - 1)) - (x + 2)/(3(x^2 + x + 1))"
+    );
+    // coprime factors whose cofactors overflowed an i128 before arithmetic was checked
+    assert_eq!(
+        interpret("apart(1/((x^4+3x+7)(x^3-2x+11)), x)")?,
+        "(14x^2 + 4x - 61)/(717(x^3 - 2x + 11)) - (14x^3 + 4x^2 - 33x - 104)/(717(x^4 + 3x + 7))"
+    );
+    // numerators past the precision of a float, which rounded them before they were exact
+    assert_eq!(
+        interpret("apart(1/(4x^8+12x^7+45x^6+108x^5+177x^4+288x^3+280x^2+192x+144), x)")?,
+        "19584/(2640625(2x + 3)) + 64/(8125(2x + 3)^2) - (12x - 5)/(1521(x^2 + 1)) \
+         + (588x + 43)/(140625(x^2 + 4)) + (12x + 7)/(1875(x^2 + 4)^2)"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn apart_error_test() {
+    assert!(interpret("apart(1/(xy), x)").is_err());
+    assert!(interpret("apart(1/x)").is_err());
+}
+
+#[test]
+fn linear_system_unique_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("{2x + y = 5, x - y = 1}")?, "x = 2, y = 1");
+    assert_eq!(interpret("{x/2 + y/3 = 1, x - y = 0}")?, "x = 6/5, y = 6/5");
+    assert_eq!(interpret("2x = 3")?, "x = 3/2");
+    assert_eq!(interpret("{x = 1, y = 2, x + y = 3}")?, "x = 1, y = 2");
+    // solutions past the integers a float holds exactly
+    assert_eq!(
+        interpret("{1667x + 1000y = 99999999999999999, 3x - 1999y = 7}")?,
+        "x = 199900000000000005001/3335333, y = 299999999999988328/3335333"
+    );
+    // elimination across large prime denominators, which overflowed an i128
+    assert_eq!(
+        interpret(
+            "{x/999983 + y/999979 + z/999961 = 1, x/999959 - y/999953 + z/999931 = 2, \
+             x/999917 + y/999907 - z/999883 = 3}"
+        )?,
+        "x = 2498950180756075752251307380084538754791452/999647048853640073964179547013251183, \
+         y = -999583071798347149842817533296564382820193/1999294097707280147928359094026502366, \
+         z = -666336400557425905724460403036885024757285/666431365902426715976119698008834122"
+    );
+    // fractional solutions in lowest terms
+    assert_eq!(
+        interpret("{x/3 + y/6 = 1/4, x - y = 1}")?,
+        "x = 5/6, y = -1/6"
+    );
+    // constants stay on the right hand side rather than being solved for
+    assert_eq!(interpret("x = sqrt(2)")?, "x = sqrt(2)");
+    assert_eq!(
+        interpret("{x + y = pi, x - y = 1}")?,
+        "x = (pi + 1)/2, y = (pi - 1)/2"
+    );
+    assert_eq!(interpret("sqrt(2) = 1")?, "No solution");
+
+    Ok(())
+}
+
+#[test]
+fn linear_system_parametric_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("{x + y = 1}")?, "x = -y + 1, y free");
+    assert_eq!(
+        interpret("{x + 2y + 3z = 1, 2x + 4y + 6z = 2}")?,
+        "x = -2y - 3z + 1, y free, z free"
+    );
+    assert_eq!(interpret("x = x")?, "Always true");
+
+    Ok(())
+}
+
+#[test]
+fn linear_system_inconsistent_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("{x + y = 1, x + y = 2}")?, "No solution");
+    assert_eq!(interpret("{x = 1, y = 2, x + y = 4}")?, "No solution");
+
+    Ok(())
+}
+
+#[test]
+fn linear_system_error_test() {
+    assert!(interpret("{1/x = 1}").is_err());
+    assert!(interpret("{x + y}").is_err());
+    assert!(interpret("{x = 1").is_err());
+}
+
+#[test]
+fn polynomial_system_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("{x^2 = 1}")?, "{x = -1}, {x = 1}");
+    assert_eq!(
+        interpret("{x^2 + y^2 = 25, x + y = 7}")?,
+        "{x = 3, y = 4}, {x = 4, y = 3}"
+    );
+    assert_eq!(
+        interpret("{x^2 + y^2 + z^2 = 3, x = y, y = z}")?,
+        "{x = -1, y = -1, z = -1}, {x = 1, y = 1, z = 1}"
+    );
+    assert_eq!(interpret("{xy = 1, x = 0}")?, "No solution");
+
+    Ok(())
+}
+
+#[test]
+fn polynomial_system_irrational_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("x^2 = 2")?, "{x = -sqrt(2)}, {x = sqrt(2)}");
+    assert_eq!(interpret("x^2 + 1 = 0")?, "{x = -i}, {x = i}");
+    assert_eq!(
+        interpret("x^2 - 2x - 1 = 0")?,
+        "{x = -sqrt(2) + 1}, {x = sqrt(2) + 1}"
+    );
+    assert_eq!(
+        interpret("x^3 = 8")?,
+        "{x = 2}, {x = -i*sqrt(3) - 1}, {x = i*sqrt(3) - 1}"
+    );
+    assert_eq!(
+        interpret("{x^2 + y^2 = 1, x = y}")?,
+        "{x = -sqrt(2)/2, y = -sqrt(2)/2}, {x = sqrt(2)/2, y = sqrt(2)/2}"
+    );
+    assert_eq!(
+        interpret("{x^2y = 1, xy^2 = 1}")?,
+        "{x = 1, y = 1}, {x = (-i*sqrt(3) - 1)/2, y = (-i*sqrt(3) - 1)/2}, \
+         {x = (i*sqrt(3) - 1)/2, y = (i*sqrt(3) - 1)/2}"
+    );
+    assert_eq!(interpret("{xy = 0}")?, "{xy = 0}");
+    // an unknown linear in a free one is solved for, as a linear system would be
+    assert_eq!(interpret("{x + y = 0, (x + y)^2 = 0}")?, "{x = -y}");
+    assert_eq!(
+        interpret("{x^2 + xy = sqrt(2), x^2 = -1}")?,
+        "{x = -sqrt(2)*y + y, y^2 + 2sqrt(2) + 3 = 0}"
+    );
+    // constants are never unknowns, so sqrt(2) = 1 and pi = 1 rule the system out
+    assert_eq!(interpret("{x^2 = sqrt(2), -x = 1}")?, "No solution");
+    assert_eq!(interpret("{x^2 = pi, x = 1}")?, "No solution");
+    // an unknown whose equations have constants in them has values in them, when they
+    // can be written in radicals
+    assert_eq!(
+        interpret("{x^2 = sqrt(2)}")?,
+        "{x = -root(2, 4)}, {x = root(2, 4)}"
+    );
+    assert_eq!(interpret("{x^3 = sqrt(2)}")?, "{x^3 - sqrt(2) = 0}");
+    assert_eq!(interpret("{sqrt(2)y = sqrt(2) - 2}")?, "{y = -sqrt(2) + 1}");
+    assert_eq!(interpret("{x^2 = y, x = sqrt(2)}")?, "{x = sqrt(2), y = 2}");
+    assert_eq!(
+        interpret("{(1 + sqrt(2))x = 1, xy = 1}")?,
+        "{x = sqrt(2) - 1, y = sqrt(2) + 1}"
+    );
+    assert_eq!(
+        interpret("{x^2 - xy = sqrt(2), x^2 = x}")?,
+        "{x = 1, y = -sqrt(2) + 1}"
+    );
+    // earlier unknowns are solved over quadratics once later ones are irrational, and
+    // quadratics in y^2 as quadratics
+    assert_eq!(
+        interpret("{x^2 - 2 = 0, y^2 - 3 = 0}")?,
+        "{x = -sqrt(2), y = -sqrt(3)}, {x = sqrt(2), y = -sqrt(3)}, \
+         {x = -sqrt(2), y = sqrt(3)}, {x = sqrt(2), y = sqrt(3)}"
+    );
+    assert_eq!(
+        interpret("{x^2 + y^2 = 4, xy = 1}")?,
+        "{x = (-sqrt(2) - sqrt(6))/2, y = (sqrt(2) - sqrt(6))/2}, \
+         {x = (sqrt(2) + sqrt(6))/2, y = (-sqrt(2) + sqrt(6))/2}, \
+         {x = (sqrt(2) - sqrt(6))/2, y = (-sqrt(2) - sqrt(6))/2}, \
+         {x = (-sqrt(2) + sqrt(6))/2, y = (sqrt(2) + sqrt(6))/2}"
+    );
+    assert_eq!(
+        interpret("{x^2 = y, y^2 = 3}")?,
+        "{x = -i*root(3, 4), y = -sqrt(3)}, {x = i*root(3, 4), y = -sqrt(3)}, \
+         {x = -root(3, 4), y = sqrt(3)}, {x = root(3, 4), y = sqrt(3)}"
+    );
+
+    let mut positive = Interpreter::new();
+    interpret_with(&mut positive, "assume x > 0")?;
+    assert_eq!(interpret_with(&mut positive, "x^2 = 2")?, "{x = sqrt(2)}");
+    assert_eq!(interpret_with(&mut positive, "x^2 + 1 = 0")?, "No solution");
+
+    // factors of higher degree have no roots in radicals to give
+    assert_eq!(interpret("x^3 = 2").map_err(|e| e.code()), Err("E501"));
+    // the basis overflowed an i128 before its arithmetic was checked
+    assert_eq!(
+        interpret("{x^3 + y^3 = 7, x^2 y - y = 2}").map_err(|e| e.code()),
+        Err("E501")
+    );
+    assert_eq!(
+        interpret("{x^5 + 7y^3 = 13, 5x^2y^2 - 3y = 2}").map_err(|e| e.code()),
+        Err("E501")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn groebner_test() -> Result<(), InterpreterError> {
+    assert_eq!(
+        interpret("groebner({x^2 + y^2 = 1, x = y})")?,
+        "{x - y, 2y^2 - 1}"
+    );
+    assert_eq!(
+        interpret("groebner({x^2 + y^2 = 1, x = y}, grevlex)")?,
+        "{2y^2 - 1, x - y}"
+    );
+    assert_eq!(
+        interpret("groebner({x^3 - 2xy = 0, x^2y - 2y^2 + x = 0}, grlex)")?,
+        "{x^2, xy, -x + 2y^2}"
+    );
+    assert_eq!(interpret("groebner({xy = 1, x = 1, y = 2})")?, "{1}");
+
+    Ok(())
+}
+
+#[test]
+fn groebner_error_test() {
+    assert!(interpret("groebner({x^2 = 1}, foo)").is_err());
+    assert!(interpret("groebner(x^2)").is_err());
+    assert!(interpret("groebner({1/x = 1})").is_err());
+}
+
+#[test]
+fn monomial_order_output_test() -> Result<(), InterpreterError> {
+    let input = "1 + x + y^2 + xy + x^2";
+    assert_eq!(interpret(input)?, "x^2 + xy + x + y^2 + 1");
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                order: MonomialOrder::GrLex,
+                ..Interpreter::new()
+            },
+            input
+        )?,
+        "x^2 + xy + y^2 + x + 1"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                order: MonomialOrder::AscendingPowers,
+                ..Interpreter::new()
+            },
+            input
+        )?,
+        "1 + x + y^2 + xy + x^2"
+    );
+
+    let input = "xy^2 + x^2z";
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                order: MonomialOrder::GrLex,
+                ..Interpreter::new()
+            },
+            input
+        )?,
+        "x^2z + xy^2"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                order: MonomialOrder::GrevLex,
+                ..Interpreter::new()
+            },
+            input
+        )?,
+        "xy^2 + x^2z"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn monomial_order_groebner_test() {
+    assert!(interpret("groebner({x^2 = 1}, ascending)").is_err());
+}
+
+#[test]
+fn lexer_identifier_test() -> Result<(), InterpreterError> {
+    let tokens = lex("2x1 + v_0θ rate")?;
+    assert_eq!(
+        tokens,
+        VecDeque::from([
+            lexer::Token::Number(2.0),
+            lexer::Token::Identifier("x1".to_string()),
+            lexer::Token::Symbol('+'),
+            lexer::Token::Identifier("v_0θ".to_string()),
+            lexer::Token::Identifier("rate".to_string()),
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn subscripted_variables_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("2xy")?, "2xy");
+    assert_eq!(interpret("x1 + x1")?, "2x1");
+    assert_eq!(interpret("v_0 + v_0^2")?, "v_0^2 + v_0");
+    assert_eq!(interpret("v_10x - xv_10")?, "0");
+    assert_eq!(interpret("2θα")?, "2alpha*theta");
+    assert_eq!(interpret("θ + theta")?, "2theta");
+    assert_eq!(interpret("θ_0 - theta_0")?, "0");
+    assert_eq!(interpret("x_a1 + x_a1")?, "2x_a1");
+    assert_eq!(interpret("x_a1y")?, "x_a1y");
+    for input in ["x_", "x__1", "_x", "x_ + 1"] {
+        assert_eq!(interpret(input).unwrap_err().code(), "E201");
+    }
+    assert_eq!(interpret("factor(x1^2 - 1)")?, "(x1 - 1)(x1 + 1)");
+    assert_eq!(interpret("{x1 + x2 = 3, x1 - x2 = 1}")?, "x1 = 2, x2 = 1");
+
+    Ok(())
+}
+
+#[test]
+fn multi_letter_variables_test() -> Result<(), InterpreterError> {
+    use parser::VariableMode::{MultiLetter, SingleLetter};
+    let mut single = Interpreter::new();
+    single.parser.variable_mode = SingleLetter;
+    let mut multi = Interpreter::new();
+    multi.parser.variable_mode = MultiLetter;
+
+    assert_eq!(interpret_with(&mut single, "rate")?, "aert");
+    assert_eq!(interpret_with(&mut single, "2theta theta")?, "2theta^2");
+    assert_eq!(
+        interpret_with(&mut single, "betax + zeta")?,
+        "beta*x + zeta"
+    );
+    assert_eq!(interpret_with(&mut single, "xi")?, "ix");
+    assert_eq!(
+        interpret_with(&mut single, "sinx").unwrap_err().code(),
+        "E201"
+    );
+    assert_eq!(interpret_with(&mut multi, "rate*time")?, "rate*time");
+    assert_eq!(interpret_with(&mut multi, "theta + theta")?, "2theta");
+    assert_eq!(interpret_with(&mut multi, "θ1 - theta1")?, "0");
+    assert_eq!(
+        interpret_with(&mut multi, "rate__1").unwrap_err().code(),
+        "E201"
+    );
+    assert_eq!(
+        interpret_with(&mut multi, "{speed + time = 5, speed - time = 1}")?,
+        "speed = 3, time = 2"
+    );
+
+    let mut interpreter = Interpreter::new();
+    interpreter.parser.declare_symbol("rate");
+    let tokens = interpreter.lex("2ratex + rate")?;
+    let ast = interpreter.parse(tokens)?;
+    assert_eq!(interpreter.interpret(ast)?, "2rate*x + rate");
+
+    Ok(())
+}
+
+#[test]
+fn implicit_multiplication_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("2(x+1)")?, "2x + 2");
+    assert_eq!(interpret("(x+1)(x-1)")?, "x^2 - 1");
+    assert_eq!(interpret("x(x+2)")?, "x^2 + 2x");
+    assert_eq!(interpret("(x+1)2")?, "2x + 2");
+    assert_eq!(interpret("3sqrt(4)x")?, "6x");
+    assert_eq!(interpret("1/2x")?, interpret("1/(2x)")?);
+    assert_eq!(interpret("1/2*x")?, "x/2");
+
+    // a function call after a variable is read as a call, glued to it or not
+    assert_eq!(interpret("x sin(x)")?, "sin(x)*x");
+    assert_eq!(interpret("xsin(x)")?, "sin(x)*x");
+    assert_eq!(interpret("xsqrt(4)")?, "2x");
+    assert_eq!(interpret("-1 + i sqrt(3)")?, "i*sqrt(3) - 1");
+    assert_eq!(interpret("xy(2)")?, "2xy");
+
+    // a number after a power of a number is a factor of its own, not a new term
+    assert_eq!(interpret("2^x 3^x")?, "2^x*3^x");
+    assert_eq!(interpret("2^x 3")?, "3*2^x");
+
+    // whitespace ends a number, so numbers side by side multiply
+    assert_eq!(interpret("2 3")?, "6");
+    assert_eq!(interpret("x^2 3")?, "3x^2");
+    assert_eq!(interpret("12 10x")?, "120x");
+
+    assert_eq!(
+        parse(lex("3sqrt(2)")?)?,
+        parser::ASTNode::Expression(vec![parser::ASTNode::Term(
+            Box::new(parser::ASTNode::Number(3.0)),
+            vec![parser::ASTNode::Function(
+                "sqrt".to_string(),
+                vec![parser::ASTNode::Expression(vec![parser::ASTNode::Term(
+                    Box::new(parser::ASTNode::Number(2.0)),
+                    Vec::new()
+                )])]
+            )]
+        )])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn strict_multiplication_test() -> Result<(), InterpreterError> {
+    let mut strict = Interpreter::new();
+    strict.parser.multiplication_mode = parser::MultiplicationMode::Strict;
+    assert_eq!(interpret_with(&mut strict, "2(x+1)")?, "2x + 2");
+    assert_eq!(interpret_with(&mut strict, "(x+1)(x-1)")?, "x^2 - 1");
+    assert_eq!(interpret_with(&mut strict, "1/(2x)")?, interpret("1/2x")?);
+    assert_eq!(interpret_with(&mut strict, "1/x")?, interpret("1/x")?);
+
+    assert!(interpret_with(&mut strict, "1/2x").is_err());
+    assert!(interpret_with(&mut strict, "(x+1)2").is_err());
+    assert!(interpret_with(&mut strict, "x(x+2)")
+        .unwrap_err()
+        .to_string()
+        .contains("x*(...)"));
+    assert_eq!(interpret_with(&mut strict, "x sin(x)")?, "sin(x)*x");
+    assert!(interpret_with(&mut strict, "xsin(x)")
+        .unwrap_err()
+        .to_string()
+        .contains("x*sin(...)"));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn symbolic_exponent_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("x^n")?, "x^n");
+    assert_eq!(interpret("x^a x^b")?, "x^(a + b)");
+    assert_eq!(interpret("x^n*x^2")?, "x^(n + 2)");
+    assert_eq!(interpret("(x^n)^2")?, "x^(2n)");
+    assert_eq!(interpret("x^n/x^n")?, "1");
+    assert_eq!(interpret("3*2^x")?, "3*2^x");
+    assert_eq!(interpret("2^(x+1)")?, "2*2^x");
+    assert_eq!(interpret("2^x*2^-x")?, "1");
+    assert_eq!(interpret("2^x^2")?, "2^(x^2)");
+    assert_eq!(interpret("x^2^3")?, "x^8");
+    assert_eq!(interpret("2^3^2")?, "512");
+    assert_eq!(interpret("x^(2)^3")?, "x^8");
+    assert_eq!(interpret("x^(-2)")?, "x^-2");
+    assert_eq!(interpret("x^(-2)")?, interpret("x^-2")?);
+    assert_eq!(interpret("2^(x)^2")?, "2^(x^2)");
+    assert_eq!(interpret("e^(x)^2")?, "e^(x^2)");
+    assert_eq!(interpret("x^sin(x)")?, "x^sin(x)");
+    assert_eq!(interpret("x^sqrt(2)")?, "x^sqrt(2)");
+    assert_eq!(interpret("(2x)^n")?, "2^n*x^n");
+    assert_eq!(interpret("(x+1)^n")?, "(x + 1)^n");
+    assert_eq!(interpret("x^(1/2)x^(1/2)")?, "x");
+    assert_eq!(interpret("x^(1/2)")?, "sqrt(x)");
+    assert_eq!(interpret("x^(1/3)")?, "x^(1/3)");
+    assert_eq!(interpret("x^(-1/2)")?, "x^(-1/2)");
+
+    // a sum to a fractional power keeps the fraction as a power of the sum
+    assert_eq!(interpret("sqrt(x+1)")?, "sqrt(x + 1)");
+    assert_eq!(interpret("(x+1)^(1/2)")?, "sqrt(x + 1)");
+    assert_eq!(interpret("sqrt(x^2+1)")?, "sqrt(x^2 + 1)");
+    assert_eq!(interpret("(x+1)^(1/3)")?, "(x + 1)^(1/3)");
+    assert_eq!(interpret("(x+1)^(3/2)")?, "sqrt(x + 1)*x + sqrt(x + 1)");
+    assert_eq!(interpret("1/sqrt(x+1)")?, "sqrt(x + 1)/(x + 1)");
+    assert_eq!(interpret("(x+1)^(-1/2)")?, "sqrt(x + 1)/(x + 1)");
+    assert_eq!(interpret("sqrt(x+1)^2")?, "x + 1");
+    assert_eq!(interpret("((x+1)^(1/2))^(1/2)")?, "(x + 1)^(1/4)");
+    assert_eq!(interpret("(x+1)^n sqrt(x+1)")?, "(x + 1)^(n + 1/2)");
+    assert_eq!(interpret("-2^2")?, "-4");
+
+    assert_eq!(interpret("x^(1/n)")?, "x^(n^-1)");
+    assert_eq!(interpret("x^(1/n)x^(1/n)")?, "x^(2n^-1)");
+    assert_eq!(interpret("(x^(1/n))^n")?, "x");
+    assert_eq!(interpret("(2^(1/n))^(-n)")?, "1/2");
+    assert_eq!(interpret("((x+1)^(1/n))^(-n)")?, "1/(x + 1)");
+
+    assert!(interpret("x^").is_err());
+    assert!(interpret("x^(1/(n+1))").is_err());
+    assert!(interpret("(-2)^x").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn exponent_bound_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("x^1000000")?, "x^1000000");
+    assert_eq!(interpret("(x)^100000")?, "x^100000");
+    assert_eq!(interpret("(xy)^100000")?, "x^100000y^100000");
+    assert_eq!(interpret("(x^2)^70000")?, "x^140000");
+    assert_eq!(interpret("(-x)^100001")?, "-x^100001");
+    assert!(interpret("(2x)^100000").is_err());
+    assert!(interpret("(x+1)^999")?.starts_with("x^999 + 999x^998"));
+    // 1771 terms with small coefficients
+    assert!(interpret("(x+y+z+1)^20")?.starts_with("x^20 + 20x^19y + 20x^19z + 20x^19"));
+
+    // expansions past the bound are overflows rather than hangs or saturated exponents
+    for input in [
+        "(x+1)^1000000",
+        "(x+1)^-1000000",
+        "(x+y+z)^1000",
+        "2^100000",
+        "x^(10^10)",
+        "sqrt(2)^(10^9)",
+        "sqrt(3)^(10^6)",
+    ] {
+        assert_eq!(interpret(input).unwrap_err().code(), "E401", "{}", input);
+    }
+    // fractions too fine for the float an exponent is stored as are refused, not rounded
+    assert_eq!(interpret("x^(1/1000000)")?, "x^(1/1000000)");
+    // a fractional power raised again stays the fraction it stands for
+    assert_eq!(interpret("(x^(9^(-3)))^(-3)")?, "1/x^(1/243)");
+    assert_eq!(interpret("x^(1/1000001)").unwrap_err().code(), "E501");
+    assert_eq!(interpret("(x^2)^(1/1000001)")?, "(x^2)^(1/1000001)");
+
+    Ok(())
+}
+
+#[test]
+fn parser_exponent_test() -> Result<(), InterpreterError> {
+    let ast = parse(lex("e^(-x^2)")?)?;
+
+    assert_eq!(
+        ast,
+        parser::ASTNode::Expression(vec![parser::ASTNode::Term(
+            Box::new(parser::ASTNode::Number(1.0)),
+            vec![parser::ASTNode::Variable(
+                "e".to_string(),
+                Box::new(parser::ASTNode::Expression(vec![parser::ASTNode::Term(
+                    Box::new(parser::ASTNode::Number(-1.0)),
+                    vec![parser::ASTNode::Variable(
+                        "x".to_string(),
+                        Box::new(parser::ASTNode::Number(2.0))
+                    )]
+                )]))
+            )]
+        )])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn radical_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("sqrt(12)")?, "2sqrt(3)");
+    assert_eq!(interpret("sqrt(2)sqrt(8)")?, "4");
+    assert_eq!(interpret("sqrt(2) + sqrt(8)")?, "3sqrt(2)");
+    assert_eq!(interpret("sqrt(2)^3")?, "2sqrt(2)");
+    assert_eq!(interpret("(1 + sqrt(2))^2")?, "2sqrt(2) + 3");
+    assert_eq!(interpret("root(24, 3)")?, "2root(3, 3)");
+    assert_eq!(interpret("root(4, 4)")?, "sqrt(2)");
+    assert_eq!(interpret("root(-8, 3)")?, "-2");
+    // odd roots of negative numbers are real, square roots principal, roots before powers
+    assert_eq!(interpret("(-8)^(1/3)")?, "-2");
+    assert_eq!(interpret("(-8)^(2/3)")?, "4");
+    assert_eq!(interpret("sqrt(-4)")?, "2i");
+    assert_eq!(interpret("(-4)^(3/2)")?, "-8i");
+    assert_eq!(interpret("(-4)^(-1/2)")?, "-(1/2)i");
+    assert_eq!(interpret("12^(1/2)")?, "2sqrt(3)");
+    assert_eq!(interpret("sqrt(4x^2)")?, "2sqrt(x^2)");
+    assert_eq!(interpret("1^(1/1953125)")?, "1");
+    // 5^-9 is too fine a fraction to keep exactly as the exponent of a variable
+    assert_eq!(interpret("x^(5^-9)").unwrap_err().code(), "E501");
+
+    assert!(interpret("root(-16, 4)").is_err());
+    assert!(interpret("root(8, x)").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn radical_denominator_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("1/sqrt(2)")?, "sqrt(2)/2");
+    assert_eq!(interpret("sqrt(1/2)")?, "sqrt(2)/2");
+    assert_eq!(interpret("x/sqrt(3)")?, "sqrt(3)*x/3");
+    assert_eq!(interpret("1/(1 + sqrt(2))")?, "sqrt(2) - 1");
+    // two square roots take the conjugate, two cube roots the sum of cubes
+    assert_eq!(interpret("1/(sqrt(2) + sqrt(3))")?, "-sqrt(2) + sqrt(3)");
+    assert_eq!(interpret("1/(sqrt(2) + i)")?, "(-i + sqrt(2))/3");
+    assert_eq!(
+        interpret("1/(1 + root(2, 3))")?,
+        "(-root(2, 3) + root(4, 3) + 1)/3"
+    );
+    assert_eq!(
+        interpret("1/(root(2, 3) - root(3, 3))")?,
+        "-root(4, 3) - root(6, 3) - root(9, 3)"
+    );
+    // past the largest common index the denominator is kept
+    assert_eq!(interpret("1/(1 + root(2, 13))")?, "1/(root(2, 13) + 1)");
+
+    Ok(())
+}
+
+#[test]
+fn radical_denesting_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("sqrt(3 + 2sqrt(2))")?, "sqrt(2) + 1");
+    assert_eq!(interpret("sqrt(5 - 2sqrt(6))")?, "-sqrt(2) + sqrt(3)");
+    // no rational denesting exists, so the root stays whole
+    assert_eq!(interpret("sqrt(2 + sqrt(2))")?, "sqrt(sqrt(2) + 2)");
+    assert_eq!(interpret("sqrt(2 + sqrt(2))^2")?, "sqrt(2) + 2");
+
+    Ok(())
+}
+
+#[test]
+fn complex_arithmetic_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("i^2")?, "-1");
+    assert_eq!(interpret("(1+i)^2")?, "2i");
+    assert_eq!(interpret("(2+3i)(2-3i)")?, "13");
+    assert_eq!(interpret("(x+i)(x-i)")?, "x^2 + 1");
+    assert_eq!(interpret("(3+4i)/(1-2i)")?, "2i - 1");
+    assert_eq!(interpret("1/(1+i)")?, "(-i + 1)/2");
+    assert_eq!(interpret("sqrt(-4)")?, "2i");
+    assert_eq!(interpret("sqrt(-2)")?, "i*sqrt(2)");
+    assert_eq!(interpret("sqrt(-8)*sqrt(-2)")?, "-4");
+
+    Ok(())
+}
+
+#[test]
+fn complex_function_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("conj(2+3i)")?, "-3i + 2");
+    assert_eq!(interpret("conj(x + ix)")?, "-ix + x");
+    assert_eq!(interpret("abs(3+4i)")?, "5");
+    assert_eq!(interpret("abs(1+i)")?, "sqrt(2)");
+    assert_eq!(interpret("arg(-1)")?, "pi");
+    assert_eq!(interpret("arg(1+2i)")?, "arg(2i + 1)");
+    assert_eq!(interpret("arg(1+i)")?, "pi/4");
+    assert_eq!(interpret("arg(-2+2i)")?, "3pi/4");
+    assert_eq!(interpret("arg(-1-i)")?, "-3pi/4");
+    assert_eq!(interpret("arg(-3i)")?, "-pi/2");
+    assert_eq!(interpret("exp(i pi)")?, "-1");
+    assert_eq!(interpret("e^(i pi/2)")?, "i");
+    assert_eq!(interpret("exp(x + 2i pi)")?, "e^x");
+    assert_eq!(interpret("exp(i pi/4)")?, "(i*sqrt(2) + sqrt(2))/2");
+
+    assert_eq!(interpret("abs(-x)")?, "abs(x)");
+    assert!(interpret("arg(0)").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn imaginary_unit_config_test() -> Result<(), InterpreterError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_imaginary_unit(Some("j"));
+    assert_eq!(interpret_with(&mut interpreter, "j^2")?, "-1");
+    assert_eq!(interpret_with(&mut interpreter, "i^2")?, "i^2");
+    assert_eq!(interpret_with(&mut interpreter, "sqrt(-9)")?, "3j");
+
+    // another interpreter on the same thread keeps the default unit
+    let mut other = Interpreter::new();
+    assert_eq!(interpret_with(&mut other, "i^2")?, "-1");
+    assert_eq!(interpret_with(&mut interpreter, "j^2")?, "-1");
+
+    interpreter.set_imaginary_unit(None);
+    assert!(interpret_with(&mut interpreter, "sqrt(-9)").is_err());
+    assert!(interpret_with(&mut interpreter, "ln(-1)").is_err());
+    assert_eq!(interpret_with(&mut other, "ln(-1)")?, "i*pi");
+    assert_eq!(interpret_with(&mut other, "sqrt(-9)")?, "3i");
+
+    Ok(())
+}
+
+#[test]
+fn symbol_table_test() -> Result<(), InterpreterError> {
+    // each interpreter interns into a table of its own, left out of the thread's
+    let mut first = Interpreter::new();
+    let mut second = Interpreter::new();
+    assert_eq!(interpret_with(&mut first, "b + a")?, "a + b");
+    assert_eq!(interpret_with(&mut second, "c^2 + a")?, "a + c^2");
+    assert!(first.symbols.lookup("c").is_none());
+    assert!(second.symbols.lookup("b").is_none());
+    assert!(Symbol::lookup("a").is_none());
+
+    // terms are ordered by name, not by when a variable was first seen
+    assert_eq!(interpret_with(&mut first, "z + y + b")?, "b + y + z");
+    assert_eq!(interpret_with(&mut first, "sqrt(x+1)^2 + z")?, "x + z + 1");
+    assert_eq!(interpret_with(&mut second, "sqrt(x+1)")?, "sqrt(x + 1)");
+
+    Ok(())
+}
+
+#[test]
+fn trig_special_value_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("sin(pi/6)")?, "1/2");
+    assert_eq!(interpret("cos(pi/4)")?, "sqrt(2)/2");
+    assert_eq!(interpret("tan(pi/3)")?, "sqrt(3)");
+    assert_eq!(interpret("sin(7pi/6)")?, "-1/2");
+    assert_eq!(interpret("sec(pi/3)")?, "2");
+    assert_eq!(interpret("cos(0)")?, "1");
+    assert_eq!(
+        interpret("tan(pi/2)").unwrap_err().message(),
+        "tan is undefined at pi/2"
+    );
+    assert_eq!(
+        interpret("sec(3pi/2)").unwrap_err().message(),
+        "sec is undefined at 3pi/2"
+    );
+    assert_eq!(interpret("asin(1/2)")?, "pi/6");
+    assert_eq!(interpret("acos(-1/2)")?, "2pi/3");
+    assert_eq!(interpret("atan(sqrt(3))")?, "pi/3");
+    assert!(interpret("acos(2)").is_err());
+    assert_eq!(interpret("sin(pi/12)")?, "sin(pi/12)");
+    Ok(())
+}
+
+#[test]
+fn trig_reduction_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("sin(-x)")?, "-sin(x)");
+    assert_eq!(interpret("cos(-x)")?, "cos(x)");
+    assert_eq!(interpret("sin(x + 2pi)")?, "sin(x)");
+    assert_eq!(interpret("cos(x + pi)")?, "-cos(x)");
+    assert_eq!(interpret("tan(x + pi)")?, "tan(x)");
+    assert_eq!(interpret("sin(pi - x)")?, "sin(x)");
+    assert_eq!(interpret("sin(x + pi/2)")?, "cos(x)");
+    assert_eq!(interpret("acos(-x)")?, "-acos(x) + pi");
+    assert_eq!(interpret("sin(x/2)")?, "sin(x/2)");
+    assert_eq!(interpret("pow(x, 3)")?, "x^3");
+    Ok(())
+}
+
+#[test]
+fn trig_identity_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("simplify_trig(sin(x)^2 + cos(x)^2)")?, "1");
+    assert_eq!(interpret("simplify_trig(sec(x)^2 - tan(x)^2)")?, "1");
+    assert_eq!(interpret("simplify_trig(tan(x)cos(x))")?, "sin(x)");
+    assert_eq!(interpret("simplify_trig((1 - cos(x)^2)/sin(x))")?, "sin(x)");
+    assert_eq!(interpret("expand_trig(sin(2x))")?, "2cos(x)*sin(x)");
+    assert_eq!(
+        interpret("expand_trig(sin(2x) + xy)")?,
+        "2cos(x)*sin(x) + x*y"
+    );
+    assert_eq!(
+        interpret("expand_trig(cos(x + y))")?,
+        "cos(x)*cos(y) - sin(x)*sin(y)"
+    );
+    assert_eq!(
+        interpret("simplify_trig(expand_trig(cos(2x)) + 2sin(x)^2)")?,
+        "1"
+    );
+    Ok(())
+}
+
+#[test]
+fn exp_log_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("ln(e^3)")?, "3");
+    assert_eq!(interpret("ln(1)")?, "0");
+    assert_eq!(interpret("exp(1)")?, "e");
+    assert_eq!(interpret("exp(x)exp(y)")?, "e^(x + y)");
+    assert_eq!(interpret("e^(ln(x))")?, "x");
+    assert_eq!(interpret("exp(2ln(x))")?, "x^2");
+    assert_eq!(interpret("log(8, 2)")?, "3");
+    assert_eq!(interpret("log(4, 8)")?, "2/3");
+    assert_eq!(interpret("log10(1000)")?, "3");
+    assert_eq!(interpret("log(x, 2)")?, "ln(x)/ln(2)");
+    assert!(interpret("ln(0)").is_err());
+    assert!(interpret("log(x, 1)").is_err());
+
+    // ln(e^z) = z only on the principal branch, so x has to be known real
+    assert_eq!(interpret("ln(exp(x))")?, "ln(e^x)");
+    assert_eq!(interpret("log(2^x, 2)")?, "ln(2^x)/ln(2)");
+    assert_eq!(interpret("ln(exp(2pi*i))")?, "0");
+    assert_eq!(interpret("ln(exp(3pi*i))")?, "i*pi");
+    assert_eq!(interpret("ln(exp(-pi*i))")?, "i*pi");
+    let mut real = Interpreter::new();
+    interpret_with(&mut real, "assume x real")?;
+    assert_eq!(interpret_with(&mut real, "ln(exp(x))")?, "x");
+    assert_eq!(interpret_with(&mut real, "log(2^x, 2)")?, "x");
+    assert_eq!(interpret_with(&mut real, "ln(e^(x + 4pi*i))")?, "x");
+    Ok(())
+}
+
+#[test]
+fn log_rules_test() -> Result<(), InterpreterError> {
+    // x and y may be negative, so ln(xy) is only split when forced
+    assert_eq!(interpret("expand_log(ln(xy))")?, "ln(xy)");
+    assert_eq!(interpret("expand_log(ln(xy), force)")?, "ln(x) + ln(y)");
+    assert_eq!(interpret("expand_log(ln(x^2y), force)")?, "2ln(x) + ln(y)");
+    assert_eq!(interpret("expand_log(ln(12))")?, "2ln(2) + ln(3)");
+    assert_eq!(interpret("expand_log(ln(e^2x))")?, "ln(x) + 2");
+    // likewise only logarithms of positive arguments are combined, ln(-1) + ln(-1) being
+    // 2i·pi rather than ln(1)
+    assert_eq!(interpret("contract_log(ln(x) + ln(y))")?, "ln(x) + ln(y)");
+    assert_eq!(interpret("contract_log(2ln(x))")?, "2ln(x)");
+    assert_eq!(interpret("contract_log(ln(x) + ln(y), force)")?, "ln(xy)");
+    assert_eq!(
+        interpret("contract_log(2ln(x) + ln(y), force)")?,
+        "ln(x^2y)"
+    );
+    assert_eq!(interpret("contract_log(ln(x) - ln(2), force)")?, "ln(x/2)");
+    assert_eq!(interpret("contract_log(ln(2) + ln(4))")?, "ln(8)");
+    let mut positive = Interpreter::new();
+    interpret_with(&mut positive, "assume x > 0")?;
+    assert_eq!(
+        interpret_with(&mut positive, "contract_log(ln(x) - ln(2))")?,
+        "ln(x/2)"
+    );
+    assert_eq!(
+        interpret_with(&mut positive, "contract_log(ln(x) + ln(y))")?,
+        "ln(x) + ln(y)"
+    );
+    Ok(())
+}
+
+#[test]
+fn assumptions_test() -> Result<(), InterpreterError> {
+    let mut positive = Interpreter::new();
+    assert_eq!(
+        interpret_with(&mut positive, "assume x, y > 0")?,
+        "x, y > 0"
+    );
+    assert_eq!(interpret_with(&mut positive, "sqrt(x^2)")?, "x");
+    assert_eq!(interpret_with(&mut positive, "abs(x)")?, "x");
+    assert_eq!(interpret_with(&mut positive, "abs(-2x - 1)")?, "2x + 1");
+    assert_eq!(
+        interpret_with(&mut positive, "expand_log(ln(xy))")?,
+        "ln(x) + ln(y)"
+    );
+    assert_eq!(interpret_with(&mut positive, "x^2 = 4")?, "{x = 2}");
+    assert_eq!(interpret_with(&mut positive, "sqrt(xy)")?, "sqrt(x)sqrt(y)");
+    assert_eq!(interpret_with(&mut positive, "sqrt(-x)")?, "isqrt(x)");
+    assert_eq!(
+        interpret_with(&mut positive, "sqrt(-2x)")?,
+        "i*sqrt(2)*sqrt(x)"
+    );
+
+    let mut negative = Interpreter::new();
+    assert_eq!(interpret_with(&mut negative, "assume x < 0")?, "x < 0");
+    assert_eq!(interpret_with(&mut negative, "sqrt(x^2)")?, "-x");
+    assert_eq!(interpret_with(&mut negative, "abs(x^3)")?, "-x^3");
+    assert_eq!(interpret_with(&mut negative, "2x = 4")?, "No solution");
+    assert!(interpret_with(&mut negative, "assume x > 0").is_err());
+
+    let mut integer = Interpreter::new();
+    assert_eq!(
+        interpret_with(&mut integer, "assume n integer")?,
+        "n integer"
+    );
+    assert_eq!(interpret_with(&mut integer, "sin(x + 2n pi)")?, "sin(x)");
+    assert_eq!(interpret_with(&mut integer, "sin(n pi)")?, "0");
+    assert_eq!(interpret_with(&mut integer, "tan(x + n pi)")?, "tan(x)");
+    assert_eq!(
+        interpret_with(&mut integer, "cos(x + n pi)")?,
+        "cos(n*pi + x)"
+    );
+
+    let mut real = Interpreter::new();
+    assert_eq!(interpret_with(&mut real, "assume x real")?, "x real");
+    assert_eq!(interpret_with(&mut real, "sqrt(x^2)")?, "abs(x)");
+    assert_eq!(interpret_with(&mut real, "abs(-x^2)")?, "x^2");
+    assert_eq!(
+        interpret_with(&mut real, "expand_log(ln(x^2))")?,
+        "2ln(abs(x))"
+    );
+
+    // x may be complex, where sqrt(x^2) is x or -x
+    assert_eq!(interpret("sqrt(x^2)")?, "sqrt(x^2)");
+    assert_eq!(interpret("abs(x^2)")?, "abs(x^2)");
+    // a root only splits over a product when at most one factor may be negative or complex
+    assert_eq!(interpret("sqrt(x*y)")?, "sqrt(xy)");
+    assert_eq!(interpret("(xy)^(1/2)")?, "sqrt(xy)");
+    assert_eq!(interpret("sqrt(-x)")?, "sqrt(-x)");
+    assert_eq!(interpret("sqrt(-2x)")?, "sqrt(-2x)");
+    assert_eq!(interpret("sqrt(4x)")?, "2sqrt(x)");
+    assert!(interpret("assume x > 1").is_err());
+    assert!(interpret("assume x >= 0").is_err());
+    Ok(())
+}
+
+#[test]
+fn latex_output_test() -> Result<(), InterpreterError> {
+    let mut latex = Interpreter {
+        format: OutputFormat::Latex,
+        ..Interpreter::new()
+    };
+    assert_eq!(interpret_with(&mut latex, "3x^-2")?, "3x^{-2}");
+    assert_eq!(
+        interpret_with(&mut latex, "2x^2 - 3x - 1")?,
+        "2x^2 - 3x - 1"
+    );
+    assert_eq!(
+        interpret_with(&mut latex, "(x + 1)/(x - 1)")?,
+        "\\frac{x + 1}{x - 1}"
+    );
+    assert_eq!(interpret_with(&mut latex, "-1/(2x)")?, "-\\frac{1}{2x}");
+    assert_eq!(interpret_with(&mut latex, "root(16, 3)")?, "2\\sqrt[3]{2}");
+    assert_eq!(interpret_with(&mut latex, "sqrt(x + 1)")?, "\\sqrt{x + 1}");
+    assert_eq!(interpret_with(&mut latex, "x^(1/2)")?, "\\sqrt{x}");
+    assert_eq!(interpret_with(&mut latex, "2x^(1/3)")?, "2\\sqrt[3]{x}");
+    assert_eq!(
+        interpret_with(&mut latex, "sin(x)^2 + 2pi x")?,
+        "\\sin^2\\left(x\\right) + 2\\pi x"
+    );
+    assert_eq!(interpret_with(&mut latex, "abs(x)")?, "\\left|x\\right|");
+    assert_eq!(interpret_with(&mut latex, "e^(x + 1)")?, "e^{x + 1}");
+    assert_eq!(
+        interpret_with(&mut latex, "factor(2x^3 + 4x^2 + 2x)")?,
+        "2x\\left(x + 1\\right)^2"
+    );
+    assert_eq!(
+        interpret_with(&mut latex, "apart(1/(x^2 - 1), x)")?,
+        "\\frac{1}{2\\left(x - 1\\right)} - \\frac{1}{2\\left(x + 1\\right)}"
+    );
+    assert_eq!(
+        interpret_with(&mut latex, "{x + y = 3}")?,
+        "x = -y + 3, y\\text{ free}"
+    );
+    assert_eq!(
+        interpret_with(&mut latex, "{x = 1, x = 2}")?,
+        "\\text{No solution}"
+    );
+    assert_eq!(
+        interpret_with(&mut latex, "{x^2 = 4}")?,
+        "\\left\\{x = -2\\right\\}, \\left\\{x = 2\\right\\}"
+    );
+    assert_eq!(
+        interpret_with(&mut latex, "assume n integer")?,
+        "n \\in \\mathbb{Z}"
+    );
+    Ok(())
+}
+
+#[test]
+fn latex_ast_test() -> Result<(), InterpreterError> {
+    let latex =
+        |input: &str| -> Result<String, InterpreterError> { Ok(latex::ast(&parse(lex(input)?)?)) };
+    assert_eq!(latex("(x^2 + 1)/2")?, "\\frac{x^2 + 1}{2}");
+    assert_eq!(latex("3x^-2")?, "3x^{-2}");
+    assert_eq!(latex("2(x + 1)^10")?, "2\\left(x + 1\\right)^{10}");
+    assert_eq!(latex("x*2^x")?, "x \\cdot 2^x");
+    assert_eq!(
+        latex("sqrt(x + 1) - root(x, 3)")?,
+        "\\sqrt{x + 1} - \\sqrt[3]{x}"
+    );
+    assert_eq!(latex("abs(x - 1)")?, "\\left|x - 1\\right|");
+    assert_eq!(
+        latex("log(x, 2) + sin(x)^2")?,
+        "\\log_2\\left(x\\right) + \\sin^2\\left(x\\right)"
+    );
+    assert_eq!(latex("pi x = 1")?, "\\pi x = 1");
+    assert_eq!(
+        latex("{x + y = 1, x - y = 0}")?,
+        "\\begin{cases} x + y = 1 \\\\ x - y = 0 \\end{cases}"
+    );
+    Ok(())
+}
+
+#[test]
+fn latex_input_test() -> Result<(), InterpreterError> {
+    let mut interpreter = Interpreter::new();
+    for (latex, plain) in [
+        ("\\frac{x^2+1}{2}", "(x^2+1)/2"),
+        ("\\sqrt[3]{x} + \\sqrt{2}", "root(x, 3) + sqrt(2)"),
+        ("3x^{10} \\cdot y", "3x^10*y"),
+        ("\\left(x + 1\\right)^{2} - \\pi", "(x + 1)^2 - pi"),
+        ("\\frac{1}{2}\\sin\\left(x\\right)", "(1/2)sin(x)"),
+    ] {
+        let tokens = interpreter.lex_latex(latex)?;
+        let from_latex = interpreter.parse(tokens)?;
+        assert_eq!(from_latex, parse(lex(plain)?)?, "{}", latex);
+    }
+
+    // LaTeX output reads back in to the same value
+    for input in [
+        "3x^-2 + 1/2",
+        "(x + 1)/(x - 1)",
+        "sqrt(8)sin(x)^2",
+        "2^(xy)*z",
+        "x^(ab)",
+    ] {
+        let mut latex_interpreter = Interpreter::new();
+        latex_interpreter.format = OutputFormat::Latex;
+        let latex = latex_interpreter.interpret(parse(lex(input)?)?)?;
+        let tokens = interpreter.lex_latex(&latex)?;
+        let reread = interpreter.parse(tokens)?;
+        assert_eq!(
+            interpreter.interpret(reread)?,
+            interpret(input)?,
+            "{}",
+            latex
+        );
+    }
+
+    let error = interpreter.lex_latex("\\frac{1}{2} + \\infty").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "InterpreterError: Unknown LaTeX command \\infty"
+    );
+    assert_eq!(error.span().map(|span| span.start), Some(14));
+    Ok(())
+}
+
+#[test]
+fn mathml_output_test() -> Result<(), InterpreterError> {
+    let math = |body: &str| {
+        format!(
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+            body
+        )
+    };
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::MathML,
+                ..Interpreter::new()
+            },
+            "2x^2 - 3x - 1"
+        )?,
+        math(
+            "<mrow><mn>2</mn><mo>&#x2062;</mo><msup><mi>x</mi><mn>2</mn></msup>\
+              <mo>-</mo><mn>3</mn><mo>&#x2062;</mo><mi>x</mi><mo>-</mo><mn>1</mn></mrow>"
+        )
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::MathML,
+                ..Interpreter::new()
+            },
+            "-1/(2x)"
+        )?,
+        math(
+            "<mo>-</mo><mfrac><mn>1</mn><mrow><mn>2</mn><mo>&#x2062;</mo><mi>x</mi></mrow></mfrac>"
+        )
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::MathML,
+                ..Interpreter::new()
+            },
+            "abs(x)"
+        )?,
+        math("<mrow><mo>|</mo><mi>x</mi><mo>|</mo></mrow>")
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::MathML,
+                ..Interpreter::new()
+            },
+            "x^(1/3)"
+        )?,
+        math("<mroot><mi>x</mi><mn>3</mn></mroot>")
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::ContentMathML,
+                ..Interpreter::new()
+            },
+            "sqrt(x)"
+        )?,
+        math("<apply><root/><ci>x</ci></apply>")
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::MathML,
+                ..Interpreter::new()
+            },
+            "{x = 1, x = 2}"
+        )?,
+        math("<mtext>No solution</mtext>")
+    );
+
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::ContentMathML,
+                ..Interpreter::new()
+            },
+            "-1/(2x)"
+        )?,
+        math(
+            "<apply><divide/><cn type=\"integer\">-1</cn>\
+              <apply><times/><cn type=\"integer\">2</cn><ci>x</ci></apply></apply>"
+        )
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::ContentMathML,
+                ..Interpreter::new()
+            },
+            "pi e^x"
+        )?,
+        math("<apply><times/><apply><power/><exponentiale/><ci>x</ci></apply><pi/></apply>")
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::ContentMathML,
+                ..Interpreter::new()
+            },
+            "factor(2x^3 + 4x^2 + 2x)"
+        )?,
+        math(
+            "<apply><times/><cn type=\"integer\">2</cn><ci>x</ci><apply><power/>\
+              <apply><plus/><ci>x</ci><cn type=\"integer\">1</cn></apply>\
+              <cn type=\"integer\">2</cn></apply></apply>"
+        )
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::ContentMathML,
+                ..Interpreter::new()
+            },
+            "{x^2 = 4}"
+        )?,
+        math(
+            "<list><set><apply><eq/><ci>x</ci><cn type=\"integer\">-2</cn></apply></set>\
+              <set><apply><eq/><ci>x</ci><cn type=\"integer\">2</cn></apply></set></list>"
+        )
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::ContentMathML,
+                ..Interpreter::new()
+            },
+            "assume n integer"
+        )?,
+        math("<apply><in/><ci>n</ci><integers/></apply>")
+    );
+    Ok(())
+}
+
+#[test]
+fn mathml_ast_test() -> Result<(), InterpreterError> {
+    let ast = parse(lex("(x^2 + 1)/2 = sqrt(y)")?)?;
+    assert_eq!(
+        mathml::ast(&ast),
+        "<mrow><mfrac><mrow><msup><mi>x</mi><mn>2</mn></msup><mo>+</mo><mn>1</mn></mrow>\
+         <mn>2</mn></mfrac><mo>=</mo><msqrt><mi>y</mi></msqrt></mrow>"
+    );
+    assert_eq!(
+        content_mathml::ast(&ast),
+        "<apply><eq/><apply><divide/><apply><plus/><apply><power/><ci>x</ci>\
+         <cn type=\"integer\">2</cn></apply><cn type=\"integer\">1</cn></apply>\
+         <cn type=\"integer\">2</cn></apply><apply><root/><ci>y</ci></apply></apply>"
+    );
+    Ok(())
+}
+
+#[test]
+fn pretty_output_test() -> Result<(), InterpreterError> {
+    let mut unicode = Interpreter {
+        format: OutputFormat::Pretty(pretty::Glyphs::Unicode),
+        ..Interpreter::new()
+    };
+    let mut ascii = Interpreter {
+        format: OutputFormat::Pretty(pretty::Glyphs::Ascii),
+        ..Interpreter::new()
+    };
+    assert_eq!(interpret_with(&mut unicode, "3x^-2")?, "3x⁻²");
+    assert_eq!(interpret_with(&mut ascii, "3x^-2")?, "  -2\n3x");
+    assert_eq!(interpret_with(&mut unicode, "-x/(2y)")?, "  x\n- ──\n  2y");
+    assert_eq!(interpret_with(&mut ascii, "-x/(2y)")?, "  x\n- --\n  2y");
+    assert_eq!(
+        interpret_with(&mut unicode, "x^y + sqrt(2)")?,
+        " y    _\nx  + √2"
+    );
+    assert_eq!(
+        interpret_with(&mut unicode, "factor(2x^3 + 4x^2 + 2x)")?,
+        "2x(x + 1)²"
+    );
+    assert_eq!(
+        interpret_with(&mut unicode, "sqrt(x + 1)")?,
+        " _____\n√x + 1"
+    );
+    assert_eq!(interpret_with(&mut unicode, "θ + theta")?, "2θ");
+    assert_eq!(
+        interpret_with(&mut ascii, "apart(x^3/(x^2-1), x)")?,
+        "       1          1\nx + -------- + --------\n    2(x - 1)   2(x + 1)"
+    );
+    assert_eq!(
+        interpret_with(&mut unicode, "{x^2 = 4}")?,
+        "{x = -2}, {x = 2}"
+    );
+    Ok(())
+}
+
+#[test]
+fn canonical_output_test() -> Result<(), InterpreterError> {
+    let mut canonical = Interpreter {
+        format: OutputFormat::Canonical,
+        ..Interpreter::new()
+    };
+    assert_eq!(interpret_with(&mut canonical, "3x^-2")?, "3*x^(-2)");
+    assert_eq!(interpret_with(&mut canonical, "2x^2 + -1")?, "2*x^2 - 1");
+    assert_eq!(interpret_with(&mut canonical, "-x/(2y)")?, "-x/(2*y)");
+    assert_eq!(interpret_with(&mut canonical, "e^x/3")?, "(1/3)*e^(x)");
+    assert_eq!(
+        interpret_with(&mut canonical, "3^40 x")?,
+        "12157665459056928801*x"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "12157665459056928801x/3")?,
+        "4052555153018976267*x"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "factor(2x^3 + 4x^2 + 2x)")?,
+        "2*x*(x + 1)^2"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "apart(x^3/(x^2-1), x)")?,
+        "x + 1/(2*(x - 1)) + 1/(2*(x + 1))"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "{x + y = 1}")?,
+        "{x = -y + 1}"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "{x^2 = 4}")?,
+        "{{x = -2}, {x = 2}}"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "{{x = -2}, {x = 2}}")?,
+        "{{x = -2}, {x = 2}}"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "{x^2 = 2}")?,
+        "{{x = -sqrt(2)}, {x = sqrt(2)}}"
+    );
+    assert_eq!(interpret_with(&mut canonical, "{x = 1, x = 2}")?, "{0 = 1}");
+    assert_eq!(interpret_with(&mut canonical, "{0 = 1}")?, "{0 = 1}");
+    assert_eq!(interpret_with(&mut canonical, "{x = x}")?, "{0 = 0}");
+    assert_eq!(interpret_with(&mut canonical, "{0 = 0}")?, "{0 = 0}");
+    assert_eq!(
+        interpret_with(&mut canonical, "groebner({x^2 + y^2 = 1, x = y})")?,
+        "groebner({x - y = 0, 2*y^2 - 1 = 0})"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "groebner({x - y = 0, 2*y^2 - 1 = 0})")?,
+        "groebner({x - y = 0, 2*y^2 - 1 = 0})"
+    );
+    assert_eq!(
+        interpret_with(&mut canonical, "groebner({x^2 + y = 1, xy = 2}, grlex)")?,
+        "groebner({x^2 + y - 1 = 0, x*y - 2 = 0, 2*x + y^2 - y = 0}, grlex)"
+    );
+    assert_eq!(
+        interpret("{{x = 1}, {x = 2, y = 3}}")?,
+        "{x = 1}, {x = 2, y = 3}"
+    );
+    assert_eq!(interpret("{{x = 1}, {x = 1, x = 2}}")?, "{x = 1}");
+    Ok(())
+}
+
+#[test]
+fn json_output_test() -> Result<(), InterpreterError> {
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::Json,
+                ..Interpreter::new()
+            },
+            "3^40"
+        )?,
+        "{\"version\":1,\"result\":\
+         {\"type\":\"rational\",\"numerator\":12157665459056928801,\"denominator\":1}}"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::Json,
+                ..Interpreter::new()
+            },
+            "x^2 - 1"
+        )?,
+        "{\"version\":1,\"result\":{\"type\":\"sum\",\"terms\":[\
+         {\"type\":\"power\",\"base\":{\"type\":\"variable\",\"name\":\"x\"},\
+         \"exponent\":{\"type\":\"number\",\"value\":2.0}},\
+         {\"type\":\"rational\",\"numerator\":-1,\"denominator\":1}]}}"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::Json,
+                ..Interpreter::new()
+            },
+            "{x + y = 1, x - y = 1}"
+        )?,
+        "{\"version\":1,\"result\":{\"type\":\"list\",\"elements\":[\
+         {\"type\":\"equation\",\"lhs\":{\"type\":\"variable\",\"name\":\"x\"},\
+         \"rhs\":{\"type\":\"rational\",\"numerator\":1,\"denominator\":1}},\
+         {\"type\":\"equation\",\"lhs\":{\"type\":\"variable\",\"name\":\"y\"},\
+         \"rhs\":{\"type\":\"rational\",\"numerator\":0,\"denominator\":1}}]}}"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                format: OutputFormat::Json,
+                ..Interpreter::new()
+            },
+            "x = x"
+        )?,
+        "{\"version\":1,\"result\":{\"type\":\"text\",\"text\":\"Always true\"}}"
+    );
+    let error = interpret_with(
+        &mut Interpreter {
+            format: OutputFormat::Json,
+            ..Interpreter::new()
+        },
+        "factor(x, y)",
+    )
+    .unwrap_err();
+    assert!(json::error(&error)
+        .starts_with("{\"version\":1,\"error\":{\"code\":\"E501\",\"kind\":\"unsupported\","));
+    Ok(())
+}
+
+#[test]
+fn json_ast_test() -> Result<(), InterpreterError> {
+    for input in [
+        "(x^2 + 1)/2 = sqrt(y)",
+        "{x + y = 1, 2x = y}",
+        "assume n integer",
+    ] {
+        let ast = parse(lex(input)?)?;
+        assert_eq!(json::read_ast(&json::ast(&ast))?, ast);
+    }
+    Ok(())
+}
+
+#[test]
+fn pretty_ast_test() -> Result<(), InterpreterError> {
+    let ast = parse(lex("(x^2 + 1)/2 = sqrt(y)")?)?;
+    assert_eq!(
+        pretty::finish(&pretty::ast(&ast, pretty::Glyphs::Unicode)),
+        "x² + 1    _\n────── = √y\n  2"
+    );
+    let ast = parse(lex("root(x + 1, 3)")?)?;
+    assert_eq!(
+        pretty::finish(&pretty::ast(&ast, pretty::Glyphs::Ascii)),
+        "3  _____\n \\/x + 1"
+    );
+    Ok(())
+}
+
+#[test]
+fn number_format_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("2x^2 - 3x - 1")?, "2x^2 - 3x - 1");
+    assert_eq!(interpret("-x - 1")?, "-x - 1");
+    assert_eq!(interpret("4/2")?, "2");
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                numbers: NumberFormat::Fixed(3),
+                ..Interpreter::new()
+            },
+            "x/4 + 1/3"
+        )?,
+        "0.25x + 0.333"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                numbers: NumberFormat::Significant(2),
+                ..Interpreter::new()
+            },
+            "{3x = 1}"
+        )?,
+        "x = 0.33"
+    );
+    assert_eq!(
+        interpret_with(
+            &mut Interpreter {
+                numbers: NumberFormat::Fraction,
+                ..Interpreter::new()
+            },
+            "x/4 + 1/3"
+        )?,
+        "(3x + 4)/12"
+    );
+
+    // terms rounding to 0 are left out, and integers are rounded to significant figures
+    let mut fixed = Interpreter {
+        numbers: NumberFormat::Fixed(2),
+        ..Interpreter::new()
+    };
+    assert_eq!(interpret_with(&mut fixed, "x/3 - y/1000")?, "0.33x");
+    assert_eq!(interpret_with(&mut fixed, "x/1000")?, "0");
+    let mut significant = Interpreter {
+        numbers: NumberFormat::Significant(2),
+        ..Interpreter::new()
+    };
+    assert_eq!(interpret_with(&mut significant, "12345x")?, "12000x");
+    Ok(())
+}
+
+#[test]
+fn decimal_and_bar_test() -> Result<(), InterpreterError> {
+    assert_eq!(interpret("0.5")?, "1/2");
+    assert_eq!(interpret("0.1 + 0.2")?, "3/10");
+    assert_eq!(interpret("2.25x - x 0.25")?, "2x");
+    assert_eq!(interpret("1.05 2")?, "21/10");
+    assert_eq!(interpret("x^1.5")?, "x^(3/2)");
+    assert_eq!(interpret("|x - 1|^2")?, "abs(x - 1)^2");
+    assert_eq!(interpret("|-3| + 2|x||y|")?, "2abs(x)*abs(y) + 3");
+    assert_eq!(interpret("||x| - 1|")?, "abs(abs(x) - 1)");
+
+    Ok(())
+}
+
+#[test]
+fn error_kinds_test() {
+    let error = |input: &str| {
+        let error = interpret(input).unwrap_err();
+        (error.code(), error.span().map(|s| (s.start, s.end)))
+    };
+    assert_eq!(error("x $ 1"), ("E101", Some((2, 3))));
+    assert_eq!(error("x = 1 = 2"), ("E201", Some((6, 7))));
+    assert_eq!(error("(x + 1"), ("E202", Some((6, 6))));
+    assert_eq!(error("x + 1)"), ("E202", Some((5, 6))));
+    assert_eq!(error("2x/"), ("E203", Some((3, 3))));
+    assert_eq!(error("x +"), ("E203", Some((3, 3))));
+    assert_eq!(error("x^"), ("E203", Some((2, 2))));
+    assert_eq!(error("+"), ("E203", Some((1, 1))));
+    assert_eq!(error("()"), ("E203", Some((1, 2))));
+    assert_eq!(error("2()"), ("E203", Some((2, 3))));
+    assert_eq!(error("x()"), ("E203", Some((2, 3))));
+    assert_eq!(error("x^()"), ("E203", Some((3, 4))));
+    assert_eq!(error("sin()"), ("E203", Some((4, 5))));
+    assert_eq!(error("sqrt()"), ("E203", Some((5, 6))));
+    assert_eq!(error("factor()"), ("E203", Some((7, 8))));
+    assert_eq!(error("factor(x,)"), ("E203", Some((9, 10))));
+    assert_eq!(error("="), ("E203", Some((0, 1))));
+    assert_eq!(error("x="), ("E203", Some((2, 2))));
+    assert_eq!(error("=1"), ("E203", Some((0, 1))));
+    assert_eq!(error("{}"), ("E203", Some((1, 2))));
+    assert_eq!(error("{x=1,}"), ("E203", Some((5, 6))));
+    assert_eq!(error("1.x"), ("E201", Some((1, 2))));
+    // a piece of a split identifier is pointed at on its own
+    assert_eq!(error("2xsin"), ("E201", Some((2, 5))));
+    assert_eq!(error("ab_ + 1"), ("E201", Some((2, 3))));
+    assert_eq!(error("2^xysin"), ("E201", Some((4, 7))));
+    // inverse reciprocal functions are refused rather than read as a·cot(1)
+    assert_eq!(error("acot(1)"), ("E501", Some((0, 4))));
+    assert_eq!(error("asec(2)"), ("E501", Some((0, 4))));
+    assert_eq!(error("2xacsc(2)"), ("E501", Some((2, 6))));
+    assert_eq!(error("|x - 1"), ("E202", Some((6, 6))));
+    assert_eq!(error("1/0"), ("E301", Some((0, 3))));
+    assert_eq!(error("ln(0)"), ("E302", Some((0, 5))));
+    assert_eq!(error("acos(2)"), ("E302", Some((0, 7))));
+    assert_eq!(error("factor(x, y)"), ("E501", Some((0, 12))));
+
+    let latex = Interpreter::new().lex_latex("\\frac{1}{x").unwrap_err();
+    assert_eq!(latex.code(), "E202");
+    assert_eq!(latex.span(), Some(Span::new(8, 9)));
+    let command = Interpreter::new().lex_latex("x + \\infty").unwrap_err();
+    assert_eq!(command.code(), "E102");
+    assert_eq!(command.span(), Some(Span::new(4, 10)));
+
+    let mut interpreter = Interpreter::new();
+    interpreter.time_limit = Some(std::time::Duration::ZERO);
+    let tokens = interpreter.lex("x^2 - 1").unwrap();
+    let ast = interpreter.parse(tokens).unwrap();
+    assert_eq!(interpreter.interpret(ast).unwrap_err().code(), "E402");
+}
+
+mod round_trip {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::time::{Duration, Instant};
+
+    /// Expressions as they might be typed: small integers, a few variables, radicals and
+    /// function calls, combined with every operator the parser reads
+    fn expression() -> impl Strategy<Value = String> {
+        let leaf = prop_oneof![
+            (0..10u32).prop_map(|n| n.to_string()),
+            prop::sample::select(vec!["x", "y", "z", "sqrt(2)", "sqrt(3)", "i", "pi"])
+                .prop_map(str::to_string),
+        ];
+        leaf.prop_recursive(4, 24, 2, |inner| {
+            prop_oneof![
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}) + ({})", a, b)),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({}) - ({})", a, b)),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})*({})", a, b)),
+                (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})/({})", a, b)),
+                (inner.clone(), -3..4i32).prop_map(|(a, n)| format!("({})^({})", a, n)),
+                (prop::sample::select(vec!["x", "y", "2"]), inner.clone())
+                    .prop_map(|(a, b)| format!("{}^({})", a, b)),
+                (
+                    prop::sample::select(vec!["sin", "cos", "abs", "exp"]),
+                    inner
+                )
+                    .prop_map(|(f, a)| format!("{}({})", f, a)),
+            ]
+        })
+    }
+
+    /// Equations and systems of them in x and y with small integer coefficients, linear or
+    /// quadratic so that they can be solved exactly, with constants on the right
+    fn system() -> impl Strategy<Value = String> {
+        let coefficient = -3..4i32;
+        let constant = prop::sample::select(vec!["0", "1", "-2", "sqrt(2)", "pi"]);
+        let equation = prop_oneof![
+            (coefficient.clone(), coefficient.clone(), constant.clone())
+                .prop_map(|(a, b, c)| format!("({})x + ({})y = {}", a, b, c)),
+            (
+                coefficient.clone(),
+                coefficient.clone(),
+                coefficient.clone()
+            )
+                .prop_map(|(a, b, c)| format!("({})x^2 + ({})x = {}", a, b, c)),
+            (coefficient.clone(), coefficient, constant)
+                .prop_map(|(a, b, c)| format!("x^2 + ({})y^2 + ({})xy = {}", a, b, c)),
+        ];
+        prop_oneof![
+            equation.clone(),
+            prop::collection::vec(equation, 1..3)
+                .prop_map(|equations| format!("{{{}}}", equations.join(", "))),
+        ]
+    }
+
+    /// The value of an input, evaluated in the symbol table the interpreter has entered
+    fn value(
+        interpreter: &mut Interpreter,
+        input: &str,
+    ) -> Result<RationalFunction, InterpreterError> {
+        let tokens = interpreter.lex(input)?;
+        let ast = interpreter.parse(tokens)?;
+        interpreter
+            .deadline
+            .set(interpreter.time_limit.map(|limit| Instant::now() + limit));
+        Ok(interpreter.evaluate(&ast)?.reduce())
+    }
+
+    /// Run on a thread of its own, failing if the engine panics or is still going after
+    /// a few seconds
+    fn in_time<T: Send + 'static>(
+        input: &str,
+        run: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, String> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || sender.send(run()));
+        receiver
+            .recv_timeout(Duration::from_secs(5))
+            .map_err(|e| match e {
+                RecvTimeoutError::Timeout => format!("{} still running after 5s", input),
+                RecvTimeoutError::Disconnected => format!("{} panicked", input),
+            })
+    }
+
+    proptest! {
+        // at most one input in four may be one the engine cannot evaluate
+        #![proptest_config(ProptestConfig {
+            cases: 256,
+            max_global_rejects: 64,
+            ..ProptestConfig::default()
+        })]
+
+        #[test]
+        fn canonical_output_reads_back(input in expression()) {
+            let checked = in_time(&input.clone(), move || -> Result<bool, String> {
+                let mut interpreter = Interpreter::new();
+                interpreter.format = OutputFormat::Canonical;
+                interpreter.time_limit = Some(Duration::from_secs(2));
+                // inputs the engine cannot evaluate, such as a division by zero, a
+                // coefficient overflowing or a simplification that runs out of time,
+                // give no output to read back
+                let output = match interpret_with(&mut interpreter, &input) {
+                    Ok(output) => output,
+                    Err(_) => return Ok(false),
+                };
+                interpreter.with_symbols(|interpreter| {
+                    let expected = match value(interpreter, &input) {
+                        Ok(expected) => expected,
+                        Err(_) => return Ok(false),
+                    };
+                    let read_back = value(interpreter, &output)
+                        .map_err(|e| format!("{} from {}: {}", output, input, e))?;
+                    // equal as fractions, whatever common factor each was left with
+                    let expected_cross = &expected.numerator * &read_back.denominator;
+                    let read_back_cross = &read_back.numerator * &expected.denominator;
+                    match expected_cross == read_back_cross {
+                        true => Ok(true),
+                        false => Err(format!(
+                            "{} from {} reads back as {}/({}), not {}/({})",
+                            output,
+                            input,
+                            read_back.numerator,
+                            read_back.denominator,
+                            expected.numerator,
+                            expected.denominator
+                        )),
+                    }
+                })
+            });
+            match checked.and_then(|result| result) {
+                Ok(evaluated) => prop_assume!(evaluated),
+                Err(message) => return Err(TestCaseError::fail(message)),
+            }
+        }
+
+        #[test]
+        fn canonical_solutions_read_back(input in system()) {
+            let checked = in_time(&input.clone(), move || -> Result<bool, String> {
+                let mut interpreter = Interpreter::new();
+                interpreter.format = OutputFormat::Canonical;
+                interpreter.time_limit = Some(Duration::from_secs(2));
+                // systems the solver cannot take, such as a quartic, give nothing to read
+                // back
+                let output = match interpret_with(&mut interpreter, &input) {
+                    Ok(output) => output,
+                    Err(_) => return Ok(false),
+                };
+                // a solution set reads back as the system of its branches, solved by them,
+                // and No solution and Always true as systems with that result
+                let read_back = interpret_with(&mut interpreter, &output)
+                    .map_err(|e| format!("{} from {}: {}", output, input, e))?;
+                match read_back == output {
+                    true => Ok(true),
+                    false => Err(format!(
+                        "{} from {} reads back as {}",
+                        output, input, read_back
+                    )),
+                }
+            });
+            match checked.and_then(|result| result) {
+                Ok(solved) => prop_assume!(solved),
+                Err(message) => return Err(TestCaseError::fail(message)),
+            }
+        }
+    }
+}
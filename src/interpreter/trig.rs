@@ -0,0 +1,511 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive};
+
+use crate::util::rational::Rational;
+
+use super::{
+    assumptions::{Assumptions, Property},
+    monomial::Monomial,
+    monomial_order::MonomialOrder,
+    number_format::NumberFormat,
+    plain::Plain,
+    polynomial::Polynomial,
+    radical,
+    rational_function::RationalFunction,
+    render::Notation,
+    symbol::Symbol,
+    InterpreterError,
+};
+
+/// The trigonometric functions and their inverses, as written in expressions
+pub const FUNCTIONS: [&str; 9] = [
+    "sin", "cos", "tan", "csc", "sec", "cot", "asin", "acos", "atan",
+];
+
+/// Inverses of the reciprocal functions, which are not supported, with the inverse of the
+/// reciprocal argument each one equals, acot(x) being atan(1/x)
+pub const UNSUPPORTED_INVERSES: [(&str, &str); 3] =
+    [("acot", "atan"), ("asec", "acos"), ("acsc", "asin")];
+
+/// The constant pi, kept as a symbol so multiples of it stay exact
+pub const PI: &str = "pi";
+
+/// Apply a trigonometric function to a polynomial argument
+///
+/// Arguments are normalised using parity and periodicity, so sin(-x) is -sin(x) and
+/// cos(x + 2pi) is cos(x). Rational multiples of pi with a denominator of 1, 2, 3, 4 or 6
+/// give exact values, e.g. sin(pi/6) = 1/2, and the inverse functions map those values back.
+/// Anything else is kept as a symbol such as sin(2x). Multiples of pi by variables assumed
+/// to be integers are reduced too, so sin(x + 2n·pi) is sin(x) and sin(n·pi) is 0.
+pub fn apply(
+    name: &str,
+    argument: &Polynomial,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    match name {
+        "asin" | "acos" | "atan" => inverse(name, argument),
+        _ => periodic(name, argument, assumptions),
+    }
+}
+
+/// sin, cos, tan and their reciprocals
+fn periodic(
+    name: &str,
+    argument: &Polynomial,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    let pi = Monomial::variable(PI, 1.0);
+    let turns = argument.coefficient(&pi);
+    let mut rest = argument.clone();
+    rest.add_term(pi.clone(), -&turns);
+
+    // whole periods drop out, as do half periods for tan and cot
+    let half_period = matches!(name, "tan" | "cot");
+    let mut odd_turns = false;
+    for (monomial, coefficient) in argument.terms() {
+        if monomial == &pi || !integer_turns(monomial, assumptions) || !coefficient.is_integer() {
+            continue;
+        }
+        if half_period || coefficient.numerator().is_even() {
+            rest.add_term(monomial.clone(), -coefficient);
+        } else {
+            odd_turns = true;
+        }
+    }
+    // sin(n·pi) = tan(n·pi) = 0, as every remaining term is an integer multiple of pi
+    let only_turns = rest.terms().iter().all(|(monomial, coefficient)| {
+        integer_turns(monomial, assumptions) && coefficient.is_integer()
+    });
+    if odd_turns && only_turns && turns.is_integer() && matches!(name, "sin" | "tan") {
+        return Ok(Polynomial::new().into());
+    }
+    let mut turns = turns;
+    let mut sign = 1.0;
+
+    // sin, tan, csc and cot are odd, cos and sec are even
+    let negative = match rest.sorted_monomials(MonomialOrder::Lex).first() {
+        Some(leading) => rest.coefficient(leading).is_negative(),
+        None => turns < Rational::zero(),
+    };
+    if negative {
+        rest = -&rest;
+        turns = -turns;
+        if matches!(name, "sin" | "tan" | "csc" | "cot") {
+            sign = -sign;
+        }
+    }
+
+    // shifting by pi negates sin and cos and leaves tan alone
+    let whole = turns.floor();
+    turns = turns - Rational::from(whole.clone());
+    if whole.is_odd() && matches!(name, "sin" | "cos" | "csc" | "sec") {
+        sign = -sign;
+    }
+
+    // a quarter turn swaps each function with its cofunction, sin(x + pi/2) = cos(x)
+    let mut name = name;
+    if !rest.is_zero() && turns == Rational::new(1, 2) {
+        let (cofunction, negated) = match name {
+            "sin" => ("cos", false),
+            "cos" => ("sin", true),
+            "csc" => ("sec", false),
+            "sec" => ("csc", true),
+            "tan" => ("cot", true),
+            _ => ("tan", true),
+        };
+        name = cofunction;
+        turns = Rational::zero();
+        if negated {
+            sign = -sign;
+        }
+    }
+
+    let normalised = &rest + &pi_times(&turns);
+    let exact = match rest.is_zero() {
+        true => exact_value(name, &turns, argument)?,
+        false => None,
+    };
+    let value = exact.unwrap_or_else(|| function(name, normalised));
+    Ok(match sign < 0.0 {
+        true => value.negate(),
+        false => value,
+    })
+}
+
+/// Whether a monomial is pi times a product of variables assumed to be integers, such as n·pi
+fn integer_turns(monomial: &Monomial, assumptions: &Assumptions) -> bool {
+    let pi = Symbol::intern(PI);
+    monomial.exponent(pi) == 1.0
+        && monomial.symbols().count() > 1
+        && monomial.symbols().filter(|s| *s != pi).all(|symbol| {
+            let exponent = monomial.exponent(symbol);
+            exponent >= 0.0
+                && exponent.fract() == 0.0
+                && assumptions.ask(&symbol.name(), Property::Integer).is_true()
+        })
+}
+
+/// The exact value at a multiple of pi in [0, 1), if it is one of the special angles
+fn exact_value(
+    name: &str,
+    turns: &Rational,
+    argument: &Polynomial,
+) -> Result<Option<RationalFunction>, InterpreterError> {
+    let half = Rational::new(1, 2);
+    let (Some(sine), Some(cosine)) = (sine(turns)?, sine(&(turns + half))?) else {
+        return Ok(None);
+    };
+    let (numerator, denominator) = match name {
+        "sin" => (sine, Polynomial::constant(1)),
+        "cos" => (cosine, Polynomial::constant(1)),
+        "tan" => (sine, cosine),
+        "csc" => (Polynomial::constant(1), sine),
+        "sec" => (Polynomial::constant(1), cosine),
+        _ => (cosine, sine),
+    };
+    if denominator.is_zero() {
+        // the angle as it was given, written exactly, e.g. 3pi/2
+        let angle = Plain(NumberFormat::Fraction)
+            .fraction(&exact(argument.clone()), MonomialOrder::default());
+        return Err(InterpreterError::domain(format!(
+            "{} is undefined at {}",
+            name, angle
+        )));
+    }
+    let value = exact(numerator).div(&exact(denominator))?;
+    Ok(Some(value.reduce()))
+}
+
+/// sin(c·pi) for the special angles, using sin(pi - x) = sin(x) and sin(x + pi) = -sin(x)
+fn sine(turns: &Rational) -> Result<Option<Polynomial>, InterpreterError> {
+    let whole = turns.floor();
+    let mut c = turns - Rational::from(whole.clone());
+    if c > Rational::new(1, 2) {
+        c = Rational::one() - c;
+    }
+    let value = match (c.numerator().to_i32(), c.denominator().to_i32()) {
+        (Some(0), _) => Polynomial::new(),
+        (Some(1), Some(6)) => Polynomial::constant(Rational::new(1, 2)),
+        (Some(1), Some(4)) => radical::rational_power(Rational::new(1, 2), Rational::new(1, 2))?,
+        (Some(1), Some(3)) => radical::rational_power(Rational::new(3, 4), Rational::new(1, 2))?,
+        (Some(1), Some(2)) => Polynomial::constant(1),
+        _ => return Ok(None),
+    };
+    Ok(Some(match whole.is_odd() {
+        true => -&value,
+        false => value,
+    }))
+}
+
+/// asin, acos and atan
+fn inverse(name: &str, argument: &Polynomial) -> Result<RationalFunction, InterpreterError> {
+    if let Some(value) = constant_value(argument) {
+        if name != "atan" && value.abs() > 1.0 + 1e-12 {
+            return Err(InterpreterError::domain(format!(
+                "{} is undefined outside [-1, 1]",
+                name
+            )));
+        }
+        // the special angles in [0, pi/2] with their sines and tangents
+        let angles = [(0, 1), (1, 6), (1, 4), (1, 3), (1, 2)].map(|(n, d)| Rational::new(n, d));
+        let found = angles.iter().find(|angle| {
+            let theta = angle.to_f64() * std::f64::consts::PI;
+            let expected = match name {
+                "atan" => theta.tan(),
+                _ => theta.sin(),
+            };
+            (value.abs() - expected).abs() < 1e-12
+        });
+        if let Some(angle) = found {
+            let angle = match value < 0.0 {
+                true => -angle,
+                false => angle.clone(),
+            };
+            // acos(x) = pi/2 - asin(x)
+            let turns = match name {
+                "acos" => Rational::new(1, 2) - angle,
+                _ => angle,
+            };
+            return Ok(exact(pi_times(&turns)));
+        }
+    }
+
+    let negative = argument
+        .sorted_monomials(MonomialOrder::Lex)
+        .first()
+        .is_some_and(|leading| argument.coefficient(leading).is_negative());
+    if !negative {
+        return Ok(function(name, argument.clone()));
+    }
+    let value = function(name, -argument);
+    Ok(match name {
+        // acos(-x) = pi - acos(x)
+        "acos" => RationalFunction::from(pi_times(&Rational::one())).sub(&value),
+        _ => value.negate(),
+    })
+}
+
+/// The numeric value of a constant that may contain radicals, e.g. sqrt(3)/2
+fn constant_value(polynomial: &Polynomial) -> Option<f64> {
+    let mut value = 0.0;
+    for (monomial, coefficient) in polynomial.terms() {
+        let mut term = coefficient.to_f64();
+        for symbol in monomial.symbols() {
+            let (radicand, index) = symbol.as_radical()?;
+            term *= (radicand as f64).powf(monomial.exponent(symbol) / index as f64);
+        }
+        value += term;
+    }
+    Some(value)
+}
+
+/// c·pi
+fn pi_times(turns: &Rational) -> Polynomial {
+    Polynomial::variable(PI, 1.0).scale(turns)
+}
+
+/// A polynomial with rational coefficients over their common denominator, e.g. pi/6
+pub(crate) fn exact(polynomial: Polynomial) -> RationalFunction {
+    let denominator = polynomial
+        .terms()
+        .values()
+        .fold(BigInt::one(), |d, c| d.lcm(c.denominator()));
+    RationalFunction {
+        numerator: polynomial.scale(denominator.clone()),
+        denominator: Polynomial::constant(denominator),
+    }
+}
+
+/// The function kept whole as a symbol
+fn function(name: &str, argument: Polynomial) -> RationalFunction {
+    let mut value = Polynomial::new();
+    value.add_term(Monomial::function(name, argument), Rational::one());
+    value.into()
+}
+
+/// Expand sines, cosines and tangents of sums and integer multiples
+///
+/// Uses the angle addition formulas, so sin(2x) is 2sin(x)cos(x) and cos(x + y) is
+/// cos(x)cos(y) - sin(x)sin(y). Reciprocal functions are expanded through their reciprocals.
+pub fn expand_trig(
+    value: &RationalFunction,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    substitute(value, &|symbol| match symbol.as_function() {
+        Some(f) if FUNCTIONS[..6].contains(&f.name.as_str()) => {
+            expand(&f.name, &f.argument, assumptions).map(Some)
+        }
+        _ => Ok(None),
+    })
+}
+
+fn expand(
+    name: &str,
+    argument: &Polynomial,
+    assumptions: &Assumptions,
+) -> Result<RationalFunction, InterpreterError> {
+    let one = RationalFunction::from(Polynomial::constant(1));
+    match name {
+        "csc" => return one.div(&expand("sin", argument, assumptions)?),
+        "sec" => return one.div(&expand("cos", argument, assumptions)?),
+        "cot" => {
+            return expand("cos", argument, assumptions)?.div(&expand(
+                "sin",
+                argument,
+                assumptions,
+            )?)
+        }
+        _ => {}
+    }
+    let Some((u, v)) = split_angle(argument) else {
+        return apply(name, argument, assumptions);
+    };
+    let (sin_u, cos_u) = (
+        expand("sin", &u, assumptions)?,
+        expand("cos", &u, assumptions)?,
+    );
+    let (sin_v, cos_v) = (
+        expand("sin", &v, assumptions)?,
+        expand("cos", &v, assumptions)?,
+    );
+    match name {
+        "sin" => Ok(sin_u.mul(&cos_v).add(&cos_u.mul(&sin_v))),
+        "cos" => Ok(cos_u.mul(&cos_v).sub(&sin_u.mul(&sin_v))),
+        _ => {
+            // tan(u + v) = (tan u + tan v)/(1 - tan u tan v)
+            let (tan_u, tan_v) = (sin_u.div(&cos_u)?, sin_v.div(&cos_v)?);
+            tan_u.add(&tan_v).div(&one.sub(&tan_u.mul(&tan_v)))
+        }
+    }
+}
+
+/// Split an angle into two parts to expand over: the leading term and the rest of a sum,
+/// or x and (k - 1)x for an integer multiple kx
+fn split_angle(argument: &Polynomial) -> Option<(Polynomial, Polynomial)> {
+    let monomials = argument.sorted_monomials(MonomialOrder::Lex);
+    let term = |monomial: &Monomial, coefficient: Rational| {
+        let mut polynomial = Polynomial::new();
+        polynomial.add_term(monomial.clone(), coefficient);
+        polynomial
+    };
+    match monomials.as_slice() {
+        [monomial] => {
+            let k = argument.coefficient(monomial);
+            if !k.is_integer() || k.abs() < 2 {
+                return None;
+            }
+            let unit = match k.is_negative() {
+                true => -Rational::one(),
+                false => Rational::one(),
+            };
+            Some((term(monomial, unit.clone()), term(monomial, k - unit)))
+        }
+        [first, ..] => {
+            let u = term(first, argument.coefficient(first));
+            Some((u.clone(), argument - &u))
+        }
+        [] => None,
+    }
+}
+
+/// Simplify using sin(x)^2 + cos(x)^2 = 1
+///
+/// Tangents and the reciprocal functions are first written in terms of sines and cosines.
+/// Squares of sines are then rewritten as 1 - cos(x)^2, or squares of cosines as
+/// 1 - sin(x)^2, whichever leaves fewer terms, and common factors are cancelled.
+pub fn simplify_trig(value: &RationalFunction) -> Result<RationalFunction, InterpreterError> {
+    let one = RationalFunction::from(Polynomial::constant(1));
+    let value = substitute(value, &|symbol| {
+        let Some(f) = symbol.as_function() else {
+            return Ok(None);
+        };
+        let (sine, cosine) = (
+            function("sin", f.argument.clone()),
+            function("cos", f.argument),
+        );
+        Ok(match f.name.as_str() {
+            "tan" => Some(sine.div(&cosine)?),
+            "csc" => Some(one.div(&sine)?),
+            "sec" => Some(one.div(&cosine)?),
+            "cot" => Some(cosine.div(&sine)?),
+            _ => None,
+        })
+    })?;
+
+    let simplest = |polynomial: &Polynomial| {
+        [
+            polynomial.clone(),
+            pythagorean(polynomial, "sin", "cos"),
+            pythagorean(polynomial, "cos", "sin"),
+        ]
+        .into_iter()
+        .min_by_key(|candidate| candidate.terms().len())
+        .unwrap_or_default()
+    };
+    RationalFunction::new(simplest(&value.numerator), simplest(&value.denominator))
+        .map(RationalFunction::reduce)
+}
+
+/// Rewrite each square of `from(x)` as 1 - `to(x)`^2
+fn pythagorean(polynomial: &Polynomial, from: &str, to: &str) -> Polynomial {
+    let mut result = Polynomial::new();
+    for (monomial, coefficient) in polynomial.terms() {
+        let mut kept = Vec::new();
+        let mut term = Polynomial::constant(coefficient.clone());
+        for (name, exponent) in monomial.powers() {
+            let squares = match Symbol::intern(&name).as_function() {
+                Some(f) if f.name == from && exponent.0.fract() == 0.0 && exponent.0 >= 2.0 => {
+                    let mut other = Polynomial::new();
+                    other.add_term(Monomial::function(to, f.argument), Rational::one());
+                    Some((
+                        exponent.0 as u32 / 2,
+                        &Polynomial::constant(1) - &other.pow(2),
+                    ))
+                }
+                _ => None,
+            };
+            match squares {
+                Some((count, replacement)) => {
+                    term = &term * &replacement.pow(count);
+                    kept.push((name, exponent.0 % 2.0));
+                }
+                None => kept.push((name, exponent.0)),
+            }
+        }
+        let mut rest = Polynomial::new();
+        rest.add_term(Monomial::new(kept), Rational::one());
+        result = &result + &(&term * &rest);
+    }
+    result
+}
+
+/// Replace symbols in a rational function, keeping those the replacement skips
+fn substitute(
+    value: &RationalFunction,
+    replace: &dyn Fn(Symbol) -> Result<Option<RationalFunction>, InterpreterError>,
+) -> Result<RationalFunction, InterpreterError> {
+    let substitute_polynomial = |polynomial: &Polynomial| {
+        let mut total = RationalFunction::from(Polynomial::new());
+        for (monomial, coefficient) in polynomial.terms() {
+            let mut kept = Vec::new();
+            let mut term = RationalFunction::from(Polynomial::constant(coefficient.clone()));
+            for (name, exponent) in monomial.powers() {
+                match replace(Symbol::intern(&name))? {
+                    Some(replacement) if exponent.0.fract() == 0.0 => {
+                        term = term.mul(&replacement.pow(exponent.0 as i64)?);
+                    }
+                    _ => kept.push((name, exponent.0)),
+                }
+            }
+            let mut rest = Polynomial::new();
+            rest.add_term(Monomial::new(kept), Rational::one());
+            total = total.add(&term.mul(&rest.into()));
+        }
+        Ok::<_, InterpreterError>(total)
+    };
+    let numerator = substitute_polynomial(&value.numerator)?;
+    let denominator = substitute_polynomial(&value.denominator)?;
+    numerator.div(&denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn angle(n: i128, d: i128) -> Polynomial {
+        Polynomial::variable(PI, 1.0).scale(Rational::new(n, d))
+    }
+
+    #[test]
+    fn test_special_values() {
+        let assumptions = Assumptions::new();
+        let apply = |name: &str, argument: &Polynomial| apply(name, argument, &assumptions);
+        let half = exact(Polynomial::constant(Rational::new(1, 2)));
+        assert_eq!(apply("sin", &angle(1, 6)).unwrap(), half);
+        assert_eq!(apply("cos", &angle(2, 3)).unwrap(), half.negate());
+        assert_eq!(apply("sin", &angle(7, 6)).unwrap(), half.negate());
+        assert_eq!(
+            apply("tan", &angle(1, 4)).unwrap(),
+            Polynomial::constant(1).into()
+        );
+        assert_eq!(
+            apply("sin", &angle(1, 1)).unwrap(),
+            Polynomial::new().into()
+        );
+        assert!(apply("tan", &angle(1, 2)).is_err());
+        assert_eq!(
+            apply("asin", &Polynomial::constant(Rational::new(-1, 2))).unwrap(),
+            exact(angle(-1, 6))
+        );
+    }
+
+    #[test]
+    fn test_split_angle() {
+        let x = Polynomial::variable("x", 1.0);
+        assert_eq!(split_angle(&x.scale(3)), Some((x.clone(), x.scale(2))));
+        assert_eq!(split_angle(&x), None);
+        let y = Polynomial::variable("y", 1.0);
+        assert_eq!(split_angle(&(&x + &y)), Some((x, y)));
+    }
+}
@@ -0,0 +1,275 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+use crate::util::rational::Rational;
+
+use super::{factor, InterpreterError};
+
+/// Dense polynomial in a single variable with exact rational coefficients
+///
+/// Coefficients are stored lowest degree first and never carry trailing zeros,
+/// so the zero polynomial is the empty vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnivariatePolynomial {
+    coefficients: Vec<Rational>,
+}
+
+impl UnivariatePolynomial {
+    pub fn new(mut coefficients: Vec<Rational>) -> Self {
+        while coefficients.last().is_some_and(|c| c.is_zero()) {
+            coefficients.pop();
+        }
+        UnivariatePolynomial { coefficients }
+    }
+
+    pub fn zero() -> Self {
+        UnivariatePolynomial::new(Vec::new())
+    }
+
+    pub fn constant(c: Rational) -> Self {
+        UnivariatePolynomial::new(vec![c])
+    }
+
+    /// c * x^degree
+    pub fn monomial(c: Rational, degree: usize) -> Self {
+        let mut coefficients = vec![Rational::zero(); degree + 1];
+        coefficients[degree] = c;
+        UnivariatePolynomial::new(coefficients)
+    }
+
+    pub fn from_integers<T: Clone>(coefficients: &[T]) -> Self
+    where
+        Rational: From<T>,
+    {
+        UnivariatePolynomial::new(coefficients.iter().cloned().map(Rational::from).collect())
+    }
+
+    pub fn coefficients(&self) -> &[Rational] {
+        &self.coefficients
+    }
+
+    pub fn coefficient(&self, degree: usize) -> Rational {
+        self.coefficients.get(degree).cloned().unwrap_or_default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// Degree of the polynomial, with the zero polynomial reported as degree 0
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    pub fn leading_coefficient(&self) -> Rational {
+        self.coefficients.last().cloned().unwrap_or_default()
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let len = self.coefficients.len().max(other.coefficients.len());
+        UnivariatePolynomial::new(
+            (0..len)
+                .map(|i| self.coefficient(i) + other.coefficient(i))
+                .collect(),
+        )
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.scale(&-Rational::one()))
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        if self.is_zero() || other.is_zero() {
+            return UnivariatePolynomial::zero();
+        }
+        let mut product =
+            vec![Rational::zero(); self.coefficients.len() + other.coefficients.len()];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                product[i + j] += &(a * b);
+            }
+        }
+        UnivariatePolynomial::new(product)
+    }
+
+    pub fn scale(&self, c: &Rational) -> Self {
+        UnivariatePolynomial::new(self.coefficients.iter().map(|a| a * c).collect())
+    }
+
+    pub fn pow(&self, exponent: usize) -> Self {
+        let mut result = UnivariatePolynomial::constant(Rational::one());
+        for _ in 0..exponent {
+            result = result.mul(self);
+        }
+        result
+    }
+
+    pub fn derivative(&self) -> Self {
+        UnivariatePolynomial::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c * Rational::from(i))
+                .collect(),
+        )
+    }
+
+    /// Euclidean division, returning `(quotient, remainder)`
+    ///
+    /// Panics if `divisor` is the zero polynomial.
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        if divisor.is_zero() {
+            panic!("polynomial division by zero");
+        }
+        let mut remainder = self.coefficients.clone();
+        if remainder.len() < divisor.coefficients.len() {
+            return (UnivariatePolynomial::zero(), self.clone());
+        }
+        let divisor_degree = divisor.degree();
+        let lead = divisor.leading_coefficient();
+        let mut quotient = vec![Rational::zero(); remainder.len() - divisor_degree];
+
+        for i in (0..quotient.len()).rev() {
+            let q = &remainder[i + divisor_degree] / &lead;
+            if q.is_zero() {
+                continue;
+            }
+            for (j, d) in divisor.coefficients.iter().enumerate() {
+                remainder[i + j] -= &(&q * d);
+            }
+            quotient[i] = q;
+        }
+
+        (
+            UnivariatePolynomial::new(quotient),
+            UnivariatePolynomial::new(remainder),
+        )
+    }
+
+    /// Scale so the leading coefficient is 1 (the zero polynomial is returned unchanged)
+    pub fn monic(&self) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+        self.scale(&self.leading_coefficient().recip())
+    }
+
+    /// Monic greatest common divisor
+    ///
+    /// Found from the primitive integer parts modulo primes, see [`factor::integer_gcd`],
+    /// since Euclid's algorithm over the rationals grows coefficients at every step.
+    pub fn gcd(&self, other: &Self) -> Result<Self, InterpreterError> {
+        let (_, a) = self.primitive_part();
+        let (_, b) = other.primitive_part();
+        Ok(UnivariatePolynomial::from_integers(&factor::integer_gcd(&a, &b)?).monic())
+    }
+
+    /// Extended Euclid: returns `(g, s, t)` with `s*self + t*other = g` and `g` monic
+    pub fn extended_gcd(&self, other: &Self) -> (Self, Self, Self) {
+        let one = UnivariatePolynomial::constant(Rational::one());
+        let (mut r0, mut r1) = (self.clone(), other.clone());
+        let (mut s0, mut s1) = (one.clone(), UnivariatePolynomial::zero());
+        let (mut t0, mut t1) = (UnivariatePolynomial::zero(), one);
+
+        while !r1.is_zero() {
+            let (q, r) = r0.div_rem(&r1);
+            (r0, r1) = (r1, r);
+            let s = s0.sub(&q.mul(&s1));
+            (s0, s1) = (s1, s);
+            let t = t0.sub(&q.mul(&t1));
+            (t0, t1) = (t1, t);
+        }
+
+        if r0.is_zero() {
+            return (r0, s0, t0);
+        }
+        let inverse = r0.leading_coefficient().recip();
+        (r0.scale(&inverse), s0.scale(&inverse), t0.scale(&inverse))
+    }
+
+    /// Split into a rational content and a primitive integer polynomial
+    ///
+    /// The integer polynomial has coprime coefficients and a positive leading
+    /// coefficient, so `self = content * primitive`.
+    pub fn primitive_part(&self) -> (Rational, Vec<BigInt>) {
+        if self.is_zero() {
+            return (Rational::zero(), Vec::new());
+        }
+        let denominator = self
+            .coefficients
+            .iter()
+            .fold(BigInt::one(), |acc, c| acc.lcm(c.denominator()));
+        let integers: Vec<BigInt> = self
+            .coefficients
+            .iter()
+            .map(|c| c.numerator() * (&denominator / c.denominator()))
+            .collect();
+        let mut divisor = integers.iter().fold(BigInt::zero(), |acc, c| acc.gcd(c));
+        if self.leading_coefficient().is_negative() {
+            divisor = -divisor;
+        }
+        let primitive = integers.iter().map(|c| c / &divisor).collect();
+        (
+            Rational::from_parts(divisor, denominator).unwrap_or_default(),
+            primitive,
+        )
+    }
+
+    pub fn evaluate(&self, x: &Rational) -> Rational {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Rational::zero(), |acc, c| acc * x + c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_rem() {
+        // x^3 - 1 = (x - 1)(x^2 + x + 1)
+        let a = UnivariatePolynomial::from_integers(&[-1, 0, 0, 1]);
+        let b = UnivariatePolynomial::from_integers(&[-1, 1]);
+        let (q, r) = a.div_rem(&b);
+
+        assert_eq!(q, UnivariatePolynomial::from_integers(&[1, 1, 1]));
+        assert!(r.is_zero());
+    }
+
+    #[test]
+    fn test_gcd() {
+        // (x - 1)(x + 2) and (x - 1)(x + 3)
+        let a = UnivariatePolynomial::from_integers(&[-2, 1, 1]);
+        let b = UnivariatePolynomial::from_integers(&[-3, 2, 1]);
+        let (g, s, t) = a.extended_gcd(&b);
+
+        assert_eq!(g, UnivariatePolynomial::from_integers(&[-1, 1]));
+        assert_eq!(a.gcd(&b).unwrap(), g);
+        assert_eq!(s.mul(&a).add(&t.mul(&b)), g);
+
+        // coprime factors of degree five, where Euclid over the rationals grows large
+        let f = UnivariatePolynomial::from_integers(&[7, 3, 0, 0, 0, 1]);
+        let g = UnivariatePolynomial::from_integers(&[11, -2, 0, 0, 0, 1]);
+        let product = f.mul(&g);
+        let derivative = product.derivative();
+        assert_eq!(
+            product.gcd(&derivative).unwrap(),
+            UnivariatePolynomial::constant(Rational::one())
+        );
+        assert_eq!(product.gcd(&f.mul(&f)).unwrap(), f);
+    }
+
+    #[test]
+    fn test_primitive_part() {
+        // -x/2 - 1 = -1/2 (x + 2)
+        let a = UnivariatePolynomial::new(vec![Rational::from(-1), Rational::new(-1, 2)]);
+        let (content, primitive) = a.primitive_part();
+
+        assert_eq!(content, Rational::new(-1, 2));
+        assert_eq!(primitive, vec![BigInt::from(2), BigInt::one()]);
+    }
+}
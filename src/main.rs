@@ -1,34 +1,152 @@
-pub mod interpreter;
-pub mod util;
+use std::io::BufRead;
+use std::time::Instant;
 
-use crate::interpreter::Interpreter;
+use cas_app::interpreter::{
+    json::{Evaluation, Failure, Stage, VERSION},
+    number_format::NumberFormat,
+    parser::ASTNode,
+    Interpreter, OutputFormat,
+};
+
+const USAGE: &str = "Usage: cas-app [--format plain|json] \
+[--numbers shortest|fixed:N|significant:N|fraction] [EXPRESSION...]
+
+Evaluates each expression in turn, or each line of standard input when none are given.
+With --format json every evaluation is printed as a JSON object on a line of its own.
+With --numbers plain output writes numbers rounded to N places or N significant figures,
+or as fractions.";
+
+/// How each evaluation is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Report {
+    /// the result on standard output, a failure on standard error
+    Plain,
+    /// a JSON object on standard output, see `json::Evaluation`
+    Json,
+}
 
 fn main() {
+    let mut report = Report::Plain;
+    let mut numbers = NumberFormat::default();
+    let mut inputs = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| match arg.strip_prefix(option)?.strip_prefix('=') {
+            Some(value) => Some(value.to_string()),
+            None if arg == option => Some(args.next().unwrap_or_default()),
+            None => None,
+        };
+        if let Some(format) = value("--format") {
+            match format.as_str() {
+                "plain" => report = Report::Plain,
+                "json" => report = Report::Json,
+                format => exit_with_usage(&format!("Unknown format: {}", format)),
+            }
+        } else if let Some(name) = value("--numbers") {
+            numbers = NumberFormat::from_name(&name)
+                .unwrap_or_else(|| exit_with_usage(&format!("Unknown number format: {}", name)));
+        } else if arg == "--help" {
+            println!("{}", USAGE);
+            return;
+        } else {
+            inputs.push(arg);
+        }
+    }
+
     let mut interpreter = Interpreter::new();
+    interpreter.numbers = numbers;
+    let mut failed = false;
+    if inputs.is_empty() {
+        for line in std::io::stdin().lock().lines() {
+            let line = line.unwrap_or_else(|e| exit_with_usage(&e.to_string()));
+            if !line.trim().is_empty() {
+                failed |= !evaluate(&mut interpreter, &line, report);
+            }
+        }
+    } else {
+        for input in &inputs {
+            failed |= !evaluate(&mut interpreter, input, report);
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
 
-    // TODO: error handling
-    match interpreter.lex("-2x^2 + -2x^2 + -2x^2") {
-        Ok(tokens) => {
-            //print!("{:#?}", tokens);
-
-            match interpreter.parse(tokens) {
-                Ok(ast_head) => {
-                    //print!("{:#?}", ast_head);
-
-                    match interpreter.interpret(ast_head) {
-                        Ok(output) => println!("{}", output),
-                        Err(e) => {
-                            eprintln!("Interpretation failed: {}", e);
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Parsing failed: {}", e);
+/// Evaluate one input and report it, whether it succeeded
+fn evaluate(interpreter: &mut Interpreter, input: &str, report: Report) -> bool {
+    match report {
+        Report::Plain => {
+            interpreter.format = OutputFormat::Plain;
+            match run(interpreter, input) {
+                Ok((_, output)) => println!("{}", output),
+                Err(failure) => {
+                    let stage = match failure.stage {
+                        Stage::Lex => "Lexing",
+                        Stage::Parse => "Parsing",
+                        Stage::Interpret => "Interpretation",
+                    };
+                    eprintln!("{} failed: InterpreterError: {}", stage, failure.message);
+                    return false;
                 }
             }
         }
-        Err(e) => {
-            eprintln!("Lexing failed: {}", e);
+        Report::Json => {
+            let started = Instant::now();
+            interpreter.format = OutputFormat::Canonical;
+            let evaluated = run(interpreter, input).map(|(ast, result)| {
+                // assumptions are made again, which leaves them as they were
+                interpreter.format = OutputFormat::Latex;
+                let latex = interpreter
+                    .interpret(ast)
+                    .map_err(|e| Failure::new(Stage::Interpret, &e));
+                (result, latex)
+            });
+            // the time covers both passes, as both are written out
+            let microseconds = started.elapsed().as_micros();
+            let evaluation = match evaluated {
+                Ok((result, latex)) => Evaluation {
+                    version: VERSION,
+                    input: input.to_string(),
+                    result: Some(result),
+                    latex: latex.as_ref().ok().cloned(),
+                    latex_error: latex.err(),
+                    error: None,
+                    microseconds,
+                },
+                Err(failure) => Evaluation {
+                    version: VERSION,
+                    input: input.to_string(),
+                    result: None,
+                    latex: None,
+                    latex_error: None,
+                    error: Some(failure),
+                    microseconds,
+                },
+            };
+            println!("{}", evaluation.to_json());
+            return evaluation.error.is_none();
         }
     }
+    true
+}
+
+/// Lex, parse and interpret an input in the interpreter's format, keeping the tree so
+/// it can be written out again in another
+fn run(interpreter: &mut Interpreter, input: &str) -> Result<(ASTNode, String), Failure> {
+    let tokens = interpreter
+        .lex(input)
+        .map_err(|e| Failure::new(Stage::Lex, &e))?;
+    let ast = interpreter
+        .parse(tokens)
+        .map_err(|e| Failure::new(Stage::Parse, &e))?;
+    let output = interpreter
+        .interpret(ast.clone())
+        .map_err(|e| Failure::new(Stage::Interpret, &e))?;
+    Ok((ast, output))
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
 }
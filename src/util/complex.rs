@@ -0,0 +1,138 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::hashable_float::HashableFloat;
+
+/// Complex number a + bi with floating point parts, for numeric results such as roots
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    pub fn is_real(&self) -> bool {
+        HashableFloat::new(self.im) == HashableFloat::new(0.0)
+    }
+
+    pub fn conj(&self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    /// |a + bi| = sqrt(a^2 + b^2)
+    pub fn modulus(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Angle from the positive real axis, in (-pi, pi]
+    pub fn argument(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn from_polar(modulus: f64, argument: f64) -> Self {
+        Complex::new(modulus * argument.cos(), modulus * argument.sin())
+    }
+
+    /// Principal square root, the one with a non-negative real part
+    pub fn sqrt(&self) -> Self {
+        let modulus = self.modulus();
+        let re = ((modulus + self.re) / 2.0).sqrt();
+        let im = ((modulus - self.re) / 2.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Complex::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex {
+        let denominator = other.re * other.re + other.im * other.im;
+        let numerator = self * other.conj();
+        Complex::new(numerator.re / denominator, numerator.im / denominator)
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl std::fmt::Display for Complex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.re, self.im) {
+            (re, _) if self.is_real() => write!(f, "{}", re),
+            (re, im) if HashableFloat::new(re) == HashableFloat::new(0.0) => write!(f, "{}i", im),
+            (re, im) => write!(f, "{} + {}i", re, im),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+
+        assert_eq!(a + b, Complex::new(4.0, 1.0));
+        assert_eq!(a - b, Complex::new(-2.0, 3.0));
+        assert_eq!(a * b, Complex::new(5.0, 5.0));
+        assert_eq!((a * b) / b, a);
+        assert_eq!(Complex::I * Complex::I, Complex::from(-1.0));
+        assert_eq!(-a, Complex::new(-1.0, -2.0));
+        assert_eq!(a.conj(), Complex::new(1.0, -2.0));
+    }
+
+    #[test]
+    fn test_polar() {
+        let z = Complex::new(3.0, 4.0);
+        assert_eq!(z.modulus(), 5.0);
+        assert_eq!(Complex::I.argument(), std::f64::consts::FRAC_PI_2);
+        assert_eq!(Complex::from(-4.0).sqrt(), Complex::new(0.0, 2.0));
+        assert_eq!(Complex::new(0.0, 2.0).sqrt(), Complex::new(1.0, 1.0));
+        assert_eq!(z.to_string(), "3 + 4i");
+    }
+}
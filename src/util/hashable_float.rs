@@ -1,6 +1,7 @@
-use std::f64::EPSILON;
 use std::hash::{Hash, Hasher};
 
+const EPSILON: f64 = f64::EPSILON;
+
 #[derive(Debug, Copy, Clone)]
 pub struct HashableFloat(pub f64);
 
@@ -1 +1,3 @@
+pub mod complex;
 pub mod hashable_float;
+pub mod rational;
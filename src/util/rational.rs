@@ -0,0 +1,426 @@
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+
+/// Most bits in a part of an integer power, past which [`Rational::pow`] refuses to
+/// compute it rather than exhaust memory or print pages of digits; 65,536 bits is some
+/// 20,000 digits
+pub const MAX_POWER_BITS: u64 = 1 << 16;
+
+/// Units in the last place a float may be off from the fraction [`round`] reads it as
+pub const ROUNDING: f64 = 4.0;
+
+/// Integers from here on are no longer exact as floats
+const EXACT: i128 = 1 << 53;
+
+/// Exact rational number of any size, always stored in lowest terms with a positive denominator.
+///
+/// The arithmetic never overflows; dividing by zero panics, so code dividing by a value
+/// that may be zero uses [`Rational::checked_div`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Rational(BigRational);
+
+impl Rational {
+    pub fn zero() -> Self {
+        Rational(BigRational::zero())
+    }
+
+    pub fn one() -> Self {
+        Rational(BigRational::one())
+    }
+
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        if denominator == 0 {
+            panic!("Rational with zero denominator");
+        }
+        Rational(BigRational::new(numerator.into(), denominator.into()))
+    }
+
+    pub fn from_integer(n: i128) -> Self {
+        Rational::from(n)
+    }
+
+    /// The quotient of two integers of any size, or None for a zero denominator
+    pub fn from_parts(numerator: BigInt, denominator: BigInt) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+        Some(Rational(BigRational::new(numerator, denominator)))
+    }
+
+    /// Recover the exact rational a float most likely represents
+    ///
+    /// Whole floats convert exactly, however large. Otherwise uses continued fractions,
+    /// accepting the first convergent that reproduces the float to within a relative
+    /// epsilon, so `0.5` gives `1/2` and `1.0 / 3.0` gives `1/3`.
+    /// Returns `None` for non-finite values.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        if value.fract() == 0.0 {
+            return BigInt::from_f64(value).map(Rational::from);
+        }
+        if value.abs() >= 1e30 {
+            return None;
+        }
+
+        let tolerance = value.abs().max(1.0) * 1e-12;
+        let (mut h0, mut h1) = (0i128, 1i128);
+        let (mut k0, mut k1) = (1i128, 0i128);
+        let mut x = value;
+        for _ in 0..64 {
+            let a = x.floor();
+            let a_int = a as i128;
+            let h2 = a_int.checked_mul(h1)?.checked_add(h0)?;
+            let k2 = a_int.checked_mul(k1)?.checked_add(k0)?;
+            (h0, h1) = (h1, h2);
+            (k0, k1) = (k1, k2);
+            if (h1 as f64 / k1 as f64 - value).abs() <= tolerance {
+                return Some(Rational::new(h1, k1));
+            }
+            let remainder = x - a;
+            if remainder == 0.0 {
+                break;
+            }
+            x = 1.0 / remainder;
+        }
+        Some(Rational::new(h1, k1))
+    }
+
+    /// The simplest fraction within `ulps` units in the last place of a float, or None when
+    /// there is none with parts small enough to be exact as floats
+    ///
+    /// The fraction is checked as it is read back, the numerator scaled by the reciprocal
+    /// of the denominator, so with some slack `3 + 5/3` is `14/3` whichever way it was
+    /// rounded.
+    pub fn from_f64_within(value: f64, ulps: f64) -> Option<Self> {
+        let r = Rational::from_f64(value)?;
+        let (numerator, denominator) = (r.numerator().to_i128()?, r.denominator().to_i128()?);
+        let close = (r.divided() - value).abs() <= ulps * f64::EPSILON * value.abs();
+        (close && numerator.abs() < EXACT && denominator < EXACT).then_some(r)
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        self.0.numer()
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        self.0.denom()
+    }
+
+    /// The value as an i128, if it is an integer small enough
+    pub fn to_i128(&self) -> Option<i128> {
+        match self.is_integer() {
+            true => self.numerator().to_i128(),
+            false => None,
+        }
+    }
+
+    /// The value as an i64, if it is an integer small enough
+    pub fn to_i64(&self) -> Option<i64> {
+        match self.is_integer() {
+            true => self.numerator().to_i64(),
+            false => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.0.is_one()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.0.is_integer()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0.is_negative()
+    }
+
+    pub fn abs(&self) -> Self {
+        Rational(self.0.abs())
+    }
+
+    pub fn recip(&self) -> Self {
+        Rational(self.0.recip())
+    }
+
+    /// The largest integer not above this value
+    pub fn floor(&self) -> BigInt {
+        self.0.floor().to_integer()
+    }
+
+    /// Raise to an integer power, or None for a negative power of zero or a power of more
+    /// than [`MAX_POWER_BITS`] bits
+    pub fn pow(&self, exponent: i32) -> Option<Self> {
+        if exponent < 0 && self.is_zero() {
+            return None;
+        }
+        let bits = self.numerator().bits().max(self.denominator().bits());
+        if bits.saturating_mul(exponent.unsigned_abs() as u64) > MAX_POWER_BITS {
+            return None;
+        }
+        Some(Rational(num_traits::Pow::pow(&self.0, exponent)))
+    }
+
+    /// The nearest float, which may be infinite for values past the range of an f64
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or_else(|| match self.is_negative() {
+            true => f64::NEG_INFINITY,
+            false => f64::INFINITY,
+        })
+    }
+
+    /// The numerator times the reciprocal of the denominator, as a fraction reads back
+    fn divided(&self) -> f64 {
+        let part = |n: &BigInt| n.to_f64().unwrap_or(f64::NAN);
+        part(self.numerator()) * (1.0 / part(self.denominator()))
+    }
+
+    /// `self / other`, or None if `other` is zero
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        if other.is_zero() {
+            return None;
+        }
+        Some(Rational(&self.0 / &other.0))
+    }
+}
+
+/// A float rounded to the fraction it is within [`ROUNDING`] of, as that fraction reads
+/// back, or unchanged when there is none
+///
+/// Symbolic exponents are rounded this way as they are computed, so a power reads back
+/// as the same symbol rather than one a few units in the last place away.
+pub fn round(value: f64) -> f64 {
+    match Rational::from_f64_within(value, ROUNDING) {
+        Some(r) => r.divided(),
+        None => value,
+    }
+}
+
+/// Greatest common divisor of two integers, always non-negative
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    if a == 0 {
+        1
+    } else {
+        a
+    }
+}
+
+/// Least common multiple of two integers, always positive
+pub fn lcm(a: i128, b: i128) -> i128 {
+    (a / gcd(a, b) * b).abs()
+}
+
+impl From<BigInt> for Rational {
+    fn from(n: BigInt) -> Self {
+        Rational(BigRational::from_integer(n))
+    }
+}
+
+impl From<&Rational> for Rational {
+    fn from(r: &Rational) -> Self {
+        r.clone()
+    }
+}
+
+macro_rules! from_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Rational {
+                fn from(n: $t) -> Self {
+                    Rational::from(BigInt::from(n))
+                }
+            }
+        )*
+    };
+}
+
+from_primitive!(i32, i64, i128, u32, u64, usize);
+
+/// The operators take values or references, so `&a * &b` leaves both operands in place
+///
+/// Sums, differences and products of integers skip the gcd that keeps a fraction in lowest
+/// terms, which is the slow part of expanding a power with large integer coefficients.
+macro_rules! operator {
+    ($trait:ident, $method:ident, $integers:expr) => {
+        impl $trait<&Rational> for &Rational {
+            type Output = Rational;
+
+            fn $method(self, other: &Rational) -> Rational {
+                if $integers && self.is_integer() && other.is_integer() {
+                    let value = self.numerator().$method(other.numerator());
+                    return Rational(BigRational::from_integer(value));
+                }
+                Rational((&self.0).$method(&other.0))
+            }
+        }
+
+        impl $trait<Rational> for &Rational {
+            type Output = Rational;
+
+            fn $method(self, other: Rational) -> Rational {
+                self.$method(&other)
+            }
+        }
+
+        impl $trait<&Rational> for Rational {
+            type Output = Rational;
+
+            fn $method(self, other: &Rational) -> Rational {
+                (&self).$method(other)
+            }
+        }
+
+        impl $trait<Rational> for Rational {
+            type Output = Rational;
+
+            fn $method(self, other: Rational) -> Rational {
+                (&self).$method(&other)
+            }
+        }
+    };
+}
+
+operator!(Add, add, true);
+operator!(Sub, sub, true);
+operator!(Mul, mul, true);
+operator!(Div, div, false);
+
+impl AddAssign<&Rational> for Rational {
+    fn add_assign(&mut self, other: &Rational) {
+        *self = &*self + other;
+    }
+}
+
+impl SubAssign<&Rational> for Rational {
+    fn sub_assign(&mut self, other: &Rational) {
+        *self = &*self - other;
+    }
+}
+
+impl MulAssign<&Rational> for Rational {
+    fn mul_assign(&mut self, other: &Rational) {
+        *self = &*self * other;
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational(-self.0)
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational(-&self.0)
+    }
+}
+
+impl PartialEq<i32> for Rational {
+    fn eq(&self, other: &i32) -> bool {
+        self.is_integer() && *self.numerator() == BigInt::from(*other)
+    }
+}
+
+impl PartialOrd<i32> for Rational {
+    fn partial_cmp(&self, other: &i32) -> Option<Ordering> {
+        Some(self.cmp(&Rational::from(*other)))
+    }
+}
+
+impl std::fmt::Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator())
+        } else {
+            write!(f, "{}/{}", self.numerator(), self.denominator())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!(Rational::new(2, 4), Rational::new(1, 2));
+        assert_eq!(Rational::new(1, -2), Rational::new(-1, 2));
+        assert_eq!(Rational::new(0, -5), Rational::zero());
+        assert_eq!(Rational::new(-3, -6).to_string(), "1/2");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let half = Rational::new(1, 2);
+        let third = Rational::new(1, 3);
+
+        assert_eq!(&half + &third, Rational::new(5, 6));
+        assert_eq!(&half - &third, Rational::new(1, 6));
+        assert_eq!(&half * &third, Rational::new(1, 6));
+        assert_eq!(&half / &third, Rational::new(3, 2));
+        assert_eq!(-&half, Rational::new(-1, 2));
+        assert!(third < half);
+        assert_eq!(half.pow(-3), Some(Rational::from(8)));
+        assert_eq!(Rational::zero().pow(-1), None);
+    }
+
+    #[test]
+    fn test_large_values() {
+        // past the range of an i128 and of exact floats, still exact
+        let large = Rational::from(3).pow(100).unwrap();
+        assert_eq!(
+            large.to_string(),
+            "515377520732011331036461129765621272702107522001"
+        );
+        assert_eq!(&(&large + &Rational::one()) - &large, Rational::one());
+        assert_eq!(large.to_i128(), None);
+        assert_eq!(Rational::one().checked_div(&Rational::zero()), None);
+
+        let a = Rational::new(i128::MAX - 1, i128::MAX - 2);
+        let b = Rational::new(i128::MAX - 2, i128::MAX - 3);
+        assert!(a < b);
+        assert!(-&b < -&a);
+        assert_eq!(a.cmp(&a), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(Rational::from_f64(3.0), Some(Rational::from_integer(3)));
+        assert_eq!(Rational::from_f64(-0.5), Some(Rational::new(-1, 2)));
+        assert_eq!(Rational::from_f64(1.0 / 3.0), Some(Rational::new(1, 3)));
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+        assert_eq!(
+            Rational::from_f64(2f64.powi(100)),
+            Some(Rational::from(2).pow(100).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_round() {
+        let third = 0.1 + 0.1 + 0.1 + 1.0 / 30.0;
+        assert_eq!(
+            Rational::from_f64_within(third, ROUNDING),
+            Some(Rational::new(1, 3))
+        );
+        assert_eq!(round(third), 1.0 * (1.0 / 3.0));
+        assert_eq!(Rational::from_f64_within(third, 0.0), None);
+        assert_eq!(round(std::f64::consts::PI), std::f64::consts::PI);
+    }
+}